@@ -0,0 +1,120 @@
+// Automaton steps and region flood fills on big maps, each against the
+// straightforward version that looks every tile up on its own: eight
+// neighbour lookups a tile for a step, and a whole-map search grid per region
+// for the fill. Run with `cargo bench --bench automaton`.
+use dungeon_generator::dungeon::Rules;
+use dungeon_generator::generator;
+use dungeon_generator::pathfinding::{bfs_distances, regions_in};
+use dungeon_generator::scratch::Scratch;
+use dungeon_generator::{Config, Dungeon, Point, Tile};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+fn time(runs: u32, mut run: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..runs {
+        run();
+    }
+    start.elapsed() / runs
+}
+
+fn per_tile_step(dungeon: &Dungeon, rules: &Rules) -> Vec<Vec<Tile>> {
+    let (width, height) = (dungeon.width() as i64, dungeon.height() as i64);
+    let wall = |x: i64, y: i64| {
+        x < 0 || y < 0 || x >= width || y >= height || dungeon.tile(x as usize, y as usize) == Tile::Wall
+    };
+    let mut next = Vec::new();
+    for y in 0..height {
+        let mut row = Vec::new();
+        for x in 0..width {
+            let mut walls = 0;
+            for (dx, dy) in [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)] {
+                walls += usize::from(wall(x + dx, y + dy));
+            }
+            row.push(match dungeon.tile(x as usize, y as usize) {
+                Tile::Wall if walls < rules.death_limit => Tile::Floor,
+                tile if tile != Tile::Wall && walls > rules.birth_limit => Tile::Wall,
+                tile => tile,
+            });
+        }
+        next.push(row);
+    }
+    next
+}
+
+fn per_region_search(dungeon: &Dungeon) -> Vec<Vec<Point>> {
+    let mut seen = vec![vec![false; dungeon.width()]; dungeon.height()];
+    let mut regions = Vec::new();
+    for y in 0..dungeon.height() {
+        for x in 0..dungeon.width() {
+            if seen[y][x] || !dungeon.tile(x, y).is_walkable() {
+                continue;
+            }
+            let mut region = Vec::new();
+            for (sy, row) in bfs_distances(dungeon, (x, y)).iter().enumerate() {
+                for (sx, distance) in row.iter().enumerate() {
+                    if distance.is_some() {
+                        seen[sy][sx] = true;
+                        region.push((sx, sy));
+                    }
+                }
+            }
+            regions.push(region);
+        }
+    }
+    regions
+}
+
+fn main() {
+    println!("{:>11}  {:>8}  {:>12}  {:>12}  {:>7}", "map", "pass", "one by one", "contiguous", "speedup");
+    let rules = Rules::default();
+
+    for size in [256, 512, 1024] {
+        let config = Config {
+            width: size,
+            height: size,
+            ..Config::default()
+        };
+        let noise = generator::start(&config, &mut generator::rng_for(1));
+        let settled = generator::generate_seeded(&config, 1);
+        let label = format!("{}x{}", size, size);
+
+        let mut stepped = generator::start(&config, &mut generator::rng_for(1));
+        stepped.simulate_step_with(&rules);
+        let expected = per_tile_step(&noise, &rules);
+        for (y, row) in expected.iter().enumerate() {
+            for (x, &tile) in row.iter().enumerate() {
+                assert_eq!(stepped.tile(x, y), tile, "both steps must agree");
+            }
+        }
+        let mut scratch = Scratch::new();
+        let per_tile = time(10, || {
+            black_box(per_tile_step(&noise, &rules));
+        });
+        let mut working = generator::start(&config, &mut generator::rng_for(1));
+        let row_wise = time(10, || {
+            black_box(working.simulate_step_in(&rules, &mut scratch));
+        });
+        report(&label, "step", per_tile, row_wise);
+
+        assert_eq!(per_region_search(&settled), regions_in(&settled, &mut scratch), "both fills must agree");
+        let per_tile = time(1, || {
+            black_box(per_region_search(&settled));
+        });
+        let row_wise = time(10, || {
+            black_box(regions_in(&settled, &mut scratch));
+        });
+        report(&label, "regions", per_tile, row_wise);
+    }
+}
+
+fn report(label: &str, pass: &str, one_by_one: Duration, contiguous: Duration) {
+    println!(
+        "{:>11}  {:>8}  {:>12?}  {:>12?}  {:>6.1}x",
+        label,
+        pass,
+        one_by_one,
+        contiguous,
+        one_by_one.as_secs_f64() / contiguous.as_secs_f64()
+    );
+}
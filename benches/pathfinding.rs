@@ -0,0 +1,57 @@
+// Plain A* against jump point search on large uniform-cost caves.
+// Run with `cargo bench --bench pathfinding`.
+use dungeon_generator::generator;
+use dungeon_generator::pathfinding::{entrance_and_exit, find_path_with, PathAlgorithm, PathOptions};
+use dungeon_generator::Config;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const RUNS: u32 = 20;
+
+fn time(mut run: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        run();
+    }
+    start.elapsed() / RUNS
+}
+
+fn main() {
+    println!("{:>11}  {:>6}  {:>12}  {:>12}  {:>7}", "map", "steps", "a*", "jps", "speedup");
+
+    for size in [128, 256, 512, 1024] {
+        let config = Config {
+            width: size,
+            height: size,
+            ..Config::default()
+        };
+        let dungeon = generator::generate(&config);
+        let (start, goal) = entrance_and_exit(&dungeon).expect("map has floor");
+
+        let a_star = PathOptions::default();
+        let jump_point = PathOptions {
+            algorithm: PathAlgorithm::JumpPoint,
+            ..PathOptions::default()
+        };
+
+        let expected = find_path_with(&dungeon, start, goal, &a_star).unwrap();
+        let found = find_path_with(&dungeon, start, goal, &jump_point).unwrap();
+        assert_eq!(expected.len(), found.len(), "both searches must find optimal paths");
+
+        let a_star_time = time(|| {
+            black_box(find_path_with(&dungeon, start, goal, &a_star));
+        });
+        let jump_point_time = time(|| {
+            black_box(find_path_with(&dungeon, start, goal, &jump_point));
+        });
+
+        println!(
+            "{:>11}  {:>6}  {:>12?}  {:>12?}  {:>6.1}x",
+            format!("{}x{}", size, size),
+            expected.len() - 1,
+            a_star_time,
+            jump_point_time,
+            a_star_time.as_secs_f64() / jump_point_time.as_secs_f64()
+        );
+    }
+}
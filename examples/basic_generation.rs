@@ -0,0 +1,33 @@
+// The shortest way to a map: a preset or the default config, a seed, and
+// the finished cave printed in the glyphs `Dungeon::from_ascii` reads back.
+// Run with `cargo run --example basic_generation [seed]`.
+use dungeon_generator::prelude::*;
+use std::env;
+
+fn main() {
+    let seed = env::args().nth(1).and_then(|seed| seed.parse().ok()).unwrap_or(7);
+
+    // The same config and seed always make the same map
+    let config = Config {
+        width: 48,
+        height: 20,
+        ..Config::default()
+    };
+    let (dungeon, report) = generate_reported(&config, seed);
+    print!("{}", dungeon.to_ascii());
+    println!(
+        "Seed {}: {} after {} step(s){}",
+        seed,
+        report.algorithm,
+        report.steps,
+        if report.converged { ", settled" } else { "" }
+    );
+
+    // A preset is a whole config tuned for one kind of level
+    let crypt = generate_seeded(&Preset::Crypt.config(), seed);
+    let floor = (0..crypt.height())
+        .flat_map(|y| (0..crypt.width()).map(move |x| (x, y)))
+        .filter(|&(x, y)| crypt.tile(x, y).is_walkable())
+        .count();
+    println!("The crypt for the same seed is {}x{} with {} walkable tiles", crypt.width(), crypt.height(), floor);
+}
@@ -0,0 +1,47 @@
+// Rings the terminal bell for generation and exploration events, the way a
+// game would play a sample for each: a tick per automaton step, a creak per
+// door and a chime per room. Run with `cargo run --example beep [seed]`.
+use dungeon_generator::autoexplore;
+use dungeon_generator::events::{self, RegionWatch};
+use dungeon_generator::exploration::Exploration;
+use dungeon_generator::generator;
+use dungeon_generator::pathfinding;
+use dungeon_generator::prelude::*;
+use std::env;
+use std::io::{self, Write};
+use std::thread;
+use std::time::Duration;
+
+fn play(event: &Event) {
+    let sample = match event {
+        Event::Step { changed: true, .. } => "tick",
+        Event::DoorOpened(_) => "creak",
+        Event::RegionRevealed { .. } => "chime",
+        _ => return,
+    };
+    println!("\x07{:?} -> {}", event, sample);
+    io::stdout().flush().ok();
+    thread::sleep(Duration::from_millis(100));
+}
+
+fn main() {
+    let seed = env::args().nth(1).and_then(|seed| seed.parse().ok()).unwrap_or(1);
+    let config = Preset::Sewer.config();
+
+    let (mut dungeon, _) = generator::generate_observed(&config, seed, &mut play);
+    let Some((mut position, _)) = pathfinding::entrance_and_exit(&dungeon) else {
+        return;
+    };
+    let options = PathOptions::default();
+    let mut exploration = Exploration::for_dungeon(&dungeon);
+    let mut rooms = RegionWatch::new(&dungeon);
+    exploration.look(&dungeon, position, 8);
+    rooms.update(&exploration, &mut play);
+    while let Some(next) = autoexplore::next_step(&dungeon, &exploration, position, &options) {
+        events::step_onto(&mut dungeon, next, &options, &mut play);
+        position = next;
+        exploration.look(&dungeon, position, 8);
+        rooms.update(&exploration, &mut play);
+    }
+    println!("Found {} of {} rooms", rooms.revealed(), rooms.rooms());
+}
@@ -0,0 +1,130 @@
+// Turning a map into game-engine entities, laid out the way a Bevy startup
+// system would do it. The crate doesn't depend on Bevy, so a small `World`
+// stands in for its `Commands`: in a Bevy app `MapSeed` is a `Resource`,
+// `spawn_level` a system taking `Res<MapSeed>` and `Commands`, each `spawn`
+// a `commands.spawn((Transform::from_translation(..), Sprite { .. }))` and
+// `Solid` a marker component the physics reads. Each room's contents are
+// rolled from that room's own stream, so a level rebuilt from the same seed
+// gets the same loot room for room however the systems are scheduled.
+// Run with `cargo run --example bevy_integration [seed]`.
+use dungeon_generator::door::DoorState;
+use dungeon_generator::pathfinding;
+use dungeon_generator::prelude::*;
+use rand::Rng;
+use std::collections::BTreeMap;
+use std::env;
+
+// World units a tile takes up
+const TILE_SIZE: f32 = 16.0;
+
+struct MapSeed(u64);
+
+enum Sprite {
+    Floor,
+    Wall,
+    Water,
+    Rubble,
+    Door,
+    Stairs { down: bool },
+    Monster(String),
+    Chest { gold: u32 },
+}
+
+// One spawned entity: where it stands, what it looks like and whether it
+// blocks movement
+struct Spawned {
+    translation: [f32; 3],
+    sprite: Sprite,
+    solid: bool,
+}
+
+#[derive(Default)]
+struct World {
+    spawned: Vec<Spawned>,
+}
+
+impl World {
+    fn spawn(&mut self, (x, y): Point, layer: f32, sprite: Sprite, solid: bool) {
+        // Engines count y upwards; maps count rows downwards
+        let translation = [x as f32 * TILE_SIZE, -(y as f32) * TILE_SIZE, layer];
+        self.spawned.push(Spawned { translation, sprite, solid });
+    }
+}
+
+fn spawn_level(seed: &MapSeed, world: &mut World) {
+    let dungeon = generate_seeded(&Preset::Sewer.config(), seed.0);
+
+    // Terrain on layer 0, one sprite a tile
+    for y in 0..dungeon.height() {
+        for x in 0..dungeon.width() {
+            let sprite = match dungeon.tile(x, y) {
+                Tile::Wall => Sprite::Wall,
+                Tile::Water => Sprite::Water,
+                Tile::Rubble => Sprite::Rubble,
+                _ => Sprite::Floor,
+            };
+            let solid = !dungeon.tile(x, y).is_walkable();
+            world.spawn((x, y), 0.0, sprite, solid);
+        }
+    }
+
+    // Doors and entities on layer 1, in reading order so the spawn order is
+    // the same every run; only an open door lets anything through
+    for y in 0..dungeon.height() {
+        for x in 0..dungeon.width() {
+            if let Some(Feature::Door(door)) = dungeon.feature(x, y) {
+                world.spawn((x, y), 1.0, Sprite::Door, door.state != DoorState::Open);
+            }
+        }
+    }
+    // The marked entrance and exit, or the far ends of the largest cave
+    // where the map marks none
+    if let Some((entrance, exit)) = pathfinding::entrance_and_exit(&dungeon) {
+        world.spawn(entrance, 1.0, Sprite::Stairs { down: false }, false);
+        world.spawn(exit, 1.0, Sprite::Stairs { down: true }, false);
+    }
+    let mut entities: Vec<(Point, &Entity)> = dungeon.entities().iter().collect();
+    entities.sort_by_key(|&((x, y), _)| (y, x));
+    let (rooms, _) = dungeon.label_regions();
+    let rng = SeededRng::new(seed.0);
+    for ((x, y), entity) in entities {
+        let sprite = match entity {
+            Entity::Entrance | Entity::Exit => continue,
+            Entity::Monster(kind) => Sprite::Monster(kind.clone()),
+            Entity::Item(_) => {
+                // Gold drawn from the room's stream, the item's place in it
+                // keeping two chests in one room apart
+                let room = rooms[y][x].unwrap_or(usize::MAX);
+                let gold = rng.room(room).for_topic(&format!("chest {} {}", x, y)).gen_range(5..50);
+                Sprite::Chest { gold }
+            }
+        };
+        world.spawn((x, y), 1.0, sprite, false);
+    }
+}
+
+fn main() {
+    let seed = MapSeed(env::args().nth(1).and_then(|seed| seed.parse().ok()).unwrap_or(7));
+    let mut world = World::default();
+    spawn_level(&seed, &mut world);
+
+    let solid = world.spawned.iter().filter(|spawned| spawned.solid).count();
+    println!("Spawned {} entities, {} of them solid", world.spawned.len(), solid);
+    let mut monsters: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut gold = 0;
+    for spawned in &world.spawned {
+        match &spawned.sprite {
+            Sprite::Stairs { down } => {
+                let way = if *down { "down" } else { "up" };
+                println!("  Stairs {} at {:?}", way, spawned.translation);
+            }
+            Sprite::Monster(kind) => *monsters.entry(kind).or_default() += 1,
+            Sprite::Chest { gold: coins } => gold += coins,
+            _ => {}
+        }
+    }
+    for (kind, count) in monsters {
+        println!("  {} x {}", count, kind);
+    }
+    println!("  {} gold in chests", gold);
+}
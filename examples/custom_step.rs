@@ -0,0 +1,51 @@
+// A pass of your own between the generator's: start the map, run the
+// automaton step by step, do something it doesn't, then hand back to the
+// post-passes, which draw from the same RNG as if nothing had come between.
+// Here a round plaza is cleared in the middle of the settled cave, too late
+// for the automaton to wear it away and in time for the post-passes to
+// furnish it. Run with `cargo run --example custom_step [seed]`.
+use dungeon_generator::generator;
+use dungeon_generator::prelude::*;
+use dungeon_generator::scratch::Scratch;
+use std::env;
+
+const PLAZA_RADIUS: usize = 4;
+
+// Returns the wall tiles cleared
+fn clear_plaza(dungeon: &mut Dungeon) -> usize {
+    let (cx, cy) = (dungeon.width() / 2, dungeon.height() / 2);
+    let mut cleared = 0;
+    for y in cy - PLAZA_RADIUS..=cy + PLAZA_RADIUS {
+        for x in cx - PLAZA_RADIUS..=cx + PLAZA_RADIUS {
+            let inside = x.abs_diff(cx).pow(2) + y.abs_diff(cy).pow(2) <= PLAZA_RADIUS.pow(2);
+            if inside && dungeon.tile(x, y) == Tile::Wall {
+                dungeon.set_tile(x, y, Tile::Floor);
+                cleared += 1;
+            }
+        }
+    }
+    cleared
+}
+
+fn main() {
+    let seed = env::args().nth(1).and_then(|seed| seed.parse().ok()).unwrap_or(7);
+    let config = Config {
+        width: 48,
+        height: 20,
+        wall_probability: 0.5,
+        ..Config::default()
+    };
+
+    let (mut dungeon, mut rng) = generator::start_seeded(&config, seed);
+    let mut scratch = Scratch::new();
+    let mut steps = 0;
+    while steps < generator::step_limit(&config) && generator::step_in(&mut dungeon, &config, &mut scratch) {
+        steps += 1;
+    }
+
+    let cleared = clear_plaza(&mut dungeon);
+
+    generator::finish(&mut dungeon, &config, &mut rng);
+    print!("{}", dungeon.to_ascii());
+    println!("Seed {}: {} step(s), then {} wall tile(s) cleared for the plaza", seed, steps, cleared);
+}
@@ -0,0 +1,32 @@
+// Writing a map out as a picture: a PNG in any theme at any scale, or the
+// bytes themselves for a program that would rather keep them in memory.
+// Run with `cargo run --example export_png [path] [theme]`.
+use dungeon_generator::image;
+use dungeon_generator::prelude::*;
+use std::env;
+use std::io;
+use std::path::PathBuf;
+
+fn main() -> io::Result<()> {
+    let mut args = env::args().skip(1);
+    let path = PathBuf::from(args.next().unwrap_or_else(|| "dungeon.png".to_string()));
+    // A built-in theme by name or a theme .toml
+    let theme = Theme::resolve(&args.next().unwrap_or_else(|| "classic".to_string()))?;
+    for warning in theme.contrast_warnings() {
+        eprintln!("Warning: {}", warning);
+    }
+
+    let config = Config {
+        theme: theme.clone(),
+        ..Preset::Sewer.config()
+    };
+    let dungeon = generate_seeded(&config, 7);
+
+    // Eight pixels a side for each tile
+    image::write_png(&dungeon, &theme, 8, &path)?;
+    println!("Wrote {}x{} {} image to {}", dungeon.width() * 8, dungeon.height() * 8, theme.name, path.display());
+
+    let thumbnail = image::png_bytes(&dungeon, &theme, 1);
+    println!("A one-pixel-a-tile thumbnail would take {} bytes", thumbnail.len());
+    Ok(())
+}
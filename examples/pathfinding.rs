@@ -0,0 +1,75 @@
+// Routing over a map: the plain shortest walk, a route that weighs water and
+// rubble and won't force locked doors, the step count to everywhere from the
+// entrance, and the same search over a grid type of your own through
+// `GridMap`. Run with `cargo run --example pathfinding [seed]`.
+use dungeon_generator::grid::GridMap;
+use dungeon_generator::pathfinding::{self, MovementCosts};
+use dungeon_generator::prelude::*;
+use std::env;
+
+// A game's own map, never copied into a `Dungeon`: open floor with a wall
+// down the middle that has one gap
+struct Arena {
+    tiles: Vec<bool>,
+}
+
+impl GridMap for Arena {
+    fn width(&self) -> usize {
+        16
+    }
+
+    fn height(&self) -> usize {
+        8
+    }
+
+    fn tile(&self, x: usize, y: usize) -> Tile {
+        if self.tiles[y * 16 + x] {
+            Tile::Wall
+        } else {
+            Tile::Floor
+        }
+    }
+
+    fn set_tile(&mut self, x: usize, y: usize, tile: Tile) {
+        self.tiles[y * 16 + x] = tile == Tile::Wall;
+    }
+}
+
+fn main() {
+    let seed = env::args().nth(1).and_then(|seed| seed.parse().ok()).unwrap_or(7);
+    let dungeon = generate_seeded(&Preset::Sewer.config(), seed);
+    let Some((entrance, exit)) = pathfinding::entrance_and_exit(&dungeon) else {
+        println!("Seed {} has nowhere to walk", seed);
+        return;
+    };
+
+    match find_path(&dungeon, entrance, exit) {
+        Some(path) => println!("Shortest walk from {:?} to {:?}: {} steps", entrance, exit, path.len() - 1),
+        None => println!("No walk from {:?} to {:?}", entrance, exit),
+    }
+
+    // Wading costs more than walking, and locked doors stay shut
+    let wary = PathOptions {
+        costs: MovementCosts { floor: 1, water: 6, rubble: 3 },
+        locked_door_cost: None,
+        ..PathOptions::default()
+    };
+    if let Some(path) = pathfinding::find_path_with(&dungeon, entrance, exit, &wary) {
+        let wet = path.iter().filter(|&&(x, y)| dungeon.tile(x, y) == Tile::Water).count();
+        println!("Keeping dry: {} steps, {} of them through water", path.len() - 1, wet);
+    }
+
+    let distances = pathfinding::bfs_distances(&dungeon, entrance);
+    let reachable = distances.iter().flatten().flatten().count();
+    let farthest = distances.iter().flatten().flatten().max().copied().unwrap_or(0);
+    println!("{} tiles reachable from the entrance, the farthest {} steps away", reachable, farthest);
+
+    let mut arena = Arena { tiles: vec![false; 16 * 8] };
+    for y in 0..8 {
+        if y != 6 {
+            arena.set_tile(8, y, Tile::Wall);
+        }
+    }
+    let around = find_path(&arena, (2, 1), (13, 1)).map(|path| path.len() - 1);
+    println!("Across the arena wall through its gap: {:?} steps", around);
+}
@@ -0,0 +1,153 @@
+// Purely decorative particles drifting over a drawn map: drops rippling on
+// the floor of wet places and mist rolling through caverns. They live on
+// top of the map and never touch it.
+use crate::dungeon::{Dungeon, Point};
+use crate::generator::SeededRng;
+use crate::theme::Theme;
+use crossterm::style::Color;
+use rand::rngs::StdRng;
+use rand::Rng;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Ambient {
+    #[default]
+    Off,
+    // Whatever suits the theme: drips in the sewer, mist in caverns
+    Auto,
+    Drips,
+    Mist,
+}
+
+impl Ambient {
+    pub const ALL: [Ambient; 4] = [Ambient::Off, Ambient::Auto, Ambient::Drips, Ambient::Mist];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Ambient::Off => "off",
+            Ambient::Auto => "auto",
+            Ambient::Drips => "drips",
+            Ambient::Mist => "mist",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|ambient| ambient.name() == name)
+    }
+
+    // The effect to play under `theme`, None for no effect at all
+    pub fn effect(self, theme: &Theme) -> Option<Self> {
+        match self {
+            Ambient::Off => None,
+            Ambient::Auto => match theme.name.as_str() {
+                "sewer" => Some(Ambient::Drips),
+                "cavern" => Some(Ambient::Mist),
+                _ => None,
+            },
+            effect => Some(effect),
+        }
+    }
+}
+
+// A drop's ripple widening over a few frames
+const RIPPLE: [char; 3] = ['.', 'o', 'O'];
+// Mist thins toward the edges of a bank
+const MIST: [char; 3] = ['░', '▒', '░'];
+
+// One tile of floor per this many gets a particle
+const DRIP_SPACING: usize = 120;
+const MIST_SPACING: usize = 40;
+
+#[derive(Clone, Debug)]
+struct Particle {
+    x: f64,
+    y: f64,
+    // Frames lived, for ripples; which shade of a bank, for mist
+    age: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct Weather {
+    effect: Ambient,
+    particles: Vec<Particle>,
+    rng: StdRng,
+}
+
+impl Weather {
+    // None where `ambient` asks for nothing under `theme`
+    pub fn new(ambient: Ambient, theme: &Theme, dungeon: &Dungeon, seed: u64) -> Option<Self> {
+        let effect = ambient.effect(theme)?;
+        let mut weather = Self {
+            effect,
+            particles: Vec::new(),
+            rng: SeededRng::new(seed).for_topic("ambient"),
+        };
+        let floor = dungeon.tiles.iter().flatten().filter(|tile| tile.is_walkable()).count();
+        let spacing = if effect == Ambient::Drips { DRIP_SPACING } else { MIST_SPACING };
+        for _ in 0..floor / spacing {
+            let particle = weather.spawn(dungeon);
+            weather.particles.extend(particle);
+        }
+        Some(weather)
+    }
+
+    // A particle somewhere on the floor, ripples already part way through
+    // so they don't all pulse together
+    fn spawn(&mut self, dungeon: &Dungeon) -> Option<Particle> {
+        for _ in 0..32 {
+            let (x, y) = (self.rng.gen_range(0..dungeon.width), self.rng.gen_range(0..dungeon.height));
+            if dungeon.tiles[y][x].is_walkable() {
+                let age = self.rng.gen_range(0..RIPPLE.len() + 4);
+                return Some(Particle { x: x as f64, y: y as f64, age });
+            }
+        }
+        None
+    }
+
+    // One frame on: ripples grow and give way to new drops elsewhere, mist
+    // drifts east and wraps round
+    pub fn tick(&mut self, dungeon: &Dungeon) {
+        let width = dungeon.width as f64;
+        for index in 0..self.particles.len() {
+            let particle = &mut self.particles[index];
+            match self.effect {
+                Ambient::Drips => {
+                    particle.age += 1;
+                    // A pause between drops keeps the floor from boiling
+                    if particle.age >= RIPPLE.len() + 4 {
+                        if let Some(mut drop) = self.spawn(dungeon) {
+                            drop.age = 0;
+                            self.particles[index] = drop;
+                        }
+                    }
+                }
+                _ => {
+                    particle.x = (particle.x + 0.25 + self.rng.gen_range(0.0..0.1)) % width;
+                    let drift = self.rng.gen_range(-0.1..0.1);
+                    particle.y = (particle.y + drift).clamp(0.0, dungeon.height as f64 - 0.5);
+                    particle.age = (particle.age + self.rng.gen_range(0..2)) % MIST.len();
+                }
+            }
+        }
+    }
+
+    // Tiles with a particle showing on them right now. Particles only show
+    // over bare floor, so walls, doors and whatever stands about stay in
+    // view.
+    pub fn cells(&self, dungeon: &Dungeon) -> Vec<(Point, Color, char)> {
+        self.particles
+            .iter()
+            .filter_map(|particle| {
+                let (x, y) = (particle.x as usize, particle.y.round() as usize);
+                let bare = y < dungeon.height
+                    && dungeon.tiles[y][x].is_walkable()
+                    && !dungeon.features.contains(x, y)
+                    && !dungeon.entities.contains(x, y);
+                match self.effect {
+                    Ambient::Drips if bare => RIPPLE.get(particle.age).map(|&glyph| ((x, y), Color::Cyan, glyph)),
+                    Ambient::Mist if bare => Some(((x, y), Color::Grey, MIST[particle.age])),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+}
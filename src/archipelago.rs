@@ -0,0 +1,136 @@
+// Islands in open water. The automaton works the noise as it would a cave,
+// walls standing for the sea, and once it settles the islands are raised:
+// the smallest sink, the rest are linked up the same way the connectivity
+// pass tunnels to the main region, and the walls are flooded. Water stays
+// walkable, if slowly, so connectors are what make a crossing cheap.
+use crate::dungeon::{Dungeon, Point, Tile};
+use crate::layers::Feature;
+use crate::pathfinding::regions_in;
+use crate::pins::{pinned, Pin};
+use crate::scratch::Scratch;
+use std::collections::HashSet;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Connectors {
+    // Islands are left apart
+    None,
+    // A bridge over the shortest crossing from each island to the largest
+    #[default]
+    Bridges,
+    // A boat moored at each end of that crossing instead
+    Boats,
+}
+
+impl Connectors {
+    pub const ALL: [Connectors; 3] = [Connectors::None, Connectors::Bridges, Connectors::Boats];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Connectors::None => "none",
+            Connectors::Bridges => "bridges",
+            Connectors::Boats => "boats",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|connectors| connectors.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ArchipelagoOptions {
+    // Share of the starting noise that is sea; it stands in for the wall
+    // probability
+    pub sea: f64,
+    // Islands with fewer tiles than this go under
+    pub min_island: usize,
+    pub connectors: Connectors,
+}
+
+impl Default for ArchipelagoOptions {
+    fn default() -> Self {
+        Self {
+            sea: 0.54,
+            min_island: 12,
+            connectors: Connectors::Bridges,
+        }
+    }
+}
+
+pub const BOAT: &str = "boat";
+
+impl Dungeon {
+    // Turns a settled automaton map into islands; returns the crossings made
+    pub fn raise_islands(&mut self, options: &ArchipelagoOptions, pins: &[Pin], scratch: &mut Scratch) -> usize {
+        let fixed =
+            |dungeon: &Dungeon, &(x, y): &Point| dungeon.frozen[y][x] || pinned(pins, x, y) == Some(Tile::Floor);
+        for island in regions_in(self, scratch) {
+            if island.len() < options.min_island && !island.iter().any(|point| fixed(self, point)) {
+                for (x, y) in island {
+                    self.tiles[y][x] = Tile::Wall;
+                }
+            }
+        }
+
+        let mut crossings = 0;
+        if options.connectors != Connectors::None {
+            // Islands already reached by boat, or out of reach, by one tile of each
+            let mut linked: HashSet<Point> = HashSet::new();
+            loop {
+                let islands = regions_in(self, scratch);
+                let Some(main) = (0..islands.len()).max_by_key(|&index| (islands[index].len(), std::cmp::Reverse(index)))
+                else {
+                    break;
+                };
+                let Some(island) = islands
+                    .iter()
+                    .enumerate()
+                    .find(|&(index, island)| index != main && !island.iter().any(|tile| linked.contains(tile)))
+                    .map(|(_, island)| island)
+                else {
+                    break;
+                };
+                linked.insert(island[0]);
+                let Some(crossing) = self.tunnel_to_main(island, pins, scratch) else {
+                    continue;
+                };
+                crossings += 1;
+                match options.connectors {
+                    Connectors::Bridges => {
+                        for (x, y) in crossing {
+                            if self.tiles[y][x] == Tile::Wall {
+                                self.tiles[y][x] = Tile::Bridge;
+                            }
+                        }
+                    }
+                    // Wherever the crossing leaves land for the sea or comes ashore,
+                    // counting every island it lands on as reached
+                    _ => {
+                        linked.extend(crossing.iter().filter(|&&(x, y)| self.tiles[y][x] != Tile::Wall));
+                        for pair in crossing.windows(2) {
+                            let [(ax, ay), (bx, by)] = [pair[0], pair[1]];
+                            let shore = match (self.tiles[ay][ax] == Tile::Wall, self.tiles[by][bx] == Tile::Wall) {
+                                (false, true) => (ax, ay),
+                                (true, false) => (bx, by),
+                                _ => continue,
+                            };
+                            if !self.features.contains(shore.0, shore.1) && !self.frozen[shore.1][shore.0] {
+                                self.features.set(shore.0, shore.1, Feature::Decoration(BOAT.to_string()));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.tiles[y][x] == Tile::Wall && !self.frozen[y][x] {
+                    self.tiles[y][x] = Tile::Water;
+                }
+            }
+        }
+        self.prune_layers();
+        crossings
+    }
+}
@@ -0,0 +1,122 @@
+// Auto-explore as roguelikes have it: head for the nearest edge of the known
+// map, look around, repeat until nothing reachable is left unseen. Run from
+// the entrance it doubles as a coverage check of a generated map.
+use crate::dungeon::{Dungeon, Point};
+use crate::exploration::Exploration;
+use crate::pathfinding::{distance_map, PathOptions};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+const STEPS: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+fn neighbors(dungeon: &Dungeon, (x, y): Point) -> impl Iterator<Item = Point> + '_ {
+    STEPS.iter().filter_map(move |&(dx, dy)| {
+        let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+        (nx >= 0 && ny >= 0 && (nx as usize) < dungeon.width && (ny as usize) < dungeon.height)
+            .then_some((nx as usize, ny as usize))
+    })
+}
+
+// A known tile that can be walked onto and borders one not seen yet
+fn is_frontier(dungeon: &Dungeon, exploration: &Exploration, options: &PathOptions, point: Point) -> bool {
+    exploration.is_seen(point.0, point.1)
+        && options.step_cost(dungeon, point).is_some()
+        && neighbors(dungeon, point).any(|(x, y)| !exploration.is_seen(x, y))
+}
+
+// The cheapest route over seen ground to the nearest frontier, `from`
+// excluded and left off the front; None once there is nowhere left to go
+pub fn route_to_unexplored(
+    dungeon: &Dungeon,
+    exploration: &Exploration,
+    from: Point,
+    options: &PathOptions,
+) -> Option<Vec<Point>> {
+    let mut cost = vec![vec![None; dungeon.width]; dungeon.height];
+    let mut came_from = vec![vec![None; dungeon.width]; dungeon.height];
+    let mut heap = BinaryHeap::new();
+    cost[from.1][from.0] = Some(0);
+    heap.push(Reverse((0, from)));
+
+    while let Some(Reverse((current_cost, point))) = heap.pop() {
+        if cost[point.1][point.0].is_some_and(|best| current_cost > best) {
+            continue;
+        }
+        if point != from && is_frontier(dungeon, exploration, options, point) {
+            let mut route = vec![point];
+            let mut current = point;
+            while let Some(previous) = came_from[current.1][current.0] {
+                if previous == from {
+                    break;
+                }
+                route.push(previous);
+                current = previous;
+            }
+            route.reverse();
+            return Some(route);
+        }
+        for next in neighbors(dungeon, point) {
+            if !exploration.is_seen(next.0, next.1) {
+                continue;
+            }
+            let Some(step) = options.step_cost(dungeon, next) else {
+                continue;
+            };
+            let next_cost = current_cost + step;
+            if cost[next.1][next.0].is_none_or(|best| next_cost < best) {
+                cost[next.1][next.0] = Some(next_cost);
+                came_from[next.1][next.0] = Some(point);
+                heap.push(Reverse((next_cost, next)));
+            }
+        }
+    }
+    None
+}
+
+// One auto-explore step from `from`, for front ends that move a tile at a time
+pub fn next_step(dungeon: &Dungeon, exploration: &Exploration, from: Point, options: &PathOptions) -> Option<Point> {
+    route_to_unexplored(dungeon, exploration, from, options).map(|route| route[0])
+}
+
+pub struct ExploreRun {
+    // Every tile stood on, the start first
+    pub route: Vec<Point>,
+    pub exploration: Exploration,
+}
+
+impl ExploreRun {
+    // Tiles reachable from the start that never came into view; none on a
+    // map whose every reachable corner can be found by walking it
+    pub fn missed(&self, dungeon: &Dungeon, options: &PathOptions) -> Vec<Point> {
+        let start = self.route[0];
+        let reachable = distance_map(dungeon, start, options);
+        let mut missed = Vec::new();
+        for (y, row) in reachable.iter().enumerate() {
+            for (x, cost) in row.iter().enumerate() {
+                if cost.is_some() && !self.exploration.is_seen(x, y) {
+                    missed.push((x, y));
+                }
+            }
+        }
+        missed
+    }
+}
+
+// Explores from `start` until nothing reachable is left. Sight reaches at
+// least the neighbouring tiles, which takes a radius of 2, or frontiers could
+// never be cleared.
+pub fn auto_explore(dungeon: &Dungeon, start: Point, sight_radius: usize, options: &PathOptions) -> ExploreRun {
+    let sight_radius = sight_radius.max(2);
+    let mut exploration = Exploration::for_dungeon(dungeon);
+    let mut route = vec![start];
+    exploration.look(dungeon, start, sight_radius);
+    let mut position = start;
+    while let Some(leg) = route_to_unexplored(dungeon, &exploration, position, options) {
+        for point in leg {
+            exploration.look(dungeon, point, sight_radius);
+            route.push(point);
+            position = point;
+        }
+    }
+    ExploreRun { route, exploration }
+}
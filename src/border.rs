@@ -0,0 +1,163 @@
+// Framing for the edge of the map, drawn once the cave or rooms and their
+// terrain are in place. It comes before the pins, connectivity, door and
+// clearance passes, so those still see the framed map and can mend anything
+// the frame cuts off. Frozen tiles are left as they are.
+use crate::door::{Door, DoorMaterial, DoorState, Facing};
+use crate::dungeon::{Dungeon, Point, Tile};
+use rand::Rng;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BorderStyle {
+    // The edge as generated
+    #[default]
+    None,
+    // A solid ring of wall
+    Wall,
+    // A wall ring with one wide opening off the map, as a cave entrance
+    CavernMouth,
+    // An outer and an inner wall with a walk between them, and a gated
+    // opening through the inner wall midway along each side
+    Fortress,
+    // A ring of water, for island maps
+    Water,
+}
+
+impl BorderStyle {
+    pub const ALL: [BorderStyle; 5] = [
+        BorderStyle::None,
+        BorderStyle::Wall,
+        BorderStyle::CavernMouth,
+        BorderStyle::Fortress,
+        BorderStyle::Water,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BorderStyle::None => "none",
+            BorderStyle::Wall => "wall",
+            BorderStyle::CavernMouth => "cavern_mouth",
+            BorderStyle::Fortress => "fortress",
+            BorderStyle::Water => "water",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|style| style.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BorderOptions {
+    pub style: BorderStyle,
+    // Thickness of the ring, or of each of a fortress's two walls
+    pub width: usize,
+}
+
+impl Default for BorderOptions {
+    fn default() -> Self {
+        Self {
+            style: BorderStyle::None,
+            width: 1,
+        }
+    }
+}
+
+// Tiles across a cavern mouth
+const MOUTH_WIDTH: usize = 3;
+
+// Each side of the map, by the way out of it, with the step into the map
+const SIDES: [(Facing, (i64, i64)); 4] =
+    [(Facing::North, (0, 1)), (Facing::East, (-1, 0)), (Facing::South, (0, -1)), (Facing::West, (1, 0))];
+
+impl Dungeon {
+    pub fn frame<R: Rng + ?Sized>(&mut self, options: &BorderOptions, rng: &mut R) {
+        let width = options.width.max(1);
+        match options.style {
+            BorderStyle::None => return,
+            BorderStyle::Wall => self.paint_ring(0..width, Tile::Wall),
+            BorderStyle::Water => self.paint_ring(0..width, Tile::Water),
+            BorderStyle::CavernMouth => {
+                self.paint_ring(0..width, Tile::Wall);
+                let (side, inward) = SIDES[rng.gen_range(0..SIDES.len())];
+                // Somewhere in the middle half of the side
+                let along = self.side_length(side);
+                let offset = rng.gen_range(along / 4..=(along * 3 / 4).saturating_sub(MOUTH_WIDTH).max(along / 4));
+                for across in 0..MOUTH_WIDTH {
+                    let start = self.side_point(side, (offset + across).min(along - 1));
+                    self.dig_inward(start, inward, width);
+                }
+            }
+            BorderStyle::Fortress => {
+                self.paint_ring(0..width, Tile::Wall);
+                self.paint_ring(width..width + 1, Tile::Floor);
+                self.paint_ring(width + 1..2 * width + 1, Tile::Wall);
+                for (side, inward) in SIDES {
+                    let gate = self.side_point(side, self.side_length(side) / 2);
+                    let (dx, dy) = inward;
+                    // The door sits in the inner wall's outer face, looking out
+                    let door = (
+                        (gate.0 as i64 + dx * (width + 1) as i64) as usize,
+                        (gate.1 as i64 + dy * (width + 1) as i64) as usize,
+                    );
+                    if self.edge_distance(door) == width + 1 && !self.frozen[door.1][door.0] {
+                        self.dig_inward(door, inward, 1);
+                        self.add_door(door.0, door.1, Door::new(DoorState::Closed, DoorMaterial::Iron, side));
+                    }
+                }
+            }
+        }
+        self.prune_layers();
+    }
+
+    // Tiles from the nearest map edge, 0 along the edge itself
+    fn edge_distance(&self, (x, y): Point) -> usize {
+        x.min(y).min(self.width - 1 - x).min(self.height - 1 - y)
+    }
+
+    fn paint_ring(&mut self, depths: std::ops::Range<usize>, tile: Tile) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if depths.contains(&self.edge_distance((x, y))) && !self.frozen[y][x] {
+                    self.tiles[y][x] = tile;
+                }
+            }
+        }
+    }
+
+    fn side_length(&self, side: Facing) -> usize {
+        match side {
+            Facing::North | Facing::South => self.width,
+            Facing::East | Facing::West => self.height,
+        }
+    }
+
+    // The edge tile `along` the given side, counted from its top or left end
+    fn side_point(&self, side: Facing, along: usize) -> Point {
+        match side {
+            Facing::North => (along, 0),
+            Facing::South => (along, self.height - 1),
+            Facing::West => (0, along),
+            Facing::East => (self.width - 1, along),
+        }
+    }
+
+    // Floor from `start` straight into the map until it opens onto ground
+    // at least `depth` tiles in from the edge, or reaches the middle
+    fn dig_inward(&mut self, start: Point, (dx, dy): (i64, i64), depth: usize) {
+        let (mut x, mut y) = (start.0 as i64, start.1 as i64);
+        let limit = self.width.min(self.height).div_ceil(2);
+        for _ in 0..limit {
+            let (tx, ty) = (x as usize, y as usize);
+            if self.edge_distance((tx, ty)) >= depth && self.tiles[ty][tx].is_walkable() && (tx, ty) != start {
+                break;
+            }
+            if !self.frozen[ty][tx] && self.tiles[ty][tx] == Tile::Wall {
+                self.tiles[ty][tx] = Tile::Floor;
+            }
+            (x, y) = (x + dx, y + dy);
+            if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,585 @@
+// One generation in a single `.dungeon` file, so the map, what spawned on it,
+// how it was made and a picture of it travel together. The file is a zip of
+// map.json, spawns.json, meta.json and thumbnail.png, stored without
+// compression so any unzip tool opens it.
+use crate::config::{invalid, Config};
+use crate::door::{Door, DoorMaterial, DoorState, Facing};
+use crate::dungeon::{Dungeon, Point, Tile};
+use crate::generator::Report;
+use crate::image::{self, Crc32};
+use crate::json::{self, Json};
+use crate::layers::{Entity, Feature, LayerKind};
+use crate::loot::{LootDrop, LootManifest};
+use crate::meta::MetaValue;
+use crate::world::WorldFrame;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Bumped whenever a reader of the old layout would get the new one wrong
+const FORMAT: u64 = 1;
+const THUMBNAIL_SCALE: usize = 4;
+
+// How the map was made
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BundleInfo {
+    pub seed: u64,
+    pub algorithm: String,
+    // Automaton steps run, whether the map had settled by then and the
+    // tiles the clearance repair dug, as in `Report`
+    pub steps: usize,
+    pub converged: bool,
+    pub carved: usize,
+    pub theme: String,
+}
+
+pub struct Bundle {
+    pub dungeon: Dungeon,
+    pub loot: LootManifest,
+    pub info: BundleInfo,
+    // PNG bytes, drawn in the theme named in `info`
+    pub thumbnail: Vec<u8>,
+}
+
+impl Bundle {
+    // Everything one run of `config` produced, loot rolled from its tables
+    pub fn new(dungeon: Dungeon, report: &Report, seed: u64, config: &Config) -> Self {
+        Self {
+            loot: dungeon.roll_loot(&config.loot, seed),
+            thumbnail: image::png_bytes(&dungeon, &config.theme, THUMBNAIL_SCALE),
+            info: BundleInfo {
+                seed,
+                algorithm: report.algorithm.to_string(),
+                steps: report.steps,
+                converged: report.converged,
+                carved: report.carved,
+                theme: config.theme.name.clone(),
+            },
+            dungeon,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        zip(&[
+            ("map.json", map_json(&self.dungeon).as_bytes()),
+            ("spawns.json", spawns_json(&self.dungeon, &self.loot).as_bytes()),
+            ("meta.json", meta_json(&self.dungeon, &self.info).as_bytes()),
+            ("thumbnail.png", &self.thumbnail),
+        ])
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let entries = unzip(bytes)?;
+        let entry = |name: &str| {
+            entries
+                .iter()
+                .find(|(entry, _)| entry == name)
+                .map(|(_, data)| data.as_slice())
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("bundle has no {}", name)))
+        };
+        let json = |name: &str| -> io::Result<Json> {
+            let text = std::str::from_utf8(entry(name)?)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("{} is not UTF-8", name)))?;
+            Json::parse(text)
+        };
+        let in_entry = |name: &'static str| move |error: io::Error| io::Error::new(error.kind(), format!("{}: {}", name, error));
+
+        let meta = json("meta.json").map_err(in_entry("meta.json"))?;
+        let info = read_info(&meta).map_err(in_entry("meta.json"))?;
+        let mut dungeon = json("map.json").and_then(|map| read_map(&map)).map_err(in_entry("map.json"))?;
+        // Bundles from before maps had a world frame sit at the default one
+        if let Some(world) = meta.get("world") {
+            dungeon.world = WorldFrame::from_json(world).map_err(in_entry("meta.json"))?;
+        }
+        let loot = json("spawns.json")
+            .and_then(|spawns| read_spawns(&spawns, &mut dungeon))
+            .map_err(in_entry("spawns.json"))?;
+        Ok(Self {
+            dungeon,
+            loot,
+            info,
+            thumbnail: entry("thumbnail.png")?.to_vec(),
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        Self::from_bytes(&bytes).map_err(|error| io::Error::new(error.kind(), format!("{}: {}", path.display(), error)))
+    }
+}
+
+// Whether `path` names a bundle rather than a plain map file
+pub fn is_bundle(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "dungeon")
+}
+
+// Layers list their entries in reading order, whatever order they are stored in
+fn sorted<T>(cells: impl Iterator<Item = (Point, T)>) -> Vec<(Point, T)> {
+    let mut cells: Vec<(Point, T)> = cells.collect();
+    cells.sort_by_key(|&((x, y), _)| (y, x));
+    cells
+}
+
+// One entry per line, so the files diff well
+fn list(items: &[String]) -> String {
+    if items.is_empty() {
+        "[]".to_string()
+    } else {
+        format!("[\n    {}\n  ]", items.join(",\n    "))
+    }
+}
+
+fn map_json(dungeon: &Dungeon) -> String {
+    let rows: Vec<String> = dungeon.layer_to_ascii(LayerKind::Terrain).lines().map(json::quote).collect();
+    let features: Vec<String> = sorted(dungeon.features.iter())
+        .into_iter()
+        .map(|((x, y), feature)| {
+            let what = match feature {
+                Feature::Door(door) => format!(
+                    "\"door\":{{\"state\":\"{}\",\"material\":\"{}\",\"facing\":\"{}\"}}",
+                    door.state.name(),
+                    door.material.name(),
+                    door.facing.name()
+                ),
+                Feature::Trap(name) => format!("\"trap\":{}", json::quote(name)),
+                Feature::Decoration(name) => format!("\"decoration\":{}", json::quote(name)),
+            };
+            format!("{{\"x\":{},\"y\":{},{}}}", x, y, what)
+        })
+        .collect();
+    let meta: Vec<String> = sorted(dungeon.meta.iter())
+        .into_iter()
+        .map(|((x, y), meta)| {
+            let values: Vec<String> = meta
+                .iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        MetaValue::Bool(value) => value.to_string(),
+                        MetaValue::Int(value) => value.to_string(),
+                        // Always with a point, so it reads back as a float
+                        MetaValue::Float(value) if value.is_finite() => format!("{:?}", value),
+                        MetaValue::Float(_) => "null".to_string(),
+                        MetaValue::Text(value) => json::quote(value),
+                    };
+                    format!("{}:{}", json::quote(key), value)
+                })
+                .collect();
+            format!("{{\"x\":{},\"y\":{},\"values\":{{{}}}}}", x, y, values.join(","))
+        })
+        .collect();
+    format!(
+        "{{\n  \"width\": {},\n  \"height\": {},\n  \"tiles\": {},\n  \"features\": {},\n  \"meta\": {}\n}}\n",
+        dungeon.width,
+        dungeon.height,
+        list(&rows),
+        list(&features),
+        list(&meta)
+    )
+}
+
+fn spawns_json(dungeon: &Dungeon, loot: &LootManifest) -> String {
+    let entities: Vec<String> = sorted(dungeon.entities.iter())
+        .into_iter()
+        .map(|((x, y), entity)| {
+            let what = match entity {
+                Entity::Entrance => "\"kind\":\"entrance\"".to_string(),
+                Entity::Exit => "\"kind\":\"exit\"".to_string(),
+                Entity::Monster(name) => format!("\"kind\":\"monster\",\"name\":{}", json::quote(name)),
+                Entity::Item(name) => format!("\"kind\":\"item\",\"name\":{}", json::quote(name)),
+            };
+            format!("{{\"x\":{},\"y\":{},{}}}", x, y, what)
+        })
+        .collect();
+    let drops: Vec<String> = loot
+        .drops
+        .iter()
+        .map(|drop| {
+            let contents: Vec<String> = drop
+                .contents
+                .iter()
+                .map(|(name, count)| format!("{}:{}", json::quote(name), count))
+                .collect();
+            format!(
+                "{{\"x\":{},\"y\":{},\"container\":{},\"contents\":{{{}}}}}",
+                drop.position.0,
+                drop.position.1,
+                json::quote(&drop.container),
+                contents.join(",")
+            )
+        })
+        .collect();
+    // Anchors come back from the meta layer; these are for the game to read
+    let anchors: Vec<String> = dungeon
+        .anchors()
+        .into_iter()
+        .map(|((x, y), anchor)| format!("{{\"x\":{},\"y\":{},\"kind\":{}}}", x, y, json::quote(anchor.name())))
+        .collect();
+    format!(
+        "{{\n  \"entities\": {},\n  \"loot\": {},\n  \"anchors\": {}\n}}\n",
+        list(&entities),
+        list(&drops),
+        list(&anchors)
+    )
+}
+
+fn meta_json(dungeon: &Dungeon, info: &BundleInfo) -> String {
+    format!(
+        "{{\n  \"format\": {},\n  \"seed\": {},\n  \"algorithm\": {},\n  \"steps\": {},\n  \"converged\": {},\n  \"carved\": {},\n  \"theme\": {},\n  \"world\": {},\n  \"content_hash\": \"{:016x}\",\n  \"fingerprint\": \"{}\"\n}}\n",
+        FORMAT,
+        info.seed,
+        json::quote(&info.algorithm),
+        info.steps,
+        info.converged,
+        info.carved,
+        json::quote(&info.theme),
+        dungeon.world.to_json(),
+        dungeon.content_hash(),
+        dungeon.fingerprint()
+    )
+}
+
+fn field<'a>(object: &'a Json, key: &str) -> io::Result<&'a Json> {
+    object.get(key).ok_or_else(|| invalid(key, "is missing"))
+}
+
+fn count(object: &Json, key: &str) -> io::Result<usize> {
+    field(object, key)?
+        .as_u64()
+        .map(|value| value as usize)
+        .ok_or_else(|| invalid(key, "must be a whole number"))
+}
+
+fn text<'a>(object: &'a Json, key: &str) -> io::Result<&'a str> {
+    field(object, key)?.as_str().ok_or_else(|| invalid(key, "must be a string"))
+}
+
+fn items<'a>(object: &'a Json, key: &str) -> io::Result<&'a [Json]> {
+    field(object, key)?.as_array().ok_or_else(|| invalid(key, "must be a list"))
+}
+
+fn point(object: &Json, dungeon: &Dungeon) -> io::Result<Point> {
+    let (x, y) = (count(object, "x")?, count(object, "y")?);
+    if x >= dungeon.width || y >= dungeon.height {
+        return Err(invalid("x", &format!("and 'y' put ({}, {}) off the map", x, y)));
+    }
+    Ok((x, y))
+}
+
+fn read_info(meta: &Json) -> io::Result<BundleInfo> {
+    let format = field(meta, "format")?.as_u64().ok_or_else(|| invalid("format", "must be a whole number"))?;
+    if format > FORMAT {
+        return Err(invalid("format", &format!("is {}, newer than this build reads ({})", format, FORMAT)));
+    }
+    Ok(BundleInfo {
+        seed: field(meta, "seed")?.as_u64().ok_or_else(|| invalid("seed", "must be a whole number"))?,
+        algorithm: text(meta, "algorithm")?.to_string(),
+        steps: count(meta, "steps")?,
+        converged: field(meta, "converged")?
+            .as_bool()
+            .ok_or_else(|| invalid("converged", "must be true or false"))?,
+        carved: count(meta, "carved")?,
+        theme: text(meta, "theme")?.to_string(),
+    })
+}
+
+fn read_map(map: &Json) -> io::Result<Dungeon> {
+    let (width, height) = (count(map, "width")?, count(map, "height")?);
+    let rows = items(map, "tiles")?;
+    if rows.len() != height {
+        return Err(invalid("tiles", &format!("has {} rows, not {}", rows.len(), height)));
+    }
+    // Every row is read before the map is made, so a size the rows don't
+    // bear out can't have it allocate more than the file holds
+    let mut tiles = Vec::with_capacity(height);
+    for (y, row) in rows.iter().enumerate() {
+        let row = row.as_str().ok_or_else(|| invalid("tiles", "must be a list of strings"))?;
+        let row = row
+            .chars()
+            .map(|glyph| {
+                Tile::from_glyph(glyph)
+                    .ok_or_else(|| invalid("tiles", &format!("has unknown terrain '{}' in row {}", glyph, y + 1)))
+            })
+            .collect::<io::Result<Vec<Tile>>>()?;
+        if row.len() != width {
+            return Err(invalid("tiles", &format!("row {} is not {} tiles wide", y + 1, width)));
+        }
+        tiles.push(row);
+    }
+    let mut dungeon = Dungeon::new(width, height);
+    dungeon.tiles = tiles;
+
+    for feature in items(map, "features")? {
+        let (x, y) = point(feature, &dungeon)?;
+        let feature = if let Some(door) = feature.get("door") {
+            let state = DoorState::parse(text(door, "state")?).ok_or_else(|| invalid("state", "is not a door state"))?;
+            let material =
+                DoorMaterial::parse(text(door, "material")?).ok_or_else(|| invalid("material", "is not a door material"))?;
+            let facing = Facing::parse(text(door, "facing")?).ok_or_else(|| invalid("facing", "is not a direction"))?;
+            Feature::Door(Door::new(state, material, facing))
+        } else if feature.get("trap").is_some() {
+            Feature::Trap(text(feature, "trap")?.to_string())
+        } else {
+            Feature::Decoration(text(feature, "decoration")?.to_string())
+        };
+        dungeon.features.set(x, y, feature);
+    }
+
+    for entry in items(map, "meta")? {
+        let (x, y) = point(entry, &dungeon)?;
+        let values = field(entry, "values")?
+            .as_object()
+            .ok_or_else(|| invalid("values", "must be an object"))?;
+        for (key, value) in values {
+            let value = match value {
+                Json::Bool(value) => MetaValue::Bool(*value),
+                Json::Number(number) if number.contains(['.', 'e', 'E']) => {
+                    MetaValue::Float(value.as_f64().ok_or_else(|| invalid(key, "is not a number"))?)
+                }
+                Json::Number(_) => MetaValue::Int(value.as_i64().ok_or_else(|| invalid(key, "is out of range"))?),
+                Json::String(text) => MetaValue::Text(text.clone()),
+                Json::Null => MetaValue::Float(f64::NAN),
+                _ => return Err(invalid(key, "must be a bool, number or string")),
+            };
+            dungeon.meta.entry(x, y).set(key.as_str(), value);
+        }
+    }
+    Ok(dungeon)
+}
+
+fn read_spawns(spawns: &Json, dungeon: &mut Dungeon) -> io::Result<LootManifest> {
+    for entity in items(spawns, "entities")? {
+        let (x, y) = point(entity, dungeon)?;
+        let entity = match text(entity, "kind")? {
+            "entrance" => Entity::Entrance,
+            "exit" => Entity::Exit,
+            "monster" => Entity::Monster(text(entity, "name")?.to_string()),
+            "item" => Entity::Item(text(entity, "name")?.to_string()),
+            kind => return Err(invalid("kind", &format!("'{}' is not an entity kind", kind))),
+        };
+        dungeon.entities.set(x, y, entity);
+    }
+
+    let mut loot = LootManifest::default();
+    for drop in items(spawns, "loot")? {
+        let contents = field(drop, "contents")?
+            .as_object()
+            .ok_or_else(|| invalid("contents", "must be an object"))?
+            .iter()
+            .map(|(name, count)| {
+                let count = count.as_u64().ok_or_else(|| invalid(name, "must be a whole number"))?;
+                Ok((name.clone(), count as usize))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        loot.drops.push(LootDrop {
+            position: point(drop, dungeon)?,
+            container: text(drop, "container")?.to_string(),
+            contents,
+        });
+    }
+    Ok(loot)
+}
+
+// 1980-01-01, the earliest date a zip can hold; a fixed stamp keeps the
+// same map byte for byte the same bundle
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+fn zip(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut directory = Vec::new();
+    for &(name, data) in entries {
+        let mut crc = Crc32::new();
+        crc.update(data);
+        let crc = crc.finish();
+        let offset = out.len() as u32;
+
+        // Version 2.0, no flags, stored, midnight
+        let mut common = Vec::new();
+        for half in [20u16, 0, 0, 0, DOS_DATE] {
+            common.extend_from_slice(&half.to_le_bytes());
+        }
+        common.extend_from_slice(&crc.to_le_bytes());
+        common.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        // No extra field
+        common.extend_from_slice(&0u16.to_le_bytes());
+
+        out.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        out.extend_from_slice(&common);
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(data);
+
+        directory.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        // Made by version 2.0
+        directory.extend_from_slice(&20u16.to_le_bytes());
+        directory.extend_from_slice(&common);
+        // No comment, disk 0, no attributes
+        directory.extend_from_slice(&[0; 10]);
+        directory.extend_from_slice(&offset.to_le_bytes());
+        directory.extend_from_slice(name.as_bytes());
+    }
+    let directory_offset = out.len() as u32;
+    out.extend_from_slice(&directory);
+
+    out.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+    out.extend_from_slice(&directory_offset.to_le_bytes());
+    // No comment
+    out.extend_from_slice(&0u16.to_le_bytes());
+    out
+}
+
+fn not_a_bundle(why: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("not a dungeon bundle: {}", why))
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> io::Result<u16> {
+    let slice = bytes.get(at..at + 2).ok_or_else(|| not_a_bundle("it is cut short"))?;
+    Ok(u16::from_le_bytes([slice[0], slice[1]]))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> io::Result<u32> {
+    let slice = bytes.get(at..at + 4).ok_or_else(|| not_a_bundle("it is cut short"))?;
+    Ok(u32::from_le_bytes([slice[0], slice[1], slice[2], slice[3]]))
+}
+
+// Every entry of a zip by way of its central directory. Only stored
+// entries are read, which is all `zip` writes; a bundle repacked with
+// compression is turned away rather than misread.
+fn unzip(bytes: &[u8]) -> io::Result<Vec<(String, Vec<u8>)>> {
+    // The end record sits last, behind a comment of up to 64 KiB
+    let end = (0..bytes.len().saturating_sub(21))
+        .rev()
+        .take(65536 + 22)
+        .find(|&at| bytes[at..].starts_with(&0x0605_4b50u32.to_le_bytes()))
+        .ok_or_else(|| not_a_bundle("no zip directory"))?;
+    let entries = u16_at(bytes, end + 10)? as usize;
+    let mut at = u32_at(bytes, end + 16)? as usize;
+
+    let mut out = Vec::with_capacity(entries);
+    for _ in 0..entries {
+        if u32_at(bytes, at)? != 0x0201_4b50 {
+            return Err(not_a_bundle("damaged zip directory"));
+        }
+        let method = u16_at(bytes, at + 10)?;
+        let crc = u32_at(bytes, at + 16)?;
+        let size = u32_at(bytes, at + 20)? as usize;
+        let name_len = u16_at(bytes, at + 28)? as usize;
+        let skip = name_len + u16_at(bytes, at + 30)? as usize + u16_at(bytes, at + 32)? as usize;
+        let local = u32_at(bytes, at + 42)? as usize;
+        let name = bytes.get(at + 46..at + 46 + name_len).ok_or_else(|| not_a_bundle("it is cut short"))?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        at += 46 + skip;
+
+        if method != 0 {
+            return Err(not_a_bundle(&format!("{} is compressed", name)));
+        }
+        let start = local + 30 + u16_at(bytes, local + 26)? as usize + u16_at(bytes, local + 28)? as usize;
+        let data = bytes.get(start..start + size).ok_or_else(|| not_a_bundle("it is cut short"))?;
+        let mut check = Crc32::new();
+        check.update(data);
+        if check.finish() != crc {
+            return Err(not_a_bundle(&format!("{} is damaged", name)));
+        }
+        out.push((name, data.to_vec()));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate_reported;
+    use crate::loot::{Loot, LootTable};
+    use crate::world::NorthEdge;
+
+    // A generated map with a door, a trap, a chest to roll, a note and a turned
+    // world frame, so every entry has something in it
+    fn bundle() -> Bundle {
+        let config = Config {
+            loot: Loot {
+                tables: vec![(
+                    "chest".to_string(),
+                    LootTable {
+                        rolls: (1, 3),
+                        items: vec![("gold".to_string(), 3), ("gem".to_string(), 1)],
+                    },
+                )],
+            },
+            ..Config::default()
+        };
+        let (mut dungeon, report) = generate_reported(&config, 12);
+        let mut open = (0..dungeon.height)
+            .flat_map(|y| (0..dungeon.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| dungeon.tiles[y][x] == Tile::Floor && dungeon.entities.get(x, y).is_none());
+        let (chest, trap, door) = (open.next().unwrap(), open.next().unwrap(), open.next().unwrap());
+        dungeon.entities.set(chest.0, chest.1, Entity::Item("chest".to_string()));
+        dungeon.features.set(trap.0, trap.1, Feature::Trap("spikes".to_string()));
+        dungeon.add_door(door.0, door.1, Door::new(DoorState::Locked, DoorMaterial::Iron, Facing::North));
+        dungeon.set_meta(chest.0, chest.1, "note", "keep out");
+        dungeon.set_world(WorldFrame {
+            north: NorthEdge::Left,
+            tile_size: 2.5,
+            origin: (-10.0, 4.0),
+        });
+        Bundle::new(dungeon, &report, 12, &config)
+    }
+
+    #[test]
+    fn a_bundle_reads_back_as_it_was_written() {
+        let written = bundle();
+        assert_eq!(written.loot.drops.len(), 1);
+        let read = Bundle::from_bytes(&written.to_bytes()).unwrap();
+        assert_eq!(read.dungeon.to_ascii(), written.dungeon.to_ascii());
+        assert_eq!(read.dungeon.content_hash(), written.dungeon.content_hash());
+        assert_eq!(read.dungeon.world(), written.dungeon.world());
+        assert_eq!(read.loot, written.loot);
+        assert_eq!(read.info, written.info);
+        assert_eq!(read.thumbnail, written.thumbnail);
+    }
+
+    #[test]
+    fn corrupt_or_cut_short_bundles_are_refused() {
+        let bytes = bundle().to_bytes();
+        assert!(Bundle::from_bytes(b"not a zip at all").is_err());
+        for length in (0..bytes.len()).step_by(61) {
+            let error = Bundle::from_bytes(&bytes[..length]).err().expect("a cut short bundle read");
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{} bytes: {}", length, error);
+        }
+
+        // A flipped byte in the map fails its checksum
+        let at = bytes.windows(4).position(|window| window == b"\"til").expect("map.json holds tiles");
+        let mut corrupt = bytes.clone();
+        corrupt[at + 1] ^= 0x20;
+        let error = Bundle::from_bytes(&corrupt).err().expect("a corrupt bundle read");
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("map.json is damaged"), "{}", error);
+    }
+
+    #[test]
+    fn a_size_the_rows_dont_bear_out_is_refused_before_allocating() {
+        let written = bundle();
+        let (width, height) = (written.dungeon.width, written.dungeon.height);
+        let sizes = [(1 << 40, height), (1 << 40, 1 << 40), (width + 1, height), (width, height - 1)];
+        for (bogus_width, bogus_height) in sizes {
+            let map = map_json(&written.dungeon)
+                .replacen(&format!("\"width\": {},", width), &format!("\"width\": {},", bogus_width), 1)
+                .replacen(&format!("\"height\": {},", height), &format!("\"height\": {},", bogus_height), 1);
+            let entries = unzip(&written.to_bytes()).unwrap();
+            let entries: Vec<(&str, &[u8])> = entries
+                .iter()
+                .map(|(name, data)| (name.as_str(), if name == "map.json" { map.as_bytes() } else { data }))
+                .collect();
+            let error = Bundle::from_bytes(&zip(&entries)).err().expect("a misshapen map read");
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+            assert!(error.to_string().starts_with("map.json: "), "{}x{}: {}", bogus_width, bogus_height, error);
+        }
+    }
+}
@@ -0,0 +1,313 @@
+// Content hashes for deduplicating level pools and checking that clients
+// built the same map, plus a canonical orientation so the same layout
+// rotated or mirrored hashes alike. The hash is FNV-1a over a fixed byte
+// encoding of every layer: stable across machines and releases, but not
+// meant to stand up to someone forging collisions on purpose.
+use crate::door::Facing;
+use crate::dungeon::{Dungeon, Point};
+use crate::layers::{Entity, Feature, Layer};
+use crate::meta::MetaValue;
+use crate::world::{NorthEdge, WorldFrame};
+
+// The eight ways to turn or mirror a rectangle onto itself
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Symmetry {
+    Identity,
+    // Clockwise
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    // Left and right swap
+    FlipHorizontal,
+    // Top and bottom swap
+    FlipVertical,
+    // Across the top-left to bottom-right diagonal
+    Transpose,
+    // Across the other diagonal
+    AntiTranspose,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipHorizontal,
+        Symmetry::FlipVertical,
+        Symmetry::Transpose,
+        Symmetry::AntiTranspose,
+    ];
+
+    // Whether width and height trade places
+    fn swaps_axes(self) -> bool {
+        matches!(
+            self,
+            Symmetry::Rotate90 | Symmetry::Rotate270 | Symmetry::Transpose | Symmetry::AntiTranspose
+        )
+    }
+
+    // Where a tile of a `width` x `height` map ends up
+    pub fn apply(self, (x, y): Point, width: usize, height: usize) -> Point {
+        let (right, bottom) = (width - 1 - x, height - 1 - y);
+        match self {
+            Symmetry::Identity => (x, y),
+            Symmetry::Rotate90 => (bottom, x),
+            Symmetry::Rotate180 => (right, bottom),
+            Symmetry::Rotate270 => (y, right),
+            Symmetry::FlipHorizontal => (right, y),
+            Symmetry::FlipVertical => (x, bottom),
+            Symmetry::Transpose => (y, x),
+            Symmetry::AntiTranspose => (bottom, right),
+        }
+    }
+
+    // Doors keep facing along the passage they block
+    fn facing(self, facing: Facing) -> Facing {
+        let (dx, dy) = match facing {
+            Facing::North => (0, -1),
+            Facing::East => (1, 0),
+            Facing::South => (0, 1),
+            Facing::West => (-1, 0),
+        };
+        let (dx, dy) = match self {
+            Symmetry::Identity => (dx, dy),
+            Symmetry::Rotate90 => (-dy, dx),
+            Symmetry::Rotate180 => (-dx, -dy),
+            Symmetry::Rotate270 => (dy, -dx),
+            Symmetry::FlipHorizontal => (-dx, dy),
+            Symmetry::FlipVertical => (dx, -dy),
+            Symmetry::Transpose => (dy, dx),
+            Symmetry::AntiTranspose => (-dy, -dx),
+        };
+        match (dx, dy) {
+            (0, -1) => Facing::North,
+            (1, 0) => Facing::East,
+            (0, 1) => Facing::South,
+            _ => Facing::West,
+        }
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+// Little-endian throughout, with lengths in front of anything variable, so
+// no two different maps encode the same
+struct Encoder(Vec<u8>);
+
+impl Encoder {
+    fn number(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn byte(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    fn text(&mut self, text: &str) {
+        self.number(text.len() as u64);
+        self.0.extend_from_slice(text.as_bytes());
+    }
+
+    // Entries in reading order, whatever order the layer stores them in
+    fn layer<T>(&mut self, layer: &Layer<T>, mut value: impl FnMut(&mut Self, &T)) {
+        let mut cells: Vec<(Point, &T)> = layer.iter().collect();
+        cells.sort_by_key(|&((x, y), _)| (y, x));
+        self.number(cells.len() as u64);
+        for ((x, y), cell) in cells {
+            self.number(x as u64);
+            self.number(y as u64);
+            value(self, cell);
+        }
+    }
+}
+
+impl Dungeon {
+    // Everything on the map but the frozen mask, which only matters while
+    // it is being generated
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Encoder(Vec::new());
+        out.number(self.width as u64);
+        out.number(self.height as u64);
+        for row in &self.tiles {
+            out.0.extend(row.iter().map(|&tile| tile.code()));
+        }
+        out.layer(&self.features, |out, feature| match feature {
+            Feature::Door(door) => {
+                out.byte(0);
+                out.byte(door.state as u8);
+                out.byte(door.material as u8);
+                out.byte(door.facing as u8);
+            }
+            Feature::Trap(name) => {
+                out.byte(1);
+                out.text(name);
+            }
+            Feature::Decoration(name) => {
+                out.byte(2);
+                out.text(name);
+            }
+        });
+        out.layer(&self.entities, |out, entity| match entity {
+            Entity::Entrance => out.byte(0),
+            Entity::Exit => out.byte(1),
+            Entity::Monster(name) => {
+                out.byte(2);
+                out.text(name);
+            }
+            Entity::Item(name) => {
+                out.byte(3);
+                out.text(name);
+            }
+        });
+        out.layer(&self.meta, |out, meta| {
+            out.number(meta.len() as u64);
+            for (key, value) in meta.iter() {
+                out.text(key);
+                match value {
+                    MetaValue::Bool(value) => {
+                        out.byte(0);
+                        out.byte(*value as u8);
+                    }
+                    MetaValue::Int(value) => {
+                        out.byte(1);
+                        out.number(*value as u64);
+                    }
+                    MetaValue::Float(value) => {
+                        out.byte(2);
+                        out.number(value.to_bits());
+                    }
+                    MetaValue::Text(value) => {
+                        out.byte(3);
+                        out.text(value);
+                    }
+                }
+            }
+        });
+        out.0
+    }
+
+    // Changes to any tile, feature, entity or annotation change the hash;
+    // so does turning the map
+    pub fn content_hash(&self) -> u64 {
+        fnv1a(&self.encode())
+    }
+
+    // The same for every rotation and reflection of the map
+    pub fn canonical_hash(&self) -> u64 {
+        let (canonical, _) = self.canonical();
+        canonical.content_hash()
+    }
+
+    // A turned or mirrored copy, doors and all
+    pub fn transformed(&self, symmetry: Symmetry) -> Dungeon {
+        let (width, height) = if symmetry.swaps_axes() {
+            (self.height, self.width)
+        } else {
+            (self.width, self.height)
+        };
+        let mut out = Dungeon::new(width, height);
+        let at = |point: Point| symmetry.apply(point, self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (nx, ny) = at((x, y));
+                out.tiles[ny][nx] = self.tiles[y][x];
+                out.frozen[ny][nx] = self.frozen[y][x];
+            }
+        }
+        for (point, feature) in self.features.iter() {
+            let mut feature = feature.clone();
+            if let Feature::Door(door) = &mut feature {
+                door.facing = symmetry.facing(door.facing);
+            }
+            let (x, y) = at(point);
+            out.features.set(x, y, feature);
+        }
+        for (point, entity) in self.entities.iter() {
+            let (x, y) = at(point);
+            out.entities.set(x, y, entity.clone());
+        }
+        for (point, meta) in self.meta.iter() {
+            let (x, y) = at(point);
+            out.meta.set(x, y, meta.clone());
+        }
+        // North stays with the edge of the map it was on, wherever that edge
+        // is turned to
+        let edge = symmetry.facing(Facing::ALL[self.world.north.quarter_turns()]);
+        out.world = WorldFrame { north: NorthEdge::ALL[edge as usize], ..self.world };
+        out
+    }
+
+    // Whichever of the eight orientations encodes smallest, and the
+    // symmetry that turns this map into it. Ties go to the earlier
+    // symmetry in `Symmetry::ALL`, so a map that already is canonical comes
+    // back as the identity.
+    pub fn canonical(&self) -> (Dungeon, Symmetry) {
+        Symmetry::ALL
+            .into_iter()
+            .map(|symmetry| {
+                let dungeon = self.transformed(symmetry);
+                (dungeon.encode(), dungeon, symmetry)
+            })
+            .min_by(|a, b| a.0.cmp(&b.0))
+            .map(|(_, dungeon, symmetry)| (dungeon, symmetry))
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::door::{Door, DoorMaterial, DoorState};
+    use crate::fixtures;
+
+    // The two rooms with a door in the corridor, a trap and a note, so every
+    // layer has something to move
+    fn furnished() -> Dungeon {
+        let mut dungeon = fixtures::two_rooms();
+        dungeon.add_door(7, 3, Door::new(DoorState::Closed, DoorMaterial::Wood, Facing::East));
+        dungeon.features.set(3, 1, Feature::Trap("spikes".to_string()));
+        dungeon.set_meta(12, 4, "note", "way out");
+        dungeon
+    }
+
+    #[test]
+    fn every_symmetry_hashes_alike_and_turns_doors_with_the_corridor() {
+        let dungeon = furnished();
+        let hash = dungeon.canonical_hash();
+        let mut seen = Vec::new();
+        for symmetry in Symmetry::ALL {
+            let turned = dungeon.transformed(symmetry);
+            assert_eq!(turned.canonical_hash(), hash, "{:?}", symmetry);
+            seen.push(turned.content_hash());
+
+            // The door still faces along the corridor, walled on both sides
+            let (x, y) = symmetry.apply((7, 3), dungeon.width, dungeon.height);
+            let door = turned.door(x, y).unwrap();
+            let (dx, dy) = match door.facing {
+                Facing::North | Facing::South => (0, 1),
+                Facing::East | Facing::West => (1, 0),
+            };
+            let open = |dx: i32, dy: i32| turned.tiles[(y as i32 + dy) as usize][(x as i32 + dx) as usize].is_walkable();
+            assert!(open(dx, dy) && open(-dx, -dy), "{:?} turned the door to {:?}", symmetry, door.facing);
+            assert!(!open(dy, dx) && !open(-dy, -dx), "{:?} turned the door to {:?}", symmetry, door.facing);
+        }
+        seen.sort();
+        seen.dedup();
+        assert_eq!(seen.len(), 8, "the fixture has no symmetry of its own");
+
+        // A quarter turn four times over is no turn at all
+        let mut turned = dungeon.clone();
+        for _ in 0..4 {
+            turned = turned.transformed(Symmetry::Rotate90);
+        }
+        assert_eq!(turned.content_hash(), dungeon.content_hash());
+    }
+}
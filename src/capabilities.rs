@@ -0,0 +1,52 @@
+// What the terminal can be trusted with, read once from the environment
+use std::env;
+use std::ffi::OsStr;
+use std::sync::OnceLock;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    // Coloured glyphs; off when NO_COLOR is set (https://no-color.org)
+    pub color: bool,
+    // Cursor moves, screen clears, raw mode and anything beyond plain ASCII;
+    // off on TERM=dumb, where the map is printed rather than drawn
+    pub cursor: bool,
+    // 24-bit color, which terminals advertise with COLORTERM=truecolor or
+    // 24bit; needed for anything finer than the theme's named colors
+    pub truecolor: bool,
+    // OSC 8 hyperlinks, which terminals that don't know them drop, showing
+    // just the text; so anything that isn't dumb gets them
+    pub hyperlinks: bool,
+}
+
+impl Capabilities {
+    pub const FULL: Self = Self {
+        color: true,
+        cursor: true,
+        truecolor: true,
+        hyperlinks: true,
+    };
+
+    // NO_COLOR only counts when it isn't empty. It asks for no colour and
+    // nothing more, so the explorer still runs, just in monochrome.
+    pub fn from_env(no_color: Option<&OsStr>, term: Option<&OsStr>, colorterm: Option<&OsStr>) -> Self {
+        let dumb = term.is_some_and(|term| term == "dumb");
+        let color = !dumb && no_color.is_none_or(OsStr::is_empty);
+        Self {
+            color,
+            cursor: !dumb,
+            truecolor: color && colorterm.is_some_and(|value| value == "truecolor" || value == "24bit"),
+            hyperlinks: !dumb,
+        }
+    }
+
+    pub fn detect() -> Self {
+        static DETECTED: OnceLock<Capabilities> = OnceLock::new();
+        *DETECTED.get_or_init(|| {
+            Self::from_env(
+                env::var_os("NO_COLOR").as_deref(),
+                env::var_os("TERM").as_deref(),
+                env::var_os("COLORTERM").as_deref(),
+            )
+        })
+    }
+}
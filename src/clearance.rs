@@ -0,0 +1,248 @@
+use crate::dungeon::{Dungeon, Point, Tile};
+use crate::layers::Entity;
+use crate::pathfinding::entrance_and_exit;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Walking into a footprint that overlaps rock costs this much per wall tile,
+// so repairs follow whichever route needs the least digging
+const CARVE_COST: usize = 8;
+
+// Counts of blocked tiles over any rectangle in constant time
+struct BlockedCounts {
+    width: usize,
+    sums: Vec<usize>,
+}
+
+impl BlockedCounts {
+    fn new(dungeon: &Dungeon) -> Self {
+        Self::counting(dungeon, |x, y| !dungeon.tiles[y][x].is_walkable())
+    }
+
+    fn counting(dungeon: &Dungeon, blocked: impl Fn(usize, usize) -> bool) -> Self {
+        let width = dungeon.width + 1;
+        let mut sums = vec![0; width * (dungeon.height + 1)];
+        for y in 0..dungeon.height {
+            for x in 0..dungeon.width {
+                let blocked = blocked(x, y) as usize;
+                sums[(y + 1) * width + x + 1] =
+                    blocked + sums[y * width + x + 1] + sums[(y + 1) * width + x] - sums[y * width + x];
+            }
+        }
+        Self { width, sums }
+    }
+
+    fn count(&self, (x, y): Point, (w, h): (usize, usize)) -> usize {
+        let at = |x: usize, y: usize| self.sums[y * self.width + x];
+        at(x + w, y + h) + at(x, y) - at(x + w, y) - at(x, y + h)
+    }
+}
+
+impl Dungeon {
+    // Whether a `width` x `height` footprint with its top-left corner at
+    // (x, y) lies entirely on walkable tiles
+    pub fn fits(&self, x: usize, y: usize, width: usize, height: usize) -> bool {
+        x + width <= self.width
+            && y + height <= self.height
+            && (y..y + height).all(|y| (x..x + width).all(|x| self.tiles[y][x].is_walkable()))
+    }
+
+    // Every top-left corner where a `width` x `height` unit can stand: the
+    // floor mask eroded by the unit's footprint
+    pub fn footprint_mask(&self, width: usize, height: usize) -> Vec<Vec<bool>> {
+        let mut mask = vec![vec![false; self.width]; self.height];
+        if width == 0 || height == 0 || width > self.width || height > self.height {
+            return mask;
+        }
+        let blocked = BlockedCounts::new(self);
+        for (y, row) in mask.iter_mut().enumerate().take(self.height - height + 1) {
+            for (x, fits) in row.iter_mut().enumerate().take(self.width - width + 1) {
+                *fits = blocked.count((x, y), (width, height)) == 0;
+            }
+        }
+        mask
+    }
+
+    // Tiles some part of a `width` x `height` unit can occupy; floor outside
+    // this is too cramped for it
+    pub fn footprint_coverage(&self, width: usize, height: usize) -> Vec<Vec<bool>> {
+        let mask = self.footprint_mask(width, height);
+        let mut coverage = vec![vec![false; self.width]; self.height];
+        for (y, row) in mask.iter().enumerate() {
+            for (x, _) in row.iter().enumerate().filter(|(_, &fits)| fits) {
+                for covered in &mut coverage[y..y + height] {
+                    covered[x..x + width].fill(true);
+                }
+            }
+        }
+        coverage
+    }
+
+    // '#' for walls, 'o' where the unit can stand with its top-left corner,
+    // '+' for the rest of the floor it can cover and '.' where it can't go
+    pub fn footprint_to_ascii(&self, width: usize, height: usize) -> String {
+        let mask = self.footprint_mask(width, height);
+        let coverage = self.footprint_coverage(width, height);
+        let mut out = String::with_capacity((self.width + 1) * self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                out.push(if !self.tiles[y][x].is_walkable() {
+                    '#'
+                } else if mask[y][x] {
+                    'o'
+                } else if coverage[y][x] {
+                    '+'
+                } else {
+                    '.'
+                });
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn write_footprint_map(&self, width: usize, height: usize, path: &Path) -> io::Result<()> {
+        fs::write(path, self.footprint_to_ascii(width, height))
+    }
+
+    // Top-left corner of the footprint covering `point`, kept on the map:
+    // centred on it where that misses frozen rock, else the nearest that
+    // does, or None if every footprint over it overlaps some
+    fn anchor_for(&self, (x, y): Point, (w, h): (usize, usize), locked: &BlockedCounts) -> Option<Point> {
+        let centred = (
+            x.saturating_sub((w - 1) / 2).min(self.width - w),
+            y.saturating_sub((h - 1) / 2).min(self.height - h),
+        );
+        let columns = x.saturating_sub(w - 1)..=x.min(self.width - w);
+        let rows = y.saturating_sub(h - 1)..=y.min(self.height - h);
+        rows.flat_map(|ay| columns.clone().map(move |ax| (ax, ay)))
+            .filter(|&anchor| locked.count(anchor, (w, h)) == 0)
+            .min_by_key(|&(ax, ay)| (ax.abs_diff(centred.0) + ay.abs_diff(centred.1), ay, ax))
+    }
+
+    // Footprint positions for a unit of the given size walking from entrance to
+    // exit, tunnelling through as little rock as it can; the total number of
+    // wall tiles it would have to dig through comes with them
+    fn clearance_route(&self, size: (usize, usize)) -> Option<(Vec<Point>, usize)> {
+        let (w, h) = size;
+        if w == 0 || h == 0 || w > self.width || h > self.height {
+            return None;
+        }
+        let (entrance, exit) = entrance_and_exit(self)?;
+        let blocked = BlockedCounts::new(self);
+        // Frozen rock can't be dug, so footprints overlapping it are off
+        // limits, the route's ends included
+        let locked = BlockedCounts::counting(self, |x, y| self.frozen[y][x] && !self.tiles[y][x].is_walkable());
+        let start = self.anchor_for(entrance, size, &locked)?;
+        let goal = self.anchor_for(exit, size, &locked)?;
+        let (columns, rows) = (self.width - w + 1, self.height - h + 1);
+
+        let mut cost = vec![vec![usize::MAX; columns]; rows];
+        let mut came_from = vec![vec![None; columns]; rows];
+        let mut heap = BinaryHeap::new();
+        cost[start.1][start.0] = blocked.count(start, size) * CARVE_COST;
+        heap.push(Reverse((cost[start.1][start.0], start)));
+
+        while let Some(Reverse((current, point))) = heap.pop() {
+            if point == goal {
+                break;
+            }
+            if current > cost[point.1][point.0] {
+                continue;
+            }
+            let (x, y) = point;
+            let steps = [
+                (x > 0).then(|| (x - 1, y)),
+                (y > 0).then(|| (x, y - 1)),
+                (x + 1 < columns).then_some((x + 1, y)),
+                (y + 1 < rows).then_some((x, y + 1)),
+            ];
+            for next in steps.into_iter().flatten() {
+                if locked.count(next, size) > 0 {
+                    continue;
+                }
+                let next_cost = current + 1 + blocked.count(next, size) * CARVE_COST;
+                if next_cost < cost[next.1][next.0] {
+                    cost[next.1][next.0] = next_cost;
+                    came_from[next.1][next.0] = Some(point);
+                    heap.push(Reverse((next_cost, next)));
+                }
+            }
+        }
+
+        let mut route = vec![goal];
+        let mut current = goal;
+        while current != start {
+            current = came_from[current.1][current.0]?;
+            route.push(current);
+        }
+        route.reverse();
+        let digging = route.iter().map(|&anchor| blocked.count(anchor, size)).sum();
+        Some((route, digging))
+    }
+
+    // Whether a unit `width` x `height` tiles big can get from the entrance
+    // to the exit without digging
+    pub fn has_clearance(&self, width: usize, height: usize) -> bool {
+        self.clearance_route((width, height))
+            .is_some_and(|(_, digging)| digging == 0)
+    }
+
+    // Widens passages until a `width` x `height` unit can walk the critical
+    // path from entrance to exit, choosing the route that needs the least
+    // digging. The ends get marked with Entrance and Exit entities so they
+    // stay put now the map has changed around them. Returns how many wall
+    // tiles were opened.
+    pub fn ensure_clearance(&mut self, width: usize, height: usize) -> usize {
+        let Some((route, _)) = self.clearance_route((width, height)) else {
+            return 0;
+        };
+        if let Some((entrance, exit)) = entrance_and_exit(self) {
+            for (point, entity) in [(entrance, Entity::Entrance), (exit, Entity::Exit)] {
+                if !self.entities.contains(point.0, point.1) {
+                    self.entities.set(point.0, point.1, entity);
+                }
+            }
+        }
+        let mut carved = 0;
+        for (ax, ay) in route {
+            for y in ay..ay + height {
+                for x in ax..ax + width {
+                    if !self.tiles[y][x].is_walkable() {
+                        self.tiles[y][x] = Tile::Floor;
+                        carved += 1;
+                    }
+                }
+            }
+        }
+        carved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frozen_rock_by_the_entrance_stays_put() {
+        let mut dungeon = Dungeon::from_ascii("#########\n#########\n#<.....>#\n#########\n#########\n").unwrap();
+        // The footprint centred on the entrance would take in this rock
+        dungeon.freeze_rect(0, 3, 4, 1);
+        let carved = dungeon.ensure_clearance(2, 2);
+        assert!(carved > 0);
+        assert!(dungeon.has_clearance(2, 2));
+        for x in 0..4 {
+            assert_eq!(dungeon.tiles[3][x], Tile::Wall, "frozen rock at ({}, 3) was dug", x);
+        }
+
+        // With the rock above frozen as well, no footprint fits at the entrance
+        let mut walled = Dungeon::from_ascii("#########\n#########\n#<.....>#\n#########\n#########\n").unwrap();
+        walled.freeze_rect(0, 3, 4, 1);
+        walled.freeze_rect(0, 1, 4, 1);
+        assert_eq!(walled.ensure_clearance(2, 2), 0);
+        assert_eq!(walled.to_ascii(), "#########\n#########\n#<.....>#\n#########\n#########\n");
+    }
+}
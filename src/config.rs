@@ -0,0 +1,724 @@
+use crate::archipelago::{ArchipelagoOptions, Connectors};
+use crate::border::{BorderOptions, BorderStyle};
+use crate::connectivity::Connectivity;
+use crate::contracts::Contract;
+use crate::counter::RngMode;
+use crate::dungeon::Rules;
+use crate::ecology::EcologyOptions;
+use crate::erosion::ErosionOptions;
+use crate::fill::Fill;
+use crate::generator::{GenerationAlgorithm, Stop};
+use crate::hardness::{HardnessOptions, HardnessSource};
+use crate::history::HistoryOptions;
+use crate::keys::KeyBindings;
+use crate::layout::{LayoutOptions, Objective};
+use crate::locks::{self, LockOptions};
+use crate::loot::Loot;
+use crate::pathfinding::MovementCosts;
+use crate::percolation;
+use crate::pins::Pin;
+use crate::rooms::{RoomOptions, RoomShape};
+use crate::roughness::RoughnessOptions;
+use crate::population::SpawnTable;
+use crate::preset::Preset;
+use crate::terrain::TerrainOptions;
+use crate::theme::Theme;
+use crate::veins::VeinOptions;
+use crate::world::WorldFrame;
+use crate::toml::{self, Table, Value};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Everything that shapes a generated dungeon. Loaded from TOML, e.g.
+//
+//     preset = "crypt"
+//     seed = 1234
+//     width = 120
+//     algorithm = "rooms"
+//     connectivity = "spanning"
+//     loop_tunnels = 2
+//     fill = "blue_noise"
+//     doors = 6
+//     pillar_span = 9
+//
+//     [rooms]
+//     count = 16
+//     shapes = { rectangle = 3, circle = 1, cavern = 2 }
+//     merge = 0.5
+//
+//     [erosion]
+//     iterations = 2
+//
+//     [terrain]
+//     water = 0.08
+//
+//     [border]
+//     style = "fortress"
+//     width = 2
+//
+//     [locks]
+//     keys = 3
+//     depth = 2
+//
+//     [history]
+//     eras = 4
+//
+//     [hardness]
+//     source = "noise"
+//     max = 6
+//
+//     [veins]
+//     count = 12
+//     resources = { iron = 3, gold = 1 }
+//
+//     [ecology]
+//     nests = 3
+//     dens = 1
+//
+//     [layout]
+//     attempts = 8
+//     objective = "backtracking"
+//
+//     [costs]
+//     water = 4
+//
+//     [spawns]
+//     trap_density = 0.004
+//
+//     [spawns.monsters]
+//     ghoul = 3
+//
+//     [spawns.items]
+//     chest = 1
+//
+//     [spawns.traps]
+//     pit = 2
+//     dart = 1
+//
+//     [loot.chest]
+//     rolls = [1, 3]
+//     items = { gold = 6, potion = 3, sword = 1 }
+//
+//     [keys]
+//     new_seed = "n"
+//
+//     [world]
+//     north = "right"
+//     tile_size = 2.0
+//     origin = [160.0, 0.0]
+//
+//     [[pin]]
+//     kind = "room"
+//     x = 10
+//     y = 20
+//     radius = 6
+//
+//     [[contract]]
+//     kind = "clear_area"
+//     width = 6
+//     height = 6
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    // None picks a new random seed for every map
+    pub seed: Option<u64>,
+    pub width: usize,
+    pub height: usize,
+    pub algorithm: GenerationAlgorithm,
+    // Only read by the rooms algorithm
+    pub rooms: RoomOptions,
+    // Only read by the archipelago algorithm
+    pub archipelago: ArchipelagoOptions,
+    pub wall_probability: f64,
+    pub fill: Fill,
+    // Settled floor share the percolation fill aims for
+    pub target_floor: f64,
+    // Counter mode makes maps reproducible across machines and lets
+    // `generator::generate_chunk` build any window of a world on its own
+    pub rng: RngMode,
+    pub iterations: usize,
+    pub stop: Stop,
+    // Hard cap on automaton steps when running until the map converges
+    pub max_iterations: usize,
+    // Runs every automaton step even after the map has stopped changing,
+    // and every layout attempt even once one scores perfectly, so each seed
+    // costs the same and a daily seed's generation time gives nothing away.
+    // The map comes out the same either way.
+    pub constant_work: bool,
+    pub rules: Rules,
+    pub doors: usize,
+    // Widest open stretch allowed before pillars go in; 0 leaves caves alone
+    pub pillar_span: usize,
+    pub roughness: RoughnessOptions,
+    pub erosion: ErosionOptions,
+    pub terrain: TerrainOptions,
+    pub border: BorderOptions,
+    // What happens to regions the main one can't be walked to from
+    pub connectivity: Connectivity,
+    // Tunnels dug past the spanning tree for loops, with "spanning"
+    pub loop_tunnels: usize,
+    // Footprint of the largest unit that must be able to walk from entrance
+    // to exit; passages are widened where it wouldn't fit
+    pub clearance: (usize, usize),
+    pub locks: LockOptions,
+    // Eras the map is aged through once built
+    pub history: HistoryOptions,
+    // Hardness marked on walls for digging
+    pub hardness: HardnessOptions,
+    // Resource veins run through the rock
+    pub veins: VeinOptions,
+    // Nests, food and dens for simulating wildlife
+    pub ecology: EcologyOptions,
+    // How many maps to try, and which one to keep
+    pub layout: LayoutOptions,
+    pub costs: MovementCosts,
+    // Setpiece constraints every map must honour, later ones winning
+    pub pins: Vec<Pin>,
+    // What every map must satisfy before it is handed over
+    pub contracts: Vec<Contract>,
+    pub spawns: SpawnTable,
+    // Tables for filling item spawns that act as containers
+    pub loot: Loot,
+    pub theme: Theme,
+    // Which way is north and where the map lies in world units
+    pub world: WorldFrame,
+    // What each key does in the interactive explorer
+    pub keys: KeyBindings,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            seed: None,
+            width: 80,
+            height: 30,
+            algorithm: GenerationAlgorithm::CellularAutomaton,
+            rooms: RoomOptions::default(),
+            archipelago: ArchipelagoOptions::default(),
+            wall_probability: 0.45,
+            fill: Fill::Uniform,
+            rng: RngMode::Standard,
+            target_floor: percolation::DEFAULT_TARGET_FLOOR,
+            iterations: 7,
+            stop: Stop::Settled,
+            max_iterations: 100,
+            constant_work: false,
+            rules: Rules::default(),
+            doors: 0,
+            pillar_span: 0,
+            roughness: RoughnessOptions::default(),
+            erosion: ErosionOptions::default(),
+            terrain: TerrainOptions::default(),
+            border: BorderOptions::default(),
+            connectivity: Connectivity::Off,
+            loop_tunnels: 0,
+            clearance: (1, 1),
+            locks: LockOptions::default(),
+            history: HistoryOptions::default(),
+            hardness: HardnessOptions::default(),
+            veins: VeinOptions::default(),
+            ecology: EcologyOptions::default(),
+            layout: LayoutOptions::default(),
+            costs: MovementCosts::default(),
+            pins: Vec::new(),
+            contracts: Vec::new(),
+            spawns: SpawnTable::default(),
+            loot: Loot::default(),
+            theme: Theme::default(),
+            world: WorldFrame::default(),
+            keys: KeyBindings::default(),
+        }
+    }
+}
+
+impl Config {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let mut config = Self::default();
+        config.apply_file(path)?;
+        Ok(config)
+    }
+
+    pub fn apply_file(&mut self, path: &Path) -> io::Result<()> {
+        let text = fs::read_to_string(path)?;
+        toml::parse(&text)
+            .and_then(|table| self.apply(&table))
+            .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", path.display(), error)))
+    }
+
+    pub fn from_toml(text: &str) -> io::Result<Self> {
+        let mut config = Self::default();
+        config.apply(&toml::parse(text)?)?;
+        Ok(config)
+    }
+
+    // Overrides only the settings present in `table`. A preset there is
+    // applied first, whatever its position, so the other keys refine it.
+    pub fn apply(&mut self, table: &Table) -> io::Result<()> {
+        if let Some(value) = table.get("preset") {
+            let name = value.as_str().ok_or_else(|| invalid("preset", "must be a string"))?;
+            let preset = Preset::parse(name).ok_or_else(|| {
+                invalid("preset", &format!("must be one of {}", Preset::names()))
+            })?;
+            *self = preset.config();
+        }
+
+        for (key, value) in table {
+            match key.as_str() {
+                "preset" => {}
+                "theme" => {
+                    let name = value.as_str().ok_or_else(|| invalid(key, "must be a string"))?;
+                    self.theme = Theme::resolve(name)?;
+                }
+                "seed" => {
+                    let seed = value
+                        .as_integer()
+                        .and_then(|seed| u64::try_from(seed).ok())
+                        .ok_or_else(|| invalid(key, "must be a non-negative integer"))?;
+                    self.seed = Some(seed);
+                }
+                "width" => self.width = read_usize(key, value)?,
+                "height" => self.height = read_usize(key, value)?,
+                "algorithm" => {
+                    let name = value.as_str().ok_or_else(|| invalid(key, "must be a string"))?;
+                    self.algorithm = GenerationAlgorithm::parse(name)
+                        .ok_or_else(|| invalid(key, "must be \"cellular_automaton\", \"rooms\" or \"archipelago\""))?;
+                }
+                "rooms" => {
+                    for (key, value) in read_table(key, value)? {
+                        match key.as_str() {
+                            "count" => self.rooms.count = read_usize(key, value)?,
+                            "min_size" => self.rooms.min_size = read_usize(key, value)?,
+                            "max_size" => self.rooms.max_size = read_usize(key, value)?,
+                            "junction_size" => self.rooms.junction_size = read_usize(key, value)?,
+                            "merge" => self.rooms.merge = read_fraction(key, value)?,
+                            // Replaces the weights outright: shapes it leaves out get none
+                            "shapes" => {
+                                for shape in RoomShape::ALL {
+                                    *self.rooms.shapes.weight_mut(shape) = 0;
+                                }
+                                for (key, value) in read_table(key, value)? {
+                                    let shape = RoomShape::parse(key).ok_or_else(|| unknown_key("rooms.shapes.", key))?;
+                                    *self.rooms.shapes.weight_mut(shape) = read_usize(key, value)?;
+                                }
+                            }
+                            _ => return Err(unknown_key("rooms.", key)),
+                        }
+                    }
+                }
+                "archipelago" => {
+                    for (key, value) in read_table(key, value)? {
+                        match key.as_str() {
+                            "sea" => self.archipelago.sea = read_fraction(key, value)?,
+                            "min_island" => self.archipelago.min_island = read_usize(key, value)?,
+                            "connectors" => {
+                                let name = value.as_str().ok_or_else(|| invalid(key, "must be a string"))?;
+                                self.archipelago.connectors = Connectors::parse(name)
+                                    .ok_or_else(|| invalid(key, "must be \"none\", \"bridges\" or \"boats\""))?;
+                            }
+                            _ => return Err(unknown_key("archipelago.", key)),
+                        }
+                    }
+                }
+                "wall_probability" => self.wall_probability = read_fraction(key, value)?,
+                "fill" => {
+                    let name = value.as_str().ok_or_else(|| invalid(key, "must be a string"))?;
+                    self.fill = Fill::parse(name).ok_or_else(|| {
+                        invalid(key, "must be \"uniform\", \"blue_noise\" or \"percolation\"")
+                    })?;
+                }
+                "target_floor" => self.target_floor = read_fraction(key, value)?,
+                "rng" => {
+                    let name = value.as_str().ok_or_else(|| invalid(key, "must be a string"))?;
+                    self.rng = RngMode::parse(name).ok_or_else(|| invalid(key, "must be \"standard\" or \"counter\""))?;
+                }
+                "iterations" => self.iterations = read_usize(key, value)?,
+                "stop" => {
+                    let name = value.as_str().ok_or_else(|| invalid(key, "must be a string"))?;
+                    self.stop = Stop::parse(name)
+                        .ok_or_else(|| invalid(key, "must be \"settled\", \"exact\" or \"converged\""))?;
+                }
+                "max_iterations" => self.max_iterations = read_usize(key, value)?,
+                "constant_work" => {
+                    self.constant_work = value.as_bool().ok_or_else(|| invalid(key, "must be true or false"))?;
+                }
+                "rules" => {
+                    for (key, value) in read_table(key, value)? {
+                        match key.as_str() {
+                            "birth_limit" => self.rules.birth_limit = read_usize(key, value)?,
+                            "death_limit" => self.rules.death_limit = read_usize(key, value)?,
+                            _ => return Err(unknown_key("rules.", key)),
+                        }
+                    }
+                }
+                "doors" => self.doors = read_usize(key, value)?,
+                "pillar_span" => self.pillar_span = read_usize(key, value)?,
+                "roughness" => {
+                    for (key, value) in read_table(key, value)? {
+                        match key.as_str() {
+                            "amount" => self.roughness.amount = read_fraction(key, value)?,
+                            "amplitude" => self.roughness.amplitude = read_usize(key, value)?,
+                            _ => return Err(unknown_key("roughness.", key)),
+                        }
+                    }
+                }
+                "erosion" => {
+                    for (key, value) in read_table(key, value)? {
+                        match key.as_str() {
+                            "iterations" => self.erosion.iterations = read_usize(key, value)?,
+                            "strength" => self.erosion.strength = read_fraction(key, value)?,
+                            _ => return Err(unknown_key("erosion.", key)),
+                        }
+                    }
+                }
+                "terrain" => {
+                    for (key, value) in read_table(key, value)? {
+                        match key.as_str() {
+                            "water" => self.terrain.water = read_fraction(key, value)?,
+                            "rubble" => self.terrain.rubble = read_fraction(key, value)?,
+                            _ => return Err(unknown_key("terrain.", key)),
+                        }
+                    }
+                }
+                "locks" => {
+                    for (key, value) in read_table(key, value)? {
+                        match key.as_str() {
+                            "keys" => self.locks.keys = read_usize(key, value)?,
+                            "depth" => self.locks.depth = read_usize(key, value)?,
+                            "side_paths" => {
+                                self.locks.side_paths =
+                                    value.as_bool().ok_or_else(|| invalid(key, "must be true or false"))?;
+                            }
+                            _ => return Err(unknown_key("locks.", key)),
+                        }
+                    }
+                }
+                "history" => {
+                    for (key, value) in read_table(key, value)? {
+                        match key.as_str() {
+                            "eras" => self.history.eras = read_usize(key, value)?,
+                            _ => return Err(unknown_key("history.", key)),
+                        }
+                    }
+                }
+                "hardness" => {
+                    for (key, value) in read_table(key, value)? {
+                        match key.as_str() {
+                            "source" => {
+                                let name = value.as_str().ok_or_else(|| invalid(key, "must be a string"))?;
+                                self.hardness.source = HardnessSource::parse(name)
+                                    .ok_or_else(|| invalid(key, "must be \"off\", \"noise\" or \"depth\""))?;
+                            }
+                            "min" => self.hardness.min = read_usize(key, value)?,
+                            "max" => self.hardness.max = read_usize(key, value)?,
+                            "scale" => self.hardness.scale = read_usize(key, value)?,
+                            _ => return Err(unknown_key("hardness.", key)),
+                        }
+                    }
+                }
+                "veins" => self.veins.apply(read_table(key, value)?)?,
+                "world" => self.world.apply(read_table(key, value)?)?,
+                "keys" => self.keys.apply(read_table(key, value)?)?,
+                "ecology" => {
+                    for (key, value) in read_table(key, value)? {
+                        match key.as_str() {
+                            "nests" => self.ecology.nests = read_usize(key, value)?,
+                            "fungus" => self.ecology.fungus = read_usize(key, value)?,
+                            "dens" => self.ecology.dens = read_usize(key, value)?,
+                            "spacing" => self.ecology.spacing = read_usize(key, value)?,
+                            _ => return Err(unknown_key("ecology.", key)),
+                        }
+                    }
+                }
+                "layout" => {
+                    for (key, value) in read_table(key, value)? {
+                        match key.as_str() {
+                            "attempts" => self.layout.attempts = read_usize(key, value)?,
+                            "objective" => {
+                                let name = value.as_str().ok_or_else(|| invalid(key, "must be a string"))?;
+                                self.layout.objective = Objective::parse(name)
+                                    .ok_or_else(|| invalid(key, "must be \"none\" or \"backtracking\""))?;
+                            }
+                            _ => return Err(unknown_key("layout.", key)),
+                        }
+                    }
+                }
+                "border" => {
+                    for (key, value) in read_table(key, value)? {
+                        match key.as_str() {
+                            "style" => {
+                                let name = value.as_str().ok_or_else(|| invalid(key, "must be a string"))?;
+                                self.border.style = BorderStyle::parse(name).ok_or_else(|| {
+                                    invalid(
+                                        key,
+                                        "must be \"none\", \"wall\", \"cavern_mouth\", \"fortress\" or \"water\"",
+                                    )
+                                })?;
+                            }
+                            "width" => self.border.width = read_usize(key, value)?,
+                            _ => return Err(unknown_key("border.", key)),
+                        }
+                    }
+                }
+                "connectivity" => {
+                    let name = value.as_str().ok_or_else(|| invalid(key, "must be a string"))?;
+                    self.connectivity = Connectivity::parse(name)
+                        .ok_or_else(|| invalid(key, "must be \"off\", \"connect\", \"spanning\" or \"prune\""))?;
+                }
+                "loop_tunnels" => self.loop_tunnels = read_usize(key, value)?,
+                "clearance" => {
+                    for (key, value) in read_table(key, value)? {
+                        let size = read_usize(key, value)?;
+                        if size == 0 {
+                            return Err(invalid(key, "must be at least 1"));
+                        }
+                        match key.as_str() {
+                            "width" => self.clearance.0 = size,
+                            "height" => self.clearance.1 = size,
+                            _ => return Err(unknown_key("clearance.", key)),
+                        }
+                    }
+                }
+                "spawns" => self.spawns.apply(read_table(key, value)?)?,
+                "loot" => self.loot.apply(read_table(key, value)?)?,
+                "pin" => {
+                    let pins = value.as_array().ok_or_else(|| invalid(key, "must be an array of tables"))?;
+                    self.pins = pins
+                        .iter()
+                        .map(|pin| Pin::from_table(read_table(key, pin)?))
+                        .collect::<io::Result<_>>()?;
+                }
+                "contract" => {
+                    let contracts = value.as_array().ok_or_else(|| invalid(key, "must be an array of tables"))?;
+                    self.contracts = contracts
+                        .iter()
+                        .map(|contract| Contract::from_table(read_table(key, contract)?))
+                        .collect::<io::Result<_>>()?;
+                }
+                "costs" => {
+                    for (key, value) in read_table(key, value)? {
+                        let cost = read_usize(key, value)?;
+                        if cost == 0 {
+                            return Err(invalid(key, "must be at least 1"));
+                        }
+                        match key.as_str() {
+                            "floor" => self.costs.floor = cost,
+                            "water" => self.costs.water = cost,
+                            "rubble" => self.costs.rubble = cost,
+                            _ => return Err(unknown_key("costs.", key)),
+                        }
+                    }
+                }
+                _ => return Err(unknown_key("", key)),
+            }
+        }
+
+        if self.width == 0 || self.height == 0 {
+            return Err(invalid("width/height", "must be at least 1"));
+        }
+        if self.rooms.min_size == 0 || self.rooms.min_size > self.rooms.max_size {
+            return Err(invalid("rooms.min_size", "must be at least 1 and no more than rooms.max_size"));
+        }
+        if self.locks.keys > locks::MAX_KEYS {
+            return Err(invalid("locks.keys", &format!("must be at most {}", locks::MAX_KEYS)));
+        }
+        let most_keys = self.width * self.height / locks::TILES_PER_KEY;
+        if self.locks.keys > most_keys {
+            return Err(invalid(
+                "locks.keys",
+                &format!("needs {} tiles of map for each key, so at most {} fit", locks::TILES_PER_KEY, most_keys),
+            ));
+        }
+        if self.locks.keys > 0 && self.locks.depth > self.locks.keys {
+            return Err(invalid("locks.depth", "must be no more than locks.keys"));
+        }
+        if self.hardness.min == 0 || self.hardness.min > self.hardness.max {
+            return Err(invalid("hardness.min", "must be at least 1 and no more than hardness.max"));
+        }
+        if self.hardness.scale == 0 {
+            return Err(invalid("hardness.scale", "must be at least 1"));
+        }
+        if self.veins.count > 0 && self.veins.resources.is_empty() {
+            return Err(invalid("veins.resources", "must list at least one resource"));
+        }
+        if self.layout.attempts == 0 {
+            return Err(invalid("layout.attempts", "must be at least 1"));
+        }
+        if self.border.width == 0 {
+            return Err(invalid("border.width", "must be at least 1"));
+        }
+        if RoomShape::ALL.iter().all(|&shape| self.rooms.shapes.weight(shape) == 0) {
+            return Err(invalid("rooms.shapes", "must give at least one shape a weight"));
+        }
+        Ok(())
+    }
+}
+
+impl Config {
+    // Every setting as TOML that `from_toml` reads back into an equal config.
+    // Themes loaded from files are left out, since only built-ins can be
+    // named.
+    pub fn to_toml(&self) -> String {
+        let mut out = String::new();
+        let mut line = |key: &str, value: String| out.push_str(&format!("{} = {}\n", key, value));
+        if let Some(seed) = self.seed {
+            line("seed", seed.to_string());
+        }
+        line("width", self.width.to_string());
+        line("height", self.height.to_string());
+        line("algorithm", toml::quote(self.algorithm.name()));
+        line("wall_probability", format!("{:?}", self.wall_probability));
+        line("fill", toml::quote(self.fill.name()));
+        line("target_floor", format!("{:?}", self.target_floor));
+        line("rng", toml::quote(self.rng.name()));
+        line("iterations", self.iterations.to_string());
+        line("stop", toml::quote(self.stop.name()));
+        line("max_iterations", self.max_iterations.to_string());
+        line("constant_work", self.constant_work.to_string());
+        line("doors", self.doors.to_string());
+        line("pillar_span", self.pillar_span.to_string());
+        line("connectivity", toml::quote(self.connectivity.name()));
+        line("loop_tunnels", self.loop_tunnels.to_string());
+        if Theme::builtin(&self.theme.name).is_some_and(|theme| theme == self.theme) {
+            line("theme", toml::quote(&self.theme.name));
+        }
+
+        let rules = &self.rules;
+        out.push_str(&format!(
+            "\n[rules]\nbirth_limit = {}\ndeath_limit = {}\n",
+            rules.birth_limit, rules.death_limit
+        ));
+        let rooms = &self.rooms;
+        out.push_str(&format!(
+            "\n[rooms]\ncount = {}\nmin_size = {}\nmax_size = {}\njunction_size = {}\nmerge = {:?}\n",
+            rooms.count, rooms.min_size, rooms.max_size, rooms.junction_size, rooms.merge
+        ));
+        let shapes: Vec<String> = RoomShape::ALL
+            .iter()
+            .map(|&shape| format!("{} = {}", shape.name(), rooms.shapes.weight(shape)))
+            .collect();
+        out.push_str(&format!("shapes = {{ {} }}\n", shapes.join(", ")));
+        let archipelago = &self.archipelago;
+        out.push_str(&format!(
+            "\n[archipelago]\nsea = {:?}\nmin_island = {}\nconnectors = {}\n",
+            archipelago.sea,
+            archipelago.min_island,
+            toml::quote(archipelago.connectors.name())
+        ));
+        let roughness = &self.roughness;
+        out.push_str(&format!(
+            "\n[roughness]\namount = {:?}\namplitude = {}\n",
+            roughness.amount, roughness.amplitude
+        ));
+        let erosion = &self.erosion;
+        out.push_str(&format!(
+            "\n[erosion]\niterations = {}\nstrength = {:?}\n",
+            erosion.iterations, erosion.strength
+        ));
+        let terrain = &self.terrain;
+        out.push_str(&format!(
+            "\n[terrain]\nwater = {:?}\nrubble = {:?}\n",
+            terrain.water, terrain.rubble
+        ));
+        out.push_str(&format!(
+            "\n[border]\nstyle = {}\nwidth = {}\n",
+            toml::quote(self.border.style.name()),
+            self.border.width
+        ));
+        out.push_str(&format!(
+            "\n[clearance]\nwidth = {}\nheight = {}\n",
+            self.clearance.0, self.clearance.1
+        ));
+        let locks = &self.locks;
+        out.push_str(&format!(
+            "\n[locks]\nkeys = {}\ndepth = {}\nside_paths = {}\n",
+            locks.keys, locks.depth, locks.side_paths
+        ));
+        out.push_str(&format!("\n[history]\neras = {}\n", self.history.eras));
+        let hardness = &self.hardness;
+        out.push_str(&format!(
+            "\n[hardness]\nsource = {}\nmin = {}\nmax = {}\nscale = {}\n",
+            toml::quote(hardness.source.name()),
+            hardness.min,
+            hardness.max,
+            hardness.scale
+        ));
+        out.push_str(&self.veins.to_toml());
+        let ecology = &self.ecology;
+        out.push_str(&format!(
+            "\n[ecology]\nnests = {}\nfungus = {}\ndens = {}\nspacing = {}\n",
+            ecology.nests, ecology.fungus, ecology.dens, ecology.spacing
+        ));
+        out.push_str(&format!(
+            "\n[layout]\nattempts = {}\nobjective = {}\n",
+            self.layout.attempts,
+            toml::quote(self.layout.objective.name())
+        ));
+        let costs = &self.costs;
+        out.push_str(&format!(
+            "\n[costs]\nfloor = {}\nwater = {}\nrubble = {}\n",
+            costs.floor, costs.water, costs.rubble
+        ));
+
+        let spawns = &self.spawns;
+        out.push_str(&format!(
+            "\n[spawns]\ndecoration_density = {:?}\nmonster_density = {:?}\nitem_density = {:?}\n\
+             steps_between_fights = {}\ntrap_density = {:?}\n",
+            spawns.decoration_density,
+            spawns.monster_density,
+            spawns.item_density,
+            spawns.steps_between_fights,
+            spawns.trap_density
+        ));
+        for (name, weights) in [
+            ("decorations", &spawns.decorations),
+            ("monsters", &spawns.monsters),
+            ("items", &spawns.items),
+            ("traps", &spawns.traps),
+        ] {
+            let entries: Vec<String> = weights
+                .iter()
+                .map(|(name, weight)| format!("{} = {}", toml::key(name), weight))
+                .collect();
+            out.push_str(&format!("{} = {{ {} }}\n", name, entries.join(", ")));
+        }
+        out.push_str(&self.loot.to_toml());
+        out.push_str(&self.world.to_toml());
+        out.push_str(&self.keys.to_toml());
+        for pin in &self.pins {
+            out.push_str(&format!("\n[[pin]]\n{}", pin.to_toml()));
+        }
+        for contract in &self.contracts {
+            out.push_str(&format!("\n[[contract]]\n{}", contract.to_toml()));
+        }
+        out
+    }
+}
+
+pub(crate) fn invalid(key: &str, message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("'{}' {}", key, message))
+}
+
+pub(crate) fn unknown_key(prefix: &str, key: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("unknown setting '{}{}'", prefix, key),
+    )
+}
+
+pub(crate) fn read_usize(key: &str, value: &Value) -> io::Result<usize> {
+    value
+        .as_integer()
+        .and_then(|value| usize::try_from(value).ok())
+        .ok_or_else(|| invalid(key, "must be a non-negative integer"))
+}
+
+pub(crate) fn read_fraction(key: &str, value: &Value) -> io::Result<f64> {
+    value
+        .as_float()
+        .filter(|value| (0.0..=1.0).contains(value))
+        .ok_or_else(|| invalid(key, "must be a number between 0 and 1"))
+}
+
+pub(crate) fn read_table<'a>(key: &str, value: &'a Value) -> io::Result<&'a Table> {
+    value.as_table().ok_or_else(|| invalid(key, "must be a table"))
+}
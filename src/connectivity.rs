@@ -0,0 +1,277 @@
+// Guarantees a single walkable area once the map is otherwise built. Each
+// region cut off from the largest one is either tunnelled into it or filled
+// in. Tiles that are frozen or pinned open can't be filled, so a region
+// holding any is always tunnelled, and kept as it is if no tunnel can reach.
+// Doors hung afterwards only go where they cut nothing off, and the locks
+// placed last each have a key on the near side, so the map can still
+// be walked end to end.
+//
+// Tunnelling each region straight to the largest digs more than it needs
+// to where small regions lie closer to each other than to it. `Spanning`
+// instead joins every region along a minimum spanning tree of the shortest
+// tunnels between them (Kruskal's, the tunnels weighed by the wall they cut
+// through), and can dig a few of the next shortest as well for loops.
+use crate::dungeon::{Dungeon, Point, Tile};
+use crate::pathfinding::regions_in;
+use crate::pins::{pinned, Pin};
+use crate::scratch::{cleared, Scratch};
+use std::collections::HashSet;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Connectivity {
+    // Disconnected regions stay as they are
+    #[default]
+    Off,
+    // Each one is tunnelled to the main region
+    Connect,
+    // All are tunnelled together along the least total tunnel
+    Spanning,
+    // Each one is filled with wall
+    Prune,
+}
+
+impl Connectivity {
+    pub const ALL: [Connectivity; 4] =
+        [Connectivity::Off, Connectivity::Connect, Connectivity::Spanning, Connectivity::Prune];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Connectivity::Off => "off",
+            Connectivity::Connect => "connect",
+            Connectivity::Spanning => "spanning",
+            Connectivity::Prune => "prune",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+}
+
+// The union-find set `index` is in, halving the path on the way
+fn root(parent: &mut [usize], mut index: usize) -> usize {
+    while parent[index] != index {
+        parent[index] = parent[parent[index]];
+        index = parent[index];
+    }
+    index
+}
+
+// A tunnel between two regions, by their index in `regions_in`, and the
+// walls it cuts through
+struct Edge {
+    walls: usize,
+    from: usize,
+    to: usize,
+    tunnel: Vec<Point>,
+}
+
+impl Dungeon {
+    // Returns the tiles changed, dug or filled. `loops` is how many tunnels
+    // past the spanning tree `Spanning` digs; the other modes ignore it.
+    pub fn ensure_connected(&mut self, mode: Connectivity, loops: usize, pins: &[Pin]) -> usize {
+        self.ensure_connected_in(mode, loops, pins, &mut Scratch::new())
+    }
+
+    pub fn ensure_connected_in(&mut self, mode: Connectivity, loops: usize, pins: &[Pin], scratch: &mut Scratch) -> usize {
+        if mode == Connectivity::Off {
+            return 0;
+        }
+        let mut changed = 0;
+        if mode == Connectivity::Spanning {
+            changed += self.span_regions(loops, pins, scratch);
+        }
+        // Regions nothing more can be done about, by one tile of each
+        let mut kept: HashSet<Point> = HashSet::new();
+        loop {
+            let regions = regions_in(self, scratch);
+            let Some(main) = (0..regions.len()).max_by_key(|&index| (regions[index].len(), std::cmp::Reverse(index)))
+            else {
+                break;
+            };
+            let Some(region) = regions
+                .iter()
+                .enumerate()
+                .find(|&(index, region)| index != main && !region.iter().any(|tile| kept.contains(tile)))
+                .map(|(_, region)| region)
+            else {
+                break;
+            };
+
+            let fixed = |&(x, y): &Point| self.frozen[y][x] || pinned(pins, x, y) == Some(Tile::Floor);
+            if mode == Connectivity::Prune && !region.iter().any(fixed) {
+                for &(x, y) in region {
+                    self.tiles[y][x] = Tile::Wall;
+                }
+                changed += region.len();
+            } else if let Some(tunnel) = self.tunnel_to_main(region, pins, scratch) {
+                for (x, y) in tunnel {
+                    if !self.tiles[y][x].is_walkable() {
+                        self.tiles[y][x] = Tile::Floor;
+                        changed += 1;
+                    }
+                }
+            } else if mode == Connectivity::Connect && !region.iter().any(fixed) {
+                for &(x, y) in region {
+                    self.tiles[y][x] = Tile::Wall;
+                }
+                changed += region.len();
+            } else {
+                kept.insert(region[0]);
+            }
+        }
+        self.prune_layers();
+        changed
+    }
+
+    // Digs the spanning tree and `loops` tunnels more. Regions no tunnel
+    // can reach are left for the `Connect` handling that follows.
+    fn span_regions(&mut self, loops: usize, pins: &[Pin], scratch: &mut Scratch) -> usize {
+        let regions = regions_in(self, scratch);
+        if regions.len() < 2 {
+            return 0;
+        }
+        let mut labels = vec![None; self.width * self.height];
+        for (index, region) in regions.iter().enumerate() {
+            for &(x, y) in region {
+                labels[y * self.width + x] = Some(index);
+            }
+        }
+        let mut edges: Vec<Edge> = Vec::new();
+        for (from, region) in regions.iter().enumerate() {
+            edges.extend(self.shortest_tunnels(from, region, &labels, pins, scratch));
+        }
+        edges.sort_by_key(|edge| (edge.walls, edge.from, edge.to));
+
+        // Kruskal's, over a union-find of the regions
+        let mut parent: Vec<usize> = (0..regions.len()).collect();
+        let mut dug: Vec<&Edge> = Vec::new();
+        let mut skipped: Vec<&Edge> = Vec::new();
+        for edge in &edges {
+            let (a, b) = (root(&mut parent, edge.from), root(&mut parent, edge.to));
+            if a == b {
+                skipped.push(edge);
+            } else {
+                parent[a] = b;
+                dug.push(edge);
+            }
+        }
+        dug.extend(skipped.into_iter().take(loops));
+
+        let mut changed = 0;
+        for edge in dug {
+            for &(x, y) in &edge.tunnel {
+                if !self.tiles[y][x].is_walkable() {
+                    self.tiles[y][x] = Tile::Floor;
+                    changed += 1;
+                }
+            }
+        }
+        changed
+    }
+
+    // 0-1 BFS out of region `from` through wall, stopping at the edge of
+    // every other region, for the cheapest tunnel to each one after it
+    fn shortest_tunnels(
+        &self,
+        from: usize,
+        region: &[Point],
+        labels: &[Option<usize>],
+        pins: &[Pin],
+        scratch: &mut Scratch,
+    ) -> Vec<Edge> {
+        let width = self.width;
+        let cost = cleared(&mut scratch.costs, width * self.height, usize::MAX);
+        let came_from = cleared(&mut scratch.came_from, width * self.height, None);
+        let queue = &mut scratch.queue;
+        queue.clear();
+        for &(x, y) in region {
+            cost[y * width + x] = 0;
+            queue.push_back((x, y));
+        }
+        let mut reached = HashSet::new();
+        let mut edges = Vec::new();
+        while let Some((x, y)) = queue.pop_front() {
+            if let Some(to) = labels[y * width + x].filter(|&label| label != from) {
+                if to > from && reached.insert(to) {
+                    let mut tunnel = vec![(x, y)];
+                    let mut current = (x, y);
+                    while let Some(previous) = came_from[current.1 * width + current.0] {
+                        tunnel.push(previous);
+                        current = previous;
+                    }
+                    edges.push(Edge {
+                        walls: cost[y * width + x],
+                        from,
+                        to,
+                        tunnel,
+                    });
+                }
+                continue;
+            }
+            let steps = [
+                (x > 0).then(|| (x - 1, y)),
+                (y > 0).then(|| (x, y - 1)),
+                (x + 1 < width).then_some((x + 1, y)),
+                (y + 1 < self.height).then_some((x, y + 1)),
+            ];
+            for (nx, ny) in steps.into_iter().flatten() {
+                let locked = self.frozen[ny][nx] && !self.tiles[ny][nx].is_walkable();
+                if locked || pinned(pins, nx, ny) == Some(Tile::Wall) {
+                    continue;
+                }
+                let step = usize::from(!self.tiles[ny][nx].is_walkable());
+                let next_cost = cost[y * width + x] + step;
+                if next_cost < cost[ny * width + nx] {
+                    cost[ny * width + nx] = next_cost;
+                    came_from[ny * width + nx] = Some((x, y));
+                    if step == 0 {
+                        queue.push_front((nx, ny));
+                    } else {
+                        queue.push_back((nx, ny));
+                    }
+                }
+            }
+        }
+        edges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::rng_for;
+    use crate::pathfinding::regions;
+
+    // A sparse cave, left in many pieces
+    fn scattered(seed: u64) -> Dungeon {
+        let mut dungeon = Dungeon::new(120, 60);
+        dungeon.initialize_random(0.56, &mut rng_for(seed));
+        for _ in 0..5 {
+            dungeon.simulate_step();
+        }
+        dungeon
+    }
+
+    fn floor(dungeon: &Dungeon) -> usize {
+        dungeon.tiles.iter().flatten().filter(|tile| tile.is_walkable()).count()
+    }
+
+    #[test]
+    fn spanning_joins_every_region_with_less_tunnel_than_connect() {
+        for seed in [7, 8, 9] {
+            let cave = scattered(seed);
+            assert!(regions(&cave).len() > 2);
+            let joined = |mode: Connectivity| {
+                let mut dungeon = cave.clone();
+                let changed = dungeon.ensure_connected(mode, 0, &[]);
+                assert_eq!(regions(&dungeon).len(), 1, "{} left regions apart with seed {}", mode.name(), seed);
+                // Every region can be reached, so nothing is filled and
+                // every tile changed is dug
+                assert_eq!(changed, floor(&dungeon) - floor(&cave));
+                floor(&dungeon)
+            };
+            assert!(joined(Connectivity::Spanning) < joined(Connectivity::Connect), "seed {}", seed);
+        }
+    }
+}
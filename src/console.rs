@@ -0,0 +1,111 @@
+// Terminal setup shared by the interactive modes
+use crossterm::{
+    cursor,
+    event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{self, ClearType},
+};
+use std::io;
+
+// Raw mode and a hidden cursor for as long as it lives, plus a UTF-8
+// console on Windows. Dropping it puts the terminal back, so an error
+// part-way through a mode doesn't leave the shell in raw mode.
+pub struct Console {
+    #[cfg(windows)]
+    code_page: u32,
+}
+
+impl Console {
+    pub fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::Clear(ClearType::All), cursor::Hide)?;
+        Ok(Self {
+            #[cfg(windows)]
+            code_page: windows::use_utf8(),
+        })
+    }
+}
+
+impl Drop for Console {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::Clear(ClearType::All));
+        let _ = terminal::disable_raw_mode();
+        #[cfg(windows)]
+        windows::restore(self.code_page);
+    }
+}
+
+// Key presses only, as Windows consoles report releases too
+pub fn pressed(event: Event) -> Option<KeyEvent> {
+    match event {
+        Event::Key(key) if key.kind != KeyEventKind::Release => Some(key),
+        _ => None,
+    }
+}
+
+pub fn quits(key: &KeyEvent) -> bool {
+    matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) || interrupted(key)
+}
+
+// Raw mode swallows the interrupt signal, so Ctrl+C has to be caught as a key
+pub fn interrupted(key: &KeyEvent) -> bool {
+    key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+// The key as `[keys]` bindings name it; None with Ctrl or Alt held, or for
+// a key bindings can't name
+pub fn key_name(key: &KeyEvent) -> Option<String> {
+    if key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+        return None;
+    }
+    let name = match key.code {
+        KeyCode::Char(' ') => "space",
+        KeyCode::Char(c) => return Some(c.to_string()),
+        KeyCode::Up => "up",
+        KeyCode::Down => "down",
+        KeyCode::Left => "left",
+        KeyCode::Right => "right",
+        KeyCode::Tab => "tab",
+        KeyCode::BackTab => "backtab",
+        KeyCode::Enter => "enter",
+        KeyCode::Esc => "esc",
+        KeyCode::Backspace => "backspace",
+        KeyCode::Delete => "delete",
+        KeyCode::Home => "home",
+        KeyCode::End => "end",
+        KeyCode::PageUp => "pageup",
+        KeyCode::PageDown => "pagedown",
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+#[cfg(windows)]
+mod windows {
+    const CP_UTF8: u32 = 65001;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetConsoleOutputCP() -> u32;
+        fn SetConsoleOutputCP(code_page: u32) -> i32;
+    }
+
+    // Consoles still start in a legacy OEM code page, which turns the
+    // block and Braille glyphs into mojibake. Returns the one to restore.
+    pub fn use_utf8() -> u32 {
+        unsafe {
+            let previous = GetConsoleOutputCP();
+            SetConsoleOutputCP(CP_UTF8);
+            previous
+        }
+    }
+
+    pub fn restore(code_page: u32) {
+        // 0 means the query failed, e.g. output isn't a console
+        if code_page != 0 {
+            unsafe {
+                SetConsoleOutputCP(code_page);
+            }
+        }
+    }
+}
@@ -0,0 +1,316 @@
+// What a downstream engine needs of every map it is handed, checked before
+// the map gets to it. Contracts come from the config as [[contract]] tables,
+//
+//     [[contract]]
+//     kind = "clear_area"
+//     width = 6
+//     height = 6
+//
+// or from code as named closures, and a map that breaks any of them comes
+// back as a list of violations rather than a map.
+use crate::config::{invalid, read_fraction, read_usize, unknown_key};
+use crate::dungeon::Dungeon;
+use crate::pathfinding::{regions, PathOptions};
+use crate::solver;
+use crate::toml::{Table, Value};
+use std::fmt;
+use std::io;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Contract {
+    // At least `count` spots, not overlapping, where a width x height block
+    // of walkable tiles stands clear
+    ClearArea { width: usize, height: usize, count: usize },
+    // Share of the map that is walkable
+    Floor { min: f64, max: f64 },
+    // Steps from entrance to exit
+    Route { min: usize, max: usize },
+    // Every walkable tile can be walked to from every other
+    Connected,
+}
+
+impl Contract {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Contract::ClearArea { .. } => "clear_area",
+            Contract::Floor { .. } => "floor",
+            Contract::Route { .. } => "route",
+            Contract::Connected => "connected",
+        }
+    }
+
+    // Why the map breaks the contract, if it does
+    pub fn check(&self, dungeon: &Dungeon) -> Result<(), String> {
+        match *self {
+            Contract::ClearArea { width, height, count } => {
+                let found = clear_areas(dungeon, width, height, count);
+                if found < count {
+                    return Err(format!("needs {} clear {}x{} area(s), found {}", count, width, height, found));
+                }
+            }
+            Contract::Floor { min, max } => {
+                let walkable = dungeon.tiles.iter().flatten().filter(|tile| tile.is_walkable()).count();
+                let share = walkable as f64 / (dungeon.width * dungeon.height).max(1) as f64;
+                if share < min || share > max {
+                    let (share, min, max) = (share * 100.0, min * 100.0, max * 100.0);
+                    return Err(format!("floor covers {:.1}%, outside {:.1}%-{:.1}%", share, min, max));
+                }
+            }
+            Contract::Route { min, max } => {
+                let Some(solution) = solver::solve(dungeon, &PathOptions::default()) else {
+                    return Err("no route from entrance to exit".to_string());
+                };
+                if solution.steps() < min || solution.steps() > max {
+                    return Err(format!("route takes {} steps, outside {}-{}", solution.steps(), min, max));
+                }
+            }
+            Contract::Connected => {
+                let count = regions(dungeon).len();
+                if count > 1 {
+                    return Err(format!("floor falls into {} unconnected regions", count));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // One [[contract]] table
+    pub fn from_table(table: &Table) -> io::Result<Self> {
+        const KINDS: &str = "must be \"clear_area\", \"floor\", \"route\" or \"connected\"";
+        let kind = table.get("kind").and_then(Value::as_str).ok_or_else(|| invalid("contract.kind", KINDS))?;
+        let allowed: &[&str] = match kind {
+            "clear_area" => &["kind", "width", "height", "count"],
+            "floor" | "route" => &["kind", "min", "max"],
+            "connected" => &["kind"],
+            _ => return Err(invalid("contract.kind", KINDS)),
+        };
+        if let Some(key) = table.keys().find(|key| !allowed.contains(&key.as_str())) {
+            return Err(unknown_key("contract.", key));
+        }
+        let number = |key: &str, default: Option<usize>| match table.get(key) {
+            Some(value) => read_usize(&format!("contract.{}", key), value),
+            None => default.ok_or_else(|| invalid(&format!("contract.{}", key), "is missing")),
+        };
+        let fraction = |key: &str, default: f64| match table.get(key) {
+            Some(value) => read_fraction(&format!("contract.{}", key), value),
+            None => Ok(default),
+        };
+        let contract = match kind {
+            "clear_area" => Contract::ClearArea {
+                width: number("width", None)?,
+                height: number("height", None)?,
+                count: number("count", Some(1))?,
+            },
+            "floor" => Contract::Floor {
+                min: fraction("min", 0.0)?,
+                max: fraction("max", 1.0)?,
+            },
+            "route" => Contract::Route {
+                min: number("min", Some(0))?,
+                max: number("max", Some(usize::MAX))?,
+            },
+            _ => Contract::Connected,
+        };
+        match contract {
+            Contract::ClearArea { width, height, .. } if width == 0 || height == 0 => {
+                Err(invalid("contract.width", "and height must be at least 1"))
+            }
+            Contract::Floor { min, max } if min > max => Err(invalid("contract.min", "must not exceed max")),
+            Contract::Route { min, max } if min > max => Err(invalid("contract.min", "must not exceed max")),
+            contract => Ok(contract),
+        }
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut out = format!("kind = \"{}\"\n", self.name());
+        match *self {
+            Contract::ClearArea { width, height, count } => {
+                out += &format!("width = {}\nheight = {}\ncount = {}\n", width, height, count);
+            }
+            Contract::Floor { min, max } => out += &format!("min = {}\nmax = {}\n", min, max),
+            Contract::Route { min, max } => {
+                out += &format!("min = {}\n", min);
+                if max != usize::MAX {
+                    out += &format!("max = {}\n", max);
+                }
+            }
+            Contract::Connected => {}
+        }
+        out
+    }
+}
+
+// Clear spots found, stopping at `wanted`; each one taken rules out the
+// spots overlapping it, scanning row by row
+fn clear_areas(dungeon: &Dungeon, width: usize, height: usize, wanted: usize) -> usize {
+    let mut mask = dungeon.footprint_mask(width, height);
+    let mut found = 0;
+    for y in 0..mask.len() {
+        for x in 0..dungeon.width {
+            if found == wanted {
+                return found;
+            }
+            if !mask[y][x] {
+                continue;
+            }
+            found += 1;
+            for row in mask.iter_mut().take(y + height).skip(y.saturating_sub(height - 1)) {
+                for fits in row.iter_mut().take(x + width).skip(x.saturating_sub(width - 1)) {
+                    *fits = false;
+                }
+            }
+        }
+    }
+    found
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    // The contract's kind, or the name a closure was registered under
+    pub contract: String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.contract, self.message)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContractError {
+    pub seed: u64,
+    pub violations: Vec<Violation>,
+}
+
+impl fmt::Display for ContractError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "map for seed {} breaks {} contract(s)", self.seed, self.violations.len())?;
+        for violation in &self.violations {
+            write!(f, "\n  {}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ContractError {}
+
+impl From<ContractError> for io::Error {
+    fn from(error: ContractError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+type Check = Box<dyn Fn(&Dungeon) -> Result<(), String>>;
+
+// Contracts registered from code, on top of whatever the config lists
+#[derive(Default)]
+pub struct Contracts {
+    rules: Vec<Contract>,
+    checks: Vec<(String, Check)>,
+}
+
+impl Contracts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn rule(mut self, contract: Contract) -> Self {
+        self.rules.push(contract);
+        self
+    }
+
+    // A closure returning why the map won't do, if it won't
+    pub fn check(mut self, name: &str, check: impl Fn(&Dungeon) -> Result<(), String> + 'static) -> Self {
+        self.checks.push((name.to_string(), Box::new(check)));
+        self
+    }
+
+    // Every violation, rules first in the order they were added
+    pub fn violations(&self, dungeon: &Dungeon) -> Vec<Violation> {
+        let mut violations = violations(&self.rules, dungeon);
+        for (name, check) in &self.checks {
+            if let Err(message) = check(dungeon) {
+                violations.push(Violation {
+                    contract: name.clone(),
+                    message,
+                });
+            }
+        }
+        violations
+    }
+}
+
+// Violations of a list of contracts, such as a config's own
+pub fn violations(contracts: &[Contract], dungeon: &Dungeon) -> Vec<Violation> {
+    contracts
+        .iter()
+        .filter_map(|contract| {
+            contract.check(dungeon).err().map(|message| Violation {
+                contract: contract.name().to_string(),
+                message,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use crate::layers::Entity;
+
+    #[test]
+    fn each_kind_passes_what_it_asks_for_and_says_why_not_otherwise() {
+        // Two 4x5 rooms of 20 tiles each, and a 5-tile corridor, on 15x7
+        let dungeon = fixtures::two_rooms();
+        let passes = [
+            Contract::ClearArea { width: 4, height: 5, count: 2 },
+            Contract::Floor { min: 0.4, max: 0.5 },
+            Contract::Route { min: 12, max: 12 },
+            Contract::Connected,
+        ];
+        for contract in passes {
+            assert_eq!(contract.check(&dungeon), Ok(()), "{:?}", contract);
+        }
+
+        let fails = [
+            (Contract::ClearArea { width: 4, height: 5, count: 3 }, "needs 3 clear 4x5 area(s), found 2"),
+            (Contract::Floor { min: 0.0, max: 0.25 }, "floor covers 42.9%, outside 0.0%-25.0%"),
+            (Contract::Route { min: 20, max: 30 }, "route takes 12 steps, outside 20-30"),
+        ];
+        for (contract, message) in fails {
+            assert_eq!(contract.check(&dungeon), Err(message.to_string()));
+        }
+        let mut apart = fixtures::disconnected_pair();
+        assert_eq!(Contract::Connected.check(&apart), Err("floor falls into 2 unconnected regions".to_string()));
+        apart.entities.set(1, 1, Entity::Entrance);
+        apart.entities.set(13, 5, Entity::Exit);
+        let any_route = Contract::Route { min: 0, max: 99 };
+        assert_eq!(any_route.check(&apart), Err("no route from entrance to exit".to_string()));
+    }
+
+    #[test]
+    fn every_broken_contract_is_listed_in_the_order_added() {
+        let dungeon = fixtures::two_rooms();
+        let contracts = Contracts::new()
+            .rule(Contract::Connected)
+            .rule(Contract::Floor { min: 0.9, max: 1.0 })
+            .check("has a door", |dungeon| match dungeon.doors().count() {
+                0 => Err("no doors".to_string()),
+                _ => Ok(()),
+            })
+            .check("small", |dungeon| if dungeon.width < 20 { Ok(()) } else { Err("too wide".to_string()) })
+            .rule(Contract::ClearArea { width: 6, height: 6, count: 1 });
+        let broken: Vec<String> = contracts.violations(&dungeon).iter().map(Violation::to_string).collect();
+        assert_eq!(
+            broken,
+            [
+                "floor: floor covers 42.9%, outside 90.0%-100.0%",
+                "clear_area: needs 1 clear 6x6 area(s), found 0",
+                "has a door: no doors",
+            ]
+        );
+        assert!(Contracts::new().rule(Contract::Connected).violations(&dungeon).is_empty());
+    }
+}
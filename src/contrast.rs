@@ -0,0 +1,173 @@
+// Whether a theme's tiles can be told apart. Two colors read as different
+// when they are far enough apart in lightness, by the WCAG contrast ratio,
+// or in hue, by their distance in CIE Lab, and that has to hold not just for
+// typical vision but for each of the three kinds of color blindness too, so
+// each pair is checked again with both colors as a colorblind viewer sees
+// them (after Machado, Oliveira and Fernandes, 2009). Lightness carries
+// through every kind, which is why the built-in "high-contrast" theme leans
+// on it alone.
+use crate::dungeon::Tile;
+use crate::theme::{color_rgb, Theme};
+
+// Below both of these a pair is nearly indistinguishable
+pub const MIN_CONTRAST: f64 = 1.5;
+pub const MIN_DISTANCE: f64 = 20.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Vision {
+    Typical,
+    // No red cones
+    Protanopia,
+    // No green cones, the most common
+    Deuteranopia,
+    // No blue cones
+    Tritanopia,
+}
+
+impl Vision {
+    pub const ALL: [Vision; 4] = [Vision::Typical, Vision::Protanopia, Vision::Deuteranopia, Vision::Tritanopia];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Vision::Typical => "typical",
+            Vision::Protanopia => "protanopia",
+            Vision::Deuteranopia => "deuteranopia",
+            Vision::Tritanopia => "tritanopia",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|vision| vision.name() == name)
+    }
+
+    // `rgb` as seen with this vision
+    pub fn simulate(self, rgb: [u8; 3]) -> [u8; 3] {
+        let matrix = match self {
+            Vision::Typical => return rgb,
+            Vision::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            Vision::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            Vision::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        };
+        let linear = rgb.map(linear);
+        matrix.map(|row| {
+            let channel: f64 = row.iter().zip(linear).map(|(weight, channel)| weight * channel).sum();
+            encode(channel.clamp(0.0, 1.0))
+        })
+    }
+}
+
+// One sRGB channel as linear light, 0 to 1
+fn linear(channel: u8) -> f64 {
+    let c = channel as f64 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn encode(light: f64) -> u8 {
+    let c = if light <= 0.0031308 { light * 12.92 } else { 1.055 * light.powf(1.0 / 2.4) - 0.055 };
+    (c * 255.0).round() as u8
+}
+
+pub fn relative_luminance(rgb: [u8; 3]) -> f64 {
+    let [r, g, b] = rgb.map(linear);
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+// From 1 for the same lightness to 21 for black on white
+pub fn contrast_ratio(a: [u8; 3], b: [u8; 3]) -> f64 {
+    let (a, b) = (relative_luminance(a), relative_luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+fn lab(rgb: [u8; 3]) -> [f64; 3] {
+    let [r, g, b] = rgb.map(linear);
+    // Against the D65 white point
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+    let f = |t: f64| if t > 216.0 / 24389.0 { t.cbrt() } else { (24389.0 / 27.0 * t + 16.0) / 116.0 };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+// CIE76 distance; around 2 is the least a viewer notices side by side
+pub fn color_distance(a: [u8; 3], b: [u8; 3]) -> f64 {
+    let (a, b) = (lab(a), lab(b));
+    a.iter().zip(b).map(|(a, b)| (a - b).powi(2)).sum::<f64>().sqrt()
+}
+
+pub fn distinguishable(a: [u8; 3], b: [u8; 3], vision: Vision) -> bool {
+    let (a, b) = (vision.simulate(a), vision.simulate(b));
+    contrast_ratio(a, b) >= MIN_CONTRAST || color_distance(a, b) >= MIN_DISTANCE
+}
+
+fn tile_name(tile: Tile) -> &'static str {
+    match tile {
+        Tile::Floor => "floor",
+        Tile::Wall => "wall",
+        Tile::Water => "water",
+        Tile::Rubble => "rubble",
+        Tile::Bridge => "bridge",
+        Tile::Custom(custom) => custom.kind().name,
+    }
+}
+
+impl Theme {
+    // Floor and wall are what a map is read by, so those are what have to
+    // stand apart; no built-in theme gets a warning
+    pub fn contrast_warnings(&self) -> Vec<String> {
+        self.pair_warnings(Tile::Floor, Tile::Wall)
+    }
+
+    // A line for images and one for the terminal where `a` and `b` would be
+    // hard to tell apart, naming who would have trouble. The colorblind and
+    // high-contrast themes pass for every pair of tiles that meet.
+    pub fn pair_warnings(&self, a: Tile, b: Tile) -> Vec<String> {
+        let (a_style, b_style) = (self.style(a), self.style(b));
+        let looks = [
+            ("images", a_style.rgb, b_style.rgb),
+            ("the terminal", color_rgb(a_style.color), color_rgb(b_style.color)),
+        ];
+        let mut warnings = Vec::new();
+        for (drawn, a_rgb, b_rgb) in looks {
+            let unclear: Vec<&str> = Vision::ALL
+                .into_iter()
+                .filter(|&vision| !distinguishable(a_rgb, b_rgb, vision))
+                .map(Vision::name)
+                .collect();
+            if unclear.is_empty() {
+                continue;
+            }
+            let who = if unclear.contains(&Vision::Typical.name()) {
+                "anyone".to_string()
+            } else {
+                format!("viewers with {}", unclear.join(" or "))
+            };
+            warnings.push(format!(
+                "theme '{}' draws {} and {} nearly alike in {} for {} (contrast {:.2}:1)",
+                self.name,
+                tile_name(a),
+                tile_name(b),
+                drawn,
+                who,
+                contrast_ratio(a_rgb, b_rgb)
+            ));
+        }
+        warnings
+    }
+}
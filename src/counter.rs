@@ -0,0 +1,171 @@
+// Counter-based random numbers with Philox4x32-10 (Salmon et al., "Parallel
+// random numbers: as easy as 1, 2, 3", SC'11). Every output is a pure
+// function of a key and a counter, so a tile's draw can be worked out on
+// its own, in any order, and comes out the same on every machine and with
+// every version of the rand crate, whose StdRng makes no such promise.
+use crate::dungeon::{Dungeon, Tile};
+use rand::{Error, RngCore, SeedableRng};
+
+// Which RNG a config generates with
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RngMode {
+    // rand's StdRng, seeded from the map seed
+    #[default]
+    Standard,
+    // Philox, with the starting noise drawn per tile from its coordinates
+    Counter,
+}
+
+impl RngMode {
+    pub const ALL: [RngMode; 2] = [RngMode::Standard, RngMode::Counter];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RngMode::Standard => "standard",
+            RngMode::Counter => "counter",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+}
+
+const MULTIPLIERS: [u32; 2] = [0xd251_1f53, 0xcd9e_8d57];
+// Golden ratio and sqrt(3) - 1, bumping the key between rounds
+const WEYL: [u32; 2] = [0x9e37_79b9, 0xbb67_ae85];
+
+// One Philox4x32-10 block
+pub fn philox(key: [u32; 2], counter: [u32; 4]) -> [u32; 4] {
+    let (mut key, mut block) = (key, counter);
+    for round in 0..10 {
+        if round > 0 {
+            key = [key[0].wrapping_add(WEYL[0]), key[1].wrapping_add(WEYL[1])];
+        }
+        let first = MULTIPLIERS[0] as u64 * block[0] as u64;
+        let second = MULTIPLIERS[1] as u64 * block[2] as u64;
+        block = [
+            (second >> 32) as u32 ^ block[1] ^ key[0],
+            second as u32,
+            (first >> 32) as u32 ^ block[3] ^ key[1],
+            first as u32,
+        ];
+    }
+    block
+}
+
+fn key(seed: u64) -> [u32; 2] {
+    [seed as u32, (seed >> 32) as u32]
+}
+
+// Sequential draws put the block number in the first two counter words and
+// a stream number in the third; the last word tells them apart from the
+// per-tile draws, which use it for nothing else
+const SEQUENTIAL: u32 = 0;
+const PER_TILE: u32 = 1;
+
+// A uniform draw in [0, 1) for one world tile, on its own. Coordinates
+// wrap every 2^32 tiles.
+pub fn tile_unit(seed: u64, x: i64, y: i64) -> f64 {
+    let [high, low, _, _] = philox(key(seed), [x as u32, y as u32, 0, PER_TILE]);
+    ((high as u64) << 21 | (low as u64) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+// Philox as an ordinary RNG: block after block of one numbered stream
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CounterRng {
+    key: [u32; 2],
+    stream: u32,
+    block: u64,
+    buffer: [u32; 4],
+    // Words of `buffer` already handed out
+    used: usize,
+}
+
+impl CounterRng {
+    pub fn new(seed: u64) -> Self {
+        Self::with_stream(seed, 0)
+    }
+
+    // Streams under one seed never overlap, so each can go to a different
+    // consumer without coordinating
+    pub fn with_stream(seed: u64, stream: u32) -> Self {
+        Self {
+            key: key(seed),
+            stream,
+            block: 0,
+            buffer: [0; 4],
+            used: 4,
+        }
+    }
+
+    // Skips straight to the `block`th block of four words
+    pub fn seek(&mut self, block: u64) {
+        self.block = block;
+        self.used = 4;
+    }
+}
+
+impl RngCore for CounterRng {
+    fn next_u32(&mut self) -> u32 {
+        if self.used == 4 {
+            let counter = [self.block as u32, (self.block >> 32) as u32, self.stream, SEQUENTIAL];
+            self.buffer = philox(self.key, counter);
+            self.block = self.block.wrapping_add(1);
+            self.used = 0;
+        }
+        self.used += 1;
+        self.buffer[self.used - 1]
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let low = self.next_u32() as u64;
+        (self.next_u32() as u64) << 32 | low
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            let bytes = self.next_u32().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for CounterRng {
+    type Seed = [u8; 8];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        Self::new(u64::from_le_bytes(seed))
+    }
+
+    // The seed is the key as it is, with none of the default scrambling
+    fn seed_from_u64(seed: u64) -> Self {
+        Self::new(seed)
+    }
+}
+
+impl Dungeon {
+    // Noise where each tile's draw depends only on the seed and where the
+    // tile sits in the world, with the map's top-left corner at `origin`.
+    // Frozen tiles keep everything on them.
+    pub fn initialize_counter(&mut self, seed: u64, wall_probability: f64, origin: (i64, i64)) {
+        let frozen = &self.frozen;
+        self.features.retain(|(x, y), _| frozen[y][x]);
+        self.entities.retain(|(x, y), _| frozen[y][x]);
+        self.meta.retain(|(x, y), _| frozen[y][x]);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.frozen[y][x] {
+                    continue;
+                }
+                let wall = tile_unit(seed, origin.0 + x as i64, origin.1 + y as i64) < wall_probability;
+                self.tiles[y][x] = if wall { Tile::Wall } else { Tile::Floor };
+            }
+        }
+    }
+}
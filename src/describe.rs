@@ -0,0 +1,337 @@
+// A map told in words, for players reading it through a screen reader and
+// for tools that want its layout without its tiles. Each named region of
+// `Dungeon::region_labels` becomes a room with its size, where it lies,
+// what is in it and the ways out of it: the rooms it touches, each by the
+// compass direction it lies in from the middle of the room, turned to the
+// map's `WorldFrame` north, and whether a door stands between them. `to_text` is plain sentences, a line a room;
+// `to_json` is the same for a program.
+use crate::door::DoorState;
+use crate::dungeon::{Dungeon, Point};
+use crate::json;
+use crate::layers::Entity;
+use crate::world::WorldFrame;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Compass {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl Compass {
+    pub const ALL: [Compass; 8] = [
+        Compass::North,
+        Compass::NorthEast,
+        Compass::East,
+        Compass::SouthEast,
+        Compass::South,
+        Compass::SouthWest,
+        Compass::West,
+        Compass::NorthWest,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Compass::North => "north",
+            Compass::NorthEast => "north-east",
+            Compass::East => "east",
+            Compass::SouthEast => "south-east",
+            Compass::South => "south",
+            Compass::SouthWest => "south-west",
+            Compass::West => "west",
+            Compass::NorthWest => "north-west",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|compass| compass.name() == name)
+    }
+
+    // The nearest of the eight to the way from one point to another, rows
+    // counting down the map as south
+    pub fn toward(from: (f64, f64), to: (f64, f64)) -> Self {
+        let angle = (from.1 - to.1).atan2(to.0 - from.0).to_degrees();
+        // Clockwise from north in eighths
+        let eighth = ((90.0 - angle).rem_euclid(360.0) / 45.0).round() as usize % 8;
+        Self::ALL[eighth]
+    }
+}
+
+// A way from one room into another
+#[derive(Clone, Debug, PartialEq)]
+pub struct Passage {
+    pub direction: Compass,
+    // The region it leads to
+    pub to: usize,
+    pub name: String,
+    // The door between them, None where the floor runs straight through or
+    // any one of several ways is doorless
+    pub door: Option<DoorState>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Room {
+    pub region: usize,
+    pub name: String,
+    pub tiles: usize,
+    // "the north-west", "the middle" and so on
+    pub area: String,
+    // Entrance, exit, then monsters and items by kind with how many
+    pub contents: Vec<String>,
+    // Clockwise from north
+    pub exits: Vec<Passage>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Description {
+    pub width: usize,
+    pub height: usize,
+    pub world: WorldFrame,
+    pub rooms: Vec<Room>,
+    // The region holding the entrance, if it is one of the rooms
+    pub start: Option<usize>,
+}
+
+// Where two rooms meet, from the first's side
+#[derive(Default)]
+struct Contact {
+    points: Vec<Point>,
+    doors: Vec<DoorState>,
+    // Whether the floor runs straight across anywhere
+    open: bool,
+}
+
+fn size_word(tiles: usize) -> &'static str {
+    match tiles {
+        0..=24 => "small",
+        25..=80 => "medium-sized",
+        81..=250 => "large",
+        _ => "vast",
+    }
+}
+
+// "rat" for one, "rat (3)" for three
+fn counted(kinds: BTreeMap<&str, usize>) -> impl Iterator<Item = String> + '_ {
+    kinds.into_iter().map(|(kind, count)| if count == 1 { kind.to_string() } else { format!("{} ({})", kind, count) })
+}
+
+impl Dungeon {
+    pub fn describe(&self, seed: u64) -> Description {
+        let labels = self.region_labels(seed);
+        let (regions, _) = self.label_regions();
+        let named: BTreeMap<usize, &str> = labels.iter().map(|label| (label.region, label.name.as_str())).collect();
+        let region_at = |(x, y): Point| regions[y][x].filter(|region| named.contains_key(region));
+
+        let mut contacts: BTreeMap<(usize, usize), Contact> = BTreeMap::new();
+        let sides = |(x, y): Point| -> Vec<Point> {
+            let mut sides = Vec::new();
+            if x > 0 {
+                sides.push((x - 1, y));
+            }
+            if y > 0 {
+                sides.push((x, y - 1));
+            }
+            if x + 1 < self.width {
+                sides.push((x + 1, y));
+            }
+            if y + 1 < self.height {
+                sides.push((x, y + 1));
+            }
+            sides
+        };
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if let Some(door) = self.door(x, y) {
+                    // A door joins whichever rooms lie on either side of it
+                    let mut beside: Vec<(usize, Point)> =
+                        sides((x, y)).into_iter().filter_map(|side| Some((region_at(side)?, side))).collect();
+                    beside.sort();
+                    beside.dedup_by_key(|(region, _)| *region);
+                    for &(a, _) in &beside {
+                        for &(b, _) in &beside {
+                            if a != b {
+                                let contact = contacts.entry((a, b)).or_default();
+                                contact.points.push((x, y));
+                                contact.doors.push(door.state);
+                            }
+                        }
+                    }
+                    continue;
+                }
+                let Some(a) = region_at((x, y)) else {
+                    continue;
+                };
+                for side in sides((x, y)) {
+                    if let Some(b) = region_at(side).filter(|&b| b != a) {
+                        let contact = contacts.entry((a, b)).or_default();
+                        contact.points.push((x, y));
+                        contact.open = true;
+                    }
+                }
+            }
+        }
+
+        let mut entities: Vec<(Point, &Entity)> = self.entities.iter().collect();
+        entities.sort_by_key(|&((x, y), _)| (y, x));
+        let mut start = None;
+        let mut rooms = Vec::new();
+        for label in &labels {
+            let mut contents = Vec::new();
+            let (mut monsters, mut items) = (BTreeMap::new(), BTreeMap::new());
+            for &(point, entity) in &entities {
+                if region_at(point) != Some(label.region) {
+                    continue;
+                }
+                match entity {
+                    Entity::Entrance => {
+                        start = Some(label.region);
+                        contents.insert(0, "the entrance".to_string());
+                    }
+                    Entity::Exit => contents.push("the exit".to_string()),
+                    Entity::Monster(kind) => *monsters.entry(kind.as_str()).or_insert(0) += 1,
+                    Entity::Item(kind) => *items.entry(kind.as_str()).or_insert(0) += 1,
+                }
+            }
+            contents.extend(counted(monsters));
+            contents.extend(counted(items));
+
+            let mut exits: Vec<Passage> = contacts
+                .range((label.region, 0)..(label.region + 1, 0))
+                .map(|(&(_, to), contact)| {
+                    let count = contact.points.len() as f64;
+                    let cx = contact.points.iter().map(|&(x, _)| x as f64 + 0.5).sum::<f64>() / count;
+                    let cy = contact.points.iter().map(|&(_, y)| y as f64 + 0.5).sum::<f64>() / count;
+                    // The easiest of the doors, when every way goes through one
+                    let door = if contact.open {
+                        None
+                    } else {
+                        contact.doors.iter().copied().min_by_key(|&state| state as usize)
+                    };
+                    Passage {
+                        direction: self.world.north.compass(Compass::toward((label.x, label.y), (cx, cy))),
+                        to,
+                        name: named[&to].to_string(),
+                        door,
+                    }
+                })
+                .collect();
+            exits.sort_by_key(|exit| (exit.direction, exit.to));
+            rooms.push(Room {
+                region: label.region,
+                name: label.name.clone(),
+                tiles: label.tiles,
+                area: self.area((label.x as usize, label.y as usize)),
+                contents,
+                exits,
+            });
+        }
+        Description {
+            width: self.width,
+            height: self.height,
+            world: self.world,
+            rooms,
+            start,
+        }
+    }
+}
+
+impl Description {
+    fn name(&self, region: usize) -> Option<&str> {
+        self.rooms.iter().find(|room| room.region == region).map(|room| room.name.as_str())
+    }
+
+    // A line for the map and one a room, each a sentence or few a screen
+    // reader can read straight through
+    pub fn to_text(&self) -> String {
+        let mut out = format!("The map is {} by {} tiles with {} room(s).", self.width, self.height, self.rooms.len());
+        if let Some(name) = self.start.and_then(|region| self.name(region)) {
+            out.push_str(&format!(" You start in {}.", name));
+        }
+        out.push('\n');
+        for (number, room) in self.rooms.iter().enumerate() {
+            out.push_str(&format!(
+                "{}. {}, a {} room in {}, {} tiles.",
+                number + 1,
+                room.name,
+                size_word(room.tiles),
+                room.area,
+                room.tiles
+            ));
+            if !room.contents.is_empty() {
+                out.push_str(&format!(" Holds {}.", room.contents.join(", ")));
+            }
+            if room.exits.is_empty() {
+                out.push_str(" No ways out to another room.");
+            } else {
+                let exits: Vec<String> = room
+                    .exits
+                    .iter()
+                    .map(|exit| match exit.door {
+                        Some(door) => format!("{} to {}, through a {} door", exit.direction.name(), exit.name, door.name()),
+                        None => format!("{} to {}", exit.direction.name(), exit.name),
+                    })
+                    .collect();
+                out.push_str(&format!(" Exits: {}.", exits.join("; ")));
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let rooms: Vec<String> = self
+            .rooms
+            .iter()
+            .map(|room| {
+                let contents: Vec<String> = room.contents.iter().map(|thing| json::quote(thing)).collect();
+                let exits: Vec<String> = room
+                    .exits
+                    .iter()
+                    .map(|exit| {
+                        let door = exit.door.map_or("null".to_string(), |door| json::quote(door.name()));
+                        format!(
+                            "{{\"direction\":{},\"to\":{},\"name\":{},\"door\":{}}}",
+                            json::quote(exit.direction.name()),
+                            exit.to,
+                            json::quote(&exit.name),
+                            door
+                        )
+                    })
+                    .collect();
+                format!(
+                    "{{\"region\":{},\"name\":{},\"tiles\":{},\"area\":{},\"contents\":[{}],\"exits\":[{}]}}",
+                    room.region,
+                    json::quote(&room.name),
+                    room.tiles,
+                    json::quote(&room.area),
+                    contents.join(","),
+                    exits.join(",")
+                )
+            })
+            .collect();
+        let start = self.start.map_or("null".to_string(), |region| region.to_string());
+        format!(
+            "{{\"width\":{},\"height\":{},\"world\":{},\"start\":{},\"rooms\":[{}]}}\n",
+            self.width,
+            self.height,
+            self.world.to_json(),
+            start,
+            rooms.join(",")
+        )
+    }
+
+    // JSON for a .json path, the sentences otherwise
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let json = path.extension().is_some_and(|extension| extension == "json");
+        fs::write(path, if json { self.to_json() } else { self.to_text() })
+    }
+}
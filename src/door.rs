@@ -0,0 +1,289 @@
+use crate::dungeon::{Dungeon, Point, Tile};
+use crate::layers::Feature;
+use crate::pathfinding::regions_in;
+use crate::scratch::Scratch;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DoorState {
+    Open,
+    Closed,
+    Locked,
+    // Looks like wall until discovered
+    Secret,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DoorMaterial {
+    Wood,
+    Iron,
+    Stone,
+}
+
+// Direction the door's front faces; always along the passage it blocks
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Facing {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl DoorState {
+    pub const ALL: [DoorState; 4] = [DoorState::Open, DoorState::Closed, DoorState::Locked, DoorState::Secret];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DoorState::Open => "open",
+            DoorState::Closed => "closed",
+            DoorState::Locked => "locked",
+            DoorState::Secret => "secret",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|state| state.name() == name)
+    }
+}
+
+impl DoorMaterial {
+    pub const ALL: [DoorMaterial; 3] = [DoorMaterial::Wood, DoorMaterial::Iron, DoorMaterial::Stone];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DoorMaterial::Wood => "wood",
+            DoorMaterial::Iron => "iron",
+            DoorMaterial::Stone => "stone",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|material| material.name() == name)
+    }
+}
+
+impl Facing {
+    pub const ALL: [Facing; 4] = [Facing::North, Facing::East, Facing::South, Facing::West];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Facing::North => "north",
+            Facing::East => "east",
+            Facing::South => "south",
+            Facing::West => "west",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|facing| facing.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Door {
+    pub state: DoorState,
+    pub material: DoorMaterial,
+    pub facing: Facing,
+}
+
+impl Door {
+    pub fn new(state: DoorState, material: DoorMaterial, facing: Facing) -> Self {
+        Self { state, material, facing }
+    }
+
+    // Swings between open and closed; locked and secret doors don't budge.
+    // Returns whether the state changed.
+    pub fn toggle(&mut self) -> bool {
+        self.state = match self.state {
+            DoorState::Open => DoorState::Closed,
+            DoorState::Closed => DoorState::Open,
+            DoorState::Locked | DoorState::Secret => return false,
+        };
+        true
+    }
+}
+
+// Minimum Chebyshev distance between generated doors
+const DOOR_SPACING: usize = 4;
+// Widest gap that gets narrowed into a single-tile doorway
+const MAX_DOORWAY_GAP: usize = 3;
+
+// A spot where a passage can be closed off: the door tile, the other gap
+// tiles that get walled in around it, and which ways the door may face
+struct Doorway {
+    door: Point,
+    fill: Vec<Point>,
+    facings: [Facing; 2],
+}
+
+impl Dungeon {
+    pub fn door(&self, x: usize, y: usize) -> Option<&Door> {
+        match self.features.get(x, y) {
+            Some(Feature::Door(door)) => Some(door),
+            _ => None,
+        }
+    }
+
+    pub fn door_mut(&mut self, x: usize, y: usize) -> Option<&mut Door> {
+        match self.features.get_mut(x, y) {
+            Some(Feature::Door(door)) => Some(door),
+            _ => None,
+        }
+    }
+
+    pub fn doors(&self) -> impl Iterator<Item = (Point, &Door)> {
+        self.features.iter().filter_map(|(point, feature)| match feature {
+            Feature::Door(door) => Some((point, door)),
+            _ => None,
+        })
+    }
+
+    // Doors stand in a floor doorway, replacing any other feature there
+    pub fn add_door(&mut self, x: usize, y: usize, door: Door) {
+        self.tiles[y][x] = Tile::Floor;
+        self.features.set(x, y, Feature::Door(door));
+    }
+
+    // Removes the door and leaves an empty doorway behind
+    pub fn remove_door(&mut self, x: usize, y: usize) -> Option<Door> {
+        self.door(x, y)?;
+        match self.features.remove(x, y) {
+            Some(Feature::Door(door)) => Some(door),
+            _ => None,
+        }
+    }
+
+    pub fn toggle_door(&mut self, x: usize, y: usize) -> Option<DoorState> {
+        let door = self.door_mut(x, y)?;
+        door.toggle();
+        Some(door.state)
+    }
+
+    pub fn set_door_state(&mut self, x: usize, y: usize, state: DoorState) -> bool {
+        match self.door_mut(x, y) {
+            Some(door) => {
+                door.state = state;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn is_floor(&self, x: i32, y: i32) -> bool {
+        x >= 0
+            && y >= 0
+            && (x as usize) < self.width
+            && (y as usize) < self.height
+            && self.tiles[y as usize][x as usize] == Tile::Floor
+    }
+
+    // Cross-sections of passages at most MAX_DOORWAY_GAP tiles wide, walled at
+    // both ends, with the middle tile open on both sides along the passage
+    fn doorway_candidates(&self) -> Vec<Doorway> {
+        let mut candidates = Vec::new();
+        // (step across the gap, step along the passage, facings)
+        let axes = [
+            ((0, 1), (1, 0), [Facing::East, Facing::West]),
+            ((1, 0), (0, 1), [Facing::North, Facing::South]),
+        ];
+
+        for ((ax, ay), (px, py), facings) in axes {
+            for y in 0..self.height as i32 {
+                for x in 0..self.width as i32 {
+                    // Only start at the first tile of each gap
+                    if !self.is_floor(x, y) || self.is_floor(x - ax, y - ay) {
+                        continue;
+                    }
+                    let mut gap = Vec::new();
+                    let (mut gx, mut gy) = (x, y);
+                    while self.is_floor(gx, gy) && gap.len() <= MAX_DOORWAY_GAP {
+                        gap.push((gx as usize, gy as usize));
+                        gx += ax;
+                        gy += ay;
+                    }
+                    if gap.len() > MAX_DOORWAY_GAP {
+                        continue;
+                    }
+
+                    let door = gap[gap.len() / 2];
+                    let (dx, dy) = (door.0 as i32, door.1 as i32);
+                    if !self.is_floor(dx + px, dy + py) || !self.is_floor(dx - px, dy - py) {
+                        continue;
+                    }
+                    if gap.iter().any(|&(gx, gy)| self.features.contains(gx, gy)) {
+                        continue;
+                    }
+
+                    let fill = gap.into_iter().filter(|&point| point != door).collect();
+                    candidates.push(Doorway { door, fill, facings });
+                }
+            }
+        }
+
+        candidates
+    }
+
+    // Hangs up to `count` doors in narrow passages, walling in the rest of
+    // each gap so the door spans it. Returns how many were placed.
+    pub fn place_doors<R: Rng + ?Sized>(&mut self, count: usize, rng: &mut R) -> usize {
+        self.place_doors_in(count, rng, &mut Scratch::new())
+    }
+
+    pub fn place_doors_in<R: Rng + ?Sized>(&mut self, count: usize, rng: &mut R, scratch: &mut Scratch) -> usize {
+        let mut candidates = self.doorway_candidates();
+        candidates.shuffle(rng);
+
+        let mut placed: Vec<Point> = self.doors().map(|(point, _)| point).collect();
+        let mut region_count = regions_in(self, scratch).len();
+        let mut added = 0;
+
+        for Doorway { door: (x, y), fill, facings } in candidates {
+            if added == count {
+                break;
+            }
+            let crowded = placed
+                .iter()
+                .any(|&(px, py)| px.abs_diff(x) < DOOR_SPACING && py.abs_diff(y) < DOOR_SPACING);
+            let still_open = self.tiles[y][x] == Tile::Floor
+                && fill.iter().all(|&(fx, fy)| self.tiles[fy][fx] == Tile::Floor);
+            let frozen = self.frozen[y][x] || fill.iter().any(|&(fx, fy)| self.frozen[fy][fx]);
+            if crowded || !still_open || frozen {
+                continue;
+            }
+
+            // Narrowing must not cut anything off; undo it if it does
+            for &(fx, fy) in &fill {
+                self.tiles[fy][fx] = Tile::Wall;
+            }
+            let after = regions_in(self, scratch).len();
+            if after > region_count {
+                for &(fx, fy) in &fill {
+                    self.tiles[fy][fx] = Tile::Floor;
+                }
+                continue;
+            }
+            region_count = after;
+            for &(fx, fy) in &fill {
+                self.set_tile(fx, fy, Tile::Wall);
+            }
+
+            // Locking is left to `place_locks`, which hides a key for each
+            // lock; a locked or secret door here would shut off what lies
+            // behind it
+            let state = if rng.gen_range(0..3) == 0 { DoorState::Open } else { DoorState::Closed };
+            let material = match rng.gen_range(0..6) {
+                0..=3 => DoorMaterial::Wood,
+                4 => DoorMaterial::Iron,
+                _ => DoorMaterial::Stone,
+            };
+            let facing = *facings.choose(rng).unwrap();
+
+            self.add_door(x, y, Door::new(state, material, facing));
+            placed.push((x, y));
+            added += 1;
+        }
+
+        added
+    }
+}
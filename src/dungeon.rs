@@ -0,0 +1,485 @@
+use crate::capabilities::Capabilities;
+use crate::door::DoorState;
+use crate::exploration::{Exploration, Visibility};
+use crate::grid;
+use crate::layers::{Entity, Feature, Layer};
+use crate::meta::TileMeta;
+use crate::palette::{self, CustomTile};
+use crate::scratch::{cleared, Scratch};
+use crate::shading;
+use crate::theme::Theme;
+use crate::world::WorldFrame;
+use crossterm::{
+    cursor, execute, style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use rand::Rng;
+use std::io::{self, Write};
+
+pub type Point = (usize, usize);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Tile {
+    Wall,
+    Floor,
+    Water,
+    Rubble,
+    // Planks across water, laid by the archipelago's connectors
+    Bridge,
+    // A kind the program registered with the palette
+    Custom(CustomTile),
+}
+
+impl Tile {
+    // The built-in kinds
+    pub const ALL: [Tile; 5] = [Tile::Wall, Tile::Floor, Tile::Water, Tile::Rubble, Tile::Bridge];
+
+    pub fn is_walkable(self) -> bool {
+        match self {
+            Tile::Wall => false,
+            Tile::Custom(custom) => custom.kind().walkable,
+            _ => true,
+        }
+    }
+
+    pub fn is_opaque(self) -> bool {
+        match self {
+            Tile::Wall => true,
+            Tile::Custom(custom) => custom.kind().opaque,
+            _ => false,
+        }
+    }
+
+    // How `to_ascii` draws the bare tile
+    pub fn glyph(self) -> char {
+        match self {
+            Tile::Wall => '#',
+            Tile::Floor => '.',
+            Tile::Water => '~',
+            Tile::Rubble => '%',
+            Tile::Bridge => ':',
+            Tile::Custom(custom) => custom.kind().glyph,
+        }
+    }
+
+    pub fn from_glyph(glyph: char) -> Option<Self> {
+        Self::ALL.into_iter().find(|tile| tile.glyph() == glyph).or_else(|| palette::find_glyph(glyph))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Tile::Wall => "wall",
+            Tile::Floor => "floor",
+            Tile::Water => "water",
+            Tile::Rubble => "rubble",
+            Tile::Bridge => "bridge",
+            Tile::Custom(custom) => custom.kind().name,
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|tile| tile.name() == name).or_else(|| palette::find(name))
+    }
+
+    // One byte per tile for content hashes
+    pub(crate) fn code(self) -> u8 {
+        match self {
+            Tile::Custom(custom) => custom.code(),
+            tile => Self::ALL.iter().position(|&other| other == tile).unwrap() as u8,
+        }
+    }
+}
+
+// Cellular automaton thresholds over the 8 neighbours of a tile
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rules {
+    // Floor turns to wall when more neighbours than this are walls
+    pub birth_limit: usize,
+    // Walls crumble to floor when fewer neighbours than this are walls
+    pub death_limit: usize,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            birth_limit: 4,
+            death_limit: 4,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Dungeon {
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    // Base terrain layer; doors, traps and the like live in `features`
+    pub(crate) tiles: Vec<Vec<Tile>>,
+    pub(crate) features: Layer<Feature>,
+    pub(crate) entities: Layer<Entity>,
+    pub(crate) meta: Layer<TileMeta>,
+    // Tiles no generation pass may change
+    pub(crate) frozen: Vec<Vec<bool>>,
+    // Where the map sits in a larger world
+    pub(crate) world: WorldFrame,
+}
+
+impl Dungeon {
+    pub fn new(width: usize, height: usize) -> Self {
+        let tiles = vec![vec![Tile::Wall; width]; height];
+        Self {
+            width,
+            height,
+            tiles,
+            features: Layer::new(),
+            entities: Layer::new(),
+            meta: Layer::new(),
+            frozen: vec![vec![false; width]; height],
+            world: WorldFrame::default(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn tile(&self, x: usize, y: usize) -> Tile {
+        self.tiles[y][x]
+    }
+
+    // Walling over a tile also clears whatever stood on it
+    pub fn set_tile(&mut self, x: usize, y: usize, tile: Tile) {
+        if !tile.is_walkable() {
+            self.features.remove(x, y);
+            self.entities.remove(x, y);
+        }
+        self.tiles[y][x] = tile;
+    }
+
+    // Closed, locked and secret doors block sight just like walls
+    pub fn blocks_sight(&self, x: usize, y: usize) -> bool {
+        let tile = self.tiles[y][x];
+        tile.is_opaque() || (tile.is_walkable() && self.features.get(x, y).is_some_and(Feature::blocks_sight))
+    }
+
+    // Frozen tiles keep everything on them
+    pub fn initialize_random<R: Rng + ?Sized>(&mut self, wall_probability: f64, rng: &mut R) {
+        let frozen = &self.frozen;
+        self.features.retain(|(x, y), _| frozen[y][x]);
+        self.entities.retain(|(x, y), _| frozen[y][x]);
+        self.meta.retain(|(x, y), _| frozen[y][x]);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                // Drawn either way so freezing never shifts the rest of the map
+                let wall = rng.gen::<f64>() < wall_probability;
+                if !self.frozen[y][x] {
+                    self.tiles[y][x] = if wall { Tile::Wall } else { Tile::Floor };
+                }
+            }
+        }
+    }
+
+    pub(crate) fn count_wall_neighbors(&self, x: usize, y: usize) -> usize {
+        grid::wall_neighbours(self, x, y)
+    }
+
+    pub fn simulate_step(&mut self) -> bool {
+        self.simulate_step_with(&Rules::default())
+    }
+
+    pub fn simulate_step_with(&mut self, rules: &Rules) -> bool {
+        self.simulate_step_in(rules, &mut Scratch::new())
+    }
+
+    // The same, building the next map in `scratch` rather than a new grid
+    pub fn simulate_step_in(&mut self, rules: &Rules, scratch: &mut Scratch) -> bool {
+        let mut new_tiles = std::mem::take(&mut scratch.tiles);
+        // Every tile is overwritten below, so only the shape has to match
+        if new_tiles.len() != self.height || new_tiles.first().is_some_and(|row| row.len() != self.width) {
+            new_tiles = self.tiles.clone();
+        }
+        let mut changed = false;
+        // Rows are read and written front to back, with the wall counts
+        // built from running column sums rather than eight lookups a tile.
+        // Off the map counts as wall: a solid row stands in above and below
+        // it, and the sums carry a solid column either side.
+        let solid = cleared(&mut scratch.solid, self.width, Tile::Wall);
+        let columns = cleared(&mut scratch.column_walls, self.width + 2, 3);
+        for (y, new_row) in new_tiles.iter_mut().enumerate() {
+            let here = &self.tiles[y];
+            let above = y.checked_sub(1).map_or(&*solid, |above| &self.tiles[above][..]);
+            let below = self.tiles.get(y + 1).map_or(&*solid, |below| &below[..]);
+            for (column, ((above, here), below)) in columns[1..].iter_mut().zip(above.iter().zip(here).zip(below)) {
+                *column = [above, here, below].into_iter().filter(|tile| !tile.is_walkable()).count();
+            }
+
+            let cells = new_row.iter_mut().zip(here).zip(&self.frozen[y]).zip(columns.windows(3));
+            for (((cell, &tile), &frozen), around) in cells {
+                let wall_count = around.iter().sum::<usize>() - usize::from(!tile.is_walkable());
+
+                // Cellular automata rules for cave generation
+                let new_tile = match tile {
+                    tile if frozen => tile,
+                    Tile::Wall if wall_count < rules.death_limit => Tile::Floor,
+                    tile if tile.is_walkable() && wall_count > rules.birth_limit => Tile::Wall,
+                    tile => tile,
+                };
+
+                changed |= new_tile != tile;
+                *cell = new_tile;
+            }
+        }
+
+        scratch.tiles = std::mem::replace(&mut self.tiles, new_tiles);
+        self.prune_layers();
+        changed
+    }
+
+    pub fn render(&self) -> io::Result<()> {
+        self.render_themed(&Theme::default())
+    }
+
+    pub fn render_themed(&self, theme: &Theme) -> io::Result<()> {
+        self.render_view(theme, &View::whole(self))
+    }
+
+    // Draws the part of the map inside `view`; cells reaching past the map
+    // edge are left blank
+    pub fn render_view(&self, theme: &Theme, view: &View) -> io::Result<()> {
+        let mut stdout = io::stdout();
+        let (cell_width, cell_height) = view.zoom.cell();
+        let capabilities = Capabilities::detect();
+        for row in 0..view.rows {
+            execute!(stdout, cursor::MoveTo(view.screen.0, view.screen.1 + row as u16))?;
+            for column in 0..view.columns {
+                let (x, y) = (view.origin.0 + column * cell_width, view.origin.1 + row * cell_height);
+                if x >= self.width || y >= self.height {
+                    execute!(stdout, Print(' '))?;
+                    continue;
+                }
+                let (color, glyph) = match view.zoom {
+                    Zoom::Tile if capabilities.truecolor => self.shaded_glyph(theme, x, y),
+                    Zoom::Tile => self.styled_glyph(theme, x, y),
+                    Zoom::Block => self.block_glyph(theme, x, y),
+                    Zoom::Braille => self.braille_glyph(theme, x, y),
+                };
+                if capabilities.color {
+                    execute!(stdout, SetForegroundColor(color), Print(glyph), ResetColor)?;
+                } else {
+                    execute!(stdout, Print(glyph))?;
+                }
+            }
+        }
+        stdout.flush()
+    }
+
+    // The map under fog of war, one tile per character, with `player` on
+    // top. Remembered tiles are dimmed and show the ground and the way in and
+    // out, but not whoever was standing there when they were last in sight.
+    pub fn render_explored(
+        &self,
+        theme: &Theme,
+        exploration: &Exploration,
+        player: Point,
+        screen: (u16, u16),
+    ) -> io::Result<()> {
+        let mut stdout = io::stdout();
+        let colored = Capabilities::detect().color;
+        for y in 0..self.height {
+            execute!(stdout, cursor::MoveTo(screen.0, screen.1 + y as u16))?;
+            for x in 0..self.width {
+                let (color, glyph) = match exploration.visibility(x, y) {
+                    _ if (x, y) == player => (Color::Yellow, '@'),
+                    Visibility::Visible => self.styled_glyph(theme, x, y),
+                    Visibility::Remembered => match self.entities.get(x, y) {
+                        Some(Entity::Entrance | Entity::Exit) => (Color::DarkGrey, self.styled_glyph(theme, x, y).1),
+                        _ => (Color::DarkGrey, self.ground_glyph(theme, x, y).1),
+                    },
+                    Visibility::Unseen => (Color::Reset, ' '),
+                };
+                if colored {
+                    execute!(stdout, SetForegroundColor(color), Print(glyph), ResetColor)?;
+                } else {
+                    execute!(stdout, Print(glyph))?;
+                }
+            }
+        }
+        stdout.flush()
+    }
+
+    // Bare walkable ground in its theme color darkened by the walls around
+    // it; anything else as usual
+    fn shaded_glyph(&self, theme: &Theme, x: usize, y: usize) -> (Color, char) {
+        let tile = self.tiles[y][x];
+        if theme.shading == 0.0 || !tile.is_walkable() || self.features.contains(x, y) || self.entities.contains(x, y) {
+            return self.styled_glyph(theme, x, y);
+        }
+        let style = theme.style(tile);
+        let [r, g, b] = shading::shade(style.rgb, self.occlusion(x, y), theme.shading);
+        (Color::Rgb { r, g, b }, style.glyph)
+    }
+
+    // The most common terrain in a 2x2 block, with anything standing in it
+    // taking precedence so entrances and monsters stay visible when zoomed out
+    fn block_glyph(&self, theme: &Theme, x: usize, y: usize) -> (Color, char) {
+        let mut counts = Vec::with_capacity(4);
+        for ty in y..(y + 2).min(self.height) {
+            for tx in x..(x + 2).min(self.width) {
+                if self.entities.contains(tx, ty) {
+                    return self.styled_glyph(theme, tx, ty);
+                }
+                let tile = self.tiles[ty][tx];
+                match counts.iter_mut().find(|(other, _)| *other == tile) {
+                    Some((_, count)) => *count += 1,
+                    None => counts.push((tile, 1)),
+                }
+            }
+        }
+        // Ties go to walkable tiles, then later ones, so thin passages survive
+        let (tile, _) = counts
+            .into_iter()
+            .max_by_key(|&(tile, count)| (count, tile.is_walkable(), tile.code()))
+            .unwrap();
+        let style = theme.style(tile);
+        (style.color, style.glyph)
+    }
+
+    // 2x4 tiles per character, with a raised dot for every walkable tile
+    fn braille_glyph(&self, theme: &Theme, x: usize, y: usize) -> (Color, char) {
+        const DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+        let mut bits = 0;
+        for (dy, row) in DOTS.iter().enumerate() {
+            for (dx, bit) in row.iter().enumerate() {
+                let (tx, ty) = (x + dx, y + dy);
+                if tx < self.width && ty < self.height && self.tiles[ty][tx].is_walkable() {
+                    bits |= bit;
+                }
+            }
+        }
+        (theme.floor.color, char::from_u32(0x2800 + bits).unwrap())
+    }
+
+    // Entities draw over features, which draw over terrain
+    fn styled_glyph(&self, theme: &Theme, x: usize, y: usize) -> (Color, char) {
+        if let Some(entity) = self.entities.get(x, y) {
+            let color = match entity {
+                Entity::Entrance => Color::Cyan,
+                Entity::Exit => Color::Green,
+                Entity::Monster(_) => Color::Red,
+                Entity::Item(_) => Color::Magenta,
+            };
+            return (color, entity.glyph());
+        }
+        self.ground_glyph(theme, x, y)
+    }
+
+    // Features over terrain, leaving out anything standing there
+    fn ground_glyph(&self, theme: &Theme, x: usize, y: usize) -> (Color, char) {
+        if let Some(feature) = self.features.get(x, y) {
+            return match feature {
+                Feature::Door(door) => match door.state {
+                    DoorState::Open => (Color::DarkYellow, '\''),
+                    DoorState::Closed => (Color::DarkYellow, '+'),
+                    DoorState::Locked => (Color::Red, '+'),
+                    // Secret doors stay hidden on the map
+                    DoorState::Secret => (theme.wall.color, theme.wall.glyph),
+                },
+                Feature::Trap(_) => (Color::Magenta, '^'),
+                Feature::Decoration(_) => (Color::Grey, '&'),
+            };
+        }
+        let style = theme.style(self.tiles[y][x]);
+        (style.color, style.glyph)
+    }
+}
+
+// How many map tiles each terminal character stands for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Zoom {
+    #[default]
+    Tile,
+    // 2x2 tiles per character, drawn as the majority tile
+    Block,
+    // 2x4 tiles per character as Braille dots
+    Braille,
+}
+
+impl Zoom {
+    pub fn cell(self) -> (usize, usize) {
+        match self {
+            Zoom::Tile => (1, 1),
+            Zoom::Block => (2, 2),
+            Zoom::Braille => (2, 4),
+        }
+    }
+
+    pub fn zoom_out(self) -> Self {
+        match self {
+            Zoom::Tile => Zoom::Block,
+            Zoom::Block | Zoom::Braille => Zoom::Braille,
+        }
+    }
+
+    pub fn zoom_in(self) -> Self {
+        match self {
+            Zoom::Tile | Zoom::Block => Zoom::Tile,
+            Zoom::Braille => Zoom::Block,
+        }
+    }
+}
+
+// A window onto the map: `columns` x `rows` characters covering the map from
+// tile `origin`, drawn with its top-left corner at terminal cell `screen`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct View {
+    pub origin: Point,
+    pub columns: usize,
+    pub rows: usize,
+    pub screen: (u16, u16),
+    pub zoom: Zoom,
+}
+
+impl View {
+    pub fn whole(dungeon: &Dungeon) -> Self {
+        Self::fit(dungeon, Zoom::Tile)
+    }
+
+    // The whole map at the given zoom
+    pub fn fit(dungeon: &Dungeon, zoom: Zoom) -> Self {
+        let (cell_width, cell_height) = zoom.cell();
+        Self {
+            origin: (0, 0),
+            columns: dungeon.width.div_ceil(cell_width),
+            rows: dungeon.height.div_ceil(cell_height),
+            screen: (0, 0),
+            zoom,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_block_half_wall_half_floor_shows_floor() {
+        let theme = Theme::default();
+        let floor = theme.style(Tile::Floor);
+        let mut dungeon = Dungeon::new(4, 2);
+        // A passage one tile wide down the block's left column, and one
+        // across the bottom of the next
+        dungeon.set_tile(0, 0, Tile::Floor);
+        dungeon.set_tile(0, 1, Tile::Floor);
+        dungeon.set_tile(2, 1, Tile::Floor);
+        dungeon.set_tile(3, 1, Tile::Floor);
+        assert_eq!(dungeon.block_glyph(&theme, 0, 0), (floor.color, floor.glyph));
+        assert_eq!(dungeon.block_glyph(&theme, 2, 0), (floor.color, floor.glyph));
+
+        // A wall past the tie still carries the block
+        dungeon.set_tile(3, 1, Tile::Wall);
+        let wall = theme.style(Tile::Wall);
+        assert_eq!(dungeon.block_glyph(&theme, 2, 0), (wall.color, wall.glyph));
+    }
+}
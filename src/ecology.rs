@@ -0,0 +1,200 @@
+// Anchors for games that simulate the creatures living in a map rather than
+// just placing them: nests on the shores of water, fungus on rock in the damp
+// around it, and predator dens in the reaches furthest from the entrance.
+// Damp is read off the moisture layer, which spreads out from water over
+// walkable ground and fades with every step. Each anchor is a floor tile
+// tagged under `ANCHOR_KEY` with its kind, kept `spacing` tiles clear of
+// any other, and listed with the spawns when the map is bundled.
+use crate::dungeon::{Dungeon, Point, Tile};
+use crate::pathfinding::{bfs_distances, entrance_and_exit};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::VecDeque;
+
+pub const ANCHOR_KEY: &str = "anchor";
+
+// Steps from water before the ground is dry
+pub const DAMP_REACH: usize = 8;
+
+// Moisture at the water's edge and up to two steps from it
+const SHORE: f64 = 1.0 - 2.0 / DAMP_REACH as f64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Anchor {
+    // Where creatures breed, by the water
+    Nest,
+    // A food source, against the rock in damp ground
+    Fungus,
+    // Where a predator lairs, far off the beaten track
+    Den,
+}
+
+impl Anchor {
+    pub const ALL: [Anchor; 3] = [Anchor::Nest, Anchor::Fungus, Anchor::Den];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Anchor::Nest => "nest",
+            Anchor::Fungus => "fungus",
+            Anchor::Den => "den",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|anchor| anchor.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EcologyOptions {
+    // How many of each to place at most; a map without water gets no
+    // nests or fungus
+    pub nests: usize,
+    pub fungus: usize,
+    pub dens: usize,
+    // Fewest tiles between any two anchors, across rows, columns or diagonals
+    pub spacing: usize,
+}
+
+impl Default for EcologyOptions {
+    fn default() -> Self {
+        Self {
+            nests: 0,
+            fungus: 0,
+            dens: 0,
+            spacing: 5,
+        }
+    }
+}
+
+impl Dungeon {
+    // From 1 on water down to 0 once `DAMP_REACH` walkable steps away from
+    // it; ground water can't be walked to stays dry
+    pub fn moisture(&self) -> Vec<Vec<f64>> {
+        let mut steps = vec![vec![usize::MAX; self.width]; self.height];
+        let mut queue = VecDeque::new();
+        for (y, row) in self.tiles.iter().enumerate() {
+            for (x, &tile) in row.iter().enumerate() {
+                if tile == Tile::Water {
+                    steps[y][x] = 0;
+                    queue.push_back((x, y));
+                }
+            }
+        }
+        while let Some((x, y)) = queue.pop_front() {
+            if steps[y][x] == DAMP_REACH {
+                continue;
+            }
+            for (dx, dy) in [(0, -1), (1, 0), (0, 1), (-1, 0)] {
+                let (nx, ny) = (x as isize + dx, y as isize + dy);
+                if nx < 0 || ny < 0 || nx as usize >= self.width || ny as usize >= self.height {
+                    continue;
+                }
+                let (nx, ny) = (nx as usize, ny as usize);
+                if steps[ny][nx] == usize::MAX && self.tiles[ny][nx].is_walkable() {
+                    steps[ny][nx] = steps[y][x] + 1;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+        steps
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|steps| 1.0 - steps.min(DAMP_REACH) as f64 / DAMP_REACH as f64)
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Returns the anchors placed, fewer than asked for where the map has too
+    // few fitting spots
+    pub fn place_anchors<R: Rng + ?Sized>(&mut self, options: &EcologyOptions, rng: &mut R) -> usize {
+        if options.nests + options.fungus + options.dens == 0 {
+            return 0;
+        }
+        let moisture = self.moisture();
+        let entrance = entrance_and_exit(self).map(|(entrance, _)| entrance);
+        let reach = entrance.map(|entrance| bfs_distances(self, entrance));
+        let farthest = reach.iter().flatten().flatten().flatten().copied().max().unwrap_or(0);
+
+        let mut placed = 0;
+        let counts = [options.nests, options.fungus, options.dens];
+        for (anchor, count) in Anchor::ALL.into_iter().zip(counts) {
+            let mut spots: Vec<Point> = Vec::new();
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    if self.tiles[y][x] != Tile::Floor
+                        || self.frozen[y][x]
+                        || self.features.contains(x, y)
+                        || self.entities.contains(x, y)
+                    {
+                        continue;
+                    }
+                    let fits = match anchor {
+                        Anchor::Nest => moisture[y][x] >= SHORE,
+                        Anchor::Fungus => moisture[y][x] > 0.0 && moisture[y][x] < SHORE && self.against_rock(x, y),
+                        // The last quarter of the walk out from the entrance
+                        Anchor::Den => reach
+                            .as_ref()
+                            .and_then(|reach| reach[y][x])
+                            .is_some_and(|steps| steps > 0 && steps * 4 >= farthest * 3),
+                    };
+                    if fits {
+                        spots.push((x, y));
+                    }
+                }
+            }
+            spots.shuffle(rng);
+            let mut left = count;
+            for (x, y) in spots {
+                if left == 0 {
+                    break;
+                }
+                if self.anchor_near(x, y, options.spacing) {
+                    continue;
+                }
+                self.set_meta(x, y, ANCHOR_KEY, anchor.name());
+                left -= 1;
+                placed += 1;
+            }
+        }
+        placed
+    }
+
+    fn against_rock(&self, x: usize, y: usize) -> bool {
+        [(0, -1), (1, 0), (0, 1), (-1, 0)].into_iter().any(|(dx, dy)| {
+            let (nx, ny) = (x as isize + dx, y as isize + dy);
+            nx >= 0
+                && ny >= 0
+                && (nx as usize) < self.width
+                && (ny as usize) < self.height
+                && self.tiles[ny as usize][nx as usize] == Tile::Wall
+        })
+    }
+
+    fn anchor_near(&self, x: usize, y: usize, spacing: usize) -> bool {
+        for ny in y.saturating_sub(spacing)..(y + spacing + 1).min(self.height) {
+            for nx in x.saturating_sub(spacing)..(x + spacing + 1).min(self.width) {
+                if self.anchor(nx, ny).is_some() {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    pub fn anchor(&self, x: usize, y: usize) -> Option<Anchor> {
+        self.meta(x, y)?.get(ANCHOR_KEY)?.as_str().and_then(Anchor::parse)
+    }
+
+    // Every anchor in reading order
+    pub fn anchors(&self) -> Vec<(Point, Anchor)> {
+        let mut anchors: Vec<(Point, Anchor)> = self
+            .find_meta(ANCHOR_KEY)
+            .filter_map(|(point, value)| value.as_str().and_then(Anchor::parse).map(|anchor| (point, anchor)))
+            .collect();
+        anchors.sort_by_key(|&((x, y), _)| (y, x));
+        anchors
+    }
+}
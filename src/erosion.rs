@@ -0,0 +1,102 @@
+use crate::dungeon::{Dungeon, Tile};
+use rand::Rng;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ErosionOptions {
+    pub iterations: usize,
+    // Chance per iteration that a fully exposed wall crumbles; walls with
+    // fewer open neighbours erode proportionally less often
+    pub strength: f64,
+}
+
+impl Default for ErosionOptions {
+    fn default() -> Self {
+        Self {
+            iterations: 0,
+            strength: 0.3,
+        }
+    }
+}
+
+// Walls need at least this many open neighbours to erode, which spares
+// straight wall faces and lets corners and corridor sides go first
+const MIN_EXPOSURE: usize = 3;
+
+impl Dungeon {
+    // Weathers walls into floor: jutting corners crumble soonest, corridor
+    // sides next, so rooms round off and passages widen unevenly. Walls
+    // beside doors are left standing so doorways keep their frames, and the
+    // map's edge stays walled. Returns how many tiles were opened up.
+    pub fn erode<R: Rng + ?Sized>(&mut self, options: &ErosionOptions, rng: &mut R) -> usize {
+        let mut eroded = 0;
+
+        for _ in 0..options.iterations {
+            // Decide against the map as it stood when the iteration began so
+            // erosion doesn't sweep along a row in scan order
+            let mut crumbled = Vec::new();
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    let edge = x == 0 || y == 0 || x + 1 == self.width || y + 1 == self.height;
+                    if edge || self.tiles[y][x] != Tile::Wall || self.frozen[y][x] || self.frames_door(x, y) {
+                        continue;
+                    }
+                    let exposure = 8 - self.count_wall_neighbors(x, y);
+                    if exposure >= MIN_EXPOSURE
+                        && rng.gen::<f64>() < options.strength * exposure as f64 / 8.0
+                    {
+                        crumbled.push((x, y));
+                    }
+                }
+            }
+            for &(x, y) in &crumbled {
+                self.tiles[y][x] = Tile::Floor;
+            }
+            eroded += crumbled.len();
+        }
+
+        eroded
+    }
+
+    fn frames_door(&self, x: usize, y: usize) -> bool {
+        let (x, y) = (x as i32, y as i32);
+        [(x, y - 1), (x + 1, y), (x, y + 1), (x - 1, y)]
+            .iter()
+            .any(|&(nx, ny)| nx >= 0 && ny >= 0 && self.door(nx as usize, ny as usize).is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use crate::generator::rng_for;
+
+    #[test]
+    fn erosion_leaves_the_border_walled() {
+        // Floor right up against the edge, so every border wall is as
+        // exposed as a wall there can be
+        let mut dungeon = fixtures::all_open(12, 8);
+        for x in 0..12 {
+            dungeon.set_tile(x, 0, Tile::Wall);
+            dungeon.set_tile(x, 7, Tile::Wall);
+        }
+        for y in 0..8 {
+            dungeon.set_tile(0, y, Tile::Wall);
+            dungeon.set_tile(11, y, Tile::Wall);
+        }
+        // A pillar in the middle, which is fair game
+        dungeon.set_tile(5, 4, Tile::Wall);
+        let options = ErosionOptions {
+            iterations: 20,
+            strength: 1.0,
+        };
+        assert_eq!(dungeon.erode(&options, &mut rng_for(1)), 1);
+        assert_eq!(dungeon.tiles[4][5], Tile::Floor);
+        for y in 0..8 {
+            for x in 0..12 {
+                let edge = x == 0 || y == 0 || x == 11 || y == 7;
+                assert_eq!(dungeon.tiles[y][x] == Tile::Wall, edge, "({}, {})", x, y);
+            }
+        }
+    }
+}
@@ -0,0 +1,146 @@
+// Things happening while a map is generated or explored, for game front ends
+// to hang sounds or anything else on. Whatever implements `Observer` hears
+// about them as they happen; so does any `FnMut(&Event)` closure, a
+// `Vec<Event>` collects them and `()` ignores them.
+use crate::door::DoorState;
+use crate::dungeon::{Dungeon, Point};
+use crate::exploration::Exploration;
+use crate::pathfinding::PathOptions;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    // An automaton step ran, counting from 1; `changed` is false once the
+    // map has settled
+    Step { step: usize, changed: bool },
+    // A post-pass finished, by its config name
+    Pass(&'static str),
+    // The explorer stepped onto a tile
+    Moved(Point),
+    // The explorer pushed a closed door open
+    DoorOpened(Point),
+    // Some of a room came into view for the first time, `tiles` of it so
+    // far, with `at` the first of them in reading order
+    RegionRevealed { region: usize, tiles: usize, at: Point },
+}
+
+pub trait Observer {
+    fn observe(&mut self, event: &Event);
+}
+
+impl Observer for () {
+    fn observe(&mut self, _: &Event) {}
+}
+
+// Keeps everything it hears, for front ends that deal with events between
+// frames
+impl Observer for Vec<Event> {
+    fn observe(&mut self, event: &Event) {
+        self.push(event.clone());
+    }
+}
+
+impl<F: FnMut(&Event)> Observer for F {
+    fn observe(&mut self, event: &Event) {
+        self(event)
+    }
+}
+
+// Rooms as the explorer meets them: walkable ground bounded by walls and
+// doors, the doors themselves belonging to none
+pub(crate) fn room_labels(dungeon: &Dungeon) -> (Vec<Vec<Option<usize>>>, usize) {
+    let mut labels = vec![vec![None; dungeon.width]; dungeon.height];
+    let is_room = |x: usize, y: usize| dungeon.tiles[y][x].is_walkable() && dungeon.door(x, y).is_none();
+    let mut rooms = 0;
+    for y in 0..dungeon.height {
+        for x in 0..dungeon.width {
+            if labels[y][x].is_some() || !is_room(x, y) {
+                continue;
+            }
+            labels[y][x] = Some(rooms);
+            let mut stack = vec![(x, y)];
+            while let Some((cx, cy)) = stack.pop() {
+                for (dx, dy) in [(0, -1), (1, 0), (0, 1), (-1, 0)] {
+                    let (nx, ny) = (cx as i32 + dx, cy as i32 + dy);
+                    if nx < 0 || ny < 0 || nx as usize >= dungeon.width || ny as usize >= dungeon.height {
+                        continue;
+                    }
+                    let (nx, ny) = (nx as usize, ny as usize);
+                    if labels[ny][nx].is_none() && is_room(nx, ny) {
+                        labels[ny][nx] = Some(rooms);
+                        stack.push((nx, ny));
+                    }
+                }
+            }
+            rooms += 1;
+        }
+    }
+    (labels, rooms)
+}
+
+// Announces each room of a map once, the first time any of it is seen
+pub struct RegionWatch {
+    labels: Vec<Vec<Option<usize>>>,
+    revealed: Vec<bool>,
+}
+
+impl RegionWatch {
+    pub fn new(dungeon: &Dungeon) -> Self {
+        let (labels, rooms) = room_labels(dungeon);
+        Self {
+            labels,
+            revealed: vec![false; rooms],
+        }
+    }
+
+    pub fn rooms(&self) -> usize {
+        self.revealed.len()
+    }
+
+    pub fn revealed(&self) -> usize {
+        self.revealed.iter().filter(|&&revealed| revealed).count()
+    }
+
+    // Call after each look around; sends a `RegionRevealed` for every room
+    // that just came into view
+    pub fn update<O: Observer + ?Sized>(&mut self, exploration: &Exploration, observer: &mut O) {
+        let mut found: Vec<(usize, usize, Point)> = Vec::new();
+        for (y, row) in self.labels.iter().enumerate().take(exploration.height) {
+            for (x, label) in row.iter().enumerate().take(exploration.width) {
+                let Some(region) = *label else {
+                    continue;
+                };
+                if self.revealed[region] || !exploration.is_seen(x, y) {
+                    continue;
+                }
+                match found.iter_mut().find(|(room, _, _)| *room == region) {
+                    Some((_, tiles, _)) => *tiles += 1,
+                    None => found.push((region, 1, (x, y))),
+                }
+            }
+        }
+        for (region, tiles, at) in found {
+            self.revealed[region] = true;
+            observer.observe(&Event::RegionRevealed { region, tiles, at });
+        }
+    }
+}
+
+// Walks onto `to` if the path options allow it, pushing open a closed door in
+// the way first. Returns whether the step was taken.
+pub fn step_onto<O: Observer + ?Sized>(
+    dungeon: &mut Dungeon,
+    to: Point,
+    options: &PathOptions,
+    observer: &mut O,
+) -> bool {
+    if options.step_cost(dungeon, to).is_none() {
+        return false;
+    }
+    let (x, y) = to;
+    if dungeon.door(x, y).is_some_and(|door| door.state == DoorState::Closed) {
+        dungeon.set_door_state(x, y, DoorState::Open);
+        observer.observe(&Event::DoorOpened(to));
+    }
+    observer.observe(&Event::Moved(to));
+    true
+}
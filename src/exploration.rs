@@ -0,0 +1,190 @@
+// Fog of war: which tiles are in sight right now and which have been seen
+// before, kept beside the map rather than in it so one dungeon can carry a
+// separate record per explorer
+use crate::dungeon::{Dungeon, Point};
+use crate::fov::compute_fov;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Visibility {
+    Unseen,
+    // Seen before but out of sight now
+    Remembered,
+    Visible,
+}
+
+impl Visibility {
+    // One digit per tile in `to_text`
+    fn digit(self) -> char {
+        match self {
+            Visibility::Unseen => '0',
+            Visibility::Remembered => '1',
+            Visibility::Visible => '2',
+        }
+    }
+
+    fn parse(digit: char) -> Option<Self> {
+        [Visibility::Unseen, Visibility::Remembered, Visibility::Visible]
+            .into_iter()
+            .find(|visibility| visibility.digit() == digit)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Exploration {
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    // Ever in sight, visible tiles included
+    pub(crate) seen: Vec<Vec<bool>>,
+    // In sight as of the last update
+    pub(crate) visible: Vec<Vec<bool>>,
+}
+
+impl Exploration {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            seen: vec![vec![false; width]; height],
+            visible: vec![vec![false; width]; height],
+        }
+    }
+
+    pub fn for_dungeon(dungeon: &Dungeon) -> Self {
+        Self::new(dungeon.width, dungeon.height)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    // Takes a field of view such as `compute_fov` returns: what it shows
+    // becomes visible and is remembered from then on, and everything else
+    // drops out of sight
+    pub fn update(&mut self, visible: &[Vec<bool>]) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let lit = visible.get(y).and_then(|row| row.get(x)).copied().unwrap_or(false);
+                self.visible[y][x] = lit;
+                self.seen[y][x] |= lit;
+            }
+        }
+    }
+
+    // Looks around from `origin`
+    pub fn look(&mut self, dungeon: &Dungeon, origin: Point, radius: usize) {
+        self.update(&compute_fov(dungeon, origin, radius));
+    }
+
+    pub fn visibility(&self, x: usize, y: usize) -> Visibility {
+        match (self.visible[y][x], self.seen[y][x]) {
+            (true, _) => Visibility::Visible,
+            (false, true) => Visibility::Remembered,
+            (false, false) => Visibility::Unseen,
+        }
+    }
+
+    pub fn is_visible(&self, x: usize, y: usize) -> bool {
+        self.visible[y][x]
+    }
+
+    pub fn is_seen(&self, x: usize, y: usize) -> bool {
+        self.seen[y][x]
+    }
+
+    pub fn is_remembered(&self, x: usize, y: usize) -> bool {
+        self.visibility(x, y) == Visibility::Remembered
+    }
+
+    pub fn seen_count(&self) -> usize {
+        self.seen.iter().flatten().filter(|&&seen| seen).count()
+    }
+
+    // Share of the map's walkable tiles seen so far, 1 on a map with none
+    pub fn explored(&self, dungeon: &Dungeon) -> f64 {
+        let mut walkable = 0;
+        let mut seen = 0;
+        for y in 0..self.height.min(dungeon.height) {
+            for x in 0..self.width.min(dungeon.width) {
+                if dungeon.tiles[y][x].is_walkable() {
+                    walkable += 1;
+                    seen += self.seen[y][x] as usize;
+                }
+            }
+        }
+        if walkable == 0 {
+            1.0
+        } else {
+            seen as f64 / walkable as f64
+        }
+    }
+
+    // Everything out of sight, as after a blindness spell
+    pub fn hide(&mut self) {
+        for row in &mut self.visible {
+            row.fill(false);
+        }
+    }
+
+    // Back to nothing seen, as on a fresh visit
+    pub fn forget(&mut self) {
+        *self = Self::new(self.width, self.height);
+    }
+
+    // A row of digits per map row: 0 unseen, 1 remembered, 2 visible.
+    // `from_text` reads it back.
+    pub fn to_text(&self) -> String {
+        let mut out = String::with_capacity((self.width + 1) * self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                out.push(self.visibility(x, y).digit());
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn from_text(text: &str) -> io::Result<Self> {
+        let lines: Vec<&str> = text.lines().collect();
+        let width = lines.first().map_or(0, |line| line.chars().count());
+        if width == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "exploration is empty"));
+        }
+        let mut exploration = Self::new(width, lines.len());
+        for (y, line) in lines.iter().enumerate() {
+            if line.chars().count() != width {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {} is not {} tiles wide", y + 1, width),
+                ));
+            }
+            for (x, digit) in line.chars().enumerate() {
+                let visibility = Visibility::parse(digit).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("unknown exploration digit '{}' at line {}, column {}", digit, y + 1, x + 1),
+                    )
+                })?;
+                exploration.visible[y][x] = visibility == Visibility::Visible;
+                exploration.seen[y][x] = visibility != Visibility::Unseen;
+            }
+        }
+        Ok(exploration)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::from_text(&text)
+            .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", path.display(), error)))
+    }
+}
@@ -0,0 +1,95 @@
+use crate::config::{invalid, read_usize};
+use crate::layers::LayerKind;
+use crate::toml::{self, Value};
+use crate::{Config, Dungeon};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+// A bookmarked map: the full config including its seed, plus a hash of the
+// layout it produced so a regenerated copy can be checked against it
+#[derive(Clone, Debug, PartialEq)]
+pub struct Favorite {
+    pub config: Config,
+    pub hash: String,
+}
+
+impl Favorite {
+    pub fn new(config: &Config, seed: u64, dungeon: &Dungeon) -> Self {
+        Self {
+            config: Config {
+                seed: Some(seed),
+                ..config.clone()
+            },
+            hash: thumbnail_hash(dungeon),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.config.seed.unwrap_or_default()
+    }
+}
+
+// FNV-1a over the terrain, as 16 hex digits
+pub fn thumbnail_hash(dungeon: &Dungeon) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in format!("{}x{}\n", dungeon.width(), dungeon.height())
+        .bytes()
+        .chain(dungeon.layer_to_ascii(LayerKind::Terrain).bytes())
+    {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+// Favorites live in a TOML file as an array of tables, one per favorite,
+// each holding its config as an embedded TOML string
+pub fn load_favorites(path: &Path) -> io::Result<Vec<Favorite>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error),
+    };
+    let in_file = |error: io::Error| io::Error::new(error.kind(), format!("{}: {}", path.display(), error));
+    let table = toml::parse(&text).map_err(in_file)?;
+    let Some(entries) = table.get("favorite") else {
+        return Ok(Vec::new());
+    };
+    let entries = entries
+        .as_array()
+        .ok_or_else(|| in_file(invalid("favorite", "must be an array of tables")))?;
+
+    let mut favorites = Vec::new();
+    for entry in entries {
+        let entry = entry
+            .as_table()
+            .ok_or_else(|| in_file(invalid("favorite", "must be an array of tables")))?;
+        let text = |key: &str| {
+            entry
+                .get(key)
+                .and_then(Value::as_str)
+                .ok_or_else(|| in_file(invalid(key, "must be a string")))
+        };
+        let mut config = Config::from_toml(text("config")?).map_err(in_file)?;
+        if let Some(seed) = entry.get("seed") {
+            config.seed = Some(read_usize("seed", seed).map_err(in_file)? as u64);
+        }
+        favorites.push(Favorite {
+            config,
+            hash: text("hash")?.to_string(),
+        });
+    }
+    Ok(favorites)
+}
+
+pub fn append_favorite(path: &Path, favorite: &Favorite) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(
+        file,
+        "[[favorite]]\nseed = {}\nhash = {}\nconfig = {}\n",
+        favorite.seed(),
+        toml::quote(&favorite.hash),
+        toml::quote(&favorite.config.to_toml())
+    )
+}
@@ -0,0 +1,85 @@
+use crate::dungeon::{Dungeon, Tile};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+// How the starting grid is seeded before the automaton runs
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Fill {
+    // Every tile a wall independently with the given probability
+    #[default]
+    Uniform,
+    // Walls spread evenly across the map with no solid or bare patches, so
+    // the cave that settles out of it is evenly distributed and seeds rarely
+    // collapse into scattered pockets or one open hall
+    BlueNoise,
+    // Uniform, at whatever wall probability settles to the target floor
+    // share after the automaton, ignoring `wall_probability`
+    Percolation,
+}
+
+impl Fill {
+    pub const ALL: [Fill; 3] = [Fill::Uniform, Fill::BlueNoise, Fill::Percolation];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Fill::Uniform => "uniform",
+            Fill::BlueNoise => "blue_noise",
+            Fill::Percolation => "percolation",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|fill| fill.name() == name)
+    }
+}
+
+// Side of the squares the fill balances exactly. Small enough that no
+// stretch of the map can come out solid or bare, large enough that walls
+// still clump within them the way the automaton needs to grow caves; an
+// evenly spaced fill just crumbles away.
+const BLOCK: usize = 6;
+
+impl Dungeon {
+    // The generator works out the percolation fill's probability itself
+    pub fn initialize<R: Rng + ?Sized>(&mut self, fill: Fill, wall_probability: f64, rng: &mut R) {
+        match fill {
+            Fill::Uniform | Fill::Percolation => self.initialize_random(wall_probability, rng),
+            Fill::BlueNoise => self.initialize_blue_noise(wall_probability, rng),
+        }
+    }
+
+    // Stratified fill: every BLOCK x BLOCK square gets exactly its share of
+    // walls, placed at random inside it, with the rounding left over carried
+    // on to the next square. That takes out the low-frequency swings in
+    // density (a blue-noise spectrum) that leave white noise with solid or
+    // empty patches. Frozen tiles keep everything on them.
+    pub fn initialize_blue_noise<R: Rng + ?Sized>(&mut self, wall_probability: f64, rng: &mut R) {
+        let frozen = &self.frozen;
+        self.features.retain(|(x, y), _| frozen[y][x]);
+        self.entities.retain(|(x, y), _| frozen[y][x]);
+        self.meta.retain(|(x, y), _| frozen[y][x]);
+
+        let mut carry = 0.0;
+        for (row, top) in (0..self.height).step_by(BLOCK).enumerate() {
+            let mut lefts: Vec<usize> = (0..self.width).step_by(BLOCK).collect();
+            // Serpentine rows carry the rounding to an adjacent square
+            if row % 2 == 1 {
+                lefts.reverse();
+            }
+            for left in lefts {
+                let mut cells: Vec<(usize, usize)> = (top..(top + BLOCK).min(self.height))
+                    .flat_map(|y| (left..(left + BLOCK).min(self.width)).map(move |x| (x, y)))
+                    .collect();
+                let target = wall_probability * cells.len() as f64 + carry;
+                let walls = (target.round().max(0.0) as usize).min(cells.len());
+                carry = target - walls as f64;
+                cells.shuffle(rng);
+                for (index, (x, y)) in cells.into_iter().enumerate() {
+                    if !self.frozen[y][x] {
+                        self.tiles[y][x] = if index < walls { Tile::Wall } else { Tile::Floor };
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+// A 64-bit sketch of a map's layout, for telling near-duplicates apart from
+// each other cheaply and for drawing a preview small enough for a list. The
+// map is cut into an 8 by 8 grid of cells and each cell gets one bit, set
+// where more of it is walkable than of the map as a whole, much as an
+// average hash works for images. Maps that differ by a few tiles here and
+// there keep most of their bits, so the number of bits two fingerprints
+// differ in measures how alike the layouts look; a content hash, by
+// contrast, changes completely for one tile. The top-left cell is the
+// highest bit, so the hex reads in the same order as the map. Turned or
+// mirrored maps fingerprint differently; compare `Dungeon::canonical` copies
+// for that.
+use crate::dungeon::Dungeon;
+use std::fmt;
+
+// Cells a side
+pub const GRID: usize = 8;
+// Fingerprints at most this many bits apart look like the same map
+pub const NEAR_DUPLICATE_BITS: u32 = 6;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Fingerprint(pub u64);
+
+impl Fingerprint {
+    // Whether the cell `x` across and `y` down is mostly open
+    pub fn open(self, x: usize, y: usize) -> bool {
+        self.0 >> (GRID * GRID - 1 - (y * GRID + x)) & 1 == 1
+    }
+
+    // Bits that differ, from 0 for the same layout to 64
+    pub fn distance(self, other: Fingerprint) -> u32 {
+        (self.0 ^ other.0).count_ones()
+    }
+
+    pub fn is_near_duplicate(self, other: Fingerprint) -> bool {
+        self.distance(other) <= NEAR_DUPLICATE_BITS
+    }
+
+    // The 16 hex digits `Display` writes
+    pub fn parse(hex: &str) -> Option<Self> {
+        if hex.len() != 16 {
+            return None;
+        }
+        u64::from_str_radix(hex, 16).ok().map(Fingerprint)
+    }
+
+    // Four lines of eight characters, two cells to a character with half
+    // blocks, open ground filled in
+    pub fn preview(self) -> Vec<String> {
+        (0..GRID / 2)
+            .map(|row| {
+                (0..GRID)
+                    .map(|x| match (self.open(x, row * 2), self.open(x, row * 2 + 1)) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl Dungeon {
+    // Cells are as even as the map's size allows; on a map narrower or
+    // shorter than the grid some are empty and never set
+    pub fn fingerprint(&self) -> Fingerprint {
+        let area = self.width * self.height;
+        let open = self.tiles.iter().flatten().filter(|tile| tile.is_walkable()).count();
+        let mut bits = 0;
+        for row in 0..GRID {
+            let (top, bottom) = (row * self.height / GRID, (row + 1) * self.height / GRID);
+            for column in 0..GRID {
+                let (left, right) = (column * self.width / GRID, (column + 1) * self.width / GRID);
+                let cell_open = self.tiles[top..bottom]
+                    .iter()
+                    .flat_map(|tiles| &tiles[left..right])
+                    .filter(|tile| tile.is_walkable())
+                    .count();
+                let cell_area = (bottom - top) * (right - left);
+                // Open share of the cell against that of the map, cross-multiplied
+                bits = bits << 1 | (cell_open * area > open * cell_area) as u64;
+            }
+        }
+        Fingerprint(bits)
+    }
+}
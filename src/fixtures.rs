@@ -0,0 +1,97 @@
+// Small maps of known shape, for tests and examples that need a topology
+// they can reason about rather than whatever a seed grows:
+//
+//     let dungeon = fixtures::disconnected_pair();
+//     assert_eq!(pathfinding::regions(&dungeon).len(), 2);
+//
+// Each is drawn below in the glyphs `to_ascii` uses, so what a test
+// expects can be read straight off the drawing. They stay as they are
+// from release to release; a new shape gets a new fixture.
+use crate::dungeon::{Dungeon, Tile};
+
+// A loop two tiles wide around a solid block: one region with no dead ends,
+// so every tile has two ways round to any other
+pub const DONUT: &str = "\
+##########
+#........#
+#........#
+#..####..#
+#..####..#
+#........#
+#........#
+##########
+";
+
+// Two rooms joined by a one-tile corridor, the entrance in the left room
+// and the exit in the right, so the one route runs through the corridor
+pub const TWO_ROOMS: &str = "\
+###############
+#....#####....#
+#.<..#####....#
+#.............#
+#....#####..>.#
+#....#####....#
+###############
+";
+
+// The same two rooms with the corridor walled up
+pub const DISCONNECTED_PAIR: &str = "\
+###############
+#....#####....#
+#....#####....#
+#....#####....#
+#....#####....#
+#....#####....#
+###############
+";
+
+pub fn donut() -> Dungeon {
+    parse(DONUT)
+}
+
+pub fn two_rooms() -> Dungeon {
+    parse(TWO_ROOMS)
+}
+
+pub fn disconnected_pair() -> Dungeon {
+    parse(DISCONNECTED_PAIR)
+}
+
+// Floor edge to edge, with no wall anywhere
+pub fn all_open(width: usize, height: usize) -> Dungeon {
+    let mut dungeon = Dungeon::new(width, height);
+    for row in &mut dungeon.tiles {
+        row.fill(Tile::Floor);
+    }
+    dungeon
+}
+
+fn parse(text: &str) -> Dungeon {
+    Dungeon::from_ascii(text).expect("fixture maps parse")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pathfinding::{entrance_and_exit, find_path, regions};
+
+    #[test]
+    fn fixtures_have_the_topology_drawn() {
+        let fixtures = [(DONUT, donut(), 1), (TWO_ROOMS, two_rooms(), 1), (DISCONNECTED_PAIR, disconnected_pair(), 2)];
+        for (drawing, dungeon, count) in fixtures {
+            assert_eq!(dungeon.to_ascii(), drawing);
+            assert_eq!(regions(&dungeon).len(), count, "in:\n{}", drawing);
+        }
+        assert_eq!(donut().metrics().dead_ends, 0);
+        assert_eq!(regions(&all_open(4, 3)), [(0..3).flat_map(|y| (0..4).map(move |x| (x, y))).collect::<Vec<_>>()]);
+
+        // Marked ends, so the route is the one through the corridor
+        let dungeon = two_rooms();
+        let (entrance, exit) = entrance_and_exit(&dungeon).unwrap();
+        assert_eq!((entrance, exit), ((2, 2), (12, 4)));
+        let route = find_path(&dungeon, entrance, exit).unwrap();
+        assert_eq!(route.len() - 1, 12);
+        assert!(route.contains(&(7, 3)));
+        assert_eq!(dungeon.metrics().route_length, Some(12));
+    }
+}
@@ -0,0 +1,141 @@
+use crate::dungeon::{Dungeon, Point};
+use crate::pathfinding::{distance_map, PathOptions};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [Direction::North, Direction::East, Direction::South, Direction::West];
+
+    pub fn delta(self) -> (i32, i32) {
+        match self {
+            Direction::North => (0, -1),
+            Direction::East => (1, 0),
+            Direction::South => (0, 1),
+            Direction::West => (-1, 0),
+        }
+    }
+
+    pub fn arrow(self) -> char {
+        match self {
+            Direction::North => '^',
+            Direction::East => '>',
+            Direction::South => 'v',
+            Direction::West => '<',
+        }
+    }
+}
+
+// For every tile that can reach the target, the step to take to get there
+// cheapest. Built once with a single Dijkstra pass and shared by any number
+// of agents heading for the same target.
+pub struct FlowField {
+    width: usize,
+    height: usize,
+    target: Point,
+    costs: Vec<Vec<Option<usize>>>,
+    directions: Vec<Vec<Option<Direction>>>,
+}
+
+impl FlowField {
+    pub fn target(&self) -> Point {
+        self.target
+    }
+
+    // None at the target itself and wherever the target can't be reached
+    pub fn direction(&self, x: usize, y: usize) -> Option<Direction> {
+        self.directions[y][x]
+    }
+
+    // Remaining cost to the target from this tile
+    pub fn cost(&self, x: usize, y: usize) -> Option<usize> {
+        self.costs[y][x]
+    }
+
+    pub fn next_step(&self, x: usize, y: usize) -> Option<Point> {
+        let (dx, dy) = self.direction(x, y)?.delta();
+        Some(((x as i32 + dx) as usize, (y as i32 + dy) as usize))
+    }
+
+    pub fn reaches_target(&self, x: usize, y: usize) -> bool {
+        self.costs[y][x].is_some()
+    }
+
+    // Arrows per tile, '*' on the target and blanks where it's out of reach
+    pub fn to_ascii(&self) -> String {
+        let mut out = String::with_capacity((self.width + 1) * self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                out.push(match self.directions[y][x] {
+                    Some(direction) => direction.arrow(),
+                    None if (x, y) == self.target => '*',
+                    None => ' ',
+                });
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+impl Dungeon {
+    pub fn flow_field(&self, target: Point) -> FlowField {
+        self.flow_field_with(target, &PathOptions::default())
+    }
+
+    pub fn flow_field_with(&self, target: Point, options: &PathOptions) -> FlowField {
+        // Dijkstra outward from the target charges each tile on entry, so
+        // `reverse[v]` is what walking v -> target costs, minus the tile being
+        // left plus the target itself. Stepping to the neighbour with the
+        // lowest reverse cost is therefore always an optimal move.
+        let reverse = match options.step_cost(self, target) {
+            Some(_) => distance_map(self, target, options),
+            None => vec![vec![None; self.width]; self.height],
+        };
+        let target_cost = options.step_cost(self, target).unwrap_or(0);
+        let mut costs = vec![vec![None; self.width]; self.height];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if let (Some(cost), Some(here)) = (reverse[y][x], options.step_cost(self, (x, y))) {
+                    costs[y][x] = Some(cost + target_cost - here);
+                }
+            }
+        }
+
+        let mut directions = vec![vec![None; self.width]; self.height];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let Some(here) = reverse[y][x] else {
+                    continue;
+                };
+                let mut best: Option<(usize, Direction)> = None;
+                for direction in Direction::ALL {
+                    let (dx, dy) = direction.delta();
+                    let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                    if nx < 0 || ny < 0 || nx >= self.width as i32 || ny >= self.height as i32 {
+                        continue;
+                    }
+                    if let Some(cost) = reverse[ny as usize][nx as usize] {
+                        if cost < here && best.is_none_or(|(best_cost, _)| cost < best_cost) {
+                            best = Some((cost, direction));
+                        }
+                    }
+                }
+                directions[y][x] = best.map(|(_, direction)| direction);
+            }
+        }
+
+        FlowField {
+            width: self.width,
+            height: self.height,
+            target,
+            costs,
+            directions,
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use crate::grid::GridMap;
+
+// Per-octant (xx, xy, yx, yy) transforms for recursive shadowcasting
+const OCTANTS: [[i32; 4]; 8] = [
+    [1, 0, 0, 1],
+    [0, 1, 1, 0],
+    [0, -1, 1, 0],
+    [-1, 0, 0, 1],
+    [-1, 0, 0, -1],
+    [0, -1, -1, 0],
+    [0, 1, -1, 0],
+    [1, 0, 0, -1],
+];
+
+// Tiles visible from `origin` within `radius`; walls are visible but block
+// sight, and so does anything else the map says does, walkable or not
+pub fn compute_fov<G: GridMap + ?Sized>(dungeon: &G, origin: (usize, usize), radius: usize) -> Vec<Vec<bool>> {
+    let mut visible = vec![vec![false; dungeon.width()]; dungeon.height()];
+    visible[origin.1][origin.0] = true;
+
+    for transform in OCTANTS {
+        cast_light(dungeon, &mut visible, origin, radius as i32, 1, 1.0, 0.0, transform);
+    }
+
+    visible
+}
+
+fn is_opaque<G: GridMap + ?Sized>(dungeon: &G, x: i32, y: i32) -> bool {
+    x < 0
+        || y < 0
+        || x >= dungeon.width() as i32
+        || y >= dungeon.height() as i32
+        || dungeon.blocks_sight(x as usize, y as usize)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn cast_light<G: GridMap + ?Sized>(
+    dungeon: &G,
+    visible: &mut Vec<Vec<bool>>,
+    origin: (usize, usize),
+    radius: i32,
+    row: i32,
+    mut start: f64,
+    end: f64,
+    [xx, xy, yx, yy]: [i32; 4],
+) {
+    if start < end {
+        return;
+    }
+
+    let (cx, cy) = (origin.0 as i32, origin.1 as i32);
+    let mut new_start = 0.0;
+
+    for distance in row..=radius {
+        let dy = -distance;
+        let mut blocked = false;
+
+        for dx in -distance..=0 {
+            let mx = cx + dx * xx + dy * xy;
+            let my = cy + dx * yx + dy * yy;
+            let left_slope = (dx as f64 - 0.5) / (dy as f64 + 0.5);
+            let right_slope = (dx as f64 + 0.5) / (dy as f64 - 0.5);
+
+            if start < right_slope {
+                continue;
+            } else if end > left_slope {
+                break;
+            }
+
+            let in_bounds = mx >= 0 && my >= 0 && mx < dungeon.width() as i32 && my < dungeon.height() as i32;
+            if in_bounds && dx * dx + dy * dy < radius * radius {
+                visible[my as usize][mx as usize] = true;
+            }
+
+            let opaque = is_opaque(dungeon, mx, my);
+            if blocked {
+                if opaque {
+                    new_start = right_slope;
+                } else {
+                    blocked = false;
+                    start = new_start;
+                }
+            } else if opaque && distance < radius {
+                blocked = true;
+                cast_light(dungeon, visible, origin, radius, distance + 1, start, left_slope, [xx, xy, yx, yy]);
+                new_start = right_slope;
+            }
+        }
+
+        if blocked {
+            break;
+        }
+    }
+}
@@ -0,0 +1,78 @@
+// Tiles locked against the generator. Hand edits and prefabs marked frozen
+// keep their terrain, features and entities through regeneration, the
+// automaton and every post-pass; the passes simply work around them.
+use crate::dungeon::{Dungeon, Point, Tile};
+use std::io;
+
+impl Dungeon {
+    pub fn is_frozen(&self, x: usize, y: usize) -> bool {
+        self.frozen[y][x]
+    }
+
+    pub fn freeze(&mut self, x: usize, y: usize) {
+        self.frozen[y][x] = true;
+    }
+
+    pub fn unfreeze(&mut self, x: usize, y: usize) {
+        self.frozen[y][x] = false;
+    }
+
+    // Freezes a rectangle, clipped to the map; handy after stamping a prefab
+    pub fn freeze_rect(&mut self, x: usize, y: usize, width: usize, height: usize) {
+        for row in self.frozen.iter_mut().skip(y).take(height) {
+            for cell in row.iter_mut().skip(x).take(width) {
+                *cell = true;
+            }
+        }
+    }
+
+    pub fn unfreeze_all(&mut self) {
+        for row in &mut self.frozen {
+            row.fill(false);
+        }
+    }
+
+    pub fn frozen_tiles(&self) -> impl Iterator<Item = Point> + '_ {
+        self.frozen.iter().enumerate().flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|&(_, &frozen)| frozen)
+                .map(move |(x, _)| (x, y))
+        })
+    }
+
+    // Sets a tile the way a level designer would: it stays as placed
+    pub fn edit_tile(&mut self, x: usize, y: usize, tile: Tile) {
+        self.set_tile(x, y, tile);
+        self.freeze(x, y);
+    }
+
+    // Copies `prefab` into the middle of the map, terrain, features and
+    // entities alike, and freezes it so generation works around it
+    pub fn stamp_prefab(&mut self, prefab: &Dungeon) -> io::Result<()> {
+        if prefab.width > self.width || prefab.height > self.height {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "prefab is {}x{}, too big for a {}x{} map",
+                    prefab.width, prefab.height, self.width, self.height
+                ),
+            ));
+        }
+        let (left, top) = ((self.width - prefab.width) / 2, (self.height - prefab.height) / 2);
+        for y in 0..prefab.height {
+            for x in 0..prefab.width {
+                let (mx, my) = (left + x, top + y);
+                self.tiles[my][mx] = prefab.tiles[y][x];
+                self.frozen[my][mx] = true;
+            }
+        }
+        for ((x, y), feature) in prefab.features.iter() {
+            self.features.set(left + x, top + y, feature.clone());
+        }
+        for ((x, y), entity) in prefab.entities.iter() {
+            self.entities.set(left + x, top + y, entity.clone());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,818 @@
+use crate::config::Config;
+use crate::contracts::{self, ContractError, Contracts};
+use crate::counter::{CounterRng, RngMode};
+use crate::dungeon::{Dungeon, Point};
+use crate::events::{Event, Observer};
+use crate::fill::Fill;
+use crate::layers::Entity;
+use crate::loot::LootManifest;
+use crate::pathfinding::regions;
+use crate::percolation;
+use crate::profiling;
+use crate::scratch::Scratch;
+use rand::rngs::StdRng;
+use rand::{Error, Rng, RngCore, SeedableRng};
+
+// The RNG every pass draws from; the same seed always builds the same map
+pub fn rng_for(seed: u64) -> StdRng {
+    StdRng::seed_from_u64(seed)
+}
+
+// The RNG a seeded run draws from, under the config's rng mode
+#[derive(Clone, Debug)]
+pub enum SeedRng {
+    Standard(Box<StdRng>),
+    Counter(CounterRng),
+}
+
+impl RngCore for SeedRng {
+    fn next_u32(&mut self) -> u32 {
+        match self {
+            SeedRng::Standard(rng) => rng.next_u32(),
+            SeedRng::Counter(rng) => rng.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            SeedRng::Standard(rng) => rng.next_u64(),
+            SeedRng::Counter(rng) => rng.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match self {
+            SeedRng::Standard(rng) => rng.fill_bytes(dest),
+            SeedRng::Counter(rng) => rng.fill_bytes(dest),
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        match self {
+            SeedRng::Standard(rng) => rng.try_fill_bytes(dest),
+            SeedRng::Counter(rng) => rng.try_fill_bytes(dest),
+        }
+    }
+}
+
+// A master seed that hands out independent, reproducible RNG streams by
+// topic, so game code can roll loot, names and the like from the same seed
+// as the map without the draws disturbing each other:
+//
+//     let rng = SeededRng::new(seed);
+//     let mut names = rng.for_topic("names");
+//     let mut boss = rng.child("bosses").for_topic("floor_3");
+//
+// Each room of a map gets its own seed too, by its number in
+// `Dungeon::label_regions`, so what a room holds is rolled from that room's
+// stream alone and comes out the same whichever order rooms are filled in
+// and however many draws other passes make first. `Dungeon::populate`,
+// `roll_loot` and `region_labels` all roll that way:
+//
+//     for label in dungeon.region_labels(seed) {
+//         let mut loot = rng.room(label.region).for_topic("loot");
+//     }
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SeededRng {
+    seed: u64,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    pub fn seed(self) -> u64 {
+        self.seed
+    }
+
+    // A nested seed for a whole family of topics
+    pub fn child(self, topic: &str) -> Self {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in topic.bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        Self {
+            seed: mix(self.seed ^ mix(hash)),
+        }
+    }
+
+    pub fn for_topic(self, topic: &str) -> StdRng {
+        rng_for(self.child(topic).seed)
+    }
+
+    // The seed of room `id`, from the master seed and the id alone
+    pub fn room(self, id: usize) -> Self {
+        Self {
+            seed: mix(self.child("rooms").seed ^ mix(id as u64)),
+        }
+    }
+
+    pub fn for_room(self, id: usize) -> StdRng {
+        rng_for(self.room(id).seed)
+    }
+}
+
+// SplitMix64's finaliser, so nearby seeds and topics land far apart
+fn mix(mut value: u64) -> u64 {
+    value = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    value ^ (value >> 31)
+}
+
+// Fresh seeds stay below 2^63 so they always fit in a TOML integer
+pub fn random_seed() -> u64 {
+    rand::random::<u64>() >> 1
+}
+
+// The configured seed, or a fresh random one when none is set
+pub fn seed_for(config: &Config) -> u64 {
+    config.seed.unwrap_or_else(random_seed)
+}
+
+pub fn generate(config: &Config) -> Dungeon {
+    generate_seeded(config, seed_for(config))
+}
+
+pub fn generate_seeded(config: &Config, seed: u64) -> Dungeon {
+    generate_reported(config, seed).0
+}
+
+// The map along with the contents of every container on it
+pub fn generate_with_loot(config: &Config, seed: u64) -> (Dungeon, LootManifest) {
+    let dungeon = generate_seeded(config, seed);
+    let manifest = dungeon.roll_loot(&config.loot, seed);
+    (dungeon, manifest)
+}
+
+// When the automaton stops
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Stop {
+    // After `iterations` steps, or sooner once the map stops changing
+    #[default]
+    Settled,
+    // After exactly `iterations` steps
+    Exact,
+    // Once the map stops changing, however many steps that takes, up to
+    // `max_iterations`
+    Converged,
+}
+
+impl Stop {
+    pub const ALL: [Stop; 3] = [Stop::Settled, Stop::Exact, Stop::Converged];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Stop::Settled => "settled",
+            Stop::Exact => "exact",
+            Stop::Converged => "converged",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|stop| stop.name() == name)
+    }
+}
+
+// How the map is laid out before the post-passes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GenerationAlgorithm {
+    // Noise smoothed into caves by the automaton
+    #[default]
+    CellularAutomaton,
+    // Rectangular rooms joined by corridors, in one go with no automaton
+    Rooms,
+    // Noise settled by the automaton into islands in open water
+    Archipelago,
+}
+
+impl GenerationAlgorithm {
+    pub const ALL: [GenerationAlgorithm; 3] =
+        [GenerationAlgorithm::CellularAutomaton, GenerationAlgorithm::Rooms, GenerationAlgorithm::Archipelago];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GenerationAlgorithm::CellularAutomaton => "cellular_automaton",
+            GenerationAlgorithm::Rooms => "rooms",
+            GenerationAlgorithm::Archipelago => "archipelago",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|algorithm| algorithm.name() == name)
+    }
+}
+
+// The most automaton steps a run of `config` takes
+pub fn step_limit(config: &Config) -> usize {
+    if config.algorithm == GenerationAlgorithm::Rooms {
+        return 0;
+    }
+    match config.stop {
+        Stop::Converged => config.max_iterations,
+        Stop::Settled | Stop::Exact => config.iterations,
+    }
+}
+
+// Whether a run of `config` ends as soon as the map stops changing
+pub fn stops_when_settled(config: &Config) -> bool {
+    config.stop != Stop::Exact && !config.constant_work
+}
+
+// What a generation run took, for the stats log
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Report {
+    pub algorithm: &'static str,
+    // Automaton steps run
+    pub steps: usize,
+    // Whether one of them found nothing left to change, rather than the
+    // step limit cutting the automaton off
+    pub converged: bool,
+    // Tiles the clearance repair had to dig out
+    pub carved: usize,
+    // Locked doors placed, fewer than `locks.keys` asks for when the route
+    // has too few chokepoints to hold them all
+    pub locks: usize,
+    // Steps walked off the critical path and back to fetch keys
+    pub backtracking: usize,
+    // Layout attempts generated, the kept one among them
+    pub attempts: usize,
+}
+
+pub fn generate_reported(config: &Config, seed: u64) -> (Dungeon, Report) {
+    let mut dungeon = Dungeon::new(config.width, config.height);
+    let report = regenerate(&mut dungeon, config, seed);
+    (dungeon, report)
+}
+
+// Generates as `generate_reported` does, but only hands the map over if it
+// keeps the config's contracts and `extra`; otherwise every broken one comes
+// back at once
+pub fn build(config: &Config, seed: u64, extra: &Contracts) -> Result<(Dungeon, Report), ContractError> {
+    let (dungeon, report) = generate_reported(config, seed);
+    let mut violations = contracts::violations(&config.contracts, &dungeon);
+    violations.extend(extra.violations(&dungeon));
+    if violations.is_empty() {
+        Ok((dungeon, report))
+    } else {
+        Err(ContractError { seed, violations })
+    }
+}
+
+// Generates as `generate_reported` does, telling `observer` about each
+// automaton step and post-pass as it finishes, of every layout attempt in turn
+pub fn generate_observed<O: Observer + ?Sized>(config: &Config, seed: u64, observer: &mut O) -> (Dungeon, Report) {
+    let mut dungeon = Dungeon::new(config.width, config.height);
+    let report = best_attempt(&mut dungeon, config, |dungeon, attempt| {
+        let mut rng = reset_seeded(dungeon, config, attempt_seed(seed, attempt));
+        settle(dungeon, config, &mut rng, observer)
+    });
+    (dungeon, report)
+}
+
+// Draws from the caller's RNG instead of one built from a seed, for game
+// engines with their own generators. Anything implementing `RngCore` will
+// do, including a `&mut dyn RngCore`. The seed-based functions are this
+// with `rng_for(seed)`.
+pub fn generate_with<R: Rng + ?Sized>(config: &Config, rng: &mut R) -> (Dungeon, Report) {
+    let mut dungeon = Dungeon::new(config.width, config.height);
+    let report = regenerate_with(&mut dungeon, config, rng);
+    (dungeon, report)
+}
+
+// Generates over an existing map at its own size, leaving its frozen tiles
+// exactly as they are; with nothing frozen this is `generate_reported`
+pub fn regenerate(dungeon: &mut Dungeon, config: &Config, seed: u64) -> Report {
+    best_attempt(dungeon, config, |dungeon, attempt| {
+        let mut rng = reset_seeded(dungeon, config, attempt_seed(seed, attempt));
+        settle(dungeon, config, &mut rng, &mut ())
+    })
+}
+
+// Later layout attempts draw on from where the one before left the RNG
+pub fn regenerate_with<R: Rng + ?Sized>(dungeon: &mut Dungeon, config: &Config, rng: &mut R) -> Report {
+    best_attempt(dungeon, config, |dungeon, _| {
+        reset(dungeon, config, rng);
+        settle(dungeon, config, rng, &mut ())
+    })
+}
+
+// Generates `region` afresh from `seed` and leaves the rest of the map as
+// it was, by freezing everything else for the run. The region's tiles next to
+// walkable ground outside it are frozen too, and so are the entrance and
+// exit, so every way in still leads in. Locks and extra layout attempts are
+// left off, as they'd reach past the region. Returns false with the map
+// untouched when the new ground cuts ways in off from each other that were
+// joined before.
+pub fn reroll_region(dungeon: &mut Dungeon, config: &Config, region: &[Point], seed: u64) -> bool {
+    let (width, height) = (dungeon.width(), dungeon.height());
+    let mut inside = vec![vec![false; width]; height];
+    for &(x, y) in region {
+        inside[y][x] = true;
+    }
+    let neighbours = |(x, y): Point| {
+        [
+            (x > 0).then(|| (x - 1, y)),
+            (y > 0).then(|| (x, y - 1)),
+            (x + 1 < width).then_some((x + 1, y)),
+            (y + 1 < height).then_some((x, y + 1)),
+        ]
+        .into_iter()
+        .flatten()
+    };
+    let ways_in: Vec<Point> = region
+        .iter()
+        .copied()
+        .filter(|&point| neighbours(point).any(|(nx, ny)| !inside[ny][nx] && dungeon.tile(nx, ny).is_walkable()))
+        .collect();
+    let joined = |dungeon: &Dungeon| {
+        let regions = regions(dungeon);
+        let which = |point: &Point| regions.iter().position(|tiles| tiles.contains(point));
+        ways_in.iter().map(which).collect::<Vec<_>>()
+    };
+    let before = joined(dungeon);
+
+    let original = dungeon.clone();
+    for (y, row) in inside.iter().enumerate() {
+        for (x, &inside) in row.iter().enumerate() {
+            let marker = matches!(dungeon.entity(x, y), Some(Entity::Entrance | Entity::Exit));
+            if !inside || marker || ways_in.contains(&(x, y)) {
+                dungeon.freeze(x, y);
+            }
+        }
+    }
+    let mut config = config.clone();
+    config.layout.attempts = 1;
+    config.locks.keys = 0;
+    regenerate(dungeon, &config, seed);
+    dungeon.frozen = original.frozen.clone();
+
+    // Ways in that shared a region before still share one
+    let after = joined(dungeon);
+    let kept = (0..before.len()).all(|a| {
+        (0..before.len()).all(|b| before[a].is_none() || before[a] != before[b] || after[a] == after[b])
+    });
+    if !kept {
+        *dungeon = original;
+    }
+    kept
+}
+
+// The first attempt runs on the run's own seed, so a single attempt is a
+// plain run
+pub(crate) fn attempt_seed(seed: u64, attempt: usize) -> u64 {
+    match attempt {
+        0 => seed,
+        _ => SeededRng::new(seed).child(&format!("layout attempt {}", attempt)).seed(),
+    }
+}
+
+// The pick among layout attempts, for `best_attempt` and the incremental
+// `Generator` alike: the first attempt stands unless the objective scores a
+// later one lower. A perfect score or an objective that doesn't care ends
+// the search, unless the config asks for constant work.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Selection {
+    attempts: usize,
+    best: Option<(usize, usize)>,
+}
+
+impl Selection {
+    // Attempts run so far, and so the index of the next
+    pub(crate) fn attempts(&self) -> usize {
+        self.attempts
+    }
+
+    // Counts the attempt just run, and whether its map beats the one kept
+    pub(crate) fn keeps(&mut self, dungeon: &Dungeon, config: &Config) -> bool {
+        // A single attempt has nothing to beat, so isn't worth scoring
+        let score = (config.layout.attempts > 1).then(|| config.layout.objective.score(dungeon, config)).flatten();
+        let kept = self.attempts == 0 || score.zip(self.best).is_some_and(|(score, best)| score < best);
+        if kept {
+            self.best = score;
+        }
+        self.attempts += 1;
+        kept
+    }
+
+    pub(crate) fn wants_more(&self, config: &Config) -> bool {
+        self.attempts < config.layout.attempts
+            && (config.constant_work || self.best.is_some_and(|best| best != (0, 0)))
+    }
+}
+
+// Runs `attempt` for each of the configured layout attempts, every one on a
+// copy of the map as it came in, and keeps the map `Selection` picks
+fn best_attempt(
+    dungeon: &mut Dungeon,
+    config: &Config,
+    mut attempt: impl FnMut(&mut Dungeon, usize) -> Report,
+) -> Report {
+    let original = (config.layout.attempts > 1).then(|| dungeon.clone());
+    let mut report = attempt(dungeon, 0);
+    let Some(original) = original else {
+        return report;
+    };
+    let mut selection = Selection::default();
+    selection.keeps(dungeon, config);
+    while selection.wants_more(config) {
+        let mut candidate = original.clone();
+        let candidate_report = attempt(&mut candidate, selection.attempts());
+        if selection.keeps(&candidate, config) {
+            *dungeon = candidate;
+            report = candidate_report;
+        }
+    }
+    Report { attempts: selection.attempts(), ..report }
+}
+
+// The automaton and then the post-passes
+fn settle<R: Rng + ?Sized, O: Observer + ?Sized>(
+    dungeon: &mut Dungeon,
+    config: &Config,
+    rng: &mut R,
+    observer: &mut O,
+) -> Report {
+    // Shared by every step and pass of the run
+    let mut scratch = Scratch::new();
+    let mut steps = 0;
+    let mut converged = false;
+    let automaton = profiling::span("automaton");
+    while steps < step_limit(config) && !(converged && stops_when_settled(config)) {
+        steps += 1;
+        let span = profiling::span("step");
+        let changed = step_in(dungeon, config, &mut scratch);
+        drop(span);
+        converged |= !changed;
+        observer.observe(&Event::Step { step: steps, changed });
+    }
+    drop(automaton);
+    let (carved, locks) = finish_in(dungeon, config, rng, observer, &mut scratch);
+    Report {
+        algorithm: config.algorithm.name(),
+        steps,
+        converged,
+        carved,
+        locks,
+        backtracking: dungeon.backtracking(),
+        attempts: 1,
+    }
+}
+
+// Noise for the automaton to work on, or finished rooms for the rooms
+// algorithm, with the pins already in place
+pub fn start<R: Rng + ?Sized>(config: &Config, rng: &mut R) -> Dungeon {
+    let mut dungeon = Dungeon::new(config.width, config.height);
+    reset(&mut dungeon, config, rng);
+    dungeon
+}
+
+// Same for a seed, honouring the config's rng mode, along with the RNG the
+// post-passes then draw from
+pub fn start_seeded(config: &Config, seed: u64) -> (Dungeon, SeedRng) {
+    let mut dungeon = Dungeon::new(config.width, config.height);
+    let rng = reset_seeded(&mut dungeon, config, seed);
+    (dungeon, rng)
+}
+
+fn wall_probability(config: &Config) -> f64 {
+    if config.algorithm == GenerationAlgorithm::Archipelago {
+        return config.archipelago.sea;
+    }
+    match config.fill {
+        Fill::Percolation => percolation::wall_probability_for(config, config.target_floor),
+        _ => config.wall_probability,
+    }
+}
+
+fn reset<R: Rng + ?Sized>(dungeon: &mut Dungeon, config: &Config, rng: &mut R) {
+    let _span = profiling::span("initialize");
+    match config.algorithm {
+        GenerationAlgorithm::CellularAutomaton | GenerationAlgorithm::Archipelago => {
+            dungeon.initialize(config.fill, wall_probability(config), rng)
+        }
+        GenerationAlgorithm::Rooms => dungeon.carve_rooms(&config.rooms, rng),
+    }
+    dungeon.stamp_pins(&config.pins);
+}
+
+pub(crate) fn reset_seeded(dungeon: &mut Dungeon, config: &Config, seed: u64) -> SeedRng {
+    let mut rng = match config.rng {
+        RngMode::Standard => SeedRng::Standard(Box::new(rng_for(seed))),
+        RngMode::Counter => SeedRng::Counter(CounterRng::new(seed)),
+    };
+    // Blue noise balances each block against the one before, so it can
+    // only ever be drawn in order,
+    // and rooms are placed one after another, each against the last
+    let per_tile = config.algorithm != GenerationAlgorithm::Rooms && config.fill != Fill::BlueNoise;
+    if config.rng == RngMode::Counter && per_tile {
+        let _span = profiling::span("initialize");
+        dungeon.initialize_counter(seed, wall_probability(config), (0, 0));
+        dungeon.stamp_pins(&config.pins);
+    } else {
+        reset(dungeon, config, &mut rng);
+    }
+    rng
+}
+
+// One window of an endless cave: the `config.width` x `config.height` tiles
+// with their top-left corner at `origin` in world coordinates. The noise is
+// drawn per tile and the automaton runs over a margin as wide as it has
+// steps, so no edge effect reaches the window and overlapping windows agree
+// tile for tile, whichever machine builds them and in whatever order. Only
+// the cave is shared: pins belong to a single map, and the post-passes look
+// at the whole of it, so both are left out. The automaton takes at most
+// `iterations` steps whatever the stop rule, as that is all the margin
+// covers. Chunks are always caves, as rooms need the whole map to be placed.
+pub fn generate_chunk(config: &Config, seed: u64, origin: (i64, i64)) -> Dungeon {
+    let margin = config.iterations;
+    let mut padded = Dungeon::new(config.width + 2 * margin, config.height + 2 * margin);
+    let corner = (origin.0 - margin as i64, origin.1 - margin as i64);
+    padded.initialize_counter(seed, wall_probability(config), corner);
+    let mut scratch = Scratch::new();
+    for _ in 0..config.iterations {
+        if !padded.simulate_step_in(&config.rules, &mut scratch) && !config.constant_work {
+            break;
+        }
+    }
+    let mut chunk = Dungeon::new(config.width, config.height);
+    for (y, row) in chunk.tiles.iter_mut().enumerate() {
+        row.copy_from_slice(&padded.tiles[y + margin][margin..margin + config.width]);
+    }
+    chunk
+}
+
+// One automaton step that holds the pins; false once the map stops changing.
+// Rooms come out finished, so for them it changes nothing.
+pub fn step(dungeon: &mut Dungeon, config: &Config) -> bool {
+    step_in(dungeon, config, &mut Scratch::new())
+}
+
+// The same with the caller's scratch space, for callers stepping a map
+// themselves
+pub fn step_in(dungeon: &mut Dungeon, config: &Config, scratch: &mut Scratch) -> bool {
+    if config.algorithm == GenerationAlgorithm::Rooms {
+        return false;
+    }
+    let changed = dungeon.simulate_step_in(&config.rules, scratch);
+    dungeon.stamp_pins(&config.pins);
+    changed
+}
+
+// Post-passes that run once the cellular automaton has settled, or the rooms
+// are carved; returns the tiles carved to restore clearance. An archipelago's
+// islands are raised first, as nothing else sees its sea as sea until then.
+pub fn finish<R: Rng + ?Sized>(dungeon: &mut Dungeon, config: &Config, rng: &mut R) -> usize {
+    finish_observed(dungeon, config, rng, &mut ())
+}
+
+// The same, with a `Pass` event after each pass
+pub fn finish_observed<R: Rng + ?Sized, O: Observer + ?Sized>(
+    dungeon: &mut Dungeon,
+    config: &Config,
+    rng: &mut R,
+    observer: &mut O,
+) -> usize {
+    finish_in(dungeon, config, rng, observer, &mut Scratch::new()).0
+}
+
+// With the locks placed as well as the tiles carved
+pub(crate) fn finish_in<R: Rng + ?Sized, O: Observer + ?Sized>(
+    dungeon: &mut Dungeon,
+    config: &Config,
+    rng: &mut R,
+    observer: &mut O,
+    scratch: &mut Scratch,
+) -> (usize, usize) {
+    // Runs a pass inside its profiling span, then tells the observer
+    macro_rules! pass {
+        ($name:literal, $run:expr) => {{
+            let span = profiling::span($name);
+            let result = $run;
+            drop(span);
+            observer.observe(&Event::Pass($name));
+            result
+        }};
+    }
+    let _span = profiling::span("post_passes");
+    dungeon.world = config.world;
+    if config.algorithm == GenerationAlgorithm::Archipelago {
+        pass!("islands", dungeon.raise_islands(&config.archipelago, &config.pins, scratch));
+    }
+    pass!("roughness", dungeon.roughen(&config.roughness, rng));
+    pass!("erosion", dungeon.erode(&config.erosion, rng));
+    pass!("pillar_span", dungeon.place_pillars(config.pillar_span));
+    pass!("terrain", dungeon.scatter_terrain(&config.terrain, rng));
+    pass!("border", dungeon.frame(&config.border, rng));
+    pass!("pins", {
+        dungeon.stamp_pins(&config.pins);
+        dungeon.connect_pins_in(&config.pins, scratch)
+    });
+    pass!("connectivity", dungeon.ensure_connected_in(config.connectivity, config.loop_tunnels, &config.pins, scratch));
+    pass!("doors", dungeon.place_doors_in(config.doors, rng, scratch));
+    // Once there are doors to brick up, and before the repairs and
+    // placements that have to work on the aged map
+    pass!("history", dungeon.age(&config.history, rng));
+    // After every pass that reshapes the map, so none can pinch the route shut again
+    let (width, height) = config.clearance;
+    let carved = pass!("clearance", if width > 1 || height > 1 {
+        let carved = dungeon.ensure_clearance(width, height);
+        // Pins win even over the clearance repair
+        dungeon.stamp_pins(&config.pins);
+        carved
+    } else {
+        0
+    });
+    // Last of the passes that touch the route, so none can open a way round a lock
+    let locks = pass!("locks", dungeon.place_locks(&config.locks, rng));
+    pass!("spawns", dungeon.populate(&config.spawns, rng));
+    // Last, so no later pass walls over or opens up marked rock
+    pass!("hardness", dungeon.mark_hardness(&config.hardness, rng));
+    pass!("veins", dungeon.seed_veins(&config.veins, rng));
+    pass!("ecology", dungeon.place_anchors(&config.ecology, rng));
+    (carved, locks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connectivity::Connectivity;
+    use crate::contracts::Contract;
+    use crate::dungeon::Tile;
+    use crate::erosion::ErosionOptions;
+    use crate::incremental::Generator;
+    use crate::layers::Feature;
+    use crate::layout::Objective;
+    use crate::pathfinding::PathOptions;
+    use crate::roughness::RoughnessOptions;
+    use crate::solver;
+
+    #[test]
+    fn each_room_rolls_the_same_whatever_is_rolled_first() {
+        let dungeon = generate_seeded(&Config::default(), 7);
+        let rooms: Vec<usize> = dungeon.region_labels(7).iter().map(|label| label.region).collect();
+        assert!(rooms.len() > 1);
+        let roll = |id: usize| SeededRng::new(7).room(id).for_topic("loot").next_u64();
+
+        let forwards: Vec<u64> = rooms.iter().map(|&id| roll(id)).collect();
+        // Backwards, with draws from another stream in between
+        let mut other = SeededRng::new(7).for_topic("names");
+        let mut backwards = Vec::new();
+        for &id in rooms.iter().rev() {
+            other.next_u64();
+            backwards.insert(0, roll(id));
+        }
+        assert_eq!(forwards, backwards);
+
+        let mut seeds: Vec<u64> = rooms.iter().map(|&id| SeededRng::new(7).room(id).seed()).collect();
+        seeds.sort();
+        seeds.dedup();
+        assert_eq!(seeds.len(), rooms.len());
+        assert_ne!(SeededRng::new(7).room(rooms[0]), SeededRng::new(8).room(rooms[0]));
+        let first = SeededRng::new(7).room(rooms[0]).seed();
+        assert_eq!(SeededRng::new(7).for_room(rooms[0]).next_u64(), rng_for(first).next_u64());
+    }
+
+    #[test]
+    fn adjacent_chunks_agree_along_their_shared_border() {
+        let config = Config {
+            width: 24,
+            height: 16,
+            rng: RngMode::Counter,
+            ..Config::default()
+        };
+        let (width, height) = (config.width as i64, config.height as i64);
+        // The four chunks round the origin, each laid where it goes in one
+        // window over all of them, so every seam, across and down, is in it
+        let whole = generate_chunk(&Config { width: 48, height: 32, ..config.clone() }, 11, (-width, -height));
+        let mut chunks = Vec::new();
+        for (cx, cy) in [(-1, -1), (0, -1), (-1, 0), (0, 0)] {
+            let chunk = generate_chunk(&config, 11, (cx * width, cy * height));
+            let (left, top) = (((cx + 1) * width) as usize, ((cy + 1) * height) as usize);
+            for (y, row) in chunk.tiles.iter().enumerate() {
+                assert_eq!(row[..], whole.tiles[top + y][left..left + config.width], "chunk ({}, {}) row {}", cx, cy, y);
+            }
+            chunks.push(chunk.tiles);
+        }
+        assert_ne!(chunks[0], chunks[3]);
+    }
+
+    #[test]
+    fn constant_work_runs_every_layout_attempt() {
+        let mut config = Config::default();
+        config.layout.attempts = 4;
+        config.layout.objective = Objective::Backtracking;
+        let (quick, report) = generate_reported(&config, 5);
+        assert_eq!(report.attempts, 1, "no locks scores perfectly at once");
+
+        config.constant_work = true;
+        let (steady, steady_report) = generate_reported(&config, 5);
+        assert_eq!(steady_report.attempts, 4);
+        assert_eq!(steady.to_ascii(), quick.to_ascii());
+        assert_eq!(Generator::new(&config, 5).finish().1, steady_report);
+
+        config.layout.objective = Objective::None;
+        assert_eq!(generate_reported(&config, 5).1.attempts, 4);
+    }
+
+    #[test]
+    fn frozen_tiles_stay_put_through_every_pass() {
+        let config = Config {
+            roughness: RoughnessOptions {
+                amount: 0.5,
+                amplitude: 2,
+            },
+            erosion: ErosionOptions {
+                iterations: 3,
+                strength: 0.5,
+            },
+            connectivity: Connectivity::Prune,
+            clearance: (2, 2),
+            ..Config::default()
+        };
+        for seed in 0..4 {
+            let mut dungeon = generate_seeded(&config, seed);
+            dungeon.freeze_rect(20, 10, 12, 8);
+            // A cell of floor inside frozen wall, which neither pruning nor
+            // tunnelling nor the clearance repair may touch
+            for y in 20..23 {
+                for x in 50..53 {
+                    dungeon.edit_tile(x, y, if (x, y) == (51, 21) { Tile::Floor } else { Tile::Wall });
+                }
+            }
+            let frozen: Vec<Point> = dungeon.frozen_tiles().collect();
+            let held = |dungeon: &Dungeon| -> Vec<(Tile, Option<Feature>)> {
+                frozen.iter().map(|&(x, y)| (dungeon.tiles[y][x], dungeon.feature(x, y).cloned())).collect()
+            };
+            let before = held(&dungeon);
+            regenerate(&mut dungeon, &config, seed + 100);
+            assert_eq!(held(&dungeon), before, "seed {}", seed);
+            assert_eq!(dungeon.frozen_tiles().collect::<Vec<_>>(), frozen);
+        }
+    }
+
+    #[test]
+    fn rerolling_a_region_leaves_the_rest_of_the_map_alone() {
+        let config = Config {
+            connectivity: Connectivity::Connect,
+            ..Config::default()
+        };
+        let mut dungeon = generate_seeded(&config, 4);
+        let original = dungeon.clone();
+        let region: Vec<Point> = (10..30).flat_map(|y| (20..45).map(move |x| (x, y))).collect();
+        let inside = |x: usize, y: usize| (20..45).contains(&x) && (10..30).contains(&y);
+        let seed = (1..20).find(|&seed| reroll_region(&mut dungeon, &config, &region, seed)).expect("no reroll kept");
+        for y in 0..dungeon.height {
+            for x in (0..dungeon.width).filter(|&x| !inside(x, y)) {
+                assert_eq!(dungeon.tiles[y][x], original.tiles[y][x], "({}, {}) with seed {}", x, y, seed);
+            }
+        }
+        assert_ne!(dungeon.to_ascii(), original.to_ascii());
+        assert_eq!(dungeon.frozen_tiles().count(), 0);
+    }
+
+    #[test]
+    fn build_hands_over_only_maps_that_keep_their_contracts() {
+        let config = Config {
+            connectivity: Connectivity::Connect,
+            contracts: vec![Contract::Connected, Contract::Floor { min: 0.2, max: 0.9 }],
+            ..Config::default()
+        };
+        let (dungeon, report) = build(&config, 3, &Contracts::new()).unwrap();
+        let (plain, plain_report) = generate_reported(&config, 3);
+        assert_eq!((dungeon.to_ascii(), report), (plain.to_ascii(), plain_report));
+
+        let strict = Config {
+            contracts: vec![Contract::Floor { min: 0.95, max: 1.0 }, Contract::Connected],
+            ..config
+        };
+        let extra = Contracts::new().check("tiny", |dungeon| Err(format!("{} tiles wide", dungeon.width)));
+        let Err(error) = build(&strict, 3, &extra) else {
+            panic!("a map that broke its contracts was handed over");
+        };
+        assert_eq!(error.seed, 3);
+        let broken: Vec<&str> = error.violations.iter().map(|violation| violation.contract.as_str()).collect();
+        assert_eq!(broken, ["floor", "tiny"]);
+        assert_eq!(error.violations[1].message, format!("{} tiles wide", strict.width));
+    }
+
+    #[test]
+    fn doors_keep_a_connected_map_solvable() {
+        let config = Config {
+            doors: 12,
+            connectivity: Connectivity::Connect,
+            ..Config::default()
+        };
+        let options = PathOptions::with_costs(config.costs);
+        let mut hung = 0;
+        for seed in 1..9 {
+            let dungeon = generate_seeded(&config, seed);
+            hung += dungeon.doors().count();
+            assert_eq!(regions(&dungeon).len(), 1, "doors cut the map apart with seed {}", seed);
+            assert!(solver::solve(&dungeon, &options).is_some(), "no way through with seed {}", seed);
+        }
+        assert!(hung > 0);
+    }
+}
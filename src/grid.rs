@@ -0,0 +1,255 @@
+// The terrain side of a map, for algorithms that run the same over a heap
+// map, a fixed one, or an engine's own map structure without copying it out.
+// `Dungeon` sizes itself at run time and keeps its layers on the heap;
+// `FixedDungeon` is its size in its type and nothing but an array of tiles,
+// so it can live on the stack or in a static. The crate itself still needs
+// `std`. `FixedDungeon::generate` and `step` keep their row buffers on the
+// stack and never touch the heap; `simulate_step` allocates two rows per
+// call for other maps, and `step_with_rows` takes buffers from the caller
+// instead. The automaton draws alike over any kind of map: the same seed,
+// probability and rules fill and step a `FixedDungeon` into the very cave
+// `Dungeon::initialize_random` and `simulate_step_with` make. Pathfinding,
+// regions, field of view and the terrain metrics take any `GridMap` too, on
+// the heap.
+use crate::door::DoorState;
+use crate::dungeon::{Dungeon, Rules, Tile};
+use core::cmp::Ordering;
+use core::fmt;
+use rand::Rng;
+
+pub trait GridMap {
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+    fn tile(&self, x: usize, y: usize) -> Tile;
+    fn set_tile(&mut self, x: usize, y: usize, tile: Tile);
+
+    // Tiles no pass may change
+    fn is_frozen(&self, _x: usize, _y: usize) -> bool {
+        false
+    }
+
+    // The door on a tile, for routing around the closed and locked ones
+    fn door_state(&self, _x: usize, _y: usize) -> Option<DoorState> {
+        None
+    }
+
+    // What field of view stops at, which needn't be what stops a walker
+    fn blocks_sight(&self, x: usize, y: usize) -> bool {
+        self.tile(x, y).is_opaque()
+    }
+}
+
+impl GridMap for Dungeon {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn tile(&self, x: usize, y: usize) -> Tile {
+        self.tiles[y][x]
+    }
+
+    fn set_tile(&mut self, x: usize, y: usize, tile: Tile) {
+        Dungeon::set_tile(self, x, y, tile);
+    }
+
+    fn is_frozen(&self, x: usize, y: usize) -> bool {
+        self.frozen[y][x]
+    }
+
+    fn door_state(&self, x: usize, y: usize) -> Option<DoorState> {
+        self.door(x, y).map(|door| door.state)
+    }
+
+    fn blocks_sight(&self, x: usize, y: usize) -> bool {
+        Dungeon::blocks_sight(self, x, y)
+    }
+}
+
+// Noise for the automaton: each tile a wall with `wall_probability`, one
+// draw a tile in row order whether or not it is frozen
+pub fn fill_random<G: GridMap + ?Sized, R: Rng + ?Sized>(grid: &mut G, wall_probability: f64, rng: &mut R) {
+    for y in 0..grid.height() {
+        for x in 0..grid.width() {
+            let wall = rng.gen::<f64>() < wall_probability;
+            if !grid.is_frozen(x, y) {
+                grid.set_tile(x, y, if wall { Tile::Wall } else { Tile::Floor });
+            }
+        }
+    }
+}
+
+// Solid tiles among the 8 neighbours, counting off the map as solid
+pub fn wall_neighbours<G: GridMap + ?Sized>(grid: &G, x: usize, y: usize) -> usize {
+    let mut count = 0;
+    for ny in y as isize - 1..=y as isize + 1 {
+        for nx in x as isize - 1..=x as isize + 1 {
+            if (nx, ny) == (x as isize, y as isize) {
+                continue;
+            }
+            let outside = nx < 0 || ny < 0 || nx as usize >= grid.width() || ny as usize >= grid.height();
+            if outside || !grid.tile(nx as usize, ny as usize).is_walkable() {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+// One automaton step in place; false once nothing changes. `above` and
+// `here` hold rows as they were before the step, so they need the grid's
+// width at least.
+pub fn step_with_rows<G: GridMap + ?Sized>(grid: &mut G, rules: &Rules, above: &mut [Tile], here: &mut [Tile]) -> bool {
+    let (width, height) = (grid.width(), grid.height());
+    assert!(above.len() >= width && here.len() >= width, "row buffers are narrower than the grid");
+    let (mut above, mut here) = (above, here);
+    let mut changed = false;
+    for y in 0..height {
+        for (x, tile) in here.iter_mut().enumerate().take(width) {
+            *tile = grid.tile(x, y);
+        }
+        for x in 0..width {
+            // The rows above and this one as they were, and the one below
+            // still untouched
+            let mut walls = 0;
+            for ny in y as isize - 1..=y as isize + 1 {
+                for nx in x as isize - 1..=x as isize + 1 {
+                    if (nx, ny) == (x as isize, y as isize) {
+                        continue;
+                    }
+                    if nx < 0 || ny < 0 || nx as usize >= width || ny as usize >= height {
+                        walls += 1;
+                        continue;
+                    }
+                    let (nx, ny) = (nx as usize, ny as usize);
+                    let tile = match ny.cmp(&y) {
+                        Ordering::Less => above[nx],
+                        Ordering::Equal => here[nx],
+                        Ordering::Greater => grid.tile(nx, ny),
+                    };
+                    walls += usize::from(!tile.is_walkable());
+                }
+            }
+            let tile = here[x];
+            let next = match tile {
+                tile if grid.is_frozen(x, y) => tile,
+                Tile::Wall if walls < rules.death_limit => Tile::Floor,
+                tile if tile.is_walkable() && walls > rules.birth_limit => Tile::Wall,
+                tile => tile,
+            };
+            if next != tile {
+                grid.set_tile(x, y, next);
+                changed = true;
+            }
+        }
+        core::mem::swap(&mut above, &mut here);
+    }
+    changed
+}
+
+// The same with row buffers of its own
+pub fn simulate_step<G: GridMap + ?Sized>(grid: &mut G, rules: &Rules) -> bool {
+    let width = grid.width();
+    step_with_rows(grid, rules, &mut vec![Tile::Wall; width], &mut vec![Tile::Wall; width])
+}
+
+// The terrain a row a line, in the glyphs `to_ascii` uses
+pub fn write_ascii<G: GridMap + ?Sized>(grid: &G, out: &mut impl fmt::Write) -> fmt::Result {
+    for y in 0..grid.height() {
+        for x in 0..grid.width() {
+            out.write_char(grid.tile(x, y).glyph())?;
+        }
+        out.write_char('\n')?;
+    }
+    Ok(())
+}
+
+// A map of `W` x `H` tiles held inline
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FixedDungeon<const W: usize, const H: usize> {
+    tiles: [[Tile; W]; H],
+}
+
+impl<const W: usize, const H: usize> Default for FixedDungeon<W, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const W: usize, const H: usize> FixedDungeon<W, H> {
+    // Solid rock, as `Dungeon::new` starts
+    pub const fn new() -> Self {
+        Self {
+            tiles: [[Tile::Wall; W]; H],
+        }
+    }
+
+    // A cave from noise and `iterations` automaton steps, stopping early
+    // once it settles
+    pub fn generate<R: Rng + ?Sized>(wall_probability: f64, rules: &Rules, iterations: usize, rng: &mut R) -> Self {
+        let mut dungeon = Self::new();
+        fill_random(&mut dungeon, wall_probability, rng);
+        for _ in 0..iterations {
+            if !dungeon.step(rules) {
+                break;
+            }
+        }
+        dungeon
+    }
+
+    pub fn step(&mut self, rules: &Rules) -> bool {
+        step_with_rows(self, rules, &mut [Tile::Wall; W], &mut [Tile::Wall; W])
+    }
+
+    pub fn rows(&self) -> &[[Tile; W]; H] {
+        &self.tiles
+    }
+
+    // None unless `dungeon` is exactly `W` x `H`; only the terrain comes over
+    pub fn from_dungeon(dungeon: &Dungeon) -> Option<Self> {
+        if dungeon.width != W || dungeon.height != H {
+            return None;
+        }
+        let mut fixed = Self::new();
+        for (row, tiles) in fixed.tiles.iter_mut().zip(&dungeon.tiles) {
+            row.copy_from_slice(tiles);
+        }
+        Some(fixed)
+    }
+
+    // A heap map with the same terrain, for everything past the cave itself
+    pub fn to_dungeon(&self) -> Dungeon {
+        let mut dungeon = Dungeon::new(W, H);
+        for (tiles, row) in dungeon.tiles.iter_mut().zip(&self.tiles) {
+            tiles.copy_from_slice(row);
+        }
+        dungeon
+    }
+}
+
+impl<const W: usize, const H: usize> GridMap for FixedDungeon<W, H> {
+    fn width(&self) -> usize {
+        W
+    }
+
+    fn height(&self) -> usize {
+        H
+    }
+
+    fn tile(&self, x: usize, y: usize) -> Tile {
+        self.tiles[y][x]
+    }
+
+    fn set_tile(&mut self, x: usize, y: usize, tile: Tile) {
+        self.tiles[y][x] = tile;
+    }
+}
+
+impl<const W: usize, const H: usize> fmt::Display for FixedDungeon<W, H> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_ascii(self, f)
+    }
+}
@@ -0,0 +1,178 @@
+// How hard each stretch of rock is to dig through, for games where walls can
+// be tunnelled. The generator marks walls with a hardness under
+// `HARDNESS_KEY` as their last pass, either from smooth noise, so veins of
+// hard and soft rock run through the map, or from depth, so rock grows
+// harder the further it lies from open ground. `dig` wears a wall down by
+// the power of each blow and opens it once nothing is left. Frozen walls
+// are never marked and never give.
+use crate::dungeon::{Dungeon, Tile};
+use rand::Rng;
+use std::collections::VecDeque;
+
+pub const HARDNESS_KEY: &str = "hardness";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HardnessSource {
+    // Walls left unmarked
+    #[default]
+    Off,
+    // Value noise over the map, `scale` tiles between lattice points
+    Noise,
+    // Steps through rock to the nearest walkable tile
+    Depth,
+}
+
+impl HardnessSource {
+    pub const ALL: [HardnessSource; 3] = [HardnessSource::Off, HardnessSource::Noise, HardnessSource::Depth];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HardnessSource::Off => "off",
+            HardnessSource::Noise => "noise",
+            HardnessSource::Depth => "depth",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|source| source.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HardnessOptions {
+    pub source: HardnessSource,
+    // Range of hardness handed out, softest first
+    pub min: usize,
+    pub max: usize,
+    // Noise only: tiles between lattice points, so larger makes broader veins
+    pub scale: usize,
+}
+
+impl Default for HardnessOptions {
+    fn default() -> Self {
+        Self {
+            source: HardnessSource::Off,
+            min: 1,
+            max: 5,
+            scale: 8,
+        }
+    }
+}
+
+// What a blow did to a tile
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dig {
+    // The wall gave and is floor now
+    Opened,
+    // The wall held, with this much hardness left
+    Weakened { remaining: usize },
+    // A frozen wall, which no blow moves
+    Frozen,
+    // Nothing to dig: the tile is not wall
+    NotRock,
+}
+
+impl Dungeon {
+    // Marks every wall not frozen with a hardness, replacing any it had
+    pub fn mark_hardness<R: Rng + ?Sized>(&mut self, options: &HardnessOptions, rng: &mut R) {
+        let (min, max) = (options.min.max(1), options.max.max(options.min.max(1)));
+        let hardness = match options.source {
+            HardnessSource::Off => return,
+            HardnessSource::Noise => self.noise_hardness(options.scale.max(1), min, max, rng),
+            HardnessSource::Depth => self.depth_hardness(min, max),
+        };
+        for (y, row) in hardness.into_iter().enumerate() {
+            for (x, hardness) in row.into_iter().enumerate() {
+                if self.tiles[y][x] == Tile::Wall && !self.frozen[y][x] {
+                    self.set_meta(x, y, HARDNESS_KEY, hardness as i64);
+                }
+            }
+        }
+    }
+
+    fn noise_hardness<R: Rng + ?Sized>(&self, scale: usize, min: usize, max: usize, rng: &mut R) -> Vec<Vec<usize>> {
+        // One more lattice point than cells each way, so every tile has four
+        // corners to blend
+        let columns = self.width / scale + 2;
+        let rows = self.height / scale + 2;
+        let lattice: Vec<Vec<f64>> = (0..rows).map(|_| (0..columns).map(|_| rng.gen()).collect()).collect();
+        let smooth = |t: f64| t * t * (3.0 - 2.0 * t);
+        let levels = (max - min + 1) as f64;
+        (0..self.height)
+            .map(|y| {
+                (0..self.width)
+                    .map(|x| {
+                        let (cx, cy) = (x / scale, y / scale);
+                        let tx = smooth((x % scale) as f64 / scale as f64);
+                        let ty = smooth((y % scale) as f64 / scale as f64);
+                        let top = lattice[cy][cx] + (lattice[cy][cx + 1] - lattice[cy][cx]) * tx;
+                        let bottom = lattice[cy + 1][cx] + (lattice[cy + 1][cx + 1] - lattice[cy + 1][cx]) * tx;
+                        let value = top + (bottom - top) * ty;
+                        (min + (value * levels) as usize).min(max)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Rock touching open ground is the softest, each step further in one
+    // harder until `max`; rock with no open ground at all is `max`
+    fn depth_hardness(&self, min: usize, max: usize) -> Vec<Vec<usize>> {
+        let mut depth = vec![vec![usize::MAX; self.width]; self.height];
+        let mut queue = VecDeque::new();
+        for (y, row) in self.tiles.iter().enumerate() {
+            for (x, tile) in row.iter().enumerate() {
+                if tile.is_walkable() {
+                    depth[y][x] = 0;
+                    queue.push_back((x, y));
+                }
+            }
+        }
+        while let Some((x, y)) = queue.pop_front() {
+            for (dx, dy) in [(0, -1), (1, 0), (0, 1), (-1, 0)] {
+                let (nx, ny) = (x as isize + dx, y as isize + dy);
+                if nx < 0 || ny < 0 || nx as usize >= self.width || ny as usize >= self.height {
+                    continue;
+                }
+                let (nx, ny) = (nx as usize, ny as usize);
+                if depth[ny][nx] == usize::MAX {
+                    depth[ny][nx] = depth[y][x] + 1;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+        depth
+            .into_iter()
+            .map(|row| row.into_iter().map(|depth| min.saturating_add(depth.saturating_sub(1)).min(max)).collect())
+            .collect()
+    }
+
+    // Hardness of the wall here, None if it is not wall. A wall the
+    // generator never marked gives to the first blow.
+    pub fn hardness(&self, x: usize, y: usize) -> Option<usize> {
+        if self.tiles[y][x] != Tile::Wall {
+            return None;
+        }
+        let marked = self.meta(x, y).and_then(|meta| meta.get(HARDNESS_KEY)).and_then(|value| value.as_int());
+        Some(marked.map_or(1, |hardness| hardness.max(0) as usize))
+    }
+
+    // One blow of `power` against the tile: a wall loses that much hardness
+    // and opens into floor once it has none left
+    pub fn dig(&mut self, x: usize, y: usize, power: usize) -> Dig {
+        let Some(hardness) = self.hardness(x, y) else {
+            return Dig::NotRock;
+        };
+        if self.frozen[y][x] {
+            return Dig::Frozen;
+        }
+        if power >= hardness {
+            self.remove_meta(x, y, HARDNESS_KEY);
+            self.set_tile(x, y, Tile::Floor);
+            return Dig::Opened;
+        }
+        let remaining = hardness - power;
+        self.set_meta(x, y, HARDNESS_KEY, remaining as i64);
+        Dig::Weakened { remaining }
+    }
+}
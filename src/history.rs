@@ -0,0 +1,386 @@
+// Ages a finished map over a run of eras, each leaving its mark: a passage
+// caves in, water rises through a wing, a door is bricked up, or builders
+// raise a new hall over the ruins. Nothing aged is ever cut off from the
+// rest of the map, and frozen tiles are left alone. Each era's event is
+// written into the meta layer at its site, under `ERA_KEY` and
+// `HISTORY_KEY`, so the history travels with the map and reads back as a
+// log with `Dungeon::history`.
+use crate::dungeon::{Dungeon, Point, Tile};
+use crate::layers::Feature;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::VecDeque;
+use std::fmt;
+
+pub const ERA_KEY: &str = "era";
+pub const HISTORY_KEY: &str = "history";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HistoryOptions {
+    // Eras to run; an era with nothing left it could do passes quietly
+    pub eras: usize,
+}
+
+// One era's event as the map remembers it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chronicle {
+    // From 1, oldest first
+    pub era: usize,
+    pub site: Point,
+    pub text: String,
+}
+
+impl fmt::Display for Chronicle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Era {}: {}", self.era, self.text)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Event {
+    Collapse,
+    Flood,
+    BrickUp,
+    Build,
+}
+
+const SIDES: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+impl Dungeon {
+    // Returns the eras that left a mark
+    pub fn age<R: Rng + ?Sized>(&mut self, options: &HistoryOptions, rng: &mut R) -> usize {
+        let mut marked = 0;
+        for era in 1..=options.eras {
+            let mut events = [Event::Collapse, Event::Flood, Event::BrickUp, Event::Build];
+            events.shuffle(rng);
+            // The first event the map has room for
+            let Some((site, text)) = events.into_iter().find_map(|event| match event {
+                Event::Collapse => self.collapse(rng),
+                Event::Flood => self.flood_wing(rng),
+                Event::BrickUp => self.brick_up(rng),
+                Event::Build => self.build_over(rng),
+            }) else {
+                continue;
+            };
+            self.set_meta(site.0, site.1, ERA_KEY, era as i64);
+            self.set_meta(site.0, site.1, HISTORY_KEY, text);
+            marked += 1;
+        }
+        marked
+    }
+
+    // Every era's event, oldest first
+    pub fn history(&self) -> Vec<Chronicle> {
+        let mut history: Vec<Chronicle> = self
+            .meta
+            .iter()
+            .filter_map(|(site, meta)| {
+                Some(Chronicle {
+                    era: meta.get(ERA_KEY)?.as_int()?.max(0) as usize,
+                    site,
+                    text: meta.get(HISTORY_KEY)?.as_str()?.to_string(),
+                })
+            })
+            .collect();
+        history.sort_by_key(|chronicle| (chronicle.era, chronicle.site.1, chronicle.site.0));
+        history
+    }
+
+    // The history a line an era, for reading alongside the map
+    pub fn history_log(&self) -> String {
+        self.history().iter().map(|chronicle| format!("{}\n", chronicle)).collect()
+    }
+
+    // "the north-west", "the east", "the middle" and so on, by the world's
+    // north
+    pub(crate) fn area(&self, point: Point) -> String {
+        let ((x, y), (width, height)) = self.world.north.orient(point, (self.width, self.height));
+        let row = ["north", "", "south"][y * 3 / height];
+        let column = ["west", "", "east"][x * 3 / width];
+        match (row, column) {
+            ("", "") => "the middle".to_string(),
+            (row, "") => format!("the {}", row),
+            ("", column) => format!("the {}", column),
+            (row, column) => format!("the {}-{}", row, column),
+        }
+    }
+
+    // A free site for an era's record, one no earlier era wrote to
+    fn unrecorded(&self, (x, y): Point) -> bool {
+        !self.meta(x, y).is_some_and(|meta| meta.contains(HISTORY_KEY))
+    }
+
+    fn open_at(&self, x: i32, y: i32) -> bool {
+        x >= 0
+            && y >= 0
+            && x < self.width as i32
+            && y < self.height as i32
+            && self.tiles[y as usize][x as usize].is_walkable()
+    }
+
+    // Bare floor straight through between two walls
+    fn corridor(&self, (x, y): Point) -> bool {
+        if self.tiles[y][x] != Tile::Floor || self.frozen[y][x] || self.features.contains(x, y) {
+            return false;
+        }
+        let [north, east, south, west] = SIDES.map(|(dx, dy)| self.open_at(x as i32 + dx, y as i32 + dy));
+        (north && south && !east && !west) || (east && west && !north && !south)
+    }
+
+    fn collapse<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Option<(Point, String)> {
+        let mut corridors: Vec<Point> = Vec::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.corridor((x, y)) && self.unrecorded((x, y)) {
+                    corridors.push((x, y));
+                }
+            }
+        }
+        let &start = corridors.choose(rng)?;
+        let length = rng.gen_range(3..=6);
+        let fallen = self.spread(start, length, |dungeon, point| dungeon.corridor(point));
+        if fallen.len() < 2 {
+            return None;
+        }
+        for &(x, y) in &fallen {
+            self.set_tile(x, y, Tile::Rubble);
+        }
+        let text = format!("the passage in {} caved in, choking {} tiles with rubble", self.area(start), fallen.len());
+        Some((start, text))
+    }
+
+    fn flood_wing<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Option<(Point, String)> {
+        let dry = |dungeon: &Dungeon, (x, y): Point| {
+            dungeon.tiles[y][x] == Tile::Floor
+                && !dungeon.frozen[y][x]
+                && !dungeon.features.contains(x, y)
+                && !dungeon.entities.contains(x, y)
+        };
+        let mut floor: Vec<Point> = Vec::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if dry(self, (x, y)) && self.unrecorded((x, y)) {
+                    floor.push((x, y));
+                }
+            }
+        }
+        let &start = floor.choose(rng)?;
+        let size = rng.gen_range(8..=20);
+        let flooded = self.spread(start, size, dry);
+        // A puddle is not worth remembering
+        if flooded.len() < 4 {
+            return None;
+        }
+        for &(x, y) in &flooded {
+            self.set_tile(x, y, Tile::Water);
+        }
+        Some((start, format!("water rose in {} and flooded {} tiles", self.area(start), flooded.len())))
+    }
+
+    fn brick_up<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Option<(Point, String)> {
+        let mut doors: Vec<Point> = self
+            .features
+            .iter()
+            .filter(|&((x, y), feature)| {
+                matches!(feature, Feature::Door(_)) && !self.frozen[y][x] && !self.entities.contains(x, y)
+            })
+            .map(|(point, _)| point)
+            .filter(|&point| self.unrecorded(point))
+            .collect();
+        doors.sort_by_key(|&(x, y)| (y, x));
+        doors.shuffle(rng);
+        let before = self.pieces();
+        for (x, y) in doors {
+            let saved = self.clone();
+            self.set_tile(x, y, Tile::Wall);
+            if self.pieces() <= before {
+                return Some(((x, y), format!("the door in {} was bricked up", self.area((x, y)))));
+            }
+            *self = saved;
+        }
+        None
+    }
+
+    // A square hall walled round, with a way in midway along each side
+    fn build_over<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Option<(Point, String)> {
+        let size = rng.gen_range(5..=7);
+        let mask = self.footprint_mask(size, size);
+        let mut spots: Vec<Point> = Vec::new();
+        for (y, row) in mask.iter().enumerate() {
+            for (x, &fits) in row.iter().enumerate() {
+                // Nothing frozen is built over, and nothing standing is walled in
+                let taken = || {
+                    (y..y + size).any(|y| {
+                        self.frozen[y][x..x + size].contains(&true) || (x..x + size).any(|x| self.entities.contains(x, y))
+                    })
+                };
+                if !fits || taken() {
+                    continue;
+                }
+                if self.unrecorded((x + size / 2, y + size / 2)) {
+                    spots.push((x, y));
+                }
+            }
+        }
+        spots.shuffle(rng);
+        let before = self.pieces();
+        for (left, top) in spots.into_iter().take(8) {
+            let saved = self.clone();
+            for y in top..top + size {
+                for x in left..left + size {
+                    let (dx, dy) = (x - left, y - top);
+                    let edge = dx == 0 || dy == 0 || dx == size - 1 || dy == size - 1;
+                    let gap = dx == size / 2 || dy == size / 2;
+                    self.features.remove(x, y);
+                    self.set_tile(x, y, if edge && !gap { Tile::Wall } else { Tile::Floor });
+                }
+            }
+            if self.pieces() <= before {
+                let site = (left + size / 2, top + size / 2);
+                return Some((site, format!("builders raised a hall over the ruins in {}", self.area(site))));
+            }
+            *self = saved;
+        }
+        None
+    }
+
+    // Stretches of walkable ground, every door taken as open, so a door
+    // is only bricked up where there is another way round
+    fn pieces(&self) -> usize {
+        let mut seen = vec![vec![false; self.width]; self.height];
+        let mut pieces = 0;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if seen[y][x] || !self.tiles[y][x].is_walkable() {
+                    continue;
+                }
+                pieces += 1;
+                seen[y][x] = true;
+                let mut queue = VecDeque::from([(x, y)]);
+                while let Some((x, y)) = queue.pop_front() {
+                    for (dx, dy) in SIDES {
+                        let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                        if self.open_at(nx, ny) && !seen[ny as usize][nx as usize] {
+                            seen[ny as usize][nx as usize] = true;
+                            queue.push_back((nx as usize, ny as usize));
+                        }
+                    }
+                }
+            }
+        }
+        pieces
+    }
+
+    // Up to `limit` tiles reached from `start` through tiles `fits` takes
+    fn spread(&self, start: Point, limit: usize, fits: impl Fn(&Dungeon, Point) -> bool) -> Vec<Point> {
+        let mut reached = vec![start];
+        let mut queue = VecDeque::from([start]);
+        while let Some((x, y)) = queue.pop_front() {
+            for (dx, dy) in SIDES {
+                if reached.len() == limit {
+                    return reached;
+                }
+                let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                if nx < 0 || ny < 0 || nx >= self.width as i32 || ny >= self.height as i32 {
+                    continue;
+                }
+                let next = (nx as usize, ny as usize);
+                if !reached.contains(&next) && fits(self, next) {
+                    reached.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+        reached
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::connectivity::Connectivity;
+    use crate::generator::{generate_seeded, rng_for};
+    use rand::rngs::StdRng;
+
+    type Change = fn(&mut Dungeon, &mut StdRng) -> Option<(Point, String)>;
+
+    // Runs one kind of event on connected maps with doors, looping enough
+    // that some door has a way round, and checks it cuts nothing off and
+    // joins nothing up. `check` gets each map before and after, the event's
+    // site and the tiles it changed; returns how many maps it happened on.
+    fn each_map(change: Change, check: impl Fn(&Dungeon, &Dungeon, Point, &[Point])) -> usize {
+        let config = Config {
+            doors: 12,
+            connectivity: Connectivity::Spanning,
+            loop_tunnels: 6,
+            ..Config::default()
+        };
+        let mut happened = 0;
+        for seed in 0..8 {
+            let before = generate_seeded(&config, seed);
+            let mut after = before.clone();
+            let site = change(&mut after, &mut rng_for(seed));
+            let changed: Vec<Point> = (0..before.height)
+                .flat_map(|y| (0..before.width).map(move |x| (x, y)))
+                .filter(|&(x, y)| before.tiles[y][x] != after.tiles[y][x])
+                .collect();
+            let Some((site, _)) = site else {
+                assert!(changed.is_empty(), "seed {} changed the map with nothing to show", seed);
+                continue;
+            };
+            assert_eq!(after.pieces(), before.pieces(), "seed {}", seed);
+            check(&before, &after, site, &changed);
+            happened += 1;
+        }
+        happened
+    }
+
+    #[test]
+    fn a_collapse_fills_only_corridor_with_rubble() {
+        let happened = each_map(Dungeon::collapse, |before, after, site, changed| {
+            assert!(changed.contains(&site) && (2..=6).contains(&changed.len()));
+            for &(x, y) in changed {
+                assert!(before.corridor((x, y)), "({}, {}) was no corridor", x, y);
+                assert_eq!(after.tiles[y][x], Tile::Rubble);
+            }
+        });
+        assert!(happened > 0);
+    }
+
+    #[test]
+    fn a_flood_turns_only_dry_floor_to_water() {
+        let happened = each_map(Dungeon::flood_wing, |before, after, site, changed| {
+            assert!(changed.contains(&site) && (4..=20).contains(&changed.len()));
+            for &(x, y) in changed {
+                assert_eq!((before.tiles[y][x], after.tiles[y][x]), (Tile::Floor, Tile::Water));
+                assert!(!before.features.contains(x, y) && !before.entities.contains(x, y));
+            }
+        });
+        assert!(happened > 0);
+    }
+
+    #[test]
+    fn bricking_up_walls_over_just_the_door() {
+        let happened = each_map(Dungeon::brick_up, |before, after, site, changed| {
+            assert_eq!(changed, [site]);
+            assert!(before.door(site.0, site.1).is_some());
+            assert_eq!(after.tiles[site.1][site.0], Tile::Wall);
+            assert!(after.door(site.0, site.1).is_none());
+        });
+        assert!(happened > 0);
+    }
+
+    #[test]
+    fn a_new_hall_stays_inside_its_footprint() {
+        let happened = each_map(Dungeon::build_over, |_, after, (cx, cy), changed| {
+            // The site is the middle of a hall 5 to 7 tiles across
+            for &(x, y) in changed {
+                let inside = x.abs_diff(cx) <= 3 && y.abs_diff(cy) <= 3;
+                assert!(inside, "({}, {}) is outside the hall at ({}, {})", x, y, cx, cy);
+            }
+            assert!(after.tiles[cy][cx].is_walkable());
+        });
+        assert!(happened > 0);
+    }
+}
@@ -0,0 +1,218 @@
+use crate::door::DoorState;
+use crate::labels::RegionLabel;
+use crate::layers::{Entity, Feature};
+use crate::rows::render_rows;
+use crate::theme::Theme;
+use crate::Dungeon;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+// Doors and entities look the same under every theme, as in the terminal
+const DOOR_RGB: [u8; 3] = [150, 90, 40];
+const LOCKED_DOOR_RGB: [u8; 3] = [180, 40, 40];
+const TRAP_RGB: [u8; 3] = [200, 60, 200];
+const DECORATION_RGB: [u8; 3] = [170, 170, 170];
+const ENTRANCE_RGB: [u8; 3] = [60, 200, 220];
+const EXIT_RGB: [u8; 3] = [60, 200, 90];
+const MONSTER_RGB: [u8; 3] = [220, 40, 40];
+const ITEM_RGB: [u8; 3] = [220, 80, 220];
+
+const LABEL_STYLE: &str = "font-family=\"serif\" text-anchor=\"middle\" dominant-baseline=\"central\" \
+                           fill=\"#f4ecd8\" stroke=\"#000\" stroke-width=\"0.12\" paint-order=\"stroke\"";
+
+// Flat color of one tile with entities over features over terrain; secret
+// doors pass for wall
+pub fn tile_rgb(dungeon: &Dungeon, theme: &Theme, x: usize, y: usize) -> [u8; 3] {
+    if let Some(entity) = dungeon.entity(x, y) {
+        return match entity {
+            Entity::Entrance => ENTRANCE_RGB,
+            Entity::Exit => EXIT_RGB,
+            Entity::Monster(_) => MONSTER_RGB,
+            Entity::Item(_) => ITEM_RGB,
+        };
+    }
+    match dungeon.feature(x, y) {
+        Some(Feature::Door(door)) => match door.state {
+            DoorState::Open | DoorState::Closed => DOOR_RGB,
+            DoorState::Locked => LOCKED_DOOR_RGB,
+            DoorState::Secret => theme.wall.rgb,
+        },
+        Some(Feature::Trap(_)) => TRAP_RGB,
+        Some(Feature::Decoration(_)) => DECORATION_RGB,
+        None => theme.style(dungeon.tile(x, y)).rgb,
+    }
+}
+
+// Truecolor PNG with `scale` x `scale` pixels per tile
+pub fn write_png(dungeon: &Dungeon, theme: &Theme, scale: usize, path: &Path) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    out.write_all(&png_bytes(dungeon, theme, scale))?;
+    out.flush()
+}
+
+// The same PNG in memory. Every tile row gives `scale` identical pixel rows,
+// so each is worked out once.
+pub fn png_bytes(dungeon: &Dungeon, theme: &Theme, scale: usize) -> Vec<u8> {
+    let (width, height) = (dungeon.width() * scale, dungeon.height() * scale);
+    let lines = render_rows(dungeon.height(), width, |y| {
+        let mut line = Vec::with_capacity(width * 3);
+        for x in 0..dungeon.width() {
+            let rgb = tile_rgb(dungeon, theme, x, y);
+            for _ in 0..scale {
+                line.extend_from_slice(&rgb);
+            }
+        }
+        line
+    });
+    let mut pixels = Vec::with_capacity((width * 3 + 1) * height);
+    for line in &lines {
+        for _ in 0..scale {
+            // Filter type: none
+            pixels.push(0);
+            pixels.extend_from_slice(line);
+        }
+    }
+
+    let mut out = Vec::new();
+    out.extend_from_slice(b"\x89PNG\r\n\x1a\n");
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // 8 bits per channel, RGB, default compression, filtering and no interlace
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    write_chunk(&mut out, b"IHDR", &header);
+    write_chunk(&mut out, b"IDAT", &zlib_stored(&pixels));
+    write_chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let mut crc = Crc32::new();
+    crc.update(kind);
+    crc.update(data);
+    out.extend_from_slice(&crc.finish().to_be_bytes());
+}
+
+// zlib stream made of uncompressed deflate blocks. Maps are small enough that
+// the size hardly matters and it keeps the writer trivially correct.
+pub(crate) fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const BLOCK: usize = 65535;
+    let mut out = Vec::with_capacity(data.len() + data.len() / BLOCK * 5 + 11);
+    out.extend_from_slice(&[0x78, 0x01]);
+    let mut blocks = data.chunks(BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        out.push(blocks.peek().is_none() as u8);
+        let len = block.len() as u16;
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    out.extend_from_slice(&((b << 16) | a).to_be_bytes());
+    out
+}
+
+pub(crate) struct Crc32 {
+    table: [u32; 256],
+    value: u32,
+}
+
+impl Crc32 {
+    pub(crate) fn new() -> Self {
+        let mut table = [0u32; 256];
+        for (n, entry) in table.iter_mut().enumerate() {
+            let mut c = n as u32;
+            for _ in 0..8 {
+                c = if c & 1 != 0 { 0xedb8_8320 ^ (c >> 1) } else { c >> 1 };
+            }
+            *entry = c;
+        }
+        Self { table, value: 0xffff_ffff }
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.value = self.table[((self.value ^ byte as u32) & 0xff) as usize] ^ (self.value >> 8);
+        }
+    }
+
+    pub(crate) fn finish(&self) -> u32 {
+        self.value ^ 0xffff_ffff
+    }
+}
+
+// One rect per run of same-colored tiles along a row, which keeps files small
+pub fn write_svg(dungeon: &Dungeon, theme: &Theme, scale: usize, path: &Path) -> io::Result<()> {
+    write_labelled_svg(dungeon, theme, scale, &[], path)
+}
+
+// The same with each room's name written over it, outlined so it reads on
+// any floor
+pub fn write_labelled_svg(
+    dungeon: &Dungeon,
+    theme: &Theme,
+    scale: usize,
+    labels: &[RegionLabel],
+    path: &Path,
+) -> io::Result<()> {
+    let rows = render_rows(dungeon.height(), dungeon.width(), |y| {
+        let mut row = String::new();
+        let mut x = 0;
+        while x < dungeon.width() {
+            let rgb = tile_rgb(dungeon, theme, x, y);
+            let start = x;
+            while x < dungeon.width() && tile_rgb(dungeon, theme, x, y) == rgb {
+                x += 1;
+            }
+            row.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"1\" fill=\"#{:02x}{:02x}{:02x}\"/>\n",
+                start,
+                y,
+                x - start,
+                rgb[0],
+                rgb[1],
+                rgb[2]
+            ));
+        }
+        row
+    });
+
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" shape-rendering=\"crispEdges\">",
+        dungeon.width() * scale,
+        dungeon.height() * scale,
+        dungeon.width(),
+        dungeon.height()
+    )?;
+    writeln!(out, "<title>{} dungeon</title>", theme.name)?;
+    for row in rows {
+        out.write_all(row.as_bytes())?;
+    }
+    for label in labels {
+        writeln!(
+            out,
+            "<text x=\"{:.2}\" y=\"{:.2}\" font-size=\"{:.2}\" {}>{}</text>",
+            label.x,
+            label.y,
+            label.font_size,
+            LABEL_STYLE,
+            label.name
+        )?;
+    }
+    out.write_all(b"</svg>\n")?;
+    out.flush()
+}
@@ -0,0 +1,156 @@
+// Generation a slice at a time, for a game that wants to keep drawing frames
+// (a loading animation, say) while a map is built on the same thread:
+//
+//     let mut generator = Generator::new(&config, seed);
+//     while let Progress::Working { share, .. } = generator.advance(Duration::from_millis(4)) {
+//         draw_loading_bar(share);
+//     }
+//     let (dungeon, report) = generator.finish();
+//
+// The work comes in units small enough to slice (laying out the noise or the
+// rooms, each automaton step, the post-passes as a whole), and `advance`
+// runs them until its budget is spent, at least one per call. The map comes
+// out exactly as `generate_reported` builds it for the same config and seed.
+use crate::config::Config;
+use crate::dungeon::Dungeon;
+use crate::generator::{
+    attempt_seed, finish_in, reset_seeded, step_in, step_limit, stops_when_settled, Report, SeedRng, Selection,
+};
+use crate::profiling;
+use crate::scratch::Scratch;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Progress {
+    // The layout attempt under way, counting from 0, the automaton steps it
+    // has run, and a rough share of the whole job done; a map that settles
+    // early or an objective that is met skips the rest
+    Working { attempt: usize, steps: usize, share: f64 },
+    Done,
+}
+
+pub struct Generator {
+    config: Config,
+    seed: u64,
+    blank: Dungeon,
+    selection: Selection,
+    run: Option<Run>,
+    // The map kept so far
+    kept: Option<(Dungeon, Report)>,
+    done: bool,
+}
+
+// One layout attempt part way through
+struct Run {
+    dungeon: Dungeon,
+    rng: SeedRng,
+    scratch: Scratch,
+    steps: usize,
+    converged: bool,
+}
+
+impl Generator {
+    pub fn new(config: &Config, seed: u64) -> Self {
+        Self {
+            config: config.clone(),
+            seed,
+            blank: Dungeon::new(config.width, config.height),
+            selection: Selection::default(),
+            run: None,
+            kept: None,
+            done: false,
+        }
+    }
+
+    // Works until `budget` is spent, finishing the unit it is in
+    pub fn advance(&mut self, budget: Duration) -> Progress {
+        let started = Instant::now();
+        while !self.done {
+            self.work();
+            if started.elapsed() >= budget {
+                break;
+            }
+        }
+        self.progress()
+    }
+
+    pub fn progress(&self) -> Progress {
+        if self.done {
+            return Progress::Done;
+        }
+        // Laying out, each step, and the post-passes
+        let units = step_limit(&self.config) + 2;
+        let attempt = self.selection.attempts();
+        let within = self.run.as_ref().map_or(0, |run| run.steps + 1);
+        let total = self.config.layout.attempts.max(1) * units;
+        Progress::Working {
+            attempt,
+            steps: self.run.as_ref().map_or(0, |run| run.steps),
+            share: (attempt * units + within) as f64 / total as f64,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    // The map as it stands, for drawing while it grows: the attempt under
+    // way, or the one kept once they are all through
+    pub fn dungeon(&self) -> &Dungeon {
+        match (&self.run, &self.kept) {
+            (Some(run), _) => &run.dungeon,
+            (None, Some((dungeon, _))) => dungeon,
+            (None, None) => &self.blank,
+        }
+    }
+
+    // Runs whatever is left in one go
+    pub fn finish(mut self) -> (Dungeon, Report) {
+        while !self.done {
+            self.work();
+        }
+        self.kept.expect("a finished run keeps a map")
+    }
+
+    fn work(&mut self) {
+        let config = &self.config;
+        let Some(run) = &mut self.run else {
+            let mut dungeon = self.blank.clone();
+            let rng = reset_seeded(&mut dungeon, config, attempt_seed(self.seed, self.selection.attempts()));
+            self.run = Some(Run {
+                dungeon,
+                rng,
+                scratch: Scratch::new(),
+                steps: 0,
+                converged: false,
+            });
+            return;
+        };
+        if run.steps < step_limit(config) && !(run.converged && stops_when_settled(config)) {
+            run.steps += 1;
+            let _span = profiling::span("step");
+            run.converged |= !step_in(&mut run.dungeon, config, &mut run.scratch);
+            return;
+        }
+
+        let mut run = self.run.take().unwrap();
+        let (carved, locks) = finish_in(&mut run.dungeon, config, &mut run.rng, &mut (), &mut run.scratch);
+        let report = Report {
+            algorithm: config.algorithm.name(),
+            steps: run.steps,
+            converged: run.converged,
+            carved,
+            locks,
+            backtracking: run.dungeon.backtracking(),
+            attempts: 1,
+        };
+        // As `generate_reported` picks among attempts
+        if self.selection.keeps(&run.dungeon, config) {
+            self.kept = Some((run.dungeon, report));
+        }
+        if let Some((_, kept)) = &mut self.kept {
+            kept.attempts = self.selection.attempts();
+        }
+        self.done = !self.selection.wants_more(config);
+    }
+}
@@ -0,0 +1,945 @@
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode},
+    execute,
+    style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor},
+    terminal::{self, ClearType},
+};
+use crate::clipboard;
+use crate::console::{self, Console};
+use crate::open;
+use dungeon_generator::ambient::{Ambient, Weather};
+use dungeon_generator::autoexplore;
+use dungeon_generator::capabilities::Capabilities;
+use dungeon_generator::erosion::ErosionOptions;
+use dungeon_generator::events::{self, RegionWatch};
+use dungeon_generator::exploration::Exploration;
+use dungeon_generator::favorites::{self, Favorite};
+use dungeon_generator::generator;
+use dungeon_generator::image;
+use dungeon_generator::keys::{Action, KeyBindings};
+use dungeon_generator::messages::{Catalog, Message};
+use dungeon_generator::pathfinding::{self, PathOptions};
+use dungeon_generator::roughness::RoughnessOptions;
+use dungeon_generator::scratch::Scratch;
+use dungeon_generator::solver::Solution;
+use dungeon_generator::terrain::TerrainOptions;
+use dungeon_generator::theme::Theme;
+use dungeon_generator::{Config, Dungeon, Point, Preset, SeededRng, Tile, View, Zoom};
+use rand::Rng;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+// Settings the parameter panel can tune while the map regenerates live
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Param {
+    WallProbability,
+    Iterations,
+    BirthLimit,
+    DeathLimit,
+}
+
+impl Param {
+    const ALL: [Param; 4] = [Param::WallProbability, Param::Iterations, Param::BirthLimit, Param::DeathLimit];
+
+    fn label(self) -> &'static str {
+        match self {
+            Param::WallProbability => "Wall probability",
+            Param::Iterations => "Iterations",
+            Param::BirthLimit => "Birth limit",
+            Param::DeathLimit => "Death limit",
+        }
+    }
+
+    fn value(self, config: &Config) -> String {
+        match self {
+            Param::WallProbability => format!("{:.2}", config.wall_probability),
+            Param::Iterations => config.iterations.to_string(),
+            Param::BirthLimit => config.rules.birth_limit.to_string(),
+            Param::DeathLimit => config.rules.death_limit.to_string(),
+        }
+    }
+
+    fn adjust(self, config: &mut Config, delta: i32) {
+        let step = |value: usize, max: usize| value.saturating_add_signed(delta as isize).min(max);
+        match self {
+            Param::WallProbability => {
+                config.wall_probability = (config.wall_probability + delta as f64 * 0.01).clamp(0.0, 1.0);
+            }
+            Param::Iterations => config.iterations = step(config.iterations, 50),
+            Param::BirthLimit => config.rules.birth_limit = step(config.rules.birth_limit, 8),
+            Param::DeathLimit => config.rules.death_limit = step(config.rules.death_limit, 8),
+        }
+    }
+}
+
+// A config file that rebuilds this exact map
+pub fn seed_text(config: &Config, seed: u64) -> String {
+    Config {
+        seed: Some(seed),
+        ..config.clone()
+    }
+    .to_toml()
+}
+
+fn status(stdout: &mut io::Stdout, row: usize, text: &str) -> io::Result<()> {
+    execute!(
+        stdout,
+        cursor::MoveTo(0, row as u16),
+        terminal::Clear(ClearType::UntilNewLine),
+        Print(text)
+    )
+}
+
+// Plays the cellular automaton step by step, then runs the post-passes. The
+// result matches `generator::generate_seeded` for the same seed.
+fn animate(stdout: &mut io::Stdout, config: &Config, seed: u64, messages: &Catalog) -> io::Result<Dungeon> {
+    let (mut dungeon, mut rng) = generator::start_seeded(config, seed);
+    let mut scratch = Scratch::new();
+
+    status(stdout, 0, &messages.text(Message::Generating, &[]))?;
+    thread::sleep(Duration::from_millis(500));
+
+    for iteration in 0..generator::step_limit(config) {
+        dungeon.render_themed(&config.theme)?;
+        let text = messages.text(Message::Iteration, &[("iteration", &(iteration + 1))]);
+        status(stdout, config.height + 1, &text)?;
+        thread::sleep(Duration::from_millis(300));
+
+        if !generator::step_in(&mut dungeon, config, &mut scratch) && generator::stops_when_settled(config) {
+            break;
+        }
+    }
+
+    generator::finish(&mut dungeon, config, &mut rng);
+    Ok(dungeon)
+}
+
+fn draw_panel(
+    stdout: &mut io::Stdout,
+    top: usize,
+    config: &Config,
+    seed: u64,
+    selected: usize,
+    messages: &Catalog,
+) -> io::Result<()> {
+    status(stdout, top, &messages.text(Message::Seed, &[("seed", &seed)]))?;
+    for (index, param) in Param::ALL.iter().enumerate() {
+        let line = format!("{:<18}{:>6}", param.label(), param.value(config));
+        execute!(
+            stdout,
+            cursor::MoveTo(0, (top + 1 + index) as u16),
+            terminal::Clear(ClearType::UntilNewLine),
+        )?;
+        if index == selected {
+            execute!(stdout, SetAttribute(Attribute::Reverse), Print(line), SetAttribute(Attribute::Reset))?;
+        } else {
+            execute!(stdout, Print(line))?;
+        }
+    }
+    status(stdout, top + 2 + Param::ALL.len(), &controls(messages, &config.keys))?;
+    stdout.flush()
+}
+
+// The controls line with the keys each action is bound to
+fn controls(messages: &Catalog, keys: &KeyBindings) -> String {
+    let key = |action| keys.short_label(action);
+    let pair = |a, b| format!("{}/{}", key(a), key(b));
+    messages.text(
+        Message::Controls,
+        &[
+            ("pick", &pair(Action::PickPrevious, Action::PickNext)),
+            ("adjust", &pair(Action::Decrease, Action::Increase)),
+            ("zoom", &pair(Action::ZoomIn, Action::ZoomOut)),
+            ("new_seed", &key(Action::NewSeed)),
+            ("favorite", &key(Action::Favorite)),
+            ("copy", &pair(Action::CopyMap, Action::CopySeed)),
+            ("image", &key(Action::ExportImage)),
+            ("room", &key(Action::NextRoom)),
+            ("help", &key(Action::Help)),
+            ("quit", &key(Action::Quit)),
+        ],
+    )
+}
+
+// Every action with its keys, in a block over the top-left of the map
+fn draw_help(stdout: &mut io::Stdout, keys: &KeyBindings) -> io::Result<()> {
+    let column = Action::ALL.iter().map(|&action| keys.label(action).chars().count()).max().unwrap_or(0) + 2;
+    let mut lines = vec!["Keys".to_string(), String::new()];
+    lines.extend(
+        Action::ALL
+            .iter()
+            .map(|&action| format!("{:<column$}{}", keys.label(action), action.describe(), column = column)),
+    );
+    lines.extend([String::new(), "Ctrl+C always quits; any key closes this".to_string()]);
+    let width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+    for (row, line) in lines.iter().enumerate() {
+        execute!(
+            stdout,
+            cursor::MoveTo(1, 1 + row as u16),
+            SetAttribute(Attribute::Reverse),
+            Print(format!(" {:<width$} ", line, width = width)),
+            SetAttribute(Attribute::Reset)
+        )?;
+    }
+    stdout.flush()
+}
+
+// Time between frames of the ambient effects
+const FRAME: Duration = Duration::from_millis(120);
+
+// Pixels a side for each tile of an image exported with e, the --png default
+const EXPORT_SCALE: usize = 8;
+
+// Puts back the tiles particles have moved off and draws them where they are
+// now, with the map's top-left corner at `screen`
+fn draw_weather(
+    stdout: &mut io::Stdout,
+    dungeon: &Dungeon,
+    theme: &Theme,
+    weather: &Weather,
+    screen: (u16, u16),
+    shown: &mut Vec<Point>,
+) -> io::Result<()> {
+    let cells = weather.cells(dungeon);
+    for &(x, y) in shown.iter() {
+        if !cells.iter().any(|&(point, _, _)| point == (x, y)) {
+            let view = View {
+                origin: (x, y),
+                columns: 1,
+                rows: 1,
+                screen: (screen.0 + x as u16, screen.1 + y as u16),
+                zoom: Zoom::Tile,
+            };
+            dungeon.render_view(theme, &view)?;
+        }
+    }
+    shown.clear();
+    for ((x, y), color, glyph) in cells {
+        mark(stdout, (screen.0 as usize + x, screen.1 as usize + y), color, glyph)?;
+        shown.push((x, y));
+    }
+    stdout.flush()
+}
+
+// The name and tiles of the map's `index`th named room, counting round
+fn named_room(dungeon: &Dungeon, seed: u64, index: usize) -> Option<(String, Vec<Point>)> {
+    let labels = dungeon.region_labels(seed);
+    let label = labels.get(index % labels.len().max(1))?;
+    let (regions, _) = dungeon.label_regions();
+    let mut tiles = Vec::new();
+    for (y, row) in regions.iter().enumerate() {
+        for (x, region) in row.iter().enumerate() {
+            if *region == Some(label.region) {
+                tiles.push((x, y));
+            }
+        }
+    }
+    Some((label.name.clone(), tiles))
+}
+
+pub fn run(mut config: Config, favorites_path: &Path, messages: &Catalog, ambient: Ambient) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    let mut seed = generator::seed_for(&config);
+
+    let _console = Console::enter()?;
+
+    let mut dungeon = animate(&mut stdout, &config, seed, messages)?;
+    let mut selected = 0;
+    let mut zoom = Zoom::Tile;
+    let mut message = messages.text(Message::Complete, &[]);
+    let mut weather = Weather::new(ambient, &config.theme, &dungeon, seed);
+    // The room picked with Tab, and how many times rooms have been rerolled
+    // so each reroll draws from its own seed
+    let mut room: Option<usize> = None;
+    let mut rerolls = 0;
+    // The last image exported, for o to open
+    let mut exported: Option<PathBuf> = None;
+    // Whether the keys are listed over the map
+    let mut help = false;
+
+    loop {
+        let view = View::fit(&dungeon, zoom);
+        dungeon.render_view(&config.theme, &view)?;
+        // Zoomed out, tiles share cells, so only full zoom shows the pick
+        if let Some((_, tiles)) = room.and_then(|index| named_room(&dungeon, seed, index)) {
+            if zoom == Zoom::Tile {
+                for (x, y) in tiles {
+                    let bare = dungeon.feature(x, y).is_none() && dungeon.entity(x, y).is_none();
+                    if dungeon.tile(x, y) == Tile::Floor && bare {
+                        mark(&mut stdout, (x, y), Color::Yellow, config.theme.floor.glyph)?;
+                    }
+                }
+            }
+        }
+        draw_panel(&mut stdout, view.rows + 1, &config, seed, selected, messages)?;
+        status(&mut stdout, view.rows + 4 + Param::ALL.len(), &message)?;
+        message.clear();
+        if help {
+            draw_help(&mut stdout, &config.keys)?;
+        }
+
+        // Particles play between key presses; zoomed out there's no room
+        let mut shown = Vec::new();
+        let event = loop {
+            match weather.as_mut() {
+                Some(weather) if zoom == Zoom::Tile && !help && !event::poll(FRAME)? => {
+                    weather.tick(&dungeon);
+                    draw_weather(&mut stdout, &dungeon, &config.theme, weather, (0, 0), &mut shown)?;
+                }
+                _ => break event::read()?,
+            }
+        };
+        // The old frame may linger past the edges of the new one
+        if let Event::Resize(..) = event {
+            execute!(stdout, terminal::Clear(ClearType::All))?;
+            continue;
+        }
+        let Some(key) = console::pressed(event) else {
+            continue;
+        };
+        if console::interrupted(&key) {
+            break;
+        }
+        // Any key puts the map back
+        if help {
+            help = false;
+            execute!(stdout, terminal::Clear(ClearType::All))?;
+            continue;
+        }
+        let action = console::key_name(&key).and_then(|name| config.keys.action(&name));
+        match action {
+            Some(Action::Quit) => break,
+            Some(Action::Help) => help = true,
+            Some(Action::PickPrevious) => selected = (selected + Param::ALL.len() - 1) % Param::ALL.len(),
+            Some(Action::PickNext) => selected = (selected + 1) % Param::ALL.len(),
+            Some(Action::Decrease | Action::Increase) => {
+                let delta = if action == Some(Action::Decrease) { -1 } else { 1 };
+                Param::ALL[selected].adjust(&mut config, delta);
+                dungeon = generator::generate_seeded(&config, seed);
+                room = None;
+            }
+            Some(Action::NewSeed) => {
+                seed = generator::random_seed();
+                dungeon = generator::generate_seeded(&config, seed);
+                room = None;
+            }
+            Some(Action::NextRoom | Action::PreviousRoom) => {
+                let rooms = dungeon.region_labels(seed).len();
+                if rooms > 0 {
+                    let back = action == Some(Action::PreviousRoom);
+                    room = Some(match room {
+                        Some(index) if back => (index + rooms - 1) % rooms,
+                        Some(index) => (index + 1) % rooms,
+                        None if back => rooms - 1,
+                        None => 0,
+                    });
+                }
+                if let Some((name, _)) = room.and_then(|index| named_room(&dungeon, seed, index)) {
+                    let keys = &config.keys;
+                    message = messages.text(
+                        Message::RoomSelected,
+                        &[
+                            ("name", &name),
+                            ("reroll", &keys.short_label(Action::RerollRoom)),
+                            ("next", &keys.short_label(Action::NextRoom)),
+                        ],
+                    );
+                }
+            }
+            Some(Action::RerollRoom) => {
+                if let Some((name, tiles)) = room.and_then(|index| named_room(&dungeon, seed, index)) {
+                    rerolls += 1;
+                    let reroll_seed = SeededRng::new(seed).child(&format!("room reroll {}", rerolls)).seed();
+                    let rerolled = generator::reroll_region(&mut dungeon, &config, &tiles, reroll_seed);
+                    let text = if rerolled { Message::RoomRerolled } else { Message::RerollFailed };
+                    message = messages.text(text, &[("name", &name)]);
+                }
+            }
+            Some(Action::Favorite) => {
+                let favorite = Favorite::new(&config, seed, &dungeon);
+                message = match favorites::append_favorite(favorites_path, &favorite) {
+                    Ok(()) => messages.text(Message::Saved, &[("seed", &seed), ("path", &favorites_path.display())]),
+                    Err(error) => messages.text(Message::SaveFailed, &[("error", &error)]),
+                };
+            }
+            Some(Action::CopyMap | Action::CopySeed) => {
+                let (text, copied, failed) = if action == Some(Action::CopyMap) {
+                    (dungeon.to_ascii(), Message::CopiedMap, Message::CopyMapFailed)
+                } else {
+                    (seed_text(&config, seed), Message::CopiedSeed, Message::CopySeedFailed)
+                };
+                message = match clipboard::copy(&text) {
+                    Ok(via) => messages.text(copied, &[("via", &via)]),
+                    Err(error) => messages.text(failed, &[("error", &error)]),
+                };
+            }
+            Some(Action::ExportImage) => {
+                let path = PathBuf::from(format!("dungeon-{}.png", seed));
+                message = match image::write_png(&dungeon, &config.theme, EXPORT_SCALE, &path) {
+                    Ok(()) => {
+                        let shown = if Capabilities::detect().hyperlinks {
+                            open::link(&path)
+                        } else {
+                            path.display().to_string()
+                        };
+                        exported = Some(path);
+                        let open = config.keys.short_label(Action::OpenImage);
+                        messages.text(Message::Exported, &[("path", &shown), ("open", &open)])
+                    }
+                    Err(error) => messages.text(Message::ExportFailed, &[("error", &error)]),
+                };
+            }
+            Some(Action::OpenImage) => {
+                if let Some(path) = &exported {
+                    message = match open::open(path) {
+                        Ok(via) => messages.text(Message::Opened, &[("path", &path.display()), ("via", &via)]),
+                        Err(error) => messages.text(Message::OpenFailed, &[("path", &path.display()), ("error", &error)]),
+                    };
+                }
+            }
+            Some(Action::ZoomIn | Action::ZoomOut) => {
+                zoom = if action == Some(Action::ZoomOut) { zoom.zoom_out() } else { zoom.zoom_in() };
+                execute!(stdout, terminal::Clear(ClearType::All))?;
+            }
+            None => {}
+        }
+    }
+
+    Ok(())
+}
+
+// How often a watched config file is looked at
+const WATCH_POLL: Duration = Duration::from_millis(250);
+
+// When the file was last written and how long it is, which changes with
+// every save; None while it isn't there, as part-way through an editor's
+// save by rename
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+// Regenerates each time `path` is saved, from the config `load` builds out
+// of it. A save that doesn't load leaves the last good map up with the
+// error beneath it. The seed carries over from save to save unless the
+// file sets one, so each edit shows on the same map; r rolls a new one.
+pub fn watch(path: &Path, mut config: Config, load: impl Fn() -> io::Result<Config>) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    let _console = Console::enter()?;
+
+    let mut seed = generator::seed_for(&config);
+    let mut dungeon = generator::generate_seeded(&config, seed);
+    let mut seen = stamp(path);
+    let mut saves = 0;
+    let mut message = String::new();
+    let mut redraw = true;
+
+    loop {
+        if redraw {
+            let view = View::fit(&dungeon, Zoom::Tile);
+            dungeon.render_view(&config.theme, &view)?;
+            let watching = format!("Watching {}  seed {}  r new seed  q quit", path.display(), seed);
+            status(&mut stdout, view.rows + 1, &watching)?;
+            status(&mut stdout, view.rows + 2, &message)?;
+            redraw = false;
+        }
+
+        if event::poll(WATCH_POLL)? {
+            let event = event::read()?;
+            if let Event::Resize(..) = event {
+                execute!(stdout, terminal::Clear(ClearType::All))?;
+                redraw = true;
+                continue;
+            }
+            let Some(key) = console::pressed(event) else {
+                continue;
+            };
+            if console::quits(&key) {
+                break;
+            }
+            if key.code == KeyCode::Char('r') {
+                seed = generator::random_seed();
+                dungeon = generator::generate_seeded(&config, seed);
+                redraw = true;
+            }
+            continue;
+        }
+
+        let now = stamp(path);
+        if now.is_none() || now == seen {
+            continue;
+        }
+        seen = now;
+        saves += 1;
+        message = match load() {
+            Ok(loaded) => {
+                config = loaded;
+                seed = config.seed.unwrap_or(seed);
+                dungeon = generator::generate_seeded(&config, seed);
+                format!("Regenerated after save {}", saves)
+            }
+            Err(error) => format!("Couldn't reload after save {}: {}", saves, error),
+        };
+        // A smaller map would leave the edges of the old one behind
+        execute!(stdout, terminal::Clear(ClearType::All))?;
+        redraw = true;
+    }
+
+    Ok(())
+}
+
+// One side of the A/B comparison
+pub struct Side {
+    pub label: String,
+    pub config: Config,
+    pub seed: u64,
+}
+
+// Two maps side by side sharing one viewport, so panning and zooming move
+// both and the same stretch of each stays lined up. Arrow keys pan, +/-
+// zoom, r rerolls both seeds (keeping them equal if they started equal).
+pub fn compare(mut a: Side, mut b: Side) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    let _console = Console::enter()?;
+
+    let mut maps = (
+        generator::generate_seeded(&a.config, a.seed),
+        generator::generate_seeded(&b.config, b.seed),
+    );
+    let mut origin = (0usize, 0usize);
+    let mut zoom = Zoom::Tile;
+
+    loop {
+        let (columns, rows) = terminal::size()?;
+        let pane = (columns as usize).saturating_sub(3) / 2;
+        let rows = (rows as usize).saturating_sub(3);
+        let (cell_width, cell_height) = zoom.cell();
+        let width = maps.0.width().max(maps.1.width());
+        let height = maps.0.height().max(maps.1.height());
+        origin.0 = origin.0.min(width.saturating_sub(pane * cell_width));
+        origin.1 = origin.1.min(height.saturating_sub(rows * cell_height));
+
+        for (index, (side, dungeon)) in [(&a, &maps.0), (&b, &maps.1)].into_iter().enumerate() {
+            let left = (index * (pane + 3)) as u16;
+            let label = format!("{} (seed {})", side.label, side.seed);
+            execute!(
+                stdout,
+                cursor::MoveTo(left, 0),
+                Print(format!("{:<width$.width$}", label, width = pane))
+            )?;
+            let view = View {
+                origin,
+                columns: pane,
+                rows,
+                screen: (left, 1),
+                zoom,
+            };
+            dungeon.render_view(&side.config.theme, &view)?;
+        }
+        status(
+            &mut stdout,
+            rows + 2,
+            &format!("({}, {})  arrows pan  +/- zoom  r reroll  q quit", origin.0, origin.1),
+        )?;
+
+        let event = event::read()?;
+        // The old frame may linger past the edges of the new one
+        if let Event::Resize(..) = event {
+            execute!(stdout, terminal::Clear(ClearType::All))?;
+            continue;
+        }
+        let Some(key) = console::pressed(event) else {
+            continue;
+        };
+        if console::quits(&key) {
+            break;
+        }
+        match key.code {
+            KeyCode::Left => origin.0 = origin.0.saturating_sub(4 * cell_width),
+            KeyCode::Right => origin.0 += 4 * cell_width,
+            KeyCode::Up => origin.1 = origin.1.saturating_sub(2 * cell_height),
+            KeyCode::Down => origin.1 += 2 * cell_height,
+            KeyCode::Char('+') | KeyCode::Char('=') => zoom = zoom.zoom_in(),
+            KeyCode::Char('-') => zoom = zoom.zoom_out(),
+            KeyCode::Char('r') => {
+                let same = a.seed == b.seed;
+                a.seed = generator::random_seed();
+                b.seed = if same { a.seed } else { generator::random_seed() };
+                maps = (
+                    generator::generate_seeded(&a.config, a.seed),
+                    generator::generate_seeded(&b.config, b.seed),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+// How a pause ended
+enum Wake {
+    // Time ran out
+    Done,
+    // A key cut it short
+    Skipped,
+    Quit,
+    // The terminal changed size, so whatever is showing needs redrawing
+    Resized,
+}
+
+// Waits until `deadline`, returning early on any key or resize
+fn pause(deadline: Instant) -> io::Result<Wake> {
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() || !event::poll(left)? {
+            return Ok(Wake::Done);
+        }
+        let event = event::read()?;
+        if let Event::Resize(..) = event {
+            return Ok(Wake::Resized);
+        }
+        if let Some(key) = console::pressed(event) {
+            return Ok(if console::quits(&key) { Wake::Quit } else { Wake::Skipped });
+        }
+    }
+}
+
+// Draws the map under a caption and holds it on screen for a while
+fn slide(
+    stdout: &mut io::Stdout,
+    dungeon: &Dungeon,
+    config: &Config,
+    caption: &str,
+    millis: u64,
+    ambient: Ambient,
+) -> io::Result<bool> {
+    let deadline = Instant::now() + Duration::from_millis(millis);
+    let mut weather = Weather::new(ambient, &config.theme, dungeon, generator::seed_for(config));
+    loop {
+        execute!(stdout, terminal::Clear(ClearType::All))?;
+        status(stdout, 0, caption)?;
+        let view = View {
+            screen: (0, 2),
+            ..View::whole(dungeon)
+        };
+        dungeon.render_view(&config.theme, &view)?;
+        status(stdout, dungeon.height() + 3, "any key skips ahead, q quits")?;
+
+        let mut shown = Vec::new();
+        let wake = loop {
+            let frame = match weather {
+                Some(_) => (Instant::now() + FRAME).min(deadline),
+                None => deadline,
+            };
+            match (pause(frame)?, weather.as_mut()) {
+                (Wake::Done, Some(weather)) if Instant::now() < deadline => {
+                    weather.tick(dungeon);
+                    draw_weather(stdout, dungeon, &config.theme, weather, view.screen, &mut shown)?;
+                }
+                (wake, _) => break wake,
+            }
+        };
+        match wake {
+            Wake::Done | Wake::Skipped => return Ok(true),
+            Wake::Quit => return Ok(false),
+            Wake::Resized => {}
+        }
+    }
+}
+
+// Post-passes the demo layers onto the settled cave, with showcase settings
+#[derive(Clone, Copy)]
+enum Pass {
+    Roughness,
+    Erosion,
+    Pillars,
+    Terrain,
+    Doors,
+    Population,
+}
+
+impl Pass {
+    const ALL: [Pass; 6] = [Pass::Roughness, Pass::Erosion, Pass::Pillars, Pass::Terrain, Pass::Doors, Pass::Population];
+
+    fn caption(self) -> &'static str {
+        match self {
+            Pass::Roughness => "Roughness: alcoves and juts break up smooth walls",
+            Pass::Erosion => "Erosion: exposed wall corners crumble away",
+            Pass::Pillars => "Pillars: columns go up wherever a hall would span more than 5 tiles",
+            Pass::Terrain => "Terrain: pools of water, and rubble along the walls",
+            Pass::Doors => "Doors: chokepoints get open or closed doors",
+            Pass::Population => "Population: an entrance, an exit, monsters, items and decorations",
+        }
+    }
+
+    fn apply<R: Rng + ?Sized>(self, dungeon: &mut Dungeon, config: &Config, rng: &mut R) {
+        match self {
+            Pass::Roughness => dungeon.roughen(
+                &RoughnessOptions {
+                    amount: 0.3,
+                    ..RoughnessOptions::default()
+                },
+                rng,
+            ),
+            Pass::Erosion => {
+                dungeon.erode(
+                    &ErosionOptions {
+                        iterations: 1,
+                        ..ErosionOptions::default()
+                    },
+                    rng,
+                );
+            }
+            Pass::Pillars => {
+                dungeon.place_pillars(5);
+            }
+            Pass::Terrain => dungeon.scatter_terrain(
+                &TerrainOptions {
+                    water: 0.05,
+                    rubble: 0.08,
+                },
+                rng,
+            ),
+            Pass::Doors => {
+                dungeon.place_doors(6, rng);
+            }
+            Pass::Population => dungeon.populate(&config.spawns, rng),
+        }
+    }
+}
+
+// The cave settling step by step, then each post-pass added on top of the
+// same map, then every preset in turn
+fn tour(stdout: &mut io::Stdout, config: &Config, ambient: Ambient) -> io::Result<bool> {
+    let (mut dungeon, mut rng) = generator::start_seeded(config, generator::seed_for(config));
+    let caption = format!(
+        "Cellular automaton: random noise, {:.0}% walls",
+        config.wall_probability * 100.0
+    );
+    if !slide(stdout, &dungeon, config, &caption, 2500, ambient)? {
+        return Ok(false);
+    }
+    let mut scratch = Scratch::new();
+    for step in 1..=config.iterations {
+        if !generator::step_in(&mut dungeon, config, &mut scratch) {
+            break;
+        }
+        let caption = format!(
+            "Cellular automaton, step {}: crowded floor fills in, lonely walls crumble",
+            step
+        );
+        if !slide(stdout, &dungeon, config, &caption, 600, ambient)? {
+            return Ok(false);
+        }
+    }
+
+    for pass in Pass::ALL {
+        pass.apply(&mut dungeon, config, &mut rng);
+        if !slide(stdout, &dungeon, config, pass.caption(), 2500, ambient)? {
+            return Ok(false);
+        }
+    }
+
+    for preset in Preset::ALL {
+        let preset_config = Config {
+            width: config.width,
+            height: config.height,
+            ..preset.config()
+        };
+        let dungeon = generator::generate(&preset_config);
+        let caption = format!("Preset {}: {}", preset.name(), preset.description());
+        if !slide(stdout, &dungeon, &preset_config, &caption, 3000, ambient)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+// A self-running gallery of what the generator can do
+pub fn demo(config: Config, ambient: Ambient) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    let _console = Console::enter()?;
+
+    if tour(&mut stdout, &config, ambient)? {
+        execute!(stdout, terminal::Clear(ClearType::All))?;
+        status(&mut stdout, 0, "That's the tour. Start from any of these with --preset NAME; press any key to exit")?;
+        while console::pressed(event::read()?).is_none() {}
+    }
+    Ok(())
+}
+
+const SIGHT_RADIUS: usize = 8;
+
+fn draw_explored(
+    stdout: &mut io::Stdout,
+    dungeon: &Dungeon,
+    config: &Config,
+    exploration: &Exploration,
+    position: Point,
+    message: &str,
+) -> io::Result<()> {
+    dungeon.render_explored(&config.theme, exploration, position, (0, 0))?;
+    let explored = format!(
+        "Explored {:.0}%  arrows move  o auto-explore  q quit",
+        exploration.explored(dungeon) * 100.0
+    );
+    status(stdout, dungeon.height() + 1, &explored)?;
+    status(stdout, dungeon.height() + 2, message)?;
+    stdout.flush()
+}
+
+// The map under fog of war, walked from the entrance. Arrow keys step; o
+// walks on its own until nothing reachable is left unseen or a key stops it.
+pub fn explore(config: Config, bell: bool) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    let mut dungeon = generator::generate_seeded(&config, generator::seed_for(&config));
+    let (mut position, _) = pathfinding::entrance_and_exit(&dungeon).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "dungeon has no floor to explore")
+    })?;
+    let options = PathOptions::default();
+    let mut exploration = Exploration::for_dungeon(&dungeon);
+    let mut rooms = RegionWatch::new(&dungeon);
+    let mut heard = Vec::new();
+    exploration.look(&dungeon, position, SIGHT_RADIUS);
+    rooms.update(&exploration, &mut heard);
+    let mut message = String::new();
+
+    let _console = Console::enter()?;
+    loop {
+        ring(&mut stdout, &mut heard, bell)?;
+        draw_explored(&mut stdout, &dungeon, &config, &exploration, position, &message)?;
+        message.clear();
+
+        let event = event::read()?;
+        if let Event::Resize(..) = event {
+            execute!(stdout, terminal::Clear(ClearType::All))?;
+            continue;
+        }
+        let Some(key) = console::pressed(event) else {
+            continue;
+        };
+        if console::quits(&key) {
+            break;
+        }
+        let (dx, dy) = match key.code {
+            KeyCode::Up => (0, -1),
+            KeyCode::Down => (0, 1),
+            KeyCode::Left => (-1, 0),
+            KeyCode::Right => (1, 0),
+            KeyCode::Char('o') => {
+                loop {
+                    let Some(next) = autoexplore::next_step(&dungeon, &exploration, position, &options) else {
+                        message = "Nothing left to explore".to_string();
+                        break;
+                    };
+                    events::step_onto(&mut dungeon, next, &options, &mut heard);
+                    position = next;
+                    exploration.look(&dungeon, position, SIGHT_RADIUS);
+                    rooms.update(&exploration, &mut heard);
+                    ring(&mut stdout, &mut heard, bell)?;
+                    draw_explored(&mut stdout, &dungeon, &config, &exploration, position, "")?;
+                    match pause(Instant::now() + Duration::from_millis(15))? {
+                        Wake::Done => {}
+                        Wake::Skipped => break,
+                        Wake::Quit => return Ok(()),
+                        Wake::Resized => execute!(stdout, terminal::Clear(ClearType::All))?,
+                    }
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        let (x, y) = (position.0 as i32 + dx, position.1 as i32 + dy);
+        if x < 0 || y < 0 || x as usize >= dungeon.width() || y as usize >= dungeon.height() {
+            continue;
+        }
+        let next = (x as usize, y as usize);
+        if events::step_onto(&mut dungeon, next, &options, &mut heard) {
+            position = next;
+            exploration.look(&dungeon, position, SIGHT_RADIUS);
+            rooms.update(&exploration, &mut heard);
+        }
+    }
+    Ok(())
+}
+
+// The terminal bell, with --bell, for each door pushed open and each room
+// first seen since the last call
+fn ring(stdout: &mut io::Stdout, heard: &mut Vec<events::Event>, bell: bool) -> io::Result<()> {
+    let rung = heard
+        .drain(..)
+        .filter(|event| matches!(event, events::Event::DoorOpened(_) | events::Event::RegionRevealed { .. }))
+        .count();
+    if bell {
+        for _ in 0..rung {
+            execute!(stdout, Print('\x07'))?;
+        }
+    }
+    Ok(())
+}
+
+// One glyph drawn over the map
+fn mark(stdout: &mut io::Stdout, (x, y): Point, color: Color, glyph: char) -> io::Result<()> {
+    execute!(stdout, cursor::MoveTo(x as u16, y as u16))?;
+    if Capabilities::detect().color {
+        execute!(stdout, SetForegroundColor(color), Print(glyph), ResetColor)
+    } else {
+        execute!(stdout, Print(glyph))
+    }
+}
+
+// Walks the solver's route across the map a step at a time; any key jumps to
+// the end, where it waits for one more to exit
+pub fn solve(dungeon: &Dungeon, config: &Config, solution: Option<&Solution>) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    let _console = Console::enter()?;
+    let route = solution.map_or(&[][..], |solution| &solution.route[..]);
+
+    let mut shown = 0;
+    let mut skipped = false;
+    loop {
+        dungeon.render_themed(&config.theme)?;
+        for &point in &route[..shown] {
+            mark(&mut stdout, point, Color::Yellow, '*')?;
+        }
+        if shown > 0 {
+            mark(&mut stdout, route[shown - 1], Color::Yellow, '@')?;
+        }
+        let text = match solution {
+            None => "No route from the entrance to the exit  any key exits".to_string(),
+            Some(solution) if shown == route.len() => {
+                format!("Solved in {} steps  any key exits", solution.steps())
+            }
+            Some(solution) => format!("Step {}/{}  any key skips ahead", shown.saturating_sub(1), solution.steps()),
+        };
+        status(&mut stdout, dungeon.height() + 1, &text)?;
+        stdout.flush()?;
+
+        if shown == route.len() {
+            let event = event::read()?;
+            if let Event::Resize(..) = event {
+                execute!(stdout, terminal::Clear(ClearType::All))?;
+                continue;
+            }
+            if console::pressed(event).is_some() {
+                return Ok(());
+            }
+            continue;
+        }
+        if skipped {
+            shown = route.len();
+            continue;
+        }
+        match pause(Instant::now() + Duration::from_millis(30))? {
+            Wake::Done => shown += 1,
+            Wake::Skipped => skipped = true,
+            Wake::Quit => return Ok(()),
+            Wake::Resized => execute!(stdout, terminal::Clear(ClearType::All))?,
+        }
+    }
+}
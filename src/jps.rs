@@ -0,0 +1,199 @@
+// Jump point search for 4-connected grids where every walkable tile costs the
+// same. Among equally short paths it only considers those that make vertical
+// moves as early as possible, so horizontal runs only turn where an obstacle
+// forces them to and whole corridors are crossed without touching the heap.
+use crate::dungeon::Point;
+use crate::grid::GridMap;
+use crate::pathfinding::PathOptions;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+struct Search<'a, G: ?Sized> {
+    dungeon: &'a G,
+    options: &'a PathOptions,
+    goal: Point,
+}
+
+impl<G: GridMap + ?Sized> Search<'_, G> {
+    fn open(&self, x: i32, y: i32) -> bool {
+        x >= 0
+            && y >= 0
+            && x < self.dungeon.width() as i32
+            && y < self.dungeon.height() as i32
+            && self.options.step_cost(self.dungeon, (x as usize, y as usize)).is_some()
+    }
+
+    fn horizontal_forced(&self, x: i32, y: i32, dx: i32) -> bool {
+        (self.open(x, y - 1) && !self.open(x - dx, y - 1))
+            || (self.open(x, y + 1) && !self.open(x - dx, y + 1))
+    }
+
+    fn jump_horizontal(&self, mut x: i32, y: i32, dx: i32) -> Option<Point> {
+        loop {
+            x += dx;
+            if !self.open(x, y) {
+                return None;
+            }
+            if (x as usize, y as usize) == self.goal || self.horizontal_forced(x, y, dx) {
+                return Some((x as usize, y as usize));
+            }
+        }
+    }
+
+    // Vertical runs stop wherever a horizontal scan from them finds something
+    fn jump_vertical(&self, x: i32, mut y: i32, dy: i32) -> Option<Point> {
+        loop {
+            y += dy;
+            if !self.open(x, y) {
+                return None;
+            }
+            if (x as usize, y as usize) == self.goal
+                || self.jump_horizontal(x, y, 1).is_some()
+                || self.jump_horizontal(x, y, -1).is_some()
+            {
+                return Some((x as usize, y as usize));
+            }
+        }
+    }
+
+    // Directions worth exploring from `point` given how we arrived there
+    fn directions(&self, point: Point, parent: Option<Point>) -> Vec<(i32, i32)> {
+        let Some(parent) = parent else {
+            return vec![(1, 0), (-1, 0), (0, 1), (0, -1)];
+        };
+        let (x, y) = (point.0 as i32, point.1 as i32);
+        let dx = (x - parent.0 as i32).signum();
+        let dy = (y - parent.1 as i32).signum();
+
+        if dy != 0 {
+            vec![(0, dy), (1, 0), (-1, 0)]
+        } else {
+            let mut directions = vec![(dx, 0)];
+            for side in [-1, 1] {
+                if self.open(x, y + side) && !self.open(x - dx, y + side) {
+                    directions.push((0, side));
+                }
+            }
+            directions
+        }
+    }
+}
+
+pub(crate) fn find_path<G: GridMap + ?Sized>(
+    dungeon: &G,
+    start: Point,
+    goal: Point,
+    options: &PathOptions,
+    step: usize,
+) -> Option<Vec<Point>> {
+    let search = Search { dungeon, options, goal };
+    let heuristic = |(x, y): Point| (x.abs_diff(goal.0) + y.abs_diff(goal.1)) * step;
+
+    let mut cost = vec![vec![usize::MAX; dungeon.width()]; dungeon.height()];
+    let mut came_from: Vec<Vec<Option<Point>>> = vec![vec![None; dungeon.width()]; dungeon.height()];
+    let mut heap = BinaryHeap::new();
+
+    cost[start.1][start.0] = 0;
+    heap.push(Reverse((heuristic(start), 0, start)));
+
+    while let Some(Reverse((_, current_cost, point))) = heap.pop() {
+        if point == goal {
+            return Some(unpack(&came_from, start, goal));
+        }
+        if current_cost > cost[point.1][point.0] {
+            continue;
+        }
+
+        let parent = (point != start).then(|| came_from[point.1][point.0].unwrap());
+        for (dx, dy) in search.directions(point, parent) {
+            let (x, y) = (point.0 as i32, point.1 as i32);
+            let jump = if dx != 0 {
+                search.jump_horizontal(x, y, dx)
+            } else {
+                search.jump_vertical(x, y, dy)
+            };
+            let Some(next) = jump else {
+                continue;
+            };
+            let distance = next.0.abs_diff(point.0) + next.1.abs_diff(point.1);
+            let next_cost = current_cost + distance * step;
+            if next_cost < cost[next.1][next.0] {
+                cost[next.1][next.0] = next_cost;
+                came_from[next.1][next.0] = Some(point);
+                heap.push(Reverse((next_cost + heuristic(next), next_cost, next)));
+            }
+        }
+    }
+
+    None
+}
+
+// Jump points are always axis-aligned with their parent, so filling in the
+// straight runs between them recovers the full tile path
+fn unpack(came_from: &[Vec<Option<Point>>], start: Point, goal: Point) -> Vec<Point> {
+    let mut path = vec![goal];
+    let mut current = goal;
+    while current != start {
+        let parent = came_from[current.1][current.0].unwrap();
+        let (mut x, mut y) = (current.0 as i32, current.1 as i32);
+        let dx = (parent.0 as i32 - x).signum();
+        let dy = (parent.1 as i32 - y).signum();
+        while (x as usize, y as usize) != parent {
+            x += dx;
+            y += dy;
+            path.push((x as usize, y as usize));
+        }
+        current = parent;
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dungeon::Point;
+    use crate::generator::{generate_seeded, rng_for};
+    use crate::pathfinding::{find_path_with, regions, MovementCosts, PathAlgorithm, PathOptions};
+    use crate::{Config, Dungeon};
+    use rand::seq::SliceRandom;
+
+    fn cost(dungeon: &Dungeon, path: &[Point], options: &PathOptions) -> usize {
+        for pair in path.windows(2) {
+            assert_eq!(pair[0].0.abs_diff(pair[1].0) + pair[0].1.abs_diff(pair[1].1), 1, "a jump in {:?}", path);
+        }
+        path[1..].iter().map(|&point| options.step_cost(dungeon, point).unwrap()).sum()
+    }
+
+    #[test]
+    fn jump_points_cost_the_same_as_a_star_on_uniform_maps() {
+        let a_star = PathOptions {
+            costs: MovementCosts {
+                floor: 2,
+                water: 2,
+                rubble: 2,
+            },
+            closed_door_cost: Some(0),
+            ..PathOptions::default()
+        };
+        let jump_point = PathOptions {
+            algorithm: PathAlgorithm::JumpPoint,
+            ..a_star
+        };
+        for seed in 0..8 {
+            let dungeon = generate_seeded(&Config::default(), seed);
+            assert_eq!(jump_point.uniform_cost(&dungeon), Some(2));
+            let mut rng = rng_for(seed);
+            for region in regions(&dungeon).iter().filter(|region| region.len() > 1) {
+                for _ in 0..8 {
+                    let (&start, &goal) = (region.choose(&mut rng).unwrap(), region.choose(&mut rng).unwrap());
+                    let route = |options: &PathOptions| {
+                        let path = find_path_with(&dungeon, start, goal, options)?;
+                        assert_eq!((path[0], path[path.len() - 1]), (start, goal));
+                        Some(cost(&dungeon, &path, options))
+                    };
+                    assert_eq!(route(&jump_point), route(&a_star), "seed {} from {:?} to {:?}", seed, start, goal);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,234 @@
+// What each key does in the interactive explorer, and the `[keys]` config
+// section that rebinds them, e.g.
+//
+//     [keys]
+//     new_seed = "n"
+//     zoom_in = ["+", "=", "pageup"]
+//     favorite = []
+//
+// A key is a single character, case counting, or one of `NAMED`. Setting an
+// action replaces all of its keys, and an empty list leaves it unbound; no
+// key may do two things. Ctrl+C quits whatever is bound.
+use crate::config::{invalid, unknown_key};
+use crate::toml::{self, Table, Value};
+use std::io;
+
+// Keys that aren't characters, by the names configs use
+pub const NAMED: [&str; 15] = [
+    "up", "down", "left", "right", "tab", "backtab", "enter", "esc", "space", "backspace", "delete", "home", "end",
+    "pageup", "pagedown",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    PickPrevious,
+    PickNext,
+    Decrease,
+    Increase,
+    NewSeed,
+    NextRoom,
+    PreviousRoom,
+    RerollRoom,
+    Favorite,
+    CopyMap,
+    CopySeed,
+    ExportImage,
+    OpenImage,
+    ZoomIn,
+    ZoomOut,
+    Help,
+    Quit,
+}
+
+impl Action {
+    pub const ALL: [Action; 17] = [
+        Action::PickPrevious,
+        Action::PickNext,
+        Action::Decrease,
+        Action::Increase,
+        Action::NewSeed,
+        Action::NextRoom,
+        Action::PreviousRoom,
+        Action::RerollRoom,
+        Action::Favorite,
+        Action::CopyMap,
+        Action::CopySeed,
+        Action::ExportImage,
+        Action::OpenImage,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::Help,
+        Action::Quit,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::PickPrevious => "pick_previous",
+            Action::PickNext => "pick_next",
+            Action::Decrease => "decrease",
+            Action::Increase => "increase",
+            Action::NewSeed => "new_seed",
+            Action::NextRoom => "next_room",
+            Action::PreviousRoom => "previous_room",
+            Action::RerollRoom => "reroll_room",
+            Action::Favorite => "favorite",
+            Action::CopyMap => "copy_map",
+            Action::CopySeed => "copy_seed",
+            Action::ExportImage => "export_image",
+            Action::OpenImage => "open_image",
+            Action::ZoomIn => "zoom_in",
+            Action::ZoomOut => "zoom_out",
+            Action::Help => "help",
+            Action::Quit => "quit",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    // A line for the help overlay
+    pub fn describe(self) -> &'static str {
+        match self {
+            Action::PickPrevious => "Pick the parameter above",
+            Action::PickNext => "Pick the parameter below",
+            Action::Decrease => "Lower the picked parameter",
+            Action::Increase => "Raise the picked parameter",
+            Action::NewSeed => "Generate from a new seed",
+            Action::NextRoom => "Pick the next room",
+            Action::PreviousRoom => "Pick the previous room",
+            Action::RerollRoom => "Reroll the picked room",
+            Action::Favorite => "Save the map to favorites",
+            Action::CopyMap => "Copy the map",
+            Action::CopySeed => "Copy the seed and config",
+            Action::ExportImage => "Export an image",
+            Action::OpenImage => "Open the last image exported",
+            Action::ZoomIn => "Zoom in",
+            Action::ZoomOut => "Zoom out",
+            Action::Help => "Show or hide these keys",
+            Action::Quit => "Quit",
+        }
+    }
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::PickPrevious => &["up"],
+            Action::PickNext => &["down"],
+            Action::Decrease => &["left"],
+            Action::Increase => &["right"],
+            Action::NewSeed => &["r"],
+            Action::NextRoom => &["tab"],
+            Action::PreviousRoom => &["backtab"],
+            Action::RerollRoom => &["x"],
+            Action::Favorite => &["f"],
+            Action::CopyMap => &["c"],
+            Action::CopySeed => &["s"],
+            Action::ExportImage => &["e"],
+            Action::OpenImage => &["o"],
+            Action::ZoomIn => &["+", "="],
+            Action::ZoomOut => &["-"],
+            Action::Help => &["?"],
+            Action::Quit => &["q", "esc"],
+        }
+    }
+}
+
+// A key name as bindings store it, or None for one this doesn't know
+pub fn parse_key(name: &str) -> Option<String> {
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(' '), None) => Some("space".to_string()),
+        (Some(c), None) if !c.is_control() => Some(name.to_string()),
+        _ => NAMED.iter().find(|named| named.eq_ignore_ascii_case(name)).map(|named| named.to_string()),
+    }
+}
+
+// "Up", "Shift+Tab", "r"
+pub fn key_label(key: &str) -> String {
+    match key {
+        "backtab" => "Shift+Tab".to_string(),
+        "pageup" => "PgUp".to_string(),
+        "pagedown" => "PgDn".to_string(),
+        _ if key.chars().count() == 1 => key.to_string(),
+        _ => key[..1].to_uppercase() + &key[1..],
+    }
+}
+
+// Keys for every action, in `Action::ALL` order
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyBindings {
+    keys: Vec<Vec<String>>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            keys: Action::ALL
+                .iter()
+                .map(|action| action.default_keys().iter().map(|key| key.to_string()).collect())
+                .collect(),
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn keys(&self, action: Action) -> &[String] {
+        &self.keys[action as usize]
+    }
+
+    // The action a key is bound to, by the name `parse_key` gives it
+    pub fn action(&self, key: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|&action| self.keys(action).iter().any(|bound| bound == key))
+    }
+
+    // Every key for the action, as the help overlay shows them
+    pub fn label(&self, action: Action) -> String {
+        match self.keys(action) {
+            [] => "none".to_string(),
+            keys => keys.iter().map(|key| key_label(key)).collect::<Vec<String>>().join(" "),
+        }
+    }
+
+    // Just the first, for status lines
+    pub fn short_label(&self, action: Action) -> String {
+        self.keys(action).first().map_or("none".to_string(), |key| key_label(key))
+    }
+
+    pub(crate) fn apply(&mut self, table: &Table) -> io::Result<()> {
+        for (key, value) in table {
+            let action = Action::parse(key).ok_or_else(|| unknown_key("keys.", key))?;
+            let names: Vec<&Value> = match value {
+                Value::Array(items) => items.iter().collect(),
+                value => vec![value],
+            };
+            self.keys[action as usize] = names
+                .into_iter()
+                .map(|name| {
+                    name.as_str()
+                        .and_then(parse_key)
+                        .ok_or_else(|| invalid(key, "must be a key like \"r\", \"tab\" or \"up\", or a list of them"))
+                })
+                .collect::<io::Result<_>>()?;
+        }
+        for action in Action::ALL {
+            for key in self.keys(action) {
+                if let Some(other) = self.action(key).filter(|&other| other != action) {
+                    return Err(invalid(key, &format!("is bound to both {} and {}", other.name(), action.name())));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn to_toml(&self) -> String {
+        let mut out = "\n[keys]\n".to_string();
+        for action in Action::ALL {
+            let keys: Vec<String> = self.keys(action).iter().map(|key| toml::quote(key)).collect();
+            match &keys[..] {
+                [key] => out.push_str(&format!("{} = {}\n", action.name(), key)),
+                keys => out.push_str(&format!("{} = [{}]\n", action.name(), keys.join(", "))),
+            }
+        }
+        out
+    }
+}
@@ -0,0 +1,243 @@
+// Names for the regions of a map and where to write them. A region is a
+// room or a stretch of cave between narrow necks: the ground at least two
+// steps from any wall or door splits into cores, and the rest of it, the
+// corridors and the necks, goes to the nearest core. Each region gets a
+// name drawn from its own seeded stream, worded after its size and what
+// covers its floor, and an anchor at its centroid with a font size to match
+// its area. Sizes are in tiles, so a renderer multiplies them by its own
+// scale; the SVG export draws them as they are.
+use crate::dungeon::{Dungeon, Point, Tile};
+use crate::generator::SeededRng;
+use crate::json;
+use rand::seq::SliceRandom;
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegionLabel {
+    pub region: usize,
+    pub name: String,
+    // Middle of the label, in tiles from the map's top-left corner; always
+    // over one of the room's own tiles
+    pub x: f64,
+    pub y: f64,
+    pub tiles: usize,
+    // Height of the lettering in tiles
+    pub font_size: f64,
+}
+
+// Steps from the nearest wall or door a tile needs to be part of a core
+const CORE_DEPTH: usize = 2;
+
+// Cores with fewer tiles are bumps in a corridor rather than rooms
+const MIN_CORE: usize = 4;
+
+// Regions smaller than this go unnamed
+const MIN_LABELLED_TILES: usize = 6;
+
+const MIN_FONT_SIZE: f64 = 0.8;
+const MAX_FONT_SIZE: f64 = 3.0;
+
+// Width of a letter for its height, near enough for most faces
+const LETTER_ASPECT: f64 = 0.6;
+
+const ADJECTIVES: [&str; 10] =
+    ["Silent", "Crooked", "Dripping", "Forgotten", "Hollow", "Echoing", "Narrow", "Sunken", "Pale", "Cold"];
+const FLOODED: [&str; 3] = ["Flooded", "Drowned", "Weeping"];
+const COLLAPSED: [&str; 3] = ["Collapsed", "Broken", "Fallen"];
+
+// Words for a room by its size, each up to a count of tiles
+const NOUNS: [(usize, [&str; 2]); 4] = [
+    (24, ["Alcove", "Nook"]),
+    (80, ["Chamber", "Room"]),
+    (250, ["Hall", "Gallery"]),
+    (usize::MAX, ["Cavern", "Deeps"]),
+];
+
+// Share of a region's tiles that has to be water or rubble for its name to
+// say so
+const TERRAIN_SHARE: f64 = 0.25;
+
+impl Dungeon {
+    // Labels by region number; the same map and seed always name alike
+    pub fn region_labels(&self, seed: u64) -> Vec<RegionLabel> {
+        let (labels, rooms) = self.label_regions();
+        let mut tiles = vec![Vec::new(); rooms];
+        for (y, row) in labels.iter().enumerate() {
+            for (x, label) in row.iter().enumerate() {
+                if let Some(room) = *label {
+                    tiles[room].push((x, y));
+                }
+            }
+        }
+
+        let mut named: Vec<RegionLabel> = Vec::new();
+        for (region, room) in tiles.iter().enumerate() {
+            if room.len() < MIN_LABELLED_TILES {
+                continue;
+            }
+            // Its own stream, so a room keeps its name whatever the rooms
+            // before it are called, clashes aside
+            let mut rng = SeededRng::new(seed).room(region).for_topic("region names");
+            let share =
+                |tile: Tile| room.iter().filter(|&&(x, y)| self.tiles[y][x] == tile).count() as f64 / room.len() as f64;
+            let adjectives: &[&str] = if share(Tile::Water) >= TERRAIN_SHARE {
+                &FLOODED
+            } else if share(Tile::Rubble) >= TERRAIN_SHARE {
+                &COLLAPSED
+            } else {
+                &ADJECTIVES
+            };
+            let (_, nouns) = NOUNS.iter().find(|(most, _)| room.len() <= *most).unwrap();
+            // A few tries at a name no other room has
+            let mut name = String::new();
+            for _ in 0..4 {
+                name = format!("The {} {}", adjectives.choose(&mut rng).unwrap(), nouns.choose(&mut rng).unwrap());
+                if named.iter().all(|label| label.name != name) {
+                    break;
+                }
+            }
+
+            let count = room.len() as f64;
+            let cx = room.iter().map(|&(x, _)| x as f64 + 0.5).sum::<f64>() / count;
+            let cy = room.iter().map(|&(_, y)| y as f64 + 0.5).sum::<f64>() / count;
+            // A crescent's centroid can fall outside it, so fall back to
+            // its nearest tile
+            let (x, y) = if labels[cy as usize][cx as usize] == Some(region) {
+                (cx, cy)
+            } else {
+                let nearest = room.iter().min_by(|a, b| {
+                    let off = |&(x, y): &(usize, usize)| (x as f64 + 0.5 - cx).powi(2) + (y as f64 + 0.5 - cy).powi(2);
+                    off(a).total_cmp(&off(b))
+                });
+                nearest.map(|&(x, y)| (x as f64 + 0.5, y as f64 + 0.5)).unwrap()
+            };
+
+            // Grows with the room but stays within its width
+            let left = room.iter().map(|&(x, _)| x).min().unwrap_or(0);
+            let right = room.iter().map(|&(x, _)| x).max().unwrap_or(0);
+            let fits = (right - left + 1) as f64 / (LETTER_ASPECT * name.chars().count() as f64);
+            let font_size = (count.sqrt() / 4.0).min(fits).clamp(MIN_FONT_SIZE, MAX_FONT_SIZE);
+            named.push(RegionLabel {
+                region,
+                name,
+                x,
+                y,
+                tiles: room.len(),
+                font_size,
+            });
+        }
+        named
+    }
+
+    // Each tile's region by row, with the number of regions
+    pub fn label_regions(&self) -> (Vec<Vec<Option<usize>>>, usize) {
+        let (width, height) = (self.width, self.height);
+        let open = |(x, y): Point| self.tiles[y][x].is_walkable() && self.door(x, y).is_none();
+        let steps = |(x, y): Point| {
+            [
+                (x > 0).then(|| (x - 1, y)),
+                (y > 0).then(|| (x, y - 1)),
+                (x + 1 < width).then_some((x + 1, y)),
+                (y + 1 < height).then_some((x, y + 1)),
+            ]
+            .into_iter()
+            .flatten()
+        };
+
+        // Steps to the nearest wall or door, the map's edge counting as wall
+        let mut depth = vec![usize::MAX; width * height];
+        let mut queue = VecDeque::new();
+        for y in 0..height {
+            for x in 0..width {
+                let edge = x == 0 || y == 0 || x + 1 == width || y + 1 == height;
+                if !open((x, y)) {
+                    depth[y * width + x] = 0;
+                    queue.push_back((x, y));
+                } else if edge {
+                    depth[y * width + x] = 1;
+                    queue.push_back((x, y));
+                }
+            }
+        }
+        while let Some(point) = queue.pop_front() {
+            let next = depth[point.1 * width + point.0] + 1;
+            for (nx, ny) in steps(point) {
+                if depth[ny * width + nx] > next {
+                    depth[ny * width + nx] = next;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+
+        let mut labels: Vec<Option<usize>> = vec![None; width * height];
+        let mut regions = 0;
+        let mut grow = VecDeque::new();
+        for start in 0..width * height {
+            if labels[start].is_some() || depth[start] < CORE_DEPTH {
+                continue;
+            }
+            let mut core = vec![(start % width, start / width)];
+            labels[start] = Some(regions);
+            let mut index = 0;
+            while let Some(&point) = core.get(index) {
+                index += 1;
+                for (nx, ny) in steps(point) {
+                    if labels[ny * width + nx].is_none() && depth[ny * width + nx] >= CORE_DEPTH {
+                        labels[ny * width + nx] = Some(regions);
+                        core.push((nx, ny));
+                    }
+                }
+            }
+            if core.len() < MIN_CORE {
+                // Left as a marker so the scan doesn't come back to it, and
+                // cleared before growing
+                for &(x, y) in &core {
+                    labels[y * width + x] = Some(usize::MAX);
+                }
+                continue;
+            }
+            grow.extend(core);
+            regions += 1;
+        }
+        for label in labels.iter_mut() {
+            if *label == Some(usize::MAX) {
+                *label = None;
+            }
+        }
+        while let Some(point) = grow.pop_front() {
+            let region = labels[point.1 * width + point.0];
+            for (nx, ny) in steps(point) {
+                if labels[ny * width + nx].is_none() && open((nx, ny)) {
+                    labels[ny * width + nx] = region;
+                    grow.push_back((nx, ny));
+                }
+            }
+        }
+        (labels.chunks(width).map(|row| row.to_vec()).collect(), regions)
+    }
+}
+
+pub fn labels_json(labels: &[RegionLabel]) -> String {
+    let entries: Vec<String> = labels
+        .iter()
+        .map(|label| {
+            format!(
+                "{{\"region\":{},\"name\":{},\"x\":{:.2},\"y\":{:.2},\"tiles\":{},\"font_size\":{:.2}}}",
+                label.region,
+                json::quote(&label.name),
+                label.x,
+                label.y,
+                label.tiles,
+                label.font_size
+            )
+        })
+        .collect();
+    format!("{{\"labels\":[{}]}}\n", entries.join(","))
+}
+
+pub fn write_labels(labels: &[RegionLabel], path: &Path) -> io::Result<()> {
+    fs::write(path, labels_json(labels))
+}
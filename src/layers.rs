@@ -0,0 +1,327 @@
+use crate::bundle::{self, Bundle};
+use crate::door::{Door, DoorMaterial, DoorState, Facing};
+use crate::dungeon::{Dungeon, Point, Tile};
+use crate::rows::render_rows;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Sparse layer holding at most one value per tile
+#[derive(Clone, Debug)]
+pub struct Layer<T> {
+    cells: HashMap<Point, T>,
+}
+
+impl<T> Default for Layer<T> {
+    fn default() -> Self {
+        Self { cells: HashMap::new() }
+    }
+}
+
+impl<T> Layer<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        self.cells.get(&(x, y))
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        self.cells.get_mut(&(x, y))
+    }
+
+    pub fn entry(&mut self, x: usize, y: usize) -> &mut T
+    where
+        T: Default,
+    {
+        self.cells.entry((x, y)).or_default()
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: T) -> Option<T> {
+        self.cells.insert((x, y), value)
+    }
+
+    pub fn remove(&mut self, x: usize, y: usize) -> Option<T> {
+        self.cells.remove(&(x, y))
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        self.cells.contains_key(&(x, y))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.cells.iter().map(|(&point, value)| (point, value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(Point, &mut T) -> bool) {
+        self.cells.retain(|&point, value| keep(point, value));
+    }
+}
+
+// Things built into the terrain: they sit on floor and may change how it behaves
+#[derive(Clone, Debug, PartialEq)]
+pub enum Feature {
+    Door(Door),
+    Trap(String),
+    Decoration(String),
+}
+
+impl Feature {
+    pub fn blocks_sight(&self) -> bool {
+        match self {
+            Feature::Door(door) => door.state != DoorState::Open,
+            Feature::Trap(_) | Feature::Decoration(_) => false,
+        }
+    }
+
+    pub fn glyph(&self) -> char {
+        match self {
+            Feature::Door(door) => match door.state {
+                DoorState::Open => '\'',
+                DoorState::Closed => '+',
+                DoorState::Locked => '=',
+                DoorState::Secret => 'S',
+            },
+            Feature::Trap(_) => '^',
+            Feature::Decoration(_) => '&',
+        }
+    }
+}
+
+// Spawn markers for things that live in the dungeon rather than being part of it
+#[derive(Clone, Debug, PartialEq)]
+pub enum Entity {
+    Entrance,
+    Exit,
+    Monster(String),
+    Item(String),
+}
+
+impl Entity {
+    pub fn glyph(&self) -> char {
+        match self {
+            Entity::Entrance => '<',
+            Entity::Exit => '>',
+            Entity::Monster(_) => 'M',
+            Entity::Item(_) => '$',
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LayerKind {
+    Terrain,
+    Features,
+    Entities,
+}
+
+impl LayerKind {
+    pub const ALL: [LayerKind; 3] = [LayerKind::Terrain, LayerKind::Features, LayerKind::Entities];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LayerKind::Terrain => "terrain",
+            LayerKind::Features => "features",
+            LayerKind::Entities => "entities",
+        }
+    }
+}
+
+impl Dungeon {
+    pub fn features(&self) -> &Layer<Feature> {
+        &self.features
+    }
+
+    pub fn features_mut(&mut self) -> &mut Layer<Feature> {
+        &mut self.features
+    }
+
+    pub fn entities(&self) -> &Layer<Entity> {
+        &self.entities
+    }
+
+    pub fn entities_mut(&mut self) -> &mut Layer<Entity> {
+        &mut self.entities
+    }
+
+    pub fn feature(&self, x: usize, y: usize) -> Option<&Feature> {
+        self.features.get(x, y)
+    }
+
+    pub fn entity(&self, x: usize, y: usize) -> Option<&Entity> {
+        self.entities.get(x, y)
+    }
+
+    // One character per tile; empty cells in the sparse layers are blank
+    pub fn layer_to_ascii(&self, kind: LayerKind) -> String {
+        self.rows_to_ascii(|x, y| match kind {
+            LayerKind::Terrain => self.terrain_glyph(x, y),
+            LayerKind::Features => self.features.get(x, y).map_or(' ', Feature::glyph),
+            LayerKind::Entities => self.entities.get(x, y).map_or(' ', Entity::glyph),
+        })
+    }
+
+    fn terrain_glyph(&self, x: usize, y: usize) -> char {
+        self.tiles[y][x].glyph()
+    }
+
+    // One line per map row, rendered a row at a time
+    pub(crate) fn rows_to_ascii(&self, glyph: impl Fn(usize, usize) -> char + Sync) -> String {
+        render_rows(self.height, self.width, |y| {
+            let mut line: String = (0..self.width).map(|x| glyph(x, y)).collect();
+            line.push('\n');
+            line
+        })
+        .concat()
+    }
+
+    // Every layer flattened into one character per tile, entities drawn over
+    // features over terrain. `from_ascii` reads it back.
+    pub fn to_ascii(&self) -> String {
+        self.rows_to_ascii(|x, y| {
+            self.entities
+                .get(x, y)
+                .map(Entity::glyph)
+                .or_else(|| self.features.get(x, y).map(Feature::glyph))
+                .unwrap_or_else(|| self.terrain_glyph(x, y))
+        })
+    }
+
+    // Parses a map drawn with the glyphs `to_ascii` and `layer_to_ascii`
+    // use. Names don't survive the trip, so decorations, traps, monsters and
+    // items come back under generic ones. Short lines are padded with wall.
+    pub fn from_ascii(text: &str) -> io::Result<Self> {
+        let lines: Vec<&str> = text.lines().collect();
+        let width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+        if width == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "map is empty"));
+        }
+        let mut dungeon = Dungeon::new(width, lines.len());
+        for (y, line) in lines.iter().enumerate() {
+            for (x, glyph) in line.chars().enumerate() {
+                dungeon.overlay_glyph(x, y, glyph).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("unknown map glyph '{}' at line {}, column {}", glyph, y + 1, x + 1),
+                    )
+                })?;
+            }
+        }
+        Ok(dungeon)
+    }
+
+    // Reads back a directory written by `write_layers`; the feature and
+    // entity layers are optional
+    pub fn read_layers(dir: &Path) -> io::Result<Self> {
+        let mut dungeon = Self::from_ascii(&fs::read_to_string(dir.join("terrain.txt"))?)?;
+        for kind in [LayerKind::Features, LayerKind::Entities] {
+            let text = match fs::read_to_string(dir.join(format!("{}.txt", kind.name()))) {
+                Ok(text) => text,
+                Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+                Err(error) => return Err(error),
+            };
+            for (y, line) in text.lines().enumerate().take(dungeon.height) {
+                for (x, glyph) in line.chars().enumerate().take(dungeon.width) {
+                    if glyph != ' ' && dungeon.overlay_glyph(x, y, glyph).is_none() {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("unknown {} glyph '{}' at line {}, column {}", kind.name(), glyph, y + 1, x + 1),
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(dungeon)
+    }
+
+    // A map file as written by `to_ascii`, a `write_layers` directory or a
+    // `.dungeon` bundle
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let in_file = |error: io::Error| io::Error::new(error.kind(), format!("{}: {}", path.display(), error));
+        if bundle::is_bundle(path) {
+            Bundle::load(path).map(|bundle| bundle.dungeon)
+        } else if path.is_dir() {
+            Self::read_layers(path).map_err(in_file)
+        } else {
+            Self::from_ascii(&fs::read_to_string(path)?).map_err(in_file)
+        }
+    }
+
+    // Whether `from_ascii` knows what to draw for `glyph`
+    pub(crate) fn reads_glyph(glyph: char) -> bool {
+        Dungeon::new(1, 1).overlay_glyph(0, 0, glyph).is_some()
+    }
+
+    fn overlay_glyph(&mut self, x: usize, y: usize, glyph: char) -> Option<()> {
+        if let Some(tile) = Tile::from_glyph(glyph) {
+            self.tiles[y][x] = tile;
+            return Some(());
+        }
+
+        // A door across a north-south passage has wall to its sides
+        let vertical = x > 0 && !self.tiles[y][x - 1].is_walkable();
+        let door = |state| {
+            let facing = if vertical { Facing::North } else { Facing::East };
+            Feature::Door(Door::new(state, DoorMaterial::Wood, facing))
+        };
+        let feature = match glyph {
+            '\'' => Some(door(DoorState::Open)),
+            '+' => Some(door(DoorState::Closed)),
+            '=' => Some(door(DoorState::Locked)),
+            'S' => Some(door(DoorState::Secret)),
+            '^' => Some(Feature::Trap("trap".to_string())),
+            '&' => Some(Feature::Decoration("decoration".to_string())),
+            _ => None,
+        };
+        let entity = match glyph {
+            '<' => Some(Entity::Entrance),
+            '>' => Some(Entity::Exit),
+            'M' => Some(Entity::Monster("monster".to_string())),
+            '$' => Some(Entity::Item("item".to_string())),
+            _ => None,
+        };
+        if feature.is_none() && entity.is_none() {
+            return None;
+        }
+        if !self.tiles[y][x].is_walkable() {
+            self.tiles[y][x] = Tile::Floor;
+        }
+        if let Some(feature) = feature {
+            self.features.set(x, y, feature);
+        }
+        if let Some(entity) = entity {
+            self.entities.set(x, y, entity);
+        }
+        Some(())
+    }
+
+    // Writes terrain.txt, features.txt and entities.txt into `dir`
+    pub fn write_layers(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        for kind in LayerKind::ALL {
+            fs::write(dir.join(format!("{}.txt", kind.name())), self.layer_to_ascii(kind))?;
+        }
+        Ok(())
+    }
+
+    // Drops features and entities left standing inside walls after terrain edits
+    pub(crate) fn prune_layers(&mut self) {
+        let tiles = &self.tiles;
+        self.features.retain(|(x, y), _| tiles[y][x].is_walkable());
+        self.entities.retain(|(x, y), _| tiles[y][x].is_walkable());
+    }
+}
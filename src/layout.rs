@@ -0,0 +1,64 @@
+// Generating a map several times over and keeping the best of the lot, by
+// whatever the layout objective measures. Each attempt after the first draws
+// from its own seed derived from the run's, so the pick is as reproducible
+// as a single map.
+use crate::config::Config;
+use crate::dungeon::Dungeon;
+use crate::layers::Entity;
+use crate::solver::KEY;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Objective {
+    // The first attempt is kept
+    #[default]
+    None,
+    // The least walking off the critical path to fetch keys, among the maps
+    // that hold as many of the configured locks as any
+    Backtracking,
+}
+
+impl Objective {
+    pub const ALL: [Objective; 2] = [Objective::None, Objective::Backtracking];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Objective::None => "none",
+            Objective::Backtracking => "backtracking",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|objective| objective.name() == name)
+    }
+
+    // Lower is better; None when any map will do
+    pub fn score(self, dungeon: &Dungeon, config: &Config) -> Option<(usize, usize)> {
+        match self {
+            Objective::None => None,
+            Objective::Backtracking => {
+                let keys = dungeon
+                    .entities()
+                    .iter()
+                    .filter(|(_, entity)| matches!(entity, Entity::Item(name) if name == KEY))
+                    .count();
+                Some((config.locks.keys.saturating_sub(keys), dungeon.backtracking()))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LayoutOptions {
+    // Maps generated in search of the best one
+    pub attempts: usize,
+    pub objective: Objective,
+}
+
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        Self {
+            attempts: 1,
+            objective: Objective::None,
+        }
+    }
+}
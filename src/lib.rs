@@ -0,0 +1,101 @@
+// Semver policy. Everything public here is stable except the modules listed
+// under `unstable!` below: while the crate is at 0.x a stable item only
+// changes or goes away in a minor release (0.1 to 0.2), never in a patch.
+// Unstable modules are still finding their shape and may change in any
+// release. They are only public with the `unstable` feature, so relying on
+// one is a choice made in Cargo.toml rather than by accident. Most programs
+// need no more than `use dungeon_generator::prelude::*;`.
+
+// Public with the `unstable` feature, private to the crate without it
+macro_rules! unstable {
+    ($($name:ident),* $(,)?) => {
+        $(
+            #[cfg(feature = "unstable")]
+            pub mod $name;
+            #[cfg(not(feature = "unstable"))]
+            #[allow(dead_code)]
+            mod $name;
+        )*
+    };
+}
+
+pub mod ambient;
+pub mod archipelago;
+pub mod autoexplore;
+pub mod border;
+pub mod bundle;
+pub mod capabilities;
+pub mod clearance;
+pub mod config;
+pub mod connectivity;
+pub mod contracts;
+pub mod contrast;
+pub mod counter;
+pub mod describe;
+pub mod door;
+pub mod dungeon;
+pub mod ecology;
+pub mod erosion;
+pub mod events;
+pub mod exploration;
+pub mod favorites;
+pub mod fill;
+pub mod fingerprint;
+pub mod fixtures;
+pub mod fov;
+pub mod frozen;
+pub mod generator;
+pub mod gif;
+pub mod grid;
+pub mod hardness;
+pub mod history;
+pub mod image;
+pub mod incremental;
+mod jps;
+mod json;
+pub mod keys;
+pub mod labels;
+pub mod layers;
+pub mod layout;
+pub mod locks;
+pub mod loot;
+pub mod mesh;
+pub mod messages;
+pub mod meta;
+pub mod overlay;
+pub mod palette;
+pub mod pathfinding;
+pub mod percolation;
+pub mod pillars;
+pub mod pins;
+pub mod population;
+pub mod prelude;
+pub mod preset;
+pub mod print;
+pub mod profiling;
+pub mod recipe;
+pub mod rooms;
+pub mod roughness;
+mod rows;
+pub mod scratch;
+pub mod script;
+pub mod shading;
+pub mod solver;
+pub mod stats;
+pub mod survey;
+pub mod terrain;
+pub mod theme;
+pub mod toml;
+pub mod traffic;
+pub mod validate;
+pub mod veins;
+pub mod walkthrough;
+pub mod world;
+mod yaml;
+
+unstable!(canonical, flow, metrics);
+
+pub use config::Config;
+pub use dungeon::{Dungeon, Point, Tile, View, Zoom};
+pub use generator::SeededRng;
+pub use preset::Preset;
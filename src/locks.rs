@@ -0,0 +1,351 @@
+// Locked doors with their keys. Locks go on chokepoints of the walk from
+// the entrance to the exit, so none can be walked around, and split the map
+// into stretches: the one before the first lock, the one between the first
+// and the second, and so on. Each key lies as far along as the nesting depth
+// allows, so with a depth of 2 the key to the third lock is found behind the
+// first two. Both a lock and its key carry the same `lock` number in their
+// tile metadata.
+use crate::door::{Door, DoorMaterial, DoorState, Facing};
+use crate::dungeon::{Dungeon, Point, Tile};
+use crate::layers::Entity;
+use crate::pathfinding::entrance_and_exit;
+use crate::solver::KEY;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::VecDeque;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LockOptions {
+    pub keys: usize,
+    // Most locks that stand between the entrance and any key
+    pub depth: usize,
+    // Whether keys may lie off the main route, down passages a player could
+    // otherwise skip; if not they sit on the route itself
+    pub side_paths: bool,
+}
+
+impl Default for LockOptions {
+    fn default() -> Self {
+        Self {
+            keys: 0,
+            depth: 1,
+            side_paths: true,
+        }
+    }
+}
+
+// Map area each key needs at the least, for config validation
+pub const TILES_PER_KEY: usize = 100;
+
+// Most keys a map can hold, so that the solver can keep the keys in hand
+// as the bits of a word
+pub const MAX_KEYS: usize = 63;
+
+// Fewest route steps between two locks
+const MIN_LOCK_GAP: usize = 3;
+
+pub const LOCK_KEY: &str = "lock";
+
+impl Dungeon {
+    // Returns the locks placed, which is fewer than asked for when the route
+    // has too few chokepoints to hold them all
+    pub fn place_locks<R: Rng + ?Sized>(&mut self, options: &LockOptions, rng: &mut R) -> usize {
+        if options.keys == 0 {
+            return 0;
+        }
+        let Some((entrance, exit)) = entrance_and_exit(self) else {
+            return 0;
+        };
+        let Some(route) = self.route(entrance, exit).filter(|route| route.len() > 1) else {
+            return 0;
+        };
+
+        // Steps of the route onto tiles nothing else stands on that the walk
+        // can't get past without
+        let chokepoints: Vec<usize> = (1..route.len() - 1)
+            .filter(|&step| {
+                let (x, y) = route[step];
+                let door = self.door(x, y).is_some();
+                let taken = (self.features.contains(x, y) && !door) || self.entities.contains(x, y);
+                self.tiles[y][x] == Tile::Floor
+                    && !taken
+                    && !self.frozen[y][x]
+                    && self.flood(entrance, &[(x, y)])[exit.1 * self.width + exit.0].is_none()
+            })
+            .collect();
+        // Spread out along the route, each the nearest chokepoint to its
+        // share of the way with room for a key between it and the last
+        let mut steps: Vec<usize> = Vec::new();
+        for index in 0..options.keys {
+            let target = (index + 1) * route.len() / (options.keys + 1);
+            let nearest = chokepoints
+                .iter()
+                .copied()
+                .filter(|&step| steps.iter().all(|&taken| taken.abs_diff(step) > MIN_LOCK_GAP))
+                .min_by_key(|&step| step.abs_diff(target));
+            steps.extend(nearest);
+        }
+        steps.sort_unstable();
+        let locks: Vec<Point> = steps.into_iter().map(|step| route[step]).collect();
+
+        // Each stretch as the tiles first reached once its lock opens
+        let mut stretches: Vec<Vec<Point>> = Vec::new();
+        let mut seen = vec![false; self.width * self.height];
+        for opened in 0..=locks.len() {
+            let reached = self.flood(entrance, &locks[opened..]);
+            let mut stretch = Vec::new();
+            for (index, parent) in reached.iter().enumerate() {
+                if parent.is_some() && !seen[index] {
+                    seen[index] = true;
+                    stretch.push((index % self.width, index / self.width));
+                }
+            }
+            stretches.push(stretch);
+        }
+
+        let mut placed = 0;
+        for (number, &(x, y)) in locks.iter().enumerate() {
+            let free = |dungeon: &Dungeon, &(kx, ky): &Point| {
+                dungeon.tiles[ky][kx] == Tile::Floor
+                    && !dungeon.features.contains(kx, ky)
+                    && !dungeon.entities.contains(kx, ky)
+                    && !dungeon.frozen[ky][kx]
+                    && (kx, ky) != entrance
+                    && (kx, ky) != exit
+                    && (options.side_paths || route.contains(&(kx, ky)))
+            };
+            // The deepest stretch allowed with somewhere to put the key
+            let Some(spots) = (0..=number.min(options.depth))
+                .rev()
+                .map(|stretch| stretches[stretch].iter().copied().filter(|point| free(self, point)).collect())
+                .find(|spots: &Vec<Point>| !spots.is_empty())
+            else {
+                continue;
+            };
+            let &(kx, ky) = spots.choose(rng).unwrap();
+            self.entities.set(kx, ky, Entity::Item(KEY.to_string()));
+            self.set_meta(kx, ky, LOCK_KEY, placed as i64 + 1);
+            match self.door_mut(x, y) {
+                Some(door) => door.state = DoorState::Locked,
+                None => {
+                    // A door across a north-south passage has wall to its sides
+                    let vertical = x > 0 && !self.tiles[y][x - 1].is_walkable();
+                    let facing = if vertical { Facing::North } else { Facing::East };
+                    self.add_door(x, y, Door::new(DoorState::Locked, DoorMaterial::Iron, facing));
+                }
+            }
+            self.set_meta(x, y, LOCK_KEY, placed as i64 + 1);
+            placed += 1;
+        }
+
+        // The route was worked out between these two, so keep them there
+        for (point, entity) in [(entrance, Entity::Entrance), (exit, Entity::Exit)] {
+            if !self.entities.contains(point.0, point.1) {
+                self.entities.set(point.0, point.1, entity);
+            }
+        }
+        placed
+    }
+
+    // Steps walked off the shortest route from the entrance to the exit and
+    // back again to pick up every key, which is what makes a layout feel like
+    // a fetch quest
+    pub fn backtracking(&self) -> usize {
+        let keys: Vec<Point> = self
+            .entities
+            .iter()
+            .filter(|(_, entity)| matches!(entity, Entity::Item(name) if name == KEY))
+            .map(|(point, _)| point)
+            .collect();
+        if keys.is_empty() {
+            return 0;
+        }
+        let Some(route) = entrance_and_exit(self).and_then(|(entrance, exit)| self.route(entrance, exit)) else {
+            return 0;
+        };
+
+        let mut distances = vec![None; self.width * self.height];
+        let mut queue = VecDeque::new();
+        for &(x, y) in &route {
+            distances[y * self.width + x] = Some(0);
+            queue.push_back((x, y));
+        }
+        while let Some((x, y)) = queue.pop_front() {
+            let distance = distances[y * self.width + x].unwrap_or(0);
+            for (nx, ny) in self.steps_from((x, y)).into_iter().flatten() {
+                let index = ny * self.width + nx;
+                if distances[index].is_none() && self.tiles[ny][nx].is_walkable() {
+                    distances[index] = Some(distance + 1);
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+        keys.iter().filter_map(|&(x, y)| distances[y * self.width + x]).map(|distance| 2 * distance).sum()
+    }
+
+    // The shortest walk from `start` to `goal`, both ends included, with
+    // doors counting as open
+    fn route(&self, start: Point, goal: Point) -> Option<Vec<Point>> {
+        let parents = self.flood(start, &[]);
+        parents[goal.1 * self.width + goal.0]?;
+        let mut route = vec![goal];
+        let mut current = goal;
+        while current != start {
+            current = parents[current.1 * self.width + current.0]?;
+            route.push(current);
+        }
+        route.reverse();
+        Some(route)
+    }
+
+    fn steps_from(&self, (x, y): Point) -> [Option<Point>; 4] {
+        [
+            (x > 0).then(|| (x - 1, y)),
+            (y > 0).then(|| (x, y - 1)),
+            (x + 1 < self.width).then_some((x + 1, y)),
+            (y + 1 < self.height).then_some((x, y + 1)),
+        ]
+    }
+
+    // Breadth-first over walkable tiles from `start`, never onto `blocked`;
+    // each reached tile's parent, flat by `y * width + x`, with the start its
+    // own parent
+    fn flood(&self, start: Point, blocked: &[Point]) -> Vec<Option<Point>> {
+        let mut parents = vec![None; self.width * self.height];
+        parents[start.1 * self.width + start.0] = Some(start);
+        let mut queue = VecDeque::from([start]);
+        while let Some((x, y)) = queue.pop_front() {
+            for (nx, ny) in self.steps_from((x, y)).into_iter().flatten() {
+                let index = ny * self.width + nx;
+                if parents[index].is_none() && self.tiles[ny][nx].is_walkable() && !blocked.contains(&(nx, ny)) {
+                    parents[index] = Some((x, y));
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+        parents
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use crate::generator::rng_for;
+    use crate::pathfinding::PathOptions;
+    use crate::solver;
+
+    // A corridor one tile wide along row 1 from the entrance at x = 1 to the
+    // exit at x = 39, with a dead end running down from it at every tenth
+    // column from x = 5, so every corridor tile is a chokepoint and the dead
+    // ends are side paths
+    fn corridor() -> Dungeon {
+        let mut dungeon = Dungeon::new(41, 9);
+        for x in 1..40 {
+            dungeon.set_tile(x, 1, Tile::Floor);
+        }
+        for x in [5, 15, 25, 35] {
+            for y in 2..8 {
+                dungeon.set_tile(x, y, Tile::Floor);
+            }
+        }
+        dungeon.entities.set(1, 1, Entity::Entrance);
+        dungeon.entities.set(39, 1, Entity::Exit);
+        dungeon
+    }
+
+    fn number(dungeon: &Dungeon, (x, y): Point) -> i64 {
+        dungeon.meta(x, y).and_then(|meta| meta.get(LOCK_KEY)).and_then(|number| number.as_int()).unwrap()
+    }
+
+    // Every lock's column, and every key with its lock's number
+    fn locks_and_keys(dungeon: &Dungeon) -> (Vec<usize>, Vec<(Point, i64)>) {
+        let locks = dungeon
+            .doors()
+            .filter(|(_, door)| door.state == DoorState::Locked)
+            .map(|((x, _), _)| x)
+            .collect();
+        let keys = dungeon
+            .entities
+            .iter()
+            .filter(|(_, entity)| matches!(entity, Entity::Item(name) if name == KEY))
+            .map(|(point, _)| (point, number(dungeon, point)))
+            .collect();
+        (locks, keys)
+    }
+
+    #[test]
+    fn each_key_lies_as_deep_as_the_depth_allows() {
+        for depth in [1, 2] {
+            for seed in 0..6 {
+                let mut dungeon = corridor();
+                let options = LockOptions {
+                    keys: 3,
+                    depth,
+                    side_paths: true,
+                };
+                assert_eq!(dungeon.place_locks(&options, &mut rng_for(seed)), 3);
+                let (mut locks, keys) = locks_and_keys(&dungeon);
+                locks.sort_unstable();
+                assert_eq!(locks.len(), 3);
+                assert!(locks.windows(2).all(|pair| pair[1] - pair[0] > MIN_LOCK_GAP));
+                for (position, lock) in locks.iter().enumerate() {
+                    assert_eq!(number(&dungeon, (*lock, 1)), position as i64 + 1);
+                }
+                assert_eq!(keys.len(), 3);
+                for &((x, _), key) in &keys {
+                    // Locks between the entrance and the key, counting the
+                    // one at the mouth of a dead end it lies down
+                    let behind = locks.iter().filter(|&&lock| lock <= x).count();
+                    let deepest = (key as usize - 1).min(depth);
+                    assert_eq!(behind, deepest, "key {} with depth {} and seed {}", key, depth, seed);
+                }
+                assert!(solver::solve(&dungeon, &PathOptions::default()).is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn keys_keep_to_the_route_without_side_paths() {
+        let mut off_route = 0;
+        for seed in 0..6 {
+            for side_paths in [false, true] {
+                let mut dungeon = corridor();
+                let options = LockOptions {
+                    keys: 2,
+                    depth: 1,
+                    side_paths,
+                };
+                assert_eq!(dungeon.place_locks(&options, &mut rng_for(seed)), 2);
+                let (_, keys) = locks_and_keys(&dungeon);
+                let down_dead_ends = keys.iter().filter(|&&((_, y), _)| y > 1).count();
+                if side_paths {
+                    off_route += down_dead_ends;
+                } else {
+                    assert_eq!(down_dead_ends, 0, "a key left the route with seed {}", seed);
+                }
+            }
+        }
+        assert!(off_route > 0, "no key ever went down a side path");
+    }
+
+    #[test]
+    fn fewer_chokepoints_place_fewer_locks() {
+        // The corridor between the two rooms has room for two locks
+        let mut dungeon = fixtures::two_rooms();
+        let options = LockOptions {
+            keys: 5,
+            ..LockOptions::default()
+        };
+        let placed = dungeon.place_locks(&options, &mut rng_for(3));
+        let (locks, keys) = locks_and_keys(&dungeon);
+        assert!((1..5).contains(&placed), "placed {}", placed);
+        assert_eq!((locks.len(), keys.len()), (placed, placed));
+        assert!(solver::solve(&dungeon, &PathOptions::default()).is_some());
+
+        // Open floor has none at all
+        let mut open = fixtures::all_open(12, 8);
+        assert_eq!(open.place_locks(&options, &mut rng_for(3)), 0);
+        assert_eq!(open.doors().count(), 0);
+    }
+}
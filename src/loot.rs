@@ -0,0 +1,233 @@
+use crate::config::{invalid, read_table, read_usize, unknown_key};
+use crate::dungeon::{Dungeon, Point};
+use crate::generator::SeededRng;
+use crate::layers::Entity;
+use crate::population::read_weights;
+use crate::toml::{self, Table, Value};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::BTreeMap;
+use std::io;
+
+// What one container holds: between `rolls.0` and `rolls.1` draws from
+// the weighted item list
+#[derive(Clone, Debug, PartialEq)]
+pub struct LootTable {
+    pub rolls: (usize, usize),
+    pub items: Vec<(String, usize)>,
+}
+
+impl Default for LootTable {
+    fn default() -> Self {
+        Self {
+            rolls: (1, 1),
+            items: Vec::new(),
+        }
+    }
+}
+
+impl LootTable {
+    fn apply(&mut self, table: &Table) -> io::Result<()> {
+        for (key, value) in table {
+            match key.as_str() {
+                "rolls" => self.rolls = read_rolls(key, value)?,
+                "items" => self.items = read_weights(key, value)?,
+                _ => return Err(unknown_key("loot.<table>.", key)),
+            }
+        }
+        Ok(())
+    }
+}
+
+// `rolls = 2` or `rolls = [1, 3]`
+pub(crate) fn read_rolls(key: &str, value: &Value) -> io::Result<(usize, usize)> {
+    if let Some(range) = value.as_array() {
+        let [min, max] = range else {
+            return Err(invalid(key, "must be a count or a [min, max] pair"));
+        };
+        let (min, max) = (read_usize(key, min)?, read_usize(key, max)?);
+        if min > max {
+            return Err(invalid(key, "must not have a minimum above its maximum"));
+        }
+        return Ok((min, max));
+    }
+    let count = read_usize(key, value)?;
+    Ok((count, count))
+}
+
+// Loot tables by name. Any item spawn sharing a table's name is a container,
+// e.g. a `chest` in [spawns.items] is filled from [loot.chest].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Loot {
+    pub tables: Vec<(String, LootTable)>,
+}
+
+impl Loot {
+    pub fn get(&self, name: &str) -> Option<&LootTable> {
+        self.tables.iter().find(|(table, _)| table == name).map(|(_, table)| table)
+    }
+
+    // Tables named in `table` are replaced wholesale; others are kept
+    pub fn apply(&mut self, table: &Table) -> io::Result<()> {
+        for (name, value) in table {
+            let mut loot = LootTable::default();
+            loot.apply(read_table(name, value)?)?;
+            match self.tables.iter_mut().find(|(table, _)| table == name) {
+                Some((_, existing)) => *existing = loot,
+                None => self.tables.push((name.clone(), loot)),
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn to_toml(&self) -> String {
+        let mut out = String::new();
+        for (name, table) in &self.tables {
+            let items: Vec<String> = table
+                .items
+                .iter()
+                .map(|(name, weight)| format!("{} = {}", toml::key(name), weight))
+                .collect();
+            out.push_str(&format!(
+                "\n[loot.{}]\nrolls = [{}, {}]\nitems = {{ {} }}\n",
+                toml::key(name),
+                table.rolls.0,
+                table.rolls.1,
+                items.join(", ")
+            ));
+        }
+        out
+    }
+}
+
+// One filled container
+#[derive(Clone, Debug, PartialEq)]
+pub struct LootDrop {
+    pub position: Point,
+    pub container: String,
+    // Each item rolled and how many times, in the order first rolled
+    pub contents: Vec<(String, usize)>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LootManifest {
+    pub drops: Vec<LootDrop>,
+}
+
+impl LootManifest {
+    // An array of [[drop]] tables, one per container in reading order
+    pub fn to_toml(&self) -> String {
+        let mut out = String::new();
+        for drop in &self.drops {
+            let contents: Vec<String> = drop
+                .contents
+                .iter()
+                .map(|(name, count)| format!("{} = {}", toml::key(name), count))
+                .collect();
+            out.push_str(&format!(
+                "[[drop]]\nx = {}\ny = {}\ncontainer = {}\ncontents = {{ {} }}\n\n",
+                drop.position.0,
+                drop.position.1,
+                toml::quote(&drop.container),
+                contents.join(", ")
+            ));
+        }
+        out
+    }
+}
+
+impl Dungeon {
+    // Rolls every container on the map from its loot table, resolved from
+    // the map's master seed so the same seed always yields the same loot
+    pub fn roll_loot(&self, loot: &Loot, seed: u64) -> LootManifest {
+        let mut containers: Vec<(Point, &str, &LootTable)> = self
+            .entities
+            .iter()
+            .filter_map(|(point, entity)| match entity {
+                Entity::Item(name) => loot.get(name).map(|table| (point, name.as_str(), table)),
+                _ => None,
+            })
+            .collect();
+        containers.sort_by_key(|&((x, y), _, _)| (y, x));
+
+        // Streams of their own, so tuning the tables never reshapes the map
+        // and the map's passes never reshuffle the loot. Each room rolls its
+        // containers from its own, and a room's loot stays put whatever
+        // another room holds; containers between rooms share one more.
+        let (labels, _) = self.label_regions();
+        let mut streams: BTreeMap<Option<usize>, StdRng> = BTreeMap::new();
+        let drops = containers
+            .into_iter()
+            .map(|(position, container, table)| {
+                let room = labels[position.1][position.0];
+                let rng = streams.entry(room).or_insert_with(|| match room {
+                    Some(room) => SeededRng::new(seed).room(room).for_topic("loot"),
+                    None => SeededRng::new(seed).for_topic("loot"),
+                });
+                let mut contents: Vec<(String, usize)> = Vec::new();
+                let rolls = rng.gen_range(table.rolls.0..=table.rolls.1);
+                for _ in 0..rolls {
+                    let Ok((name, _)) = table.items.choose_weighted(&mut *rng, |(_, weight)| *weight) else {
+                        break;
+                    };
+                    match contents.iter_mut().find(|(item, _)| item == name) {
+                        Some((_, count)) => *count += 1,
+                        None => contents.push((name.clone(), 1)),
+                    }
+                }
+                LootDrop {
+                    position,
+                    container: container.to_string(),
+                    contents,
+                }
+            })
+            .collect();
+        LootManifest { drops }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate_seeded;
+    use crate::Config;
+
+    #[test]
+    fn a_room_keeps_its_loot_whatever_another_room_holds() {
+        let mut dungeon = generate_seeded(&Config::default(), 7);
+        dungeon.entities.clear();
+        let (labels, rooms) = dungeon.label_regions();
+        assert!(rooms > 1);
+        // The first floor tile of every room, in reading order
+        let mut firsts: Vec<Option<Point>> = vec![None; rooms];
+        for (y, row) in labels.iter().enumerate() {
+            for (x, label) in row.iter().enumerate() {
+                if let Some(room) = *label {
+                    firsts[room].get_or_insert((x, y));
+                }
+            }
+        }
+        for &(x, y) in firsts.iter().flatten() {
+            dungeon.entities.set(x, y, Entity::Item("chest".to_string()));
+        }
+        let loot = Loot {
+            tables: vec![(
+                "chest".to_string(),
+                LootTable {
+                    rolls: (1, 4),
+                    items: vec![("gold".to_string(), 3), ("gem".to_string(), 1), ("potion".to_string(), 2)],
+                },
+            )],
+        };
+        let all = dungeon.roll_loot(&loot, 7);
+        assert_eq!(all.drops.len(), rooms);
+
+        // Emptying the first room leaves every other room's chest as it was
+        let (x, y) = firsts[0].unwrap();
+        dungeon.entities.remove(x, y);
+        let rest = dungeon.roll_loot(&loot, 7);
+        let others: Vec<&LootDrop> = all.drops.iter().filter(|drop| drop.position != (x, y)).collect();
+        assert_eq!(rest.drops.iter().collect::<Vec<_>>(), others);
+    }
+}
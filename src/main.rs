@@ -1,162 +1,687 @@
-use crossterm::{
-    cursor, execute, style::{Color, Print, ResetColor, SetForegroundColor},
-    terminal::{self, ClearType},
-};
-use rand::Rng;
-use std::io::{self, Write, Read};
-use std::thread;
-use std::time::Duration;
-
-#[derive(Clone, Copy, PartialEq)]
-enum Tile {
-    Wall,
-    Floor,
-}
-
-struct Dungeon {
-    width: usize,
-    height: usize,
-    tiles: Vec<Vec<Tile>>,
-}
-
-impl Dungeon {
-    fn new(width: usize, height: usize) -> Self {
-        let tiles = vec![vec![Tile::Wall; width]; height];
-        Self { width, height, tiles }
-    }
-
-    fn initialize_random(&mut self, wall_probability: f64) {
-        let mut rng = rand::thread_rng();
-        for y in 0..self.height {
-            for x in 0..self.width {
-                self.tiles[y][x] = if rng.gen::<f64>() < wall_probability {
-                    Tile::Wall
-                } else {
-                    Tile::Floor
-                };
-            }
+mod clipboard;
+mod console;
+mod interactive;
+mod open;
+
+use dungeon_generator::ambient::Ambient;
+use dungeon_generator::bundle::Bundle;
+use dungeon_generator::capabilities::Capabilities;
+use dungeon_generator::connectivity::Connectivity;
+use dungeon_generator::contracts::Contracts;
+use dungeon_generator::favorites;
+use dungeon_generator::fill::Fill;
+use dungeon_generator::generator::{self, GenerationAlgorithm};
+use dungeon_generator::image;
+use dungeon_generator::labels;
+use dungeon_generator::mesh::{self, MeshFormat};
+use dungeon_generator::messages::Catalog;
+use dungeon_generator::overlay::Overlay;
+use dungeon_generator::pathfinding::{self, PathOptions};
+use dungeon_generator::percolation;
+use dungeon_generator::print::{self, PaperSize, PrintOptions};
+use dungeon_generator::profiling;
+use dungeon_generator::recipe::Recipe;
+use dungeon_generator::script::Script;
+use dungeon_generator::solver;
+use dungeon_generator::stats::{self, RunStats};
+use dungeon_generator::survey;
+use dungeon_generator::theme::Theme;
+use dungeon_generator::validate;
+use dungeon_generator::walkthrough::{self, WalkthroughOptions};
+use dungeon_generator::{Config, Dungeon, Preset};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+#[derive(Default, PartialEq)]
+enum Command {
+    #[default]
+    Generate,
+    // List the favorites file, or regenerate the favorite with this number
+    Favorites(Option<usize>),
+    // Summarise the stats log
+    StatsSummary,
+    // Generate this many maps from consecutive seeds and sum up their measurements
+    Survey(usize),
+    // Captioned tour of the automaton, each post-pass and every preset
+    Demo,
+    // Walk the map under fog of war
+    Explore,
+    // Find and play back the cheapest entrance-to-exit route
+    Solve,
+    // Analyse a saved ASCII map or layers directory
+    Inspect(PathBuf),
+    // Run structural checks over a saved map, failing if any do not pass
+    Validate(PathBuf),
+    // Run a touch-up script over a saved map and print the result
+    Apply { map: PathBuf, script: PathBuf },
+    // Generate every level of a recipe into a directory of bundles
+    Build { recipe: PathBuf, dir: PathBuf },
+}
+
+#[derive(Default)]
+struct Options {
+    command: Command,
+    config: Config,
+    favorites: PathBuf,
+    // Opt-in log of every export run; also what `stats summary` reads
+    stats: Option<PathBuf>,
+    // Config file and/or seed for the right-hand map of the A/B view
+    compare: Option<PathBuf>,
+    compare_seed: Option<u64>,
+    print: Option<PathBuf>,
+    print_options: PrintOptions,
+    png: Option<PathBuf>,
+    svg: Option<PathBuf>,
+    // Room names written over the SVG export
+    svg_labels: bool,
+    scale: Option<usize>,
+    mesh: Option<PathBuf>,
+    wall_height: Option<f32>,
+    walkthrough: Option<PathBuf>,
+    layers: Option<PathBuf>,
+    cost_map: Option<PathBuf>,
+    traffic: Option<PathBuf>,
+    labels: Option<PathBuf>,
+    // The rooms and the ways between them in words: JSON for a .json path,
+    // sentences for a screen reader otherwise
+    describe: Option<PathBuf>,
+    footprint: Option<PathBuf>,
+    loot: Option<PathBuf>,
+    // The log of the eras the map was aged through
+    history: Option<PathBuf>,
+    // The map as built and as aged, with the difference between them
+    overlay: Option<PathBuf>,
+    // Map, spawns, generation info and thumbnail in one .dungeon file
+    bundle: Option<PathBuf>,
+    unit_size: Option<usize>,
+    // Copy the map or its seed and config to the clipboard: "map" or "seed"
+    copy: Option<String>,
+    // Status lines for the explorer, in --lang or the environment's language
+    messages: Catalog,
+    // Decorative particles over the explorer and demo maps
+    ambient: Ambient,
+    // Ring the terminal bell as the explorer opens doors and finds rooms
+    bell: bool,
+    // Timings of each generation step and pass: folded stacks for a
+    // .folded file, a Chrome trace otherwise
+    profile: Option<PathBuf>,
+    // A config file to reread and regenerate from whenever it is saved
+    watch: Option<PathBuf>,
+    // What the config file is layered onto, and the flags layered over it,
+    // so each reread of a watched file builds its config the same way
+    base: Config,
+    overrides: Overrides,
+}
+
+// Command-line flags, which win over the preset and the config file
+#[derive(Default)]
+struct Overrides {
+    theme: Option<Theme>,
+    doors: Option<usize>,
+    algorithm: Option<GenerationAlgorithm>,
+    connectivity: Option<Connectivity>,
+    constant_work: bool,
+    seed: Option<u64>,
+}
+
+impl Overrides {
+    fn apply(&self, config: &mut Config) {
+        if let Some(theme) = &self.theme {
+            config.theme = theme.clone();
+        }
+        if let Some(doors) = self.doors {
+            config.doors = doors;
+        }
+        if let Some(algorithm) = self.algorithm {
+            config.algorithm = algorithm;
+        }
+        if let Some(connectivity) = self.connectivity {
+            config.connectivity = connectivity;
+        }
+        if self.constant_work {
+            config.constant_work = true;
+        }
+        if self.seed.is_some() {
+            config.seed = self.seed;
         }
     }
+}
+
+// The watched file read afresh over the preset, under the flags
+fn watched_config(options: &Options, path: &Path) -> io::Result<Config> {
+    let mut config = options.base.clone();
+    config.apply_file(path)?;
+    options.overrides.apply(&mut config);
+    Ok(config)
+}
 
-    fn count_wall_neighbors(&self, x: usize, y: usize) -> usize {
-        let mut count = 0;
-        for dy in -1..=1 {
-            for dx in -1..=1 {
-                if dx == 0 && dy == 0 {
-                    continue;
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+fn parse_number<T: std::str::FromStr>(name: &str, text: &str) -> io::Result<T> {
+    text.parse()
+        .map_err(|_| invalid_input(format!("invalid value '{}' for {}", text, name)))
+}
+
+// Seeds must fit in a TOML integer to round-trip through config files
+fn parse_seed(name: &str, text: &str) -> io::Result<u64> {
+    parse_number::<u64>(name, text)?
+        .try_into()
+        .map(|seed: i64| seed as u64)
+        .map_err(|_| invalid_input(format!("{} must be below 2^63", name)))
+}
+
+// Flat walls would give the mesh's faces no normals
+fn parse_wall_height(text: &str) -> io::Result<f32> {
+    Some(parse_number::<f32>("--wall-height", text)?)
+        .filter(|height| height.is_finite() && *height > 0.0)
+        .ok_or_else(|| invalid_input("--wall-height must be a positive number".to_string()))
+}
+
+fn parse_args() -> io::Result<Options> {
+    let mut options = Options {
+        favorites: PathBuf::from("favorites.toml"),
+        messages: Catalog::detect(),
+        ..Options::default()
+    };
+    let mut positional = Vec::new();
+    let mut config_path = None;
+    let mut preset = None;
+    let mut theme = None;
+    let mut overrides = Overrides::default();
+    let mut args = env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| invalid_input(format!("{} expects a value", name)))
+        };
+        match arg.as_str() {
+            "--preset" => {
+                let name = value("--preset")?;
+                preset = Some(Preset::parse(&name).ok_or_else(|| {
+                    invalid_input(format!("unknown preset '{}' (expected one of {})", name, Preset::names()))
+                })?);
+            }
+            "--theme" => theme = Some(value("--theme")?),
+            "--algorithm" => {
+                let name = value("--algorithm")?;
+                overrides.algorithm = Some(GenerationAlgorithm::parse(&name).ok_or_else(|| {
+                    invalid_input(format!("unknown algorithm '{}' (expected cellular_automaton, rooms or archipelago)", name))
+                })?);
+            }
+            "--connectivity" => {
+                let name = value("--connectivity")?;
+                overrides.connectivity = Some(Connectivity::parse(&name).ok_or_else(|| {
+                    invalid_input(format!("unknown connectivity '{}' (expected off, connect, spanning or prune)", name))
+                })?);
+            }
+            "--ambient" => {
+                let name = value("--ambient")?;
+                options.ambient = Ambient::parse(&name).ok_or_else(|| {
+                    invalid_input(format!("unknown ambient effect '{}' (expected off, auto, drips or mist)", name))
+                })?;
+            }
+            "--lang" => options.messages = Catalog::resolve(&value("--lang")?)?,
+            "--png" => options.png = Some(PathBuf::from(value("--png")?)),
+            "--svg" => options.svg = Some(PathBuf::from(value("--svg")?)),
+            "--svg-labels" => options.svg_labels = true,
+            "--scale" => options.scale = Some(parse_number("--scale", &value("--scale")?)?),
+            "--config" => config_path = Some(PathBuf::from(value("--config")?)),
+            "--watch" => options.watch = Some(PathBuf::from(value("--watch")?)),
+            "--print" => options.print = Some(PathBuf::from(value("--print")?)),
+            "--paper" => {
+                let name = value("--paper")?;
+                options.print_options.paper = PaperSize::parse(&name).ok_or_else(|| {
+                    invalid_input(format!("unknown paper size '{}' (expected letter or a4)", name))
+                })?;
+            }
+            "--seed" => overrides.seed = Some(parse_seed("--seed", &value("--seed")?)?),
+            "--copy" => {
+                let what = value("--copy")?;
+                if what != "map" && what != "seed" {
+                    return Err(invalid_input(format!("--copy expects map or seed, not '{}'", what)));
                 }
-                
-                let nx = x as i32 + dx;
-                let ny = y as i32 + dy;
-                
-                // Treat out-of-bounds as walls
-                if nx < 0 || ny < 0 || nx >= self.width as i32 || ny >= self.height as i32 {
-                    count += 1;
-                } else if self.tiles[ny as usize][nx as usize] == Tile::Wall {
-                    count += 1;
+                options.copy = Some(what);
+            }
+            "--stats" => options.stats = Some(PathBuf::from(value("--stats")?)),
+            "--profile" => {
+                let path = PathBuf::from(value("--profile")?);
+                if !cfg!(feature = "profiling") {
+                    return Err(invalid_input("--profile needs a build with the profiling feature".to_string()));
                 }
+                options.profile = Some(path);
             }
+            "--favorites" => options.favorites = PathBuf::from(value("--favorites")?),
+            "--compare" => options.compare = Some(PathBuf::from(value("--compare")?)),
+            "--compare-seed" => {
+                options.compare_seed = Some(parse_seed("--compare-seed", &value("--compare-seed")?)?)
+            }
+            "--constant-work" => overrides.constant_work = true,
+            "--bell" => options.bell = true,
+            "--doors" => overrides.doors = Some(parse_number("--doors", &value("--doors")?)?),
+            "--layers" => options.layers = Some(PathBuf::from(value("--layers")?)),
+            "--cost-map" => options.cost_map = Some(PathBuf::from(value("--cost-map")?)),
+            "--traffic" => options.traffic = Some(PathBuf::from(value("--traffic")?)),
+            "--labels" => options.labels = Some(PathBuf::from(value("--labels")?)),
+            "--describe" => options.describe = Some(PathBuf::from(value("--describe")?)),
+            "--footprint" => options.footprint = Some(PathBuf::from(value("--footprint")?)),
+            "--unit-size" => options.unit_size = Some(parse_number("--unit-size", &value("--unit-size")?)?),
+            "--loot" => options.loot = Some(PathBuf::from(value("--loot")?)),
+            "--history" => options.history = Some(PathBuf::from(value("--history")?)),
+            "--overlay" => options.overlay = Some(PathBuf::from(value("--overlay")?)),
+            "--bundle" => options.bundle = Some(PathBuf::from(value("--bundle")?)),
+            "--walkthrough" => options.walkthrough = Some(PathBuf::from(value("--walkthrough")?)),
+            "--mesh" => options.mesh = Some(PathBuf::from(value("--mesh")?)),
+            "--wall-height" => options.wall_height = Some(parse_wall_height(&value("--wall-height")?)?),
+            _ if !arg.starts_with("--") => positional.push(arg),
+            _ => return Err(invalid_input(format!("unknown argument '{}'", arg))),
         }
-        count
     }
 
-    fn simulate_step(&mut self) -> bool {
-        let mut new_tiles = self.tiles.clone();
-        let mut changed = false;
+    match positional.first().map(String::as_str) {
+        None => {}
+        Some("favorites") if positional.len() <= 2 => {
+            let index = positional.get(1).map(|index| parse_number("favorites", index)).transpose()?;
+            options.command = Command::Favorites(index);
+        }
+        Some("stats") if positional.get(1).is_some_and(|sub| sub == "summary") && positional.len() == 2 => {
+            options.command = Command::StatsSummary;
+        }
+        Some("survey") if positional.len() == 2 => {
+            options.command = Command::Survey(parse_number("survey", &positional[1])?);
+        }
+        Some("demo") if positional.len() == 1 => options.command = Command::Demo,
+        Some("explore") if positional.len() == 1 => options.command = Command::Explore,
+        Some("solve") if positional.len() == 1 => options.command = Command::Solve,
+        Some("inspect") if positional.len() == 2 => options.command = Command::Inspect(PathBuf::from(&positional[1])),
+        Some("validate") if positional.len() == 2 => options.command = Command::Validate(PathBuf::from(&positional[1])),
+        Some("apply") if positional.len() == 3 => {
+            options.command = Command::Apply {
+                map: PathBuf::from(&positional[1]),
+                script: PathBuf::from(&positional[2]),
+            }
+        }
+        Some("build") if positional.len() == 3 => {
+            options.command = Command::Build {
+                recipe: PathBuf::from(&positional[1]),
+                dir: PathBuf::from(&positional[2]),
+            }
+        }
+        Some(command) => return Err(invalid_input(format!("unknown command '{}'", command))),
+    }
 
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let wall_count = self.count_wall_neighbors(x, y);
-                
-                // Cellular automata rules for cave generation
-                let new_tile = if wall_count > 4 {
-                    Tile::Wall
-                } else if wall_count < 4 {
-                    Tile::Floor
-                } else {
-                    self.tiles[y][x]
-                };
+    // A preset is the starting point, the config file refines it and
+    // command-line flags win over both
+    if let Some(preset) = preset {
+        options.base = preset.config();
+    }
+    if let Some(theme) = theme {
+        overrides.theme = Some(Theme::resolve(&theme)?);
+    }
+    options.overrides = overrides;
+    if config_path.is_some() && options.watch.is_some() {
+        return Err(invalid_input("--watch reads its own config file, so it can't be given with --config".to_string()));
+    }
+    options.config = options.base.clone();
+    if let Some(path) = config_path.as_ref().or(options.watch.as_ref()) {
+        options.config.apply_file(path)?;
+    }
+    options.overrides.apply(&mut options.config);
 
-                if new_tile != self.tiles[y][x] {
-                    changed = true;
-                }
-                new_tiles[y][x] = new_tile;
+    Ok(options)
+}
+
+// Non-interactive path: generate once and write every requested file
+fn export(options: &Options) -> io::Result<()> {
+    let seed = generator::seed_for(&options.config);
+    let started = Instant::now();
+    let (dungeon, report) = generator::build(&options.config, seed, &Contracts::new())?;
+    let elapsed = started.elapsed();
+    println!("Seed {}", seed);
+    if report.locks < options.config.locks.keys {
+        eprintln!(
+            "Warning: only {} of {} locks fit; the route has too few chokepoints for the rest",
+            report.locks, options.config.locks.keys
+        );
+    }
+
+    if let Some(path) = &options.stats {
+        let run = RunStats::new(dungeon.width(), dungeon.height(), seed, &report, elapsed);
+        stats::append_run(path, &run)?;
+    }
+
+    if let Some(path) = &options.profile {
+        let records = profiling::take();
+        let text = if path.extension().is_some_and(|extension| extension == "folded") {
+            profiling::to_folded(&records)
+        } else {
+            profiling::to_trace(&records)
+        };
+        fs::write(path, text)?;
+        println!("Wrote {} span(s) to {}", records.len(), path.display());
+    }
+
+    if let Some(path) = &options.print {
+        let pages = print::write_pdf(&dungeon, &options.print_options, path)?;
+        println!("Wrote {} page(s) to {}", pages, path.display());
+    }
+
+    if let Some(what) = &options.copy {
+        let text = match what.as_str() {
+            "map" => dungeon.to_ascii(),
+            _ => interactive::seed_text(&options.config, seed),
+        };
+        let via = clipboard::copy(&text)?;
+        println!("Copied {} via {}", what, via);
+    }
+
+    let scale = options.scale.unwrap_or(8).max(1);
+    if let Some(path) = &options.png {
+        image::write_png(&dungeon, &options.config.theme, scale, path)?;
+        println!("Wrote {} image to {}", options.config.theme.name, path.display());
+    }
+
+    if let Some(path) = &options.svg {
+        let labels = if options.svg_labels { dungeon.region_labels(seed) } else { Vec::new() };
+        image::write_labelled_svg(&dungeon, &options.config.theme, scale, &labels, path)?;
+        println!("Wrote {} image to {}", options.config.theme.name, path.display());
+    }
+
+    if let Some(path) = &options.mesh {
+        let format = MeshFormat::from_path(path).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}: mesh file must end in .obj or .stl", path.display()),
+            )
+        })?;
+        let boxes = mesh::write_mesh(&dungeon, format, options.wall_height.unwrap_or(2.0), path)?;
+        println!("Wrote {} wall boxes to {}", boxes, path.display());
+    }
+
+    if let Some(path) = &options.walkthrough {
+        let walkthrough_options = WalkthroughOptions {
+            path_options: PathOptions::with_costs(options.config.costs),
+            theme: options.config.theme.clone(),
+            ..WalkthroughOptions::default()
+        };
+        let steps = walkthrough::write_walkthrough(&dungeon, &walkthrough_options, path)?;
+        println!("Wrote {}-step walkthrough to {}", steps, path.display());
+    }
+
+    if let Some(path) = &options.cost_map {
+        let path_options = PathOptions::with_costs(options.config.costs);
+        pathfinding::write_cost_map(&dungeon, &path_options, path)?;
+        println!("Wrote movement cost map to {}", path.display());
+    }
+
+    if let Some(path) = &options.traffic {
+        dungeon.write_traffic_map(path)?;
+        println!("Wrote traffic heatmap to {}", path.display());
+    }
+
+    if let Some(path) = &options.labels {
+        let labels = dungeon.region_labels(seed);
+        labels::write_labels(&labels, path)?;
+        println!("Wrote {} room label(s) to {}", labels.len(), path.display());
+    }
+
+    if let Some(path) = &options.describe {
+        let description = dungeon.describe(seed);
+        description.write(path)?;
+        println!("Wrote a description of {} room(s) to {}", description.rooms.len(), path.display());
+    }
+
+    if let Some(path) = &options.footprint {
+        let size = options.unit_size.unwrap_or(2);
+        dungeon.write_footprint_map(size, size, path)?;
+        println!("Wrote {}x{} unit footprint map to {}", size, size, path.display());
+    }
+
+    if let Some(path) = &options.loot {
+        let manifest = dungeon.roll_loot(&options.config.loot, seed);
+        fs::write(path, manifest.to_toml())?;
+        println!("Wrote {} filled container(s) to {}", manifest.drops.len(), path.display());
+    }
+
+    if let Some(path) = &options.history {
+        fs::write(path, dungeon.history_log())?;
+        println!("Wrote {} era(s) of history to {}", dungeon.history().len(), path.display());
+    }
+
+    if let Some(dir) = &options.overlay {
+        let overlay = Overlay::generate(&options.config, seed);
+        overlay.write(dir)?;
+        println!("Wrote original and current maps, {} changed tile(s), to {}", overlay.changes().len(), dir.display());
+    }
+
+    if let Some(dir) = &options.layers {
+        dungeon.write_layers(dir)?;
+        println!("Wrote terrain, feature and entity layers to {}", dir.display());
+    }
+
+    if let Some(path) = &options.bundle {
+        Bundle::new(dungeon, &report, seed, &options.config).save(path)?;
+        println!("Wrote map, spawns, generation info and thumbnail to {}", path.display());
+    }
+
+    Ok(())
+}
+
+fn list_favorites(path: &Path) -> io::Result<()> {
+    let favorites = favorites::load_favorites(path)?;
+    if favorites.is_empty() {
+        println!("No favorites in {} yet; press f in interactive mode to add one", path.display());
+    }
+    for (index, favorite) in favorites.iter().enumerate() {
+        let config = &favorite.config;
+        println!(
+            "{:>3}  seed {:<20} {}x{:<6} {:<10} {}",
+            index + 1,
+            favorite.seed(),
+            config.width,
+            config.height,
+            config.theme.name,
+            favorite.hash
+        );
+    }
+    Ok(())
+}
+
+// Swaps in the chosen favorite's config, warning if it no longer builds the
+// same map (say, after the generator changed)
+fn use_favorite(options: &mut Options, number: usize) -> io::Result<()> {
+    let favorites = favorites::load_favorites(&options.favorites)?;
+    let favorite = number
+        .checked_sub(1)
+        .and_then(|index| favorites.get(index))
+        .ok_or_else(|| invalid_input(format!("no favorite number {} in {}", number, options.favorites.display())))?;
+    let dungeon = generator::generate_seeded(&favorite.config, favorite.seed());
+    if favorites::thumbnail_hash(&dungeon) != favorite.hash {
+        eprintln!("Warning: favorite {} now generates a different map than when it was saved", number);
+    }
+    options.config = favorite.config.clone();
+    Ok(())
+}
+
+fn summarize_stats(path: &Path) -> io::Result<()> {
+    let runs = stats::load_runs(path)?;
+    if runs.is_empty() {
+        println!("No runs in {} yet; pass --stats {} when exporting to record them", path.display(), path.display());
+        return Ok(());
+    }
+    println!("{} run(s) in {}", runs.len(), path.display());
+    for summary in stats::summarize(&runs) {
+        println!();
+        println!("{}", summary.algorithm);
+        println!("  runs             {}", summary.runs);
+        println!("  mean size        {} tiles", summary.tiles / summary.runs);
+        println!(
+            "  time             {:.2} ms mean, {:.2} ms median, {:.2} ms max",
+            summary.mean_ms, summary.median_ms, summary.max_ms
+        );
+        println!("  per 1000 tiles   {:.3} ms", summary.ms_per_kilotile());
+        println!("  automaton steps  {:.1} mean", summary.mean_steps);
+        println!("  layout attempts  {:.1} mean", summary.mean_attempts);
+        println!("  unsettled        {} run(s) hit the step limit", summary.unconverged_runs);
+        println!(
+            "  clearance fixes  {} run(s), {} tile(s) carved",
+            summary.repaired_runs, summary.carved
+        );
+    }
+    Ok(())
+}
+
+fn main() -> io::Result<()> {
+    let mut options = parse_args()?;
+    match options.command {
+        Command::Generate => {}
+        Command::Favorites(None) => return list_favorites(&options.favorites),
+        Command::Favorites(Some(number)) => use_favorite(&mut options, number)?,
+        Command::StatsSummary => {
+            return summarize_stats(options.stats.as_deref().unwrap_or(Path::new("stats.jsonl")))
+        }
+        Command::Survey(count) => {
+            let seed = generator::seed_for(&options.config);
+            print!("{}", survey::survey(&options.config, seed, count).report());
+            return Ok(());
+        }
+        Command::Demo => {
+            require_cursor("the demo")?;
+            return interactive::demo(options.config, options.ambient);
+        }
+        Command::Explore => {
+            require_cursor("explore mode")?;
+            return interactive::explore(options.config, options.bell);
+        }
+        Command::Solve => return solve(&options.config),
+        Command::Inspect(path) => {
+            print!("{}", Dungeon::load(&path)?.metrics().report());
+            return Ok(());
+        }
+        Command::Validate(path) => {
+            let validation = validate::validate(&path)?;
+            println!("Validating {}", path.display());
+            print!("{}", validation.report());
+            if !validation.passed() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} failed validation", path.display())));
             }
+            return Ok(());
         }
-
-        self.tiles = new_tiles;
-        changed
-    }
-
-    fn render(&self) -> io::Result<()> {
-        let mut stdout = io::stdout();
-        
-        execute!(stdout, cursor::MoveTo(0, 0))?;
-        
-        for row in &self.tiles {
-            for &tile in row {
-                match tile {
-                    Tile::Wall => {
-                        execute!(
-                            stdout,
-                            SetForegroundColor(Color::DarkGrey),
-                            Print("█"),
-                            ResetColor
-                        )?;
-                    }
-                    Tile::Floor => {
-                        execute!(
-                            stdout,
-                            SetForegroundColor(Color::Yellow),
-                            Print("·"),
-                            ResetColor
-                        )?;
-                    }
-                }
+        Command::Apply { map, script } => {
+            let mut dungeon = Dungeon::load(&map)?;
+            let script = Script::load(&script)?;
+            dungeon.apply_script(&script)?;
+            print!("{}", dungeon.to_ascii());
+            eprintln!("Applied {} command(s) to {}", script.commands.len(), map.display());
+            return Ok(());
+        }
+        Command::Build { recipe, dir } => {
+            let recipe = Recipe::load(&recipe)?;
+            for (level, path) in recipe.levels.iter().zip(recipe.build(&dir)?) {
+                let (config, number) = (&level.config, level.number);
+                let size = format!("{}x{} {}", config.width, config.height, config.theme.name);
+                println!("Wrote level {} ({}) to {}", number, size, path.display());
             }
-            execute!(stdout, Print("\n"))?;
+            return Ok(());
+        }
+    }
+
+    if options.config.fill != Fill::Percolation {
+        for warning in percolation::warnings(&options.config) {
+            eprintln!("Warning: {}", warning);
         }
-        
-        stdout.flush()?;
-        Ok(())
     }
+    for warning in options.config.theme.contrast_warnings() {
+        eprintln!("Warning: {}", warning);
+    }
+
+    if let Some(path) = &options.watch {
+        require_cursor("watching a config file")?;
+        return interactive::watch(path, options.config.clone(), || watched_config(&options, path));
+    }
+
+    let exporting = options.print.is_some()
+        || options.png.is_some()
+        || options.svg.is_some()
+        || options.mesh.is_some()
+        || options.walkthrough.is_some()
+        || options.layers.is_some()
+        || options.cost_map.is_some()
+        || options.traffic.is_some()
+        || options.labels.is_some()
+        || options.describe.is_some()
+        || options.footprint.is_some()
+        || options.loot.is_some()
+        || options.history.is_some()
+        || options.overlay.is_some()
+        || options.bundle.is_some()
+        || options.profile.is_some()
+        || options.copy.is_some();
+    if exporting {
+        return export(&options);
+    }
+
+    if options.compare.is_some() || options.compare_seed.is_some() {
+        let seed = generator::seed_for(&options.config);
+        let mut b = interactive::Side {
+            label: "B".to_string(),
+            config: options.config.clone(),
+            seed: options.compare_seed.unwrap_or(seed),
+        };
+        if let Some(path) = &options.compare {
+            b.config.apply_file(path)?;
+            b.label = format!("B: {}", path.display());
+        }
+        let a = interactive::Side {
+            label: "A".to_string(),
+            config: options.config,
+            seed,
+        };
+        require_cursor("comparing maps")?;
+        return interactive::compare(a, b);
+    }
+
+    // Nothing can be animated or navigated without cursor control, so the
+    // map is just printed
+    if !Capabilities::detect().cursor {
+        let seed = generator::seed_for(&options.config);
+        print!("{}", generator::generate_seeded(&options.config, seed).to_ascii());
+        eprintln!("Seed {}", seed);
+        return Ok(());
+    }
+    interactive::run(options.config, &options.favorites, &options.messages, options.ambient)
 }
 
-fn main() -> io::Result<()> {
-    let mut stdout = io::stdout();
-    
-    // Setup terminal
-    terminal::enable_raw_mode()?;
-    execute!(stdout, terminal::Clear(ClearType::All), cursor::Hide)?;
-
-    // Create dungeon
-    let mut dungeon = Dungeon::new(80, 30);
-    dungeon.initialize_random(0.45);
-
-    // Animate generation
-    println!("Generating dungeon...\n");
-    thread::sleep(Duration::from_millis(500));
-
-    for iteration in 0..7 {
-        dungeon.render()?;
-        println!("\nIteration: {}", iteration + 1);
-        thread::sleep(Duration::from_millis(300));
-        
-        if !dungeon.simulate_step() {
-            break;
-        }
-    }
-
-    // Final render
-    dungeon.render()?;
-    println!("\nDungeon complete! Press any key to exit...");
-    
-    // Cleanup
-    let mut buffer = [0u8; 1];
-    io::stdin().read_exact(&mut buffer).ok();
-    
-    execute!(stdout, cursor::Show, terminal::Clear(ClearType::All))?;
-    terminal::disable_raw_mode()?;
-    
-    Ok(())
-}
\ No newline at end of file
+// Plays the route back where the terminal allows, then prints its summary.
+// An unfinishable map is an error, so scripts can check maps by exit status.
+fn solve(config: &Config) -> io::Result<()> {
+    let seed = generator::seed_for(config);
+    let dungeon = generator::generate_seeded(config, seed);
+    let solution = solver::solve(&dungeon, &PathOptions::with_costs(config.costs));
+    if Capabilities::detect().cursor {
+        interactive::solve(&dungeon, config, solution.as_ref())?;
+    }
+    println!("Seed {}", seed);
+    match solution {
+        Some(solution) => {
+            print!("{}", solution.report());
+            Ok(())
+        }
+        None => Err(io::Error::new(io::ErrorKind::InvalidData, "no route from the entrance to the exit")),
+    }
+}
+
+fn require_cursor(mode: &str) -> io::Result<()> {
+    if Capabilities::detect().cursor {
+        return Ok(());
+    }
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("{} needs a terminal with cursor control, and TERM is dumb", mode),
+    ))
+}
@@ -0,0 +1,216 @@
+use crate::Dungeon;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MeshFormat {
+    Obj,
+    Stl,
+}
+
+impl MeshFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "obj" => Some(MeshFormat::Obj),
+            "stl" => Some(MeshFormat::Stl),
+            _ => None,
+        }
+    }
+}
+
+// An axis-aligned run of wall tiles, in tile coordinates
+#[derive(Clone, Copy, Debug)]
+pub struct WallBox {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub depth: usize,
+}
+
+// Merge wall tiles into as few rectangles as a greedy sweep finds, so a long
+// wall becomes one box instead of dozens of cubes.
+pub fn greedy_boxes(dungeon: &Dungeon) -> Vec<WallBox> {
+    let mut used = vec![vec![false; dungeon.width]; dungeon.height];
+    let free = |used: &Vec<Vec<bool>>, x: usize, y: usize| {
+        !dungeon.tiles[y][x].is_walkable() && !used[y][x]
+    };
+    let mut boxes = Vec::new();
+
+    for y in 0..dungeon.height {
+        for x in 0..dungeon.width {
+            if !free(&used, x, y) {
+                continue;
+            }
+
+            let mut width = 1;
+            while x + width < dungeon.width && free(&used, x + width, y) {
+                width += 1;
+            }
+
+            let mut depth = 1;
+            while y + depth < dungeon.height && (x..x + width).all(|cx| free(&used, cx, y + depth)) {
+                depth += 1;
+            }
+
+            for row in used.iter_mut().skip(y).take(depth) {
+                for cell in row.iter_mut().skip(x).take(width) {
+                    *cell = true;
+                }
+            }
+            boxes.push(WallBox { x, y, width, depth });
+        }
+    }
+
+    boxes
+}
+
+type Vertex = [f32; 3];
+type Triangle = [Vertex; 3];
+
+// Each box is emitted as its own closed shell with outward-facing winding, so
+// every shell is watertight and slicers union the overlapping faces.
+fn box_triangles(wall: &WallBox, dungeon: &Dungeon, wall_height: f32) -> Vec<Triangle> {
+    // Z is up and x and y are the map's world frame, so with the default
+    // frame the rows are flipped and the mesh reads like the terminal view
+    // from above. A quarter turn keeps a box square to the axes, so its
+    // opposite corners still bound it.
+    let world = dungeon.world;
+    let size = (dungeon.width, dungeon.height);
+    let (ax, ay) = world.to_world((wall.x as f64, wall.y as f64), size);
+    let (bx, by) = world.to_world(((wall.x + wall.width) as f64, (wall.y + wall.depth) as f64), size);
+    let (x0, x1) = (ax.min(bx) as f32, ax.max(bx) as f32);
+    let (y0, y1) = (ay.min(by) as f32, ay.max(by) as f32);
+    let (z0, z1) = (0.0, wall_height * world.tile_size as f32);
+
+    let corners = [
+        [x0, y0, z0],
+        [x1, y0, z0],
+        [x1, y1, z0],
+        [x0, y1, z0],
+        [x0, y0, z1],
+        [x1, y0, z1],
+        [x1, y1, z1],
+        [x0, y1, z1],
+    ];
+    // Quads listed counter-clockwise when viewed from outside
+    let quads = [
+        [0, 3, 2, 1], // bottom
+        [4, 5, 6, 7], // top
+        [0, 1, 5, 4], // south
+        [1, 2, 6, 5], // east
+        [2, 3, 7, 6], // north
+        [3, 0, 4, 7], // west
+    ];
+
+    let mut triangles = Vec::with_capacity(12);
+    for [a, b, c, d] in quads {
+        triangles.push([corners[a], corners[b], corners[c]]);
+        triangles.push([corners[a], corners[c], corners[d]]);
+    }
+    triangles
+}
+
+fn normal(triangle: &Triangle) -> Vertex {
+    let [a, b, c] = triangle;
+    let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    let n = [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ];
+    let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+    [n[0] / length, n[1] / length, n[2] / length]
+}
+
+pub fn write_mesh(
+    dungeon: &Dungeon,
+    format: MeshFormat,
+    wall_height: f32,
+    path: &Path,
+) -> io::Result<usize> {
+    let boxes = greedy_boxes(dungeon);
+    let mut out = BufWriter::new(File::create(path)?);
+
+    match format {
+        MeshFormat::Obj => write_obj(&mut out, dungeon, &boxes, wall_height)?,
+        MeshFormat::Stl => write_stl(&mut out, dungeon, &boxes, wall_height)?,
+    }
+
+    out.flush()?;
+    Ok(boxes.len())
+}
+
+fn write_obj<W: Write>(
+    out: &mut W,
+    dungeon: &Dungeon,
+    boxes: &[WallBox],
+    wall_height: f32,
+) -> io::Result<()> {
+    writeln!(out, "# dungeon_generator wall mesh, {} boxes", boxes.len())?;
+    writeln!(out, "o walls")?;
+
+    let mut base = 1;
+    for wall in boxes {
+        // Shared corners keep each box a proper indexed shell in OBJ
+        let triangles = box_triangles(wall, dungeon, wall_height);
+        let mut vertices: Vec<Vertex> = Vec::with_capacity(8);
+        let mut faces = Vec::with_capacity(triangles.len());
+
+        for triangle in &triangles {
+            let mut face = [0; 3];
+            for (slot, vertex) in face.iter_mut().zip(triangle) {
+                *slot = match vertices.iter().position(|v| v == vertex) {
+                    Some(index) => index,
+                    None => {
+                        vertices.push(*vertex);
+                        vertices.len() - 1
+                    }
+                };
+            }
+            faces.push(face);
+        }
+
+        for [x, y, z] in &vertices {
+            writeln!(out, "v {} {} {}", x, y, z)?;
+        }
+        for [a, b, c] in faces {
+            writeln!(out, "f {} {} {}", base + a, base + b, base + c)?;
+        }
+        base += vertices.len();
+    }
+
+    Ok(())
+}
+
+// Binary STL: 80-byte header, triangle count, then 50 bytes per triangle
+fn write_stl<W: Write>(
+    out: &mut W,
+    dungeon: &Dungeon,
+    boxes: &[WallBox],
+    wall_height: f32,
+) -> io::Result<()> {
+    let mut header = [0u8; 80];
+    let title = b"dungeon_generator wall mesh";
+    header[..title.len()].copy_from_slice(title);
+    out.write_all(&header)?;
+    out.write_all(&((boxes.len() * 12) as u32).to_le_bytes())?;
+
+    for wall in boxes {
+        for triangle in box_triangles(wall, dungeon, wall_height) {
+            for component in normal(&triangle) {
+                out.write_all(&component.to_le_bytes())?;
+            }
+            for vertex in &triangle {
+                for component in vertex {
+                    out.write_all(&component.to_le_bytes())?;
+                }
+            }
+            out.write_all(&[0, 0])?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,290 @@
+// User-facing status lines, looked up by key so front ends can show them in
+// the viewer's language. Templates name their blanks, e.g. "Seed {seed}",
+// so a translation is free to reorder them.
+use crate::config::{invalid, unknown_key};
+use crate::toml::{self, Table, Value};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Message {
+    Generating,
+    // {iteration}
+    Iteration,
+    Complete,
+    // {seed}
+    Seed,
+    // The keys for each: {pick} {adjust} {zoom} {new_seed} {favorite}
+    // {copy} {image} {room} {help} {quit}
+    Controls,
+    // {seed} {path}
+    Saved,
+    // {error}
+    SaveFailed,
+    // {via}
+    CopiedMap,
+    CopiedSeed,
+    // {error}
+    CopyMapFailed,
+    CopySeedFailed,
+    // {name}, and the keys {reroll} {next}
+    RoomSelected,
+    // {name}
+    RoomRerolled,
+    RerollFailed,
+    // {path}, and the key {open}
+    Exported,
+    // {error}
+    ExportFailed,
+    // {path} {via}
+    Opened,
+    // {path} {error}
+    OpenFailed,
+}
+
+impl Message {
+    pub const ALL: [Message; 18] = [
+        Message::Generating,
+        Message::Iteration,
+        Message::Complete,
+        Message::Seed,
+        Message::Controls,
+        Message::Saved,
+        Message::SaveFailed,
+        Message::CopiedMap,
+        Message::CopiedSeed,
+        Message::CopyMapFailed,
+        Message::CopySeedFailed,
+        Message::RoomSelected,
+        Message::RoomRerolled,
+        Message::RerollFailed,
+        Message::Exported,
+        Message::ExportFailed,
+        Message::Opened,
+        Message::OpenFailed,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Message::Generating => "generating",
+            Message::Iteration => "iteration",
+            Message::Complete => "complete",
+            Message::Seed => "seed",
+            Message::Controls => "controls",
+            Message::Saved => "saved",
+            Message::SaveFailed => "save_failed",
+            Message::CopiedMap => "copied_map",
+            Message::CopiedSeed => "copied_seed",
+            Message::CopyMapFailed => "copy_map_failed",
+            Message::CopySeedFailed => "copy_seed_failed",
+            Message::RoomSelected => "room_selected",
+            Message::RoomRerolled => "room_rerolled",
+            Message::RerollFailed => "reroll_failed",
+            Message::Exported => "exported",
+            Message::ExportFailed => "export_failed",
+            Message::Opened => "opened",
+            Message::OpenFailed => "open_failed",
+        }
+    }
+
+    fn parse(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|message| message.key() == key)
+    }
+}
+
+// Templates in Message::ALL order
+type Templates = [&'static str; 18];
+
+const EN: Templates = [
+    "Generating dungeon...",
+    "Iteration: {iteration}",
+    "Dungeon complete!",
+    "Seed {seed}",
+    "{pick} pick  {adjust} adjust  {zoom} zoom  {new_seed} new seed  {favorite} favorite  {copy} copy  \
+     {image} image  {room} room  {help} keys  {quit} quit",
+    "Saved seed {seed} to {path}",
+    "Couldn't save favorite: {error}",
+    "Copied map via {via}",
+    "Copied seed and config via {via}",
+    "Couldn't copy map: {error}",
+    "Couldn't copy seed and config: {error}",
+    "{name}  {reroll} reroll  {next} next",
+    "Rerolled {name}",
+    "Couldn't reroll {name} without cutting off its ways in",
+    "Exported image to {path}  {open} open",
+    "Couldn't export image: {error}",
+    "Opened {path} with {via}",
+    "Couldn't open {path}: {error}",
+];
+
+const DE: Templates = [
+    "Dungeon wird erzeugt...",
+    "Durchlauf: {iteration}",
+    "Dungeon fertig!",
+    "Seed {seed}",
+    "{pick} wählen  {adjust} ändern  {zoom} Zoom  {new_seed} neu  {favorite} Favorit  {copy} kopieren  {image} Bild  \
+     {room} Raum  {help} Tasten  {quit} beenden",
+    "Seed {seed} in {path} gespeichert",
+    "Favorit konnte nicht gespeichert werden: {error}",
+    "Karte über {via} kopiert",
+    "Seed und Konfiguration über {via} kopiert",
+    "Karte konnte nicht kopiert werden: {error}",
+    "Seed und Konfiguration konnten nicht kopiert werden: {error}",
+    "{name}  {reroll} neu würfeln  {next} nächster",
+    "{name} neu gewürfelt",
+    "{name} lässt sich nicht neu würfeln, ohne seine Zugänge abzuschneiden",
+    "Bild nach {path} exportiert  {open} öffnen",
+    "Bild konnte nicht exportiert werden: {error}",
+    "{path} mit {via} geöffnet",
+    "{path} konnte nicht geöffnet werden: {error}",
+];
+
+const ES: Templates = [
+    "Generando mazmorra...",
+    "Iteración: {iteration}",
+    "¡Mazmorra completa!",
+    "Semilla {seed}",
+    "{pick} elegir  {adjust} ajustar  {zoom} zoom  {new_seed} semilla  {favorite} favorito  {copy} copiar  \
+     {image} imagen  {room} sala  {help} teclas  {quit} salir",
+    "Semilla {seed} guardada en {path}",
+    "No se pudo guardar el favorito: {error}",
+    "Mapa copiado mediante {via}",
+    "Semilla y configuración copiadas mediante {via}",
+    "No se pudo copiar el mapa: {error}",
+    "No se pudo copiar la semilla y la configuración: {error}",
+    "{name}  {reroll} regenerar  {next} siguiente",
+    "Se regeneró {name}",
+    "No se pudo regenerar {name} sin cortar sus accesos",
+    "Imagen exportada a {path}  {open} abrir",
+    "No se pudo exportar la imagen: {error}",
+    "Se abrió {path} con {via}",
+    "No se pudo abrir {path}: {error}",
+];
+
+const FR: Templates = [
+    "Génération du donjon...",
+    "Itération : {iteration}",
+    "Donjon terminé !",
+    "Graine {seed}",
+    "{pick} choisir  {adjust} régler  {zoom} zoom  {new_seed} graine  {favorite} favori  {copy} copier  {image} image  \
+     {room} salle  {help} touches  {quit} quitter",
+    "Graine {seed} enregistrée dans {path}",
+    "Impossible d'enregistrer le favori : {error}",
+    "Carte copiée via {via}",
+    "Graine et configuration copiées via {via}",
+    "Impossible de copier la carte : {error}",
+    "Impossible de copier la graine et la configuration : {error}",
+    "{name}  {reroll} régénérer  {next} suivante",
+    "Nouveau tirage pour {name}",
+    "Impossible de régénérer {name} sans couper ses accès",
+    "Image exportée vers {path}  {open} ouvrir",
+    "Impossible d'exporter l'image : {error}",
+    "{path} ouvert avec {via}",
+    "Impossible d'ouvrir {path} : {error}",
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Catalog {
+    pub locale: String,
+    templates: Vec<String>,
+}
+
+impl Default for Catalog {
+    fn default() -> Self {
+        Self::builtin("en").unwrap()
+    }
+}
+
+impl Catalog {
+    pub const BUILTIN: [&'static str; 4] = ["en", "de", "es", "fr"];
+
+    pub fn builtin(locale: &str) -> Option<Self> {
+        let templates = match locale {
+            "en" => EN,
+            "de" => DE,
+            "es" => ES,
+            "fr" => FR,
+            _ => return None,
+        };
+        Some(Self {
+            locale: locale.to_string(),
+            templates: templates.iter().map(|template| template.to_string()).collect(),
+        })
+    }
+
+    // A built-in locale or a .toml catalog, like --theme
+    pub fn resolve(locale_or_path: &str) -> io::Result<Self> {
+        match Self::builtin(locale_or_path) {
+            Some(catalog) => Ok(catalog),
+            None if locale_or_path.ends_with(".toml") => Self::load(Path::new(locale_or_path)),
+            None => Err(invalid(
+                "lang",
+                &format!("must be a .toml file or one of {}", Self::BUILTIN.join(", ")),
+            )),
+        }
+    }
+
+    // The language of the usual POSIX locale variables, e.g. "de" for
+    // LANG=de_DE.UTF-8, falling back to English
+    pub fn detect() -> Self {
+        let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+        let language = locale.split(['_', '.', '@', '-']).next().unwrap_or_default();
+        Self::builtin(language).unwrap_or_default()
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        toml::parse(&text)
+            .and_then(|table| Self::from_table(&table))
+            .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", path.display(), error)))
+    }
+
+    // `locale = "pt"` plus any of the message keys; a `base` locale fills in
+    // the rest, English by default
+    pub fn from_table(table: &Table) -> io::Result<Self> {
+        let text = |key: &str, value: &Value| {
+            value
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| invalid(key, "must be a string"))
+        };
+        let mut catalog = match table.get("base") {
+            Some(base) => Self::builtin(&text("base", base)?).ok_or_else(|| {
+                invalid("base", &format!("must be one of {}", Self::BUILTIN.join(", ")))
+            })?,
+            None => Self::default(),
+        };
+        catalog.locale = "custom".to_string();
+        for (key, value) in table {
+            match key.as_str() {
+                "base" => {}
+                "locale" => catalog.locale = text(key, value)?,
+                _ => {
+                    let message = Message::parse(key).ok_or_else(|| unknown_key("", key))?;
+                    catalog.templates[message as usize] = text(key, value)?;
+                }
+            }
+        }
+        Ok(catalog)
+    }
+
+    pub fn template(&self, message: Message) -> &str {
+        &self.templates[message as usize]
+    }
+
+    // The message with each {name} blank filled in; unknown blanks are left
+    // as they are
+    pub fn text(&self, message: Message, args: &[(&str, &dyn std::fmt::Display)]) -> String {
+        let mut out = self.template(message).to_string();
+        for (name, value) in args {
+            out = out.replace(&format!("{{{}}}", name), &value.to_string());
+        }
+        out
+    }
+}
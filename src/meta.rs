@@ -0,0 +1,157 @@
+use crate::dungeon::{Dungeon, Point};
+use std::collections::BTreeMap;
+
+// A single typed annotation value
+#[derive(Clone, Debug, PartialEq)]
+pub enum MetaValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl MetaValue {
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            MetaValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            MetaValue::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    // Ints widen to floats so numeric readers don't care how a value was stored
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            MetaValue::Float(value) => Some(*value),
+            MetaValue::Int(value) => Some(*value as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            MetaValue::Text(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl From<bool> for MetaValue {
+    fn from(value: bool) -> Self {
+        MetaValue::Bool(value)
+    }
+}
+
+impl From<i64> for MetaValue {
+    fn from(value: i64) -> Self {
+        MetaValue::Int(value)
+    }
+}
+
+impl From<f64> for MetaValue {
+    fn from(value: f64) -> Self {
+        MetaValue::Float(value)
+    }
+}
+
+impl From<&str> for MetaValue {
+    fn from(value: &str) -> Self {
+        MetaValue::Text(value.to_string())
+    }
+}
+
+impl From<String> for MetaValue {
+    fn from(value: String) -> Self {
+        MetaValue::Text(value)
+    }
+}
+
+// Key-value annotations attached to one tile. Keys are kept sorted so
+// iteration (and anything exported from it) is deterministic.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TileMeta {
+    entries: BTreeMap<String, MetaValue>,
+}
+
+impl TileMeta {
+    pub fn get(&self, key: &str) -> Option<&MetaValue> {
+        self.entries.get(key)
+    }
+
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<MetaValue>) -> Option<MetaValue> {
+        self.entries.insert(key.into(), value.into())
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<MetaValue> {
+        self.entries.remove(key)
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &MetaValue)> {
+        self.entries.iter().map(|(key, value)| (key.as_str(), value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl Dungeon {
+    pub fn meta(&self, x: usize, y: usize) -> Option<&TileMeta> {
+        self.meta.get(x, y)
+    }
+
+    // Creates an empty entry for the tile if it has none yet
+    pub fn meta_mut(&mut self, x: usize, y: usize) -> &mut TileMeta {
+        self.meta.entry(x, y)
+    }
+
+    pub fn set_meta(
+        &mut self,
+        x: usize,
+        y: usize,
+        key: impl Into<String>,
+        value: impl Into<MetaValue>,
+    ) -> Option<MetaValue> {
+        self.meta_mut(x, y).set(key, value)
+    }
+
+    pub fn remove_meta(&mut self, x: usize, y: usize, key: &str) -> Option<MetaValue> {
+        let meta = self.meta.get_mut(x, y)?;
+        let removed = meta.remove(key);
+        if meta.is_empty() {
+            self.meta.remove(x, y);
+        }
+        removed
+    }
+
+    pub fn clear_meta(&mut self, x: usize, y: usize) -> Option<TileMeta> {
+        self.meta.remove(x, y)
+    }
+
+    pub fn annotated_tiles(&self) -> impl Iterator<Item = (Point, &TileMeta)> {
+        self.meta
+            .iter()
+            .filter(|(_, meta)| !meta.is_empty())
+    }
+
+    // Every tile carrying `key`, with its value
+    pub fn find_meta<'a>(&'a self, key: &'a str) -> impl Iterator<Item = (Point, &'a MetaValue)> + 'a {
+        self.meta
+            .iter()
+            .filter_map(move |(point, meta)| meta.get(key).map(|value| (point, value)))
+    }
+}
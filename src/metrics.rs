@@ -0,0 +1,263 @@
+use crate::dungeon::{Dungeon, Point};
+use crate::grid::GridMap;
+use crate::layers::{Entity, Feature};
+use crate::pathfinding::{bfs_distances, entrance_and_exit, regions};
+use std::collections::VecDeque;
+
+// Widest bar a chart draws
+const BAR_WIDTH: usize = 40;
+
+// Layout measurements for judging a map at a glance
+#[derive(Clone, Debug, PartialEq)]
+pub struct Metrics {
+    pub width: usize,
+    pub height: usize,
+    // Walkable tiles
+    pub floor: usize,
+    // Connected regions, largest first
+    pub region_sizes: Vec<usize>,
+    // Tiles in each stretch of one-tile-wide passage
+    pub corridor_lengths: Vec<usize>,
+    pub dead_ends: usize,
+    pub entrance: Option<Point>,
+    pub exit: Option<Point>,
+    // Steps on the shortest walk between them
+    pub route_length: Option<usize>,
+    // Steps walked off that walk and back to fetch keys
+    pub backtracking: usize,
+    // Steps from the entrance to every tile that can be reached from it
+    pub entrance_distances: Vec<usize>,
+    pub doors: usize,
+    pub decorations: usize,
+    pub monsters: usize,
+    pub items: usize,
+    pub content_hash: u64,
+    // Shared by every rotation and reflection of the map
+    pub canonical_hash: u64,
+}
+
+// What the terrain alone says about a map, for any `GridMap`
+#[derive(Clone, Debug, PartialEq)]
+pub struct TerrainMetrics {
+    pub width: usize,
+    pub height: usize,
+    pub floor: usize,
+    // Connected regions, largest first
+    pub region_sizes: Vec<usize>,
+    pub corridor_lengths: Vec<usize>,
+    pub dead_ends: usize,
+}
+
+pub fn terrain_metrics<G: GridMap + ?Sized>(grid: &G) -> TerrainMetrics {
+    let mut region_sizes: Vec<usize> = regions(grid).iter().map(Vec::len).collect();
+    region_sizes.sort_unstable_by(|a, b| b.cmp(a));
+    TerrainMetrics {
+        width: grid.width(),
+        height: grid.height(),
+        floor: region_sizes.iter().sum(),
+        region_sizes,
+        corridor_lengths: corridor_lengths(grid),
+        dead_ends: dead_ends(grid),
+    }
+}
+
+impl Dungeon {
+    pub fn metrics(&self) -> Metrics {
+        let terrain = terrain_metrics(self);
+
+        let (entrance, exit) = entrance_and_exit(self).unzip();
+        let distances = entrance.map(|entrance| bfs_distances(self, entrance));
+        let entrance_distances: Vec<usize> = distances.iter().flatten().flatten().flatten().copied().collect();
+        let route_length = distances
+            .as_ref()
+            .zip(exit)
+            .and_then(|(distances, (x, y))| distances[y][x]);
+
+        let count = |matches: fn(&Entity) -> bool| self.entities.iter().filter(|(_, entity)| matches(entity)).count();
+        Metrics {
+            width: terrain.width,
+            height: terrain.height,
+            floor: terrain.floor,
+            corridor_lengths: terrain.corridor_lengths,
+            dead_ends: terrain.dead_ends,
+            entrance,
+            exit,
+            route_length,
+            backtracking: self.backtracking(),
+            entrance_distances,
+            doors: self.doors().count(),
+            decorations: self
+                .features
+                .iter()
+                .filter(|(_, feature)| matches!(feature, Feature::Decoration(_)))
+                .count(),
+            monsters: count(|entity| matches!(entity, Entity::Monster(_))),
+            items: count(|entity| matches!(entity, Entity::Item(_))),
+            region_sizes: terrain.region_sizes,
+            content_hash: self.content_hash(),
+            canonical_hash: self.canonical_hash(),
+        }
+    }
+}
+
+fn walkable_neighbors<G: GridMap + ?Sized>(grid: &G, (x, y): Point) -> [bool; 4] {
+    let open = |x: Option<usize>, y: Option<usize>| match (x, y) {
+        (Some(x), Some(y)) if x < grid.width() && y < grid.height() => grid.tile(x, y).is_walkable(),
+        _ => false,
+    };
+    [
+        open(x.checked_sub(1), Some(y)),
+        open(Some(x + 1), Some(y)),
+        open(Some(x), y.checked_sub(1)),
+        open(Some(x), Some(y + 1)),
+    ]
+}
+
+fn dead_ends<G: GridMap + ?Sized>(grid: &G) -> usize {
+    (0..grid.height())
+        .flat_map(|y| (0..grid.width()).map(move |x| (x, y)))
+        .filter(|&(x, y)| grid.tile(x, y).is_walkable())
+        .filter(|&point| walkable_neighbors(grid, point).iter().filter(|&&open| open).count() == 1)
+        .count()
+}
+
+// A corridor tile is walled in on both sides across the way it runs;
+// each 4-connected run of them is one corridor
+fn corridor_lengths<G: GridMap + ?Sized>(grid: &G) -> Vec<usize> {
+    let (width, height) = (grid.width(), grid.height());
+    let corridor = |point: Point| {
+        let [left, right, up, down] = walkable_neighbors(grid, point);
+        grid.tile(point.0, point.1).is_walkable() && ((!left && !right) || (!up && !down))
+    };
+    let mut seen = vec![vec![false; width]; height];
+    let mut lengths = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            if seen[y][x] || !corridor((x, y)) {
+                continue;
+            }
+            seen[y][x] = true;
+            let mut queue = VecDeque::from([(x, y)]);
+            let mut length = 0;
+            while let Some((cx, cy)) = queue.pop_front() {
+                length += 1;
+                let steps = [
+                    (cx > 0).then(|| (cx - 1, cy)),
+                    (cy > 0).then(|| (cx, cy - 1)),
+                    (cx + 1 < width).then_some((cx + 1, cy)),
+                    (cy + 1 < height).then_some((cx, cy + 1)),
+                ];
+                for (nx, ny) in steps.into_iter().flatten() {
+                    if !seen[ny][nx] && corridor((nx, ny)) {
+                        seen[ny][nx] = true;
+                        queue.push_back((nx, ny));
+                    }
+                }
+            }
+            lengths.push(length);
+        }
+    }
+    lengths
+}
+
+// Horizontal bar chart, one labelled row per bucket
+pub fn bar_chart(title: &str, buckets: &[(String, usize)]) -> String {
+    let mut out = format!("{}\n", title);
+    if buckets.is_empty() {
+        out.push_str("  (none)\n");
+        return out;
+    }
+    let label_width = buckets.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+    let most = buckets.iter().map(|&(_, count)| count).max().unwrap_or(0).max(1);
+    for (label, count) in buckets {
+        // Any non-empty bucket gets at least a sliver so it isn't lost
+        let bar = (count * BAR_WIDTH).div_ceil(most);
+        let bar = if bar == 0 { String::new() } else { format!("{} ", "#".repeat(bar)) };
+        out.push_str(&format!("  {:>width$} | {}{}\n", label, bar, count, width = label_width));
+    }
+    out
+}
+
+// Buckets of 1, 2-3, 4-7, ... for sizes spanning orders of magnitude,
+// starting from the one holding the smallest value
+pub fn log_buckets(values: &[usize]) -> Vec<(String, usize)> {
+    let (Some(&smallest), Some(&largest)) = (values.iter().min(), values.iter().max()) else {
+        return Vec::new();
+    };
+    let mut buckets = Vec::new();
+    let mut low = 1 << smallest.max(1).ilog2();
+    while low <= largest.max(1) {
+        let high = low * 2 - 1;
+        let label = if low == high { low.to_string() } else { format!("{}-{}", low, high) };
+        buckets.push((label, values.iter().filter(|&&value| (low..=high).contains(&value)).count()));
+        low *= 2;
+    }
+    buckets
+}
+
+// Up to `count` equal-width buckets from 0 to the largest value
+pub fn linear_buckets(values: &[usize], count: usize) -> Vec<(String, usize)> {
+    let Some(&largest) = values.iter().max() else {
+        return Vec::new();
+    };
+    let width = (largest + 1).div_ceil(count.max(1));
+    (0..=largest)
+        .step_by(width)
+        .map(|low| {
+            let high = low + width - 1;
+            let label = if width == 1 { low.to_string() } else { format!("{}-{}", low, high) };
+            (label, values.iter().filter(|&&value| (low..=high).contains(&value)).count())
+        })
+        .collect()
+}
+
+impl Metrics {
+    // The numbers plus a chart for each distribution, for a terminal
+    pub fn report(&self) -> String {
+        let tiles = (self.width * self.height).max(1);
+        let percent = |part: usize, whole: usize| part as f64 * 100.0 / whole.max(1) as f64;
+        let point = |point: Option<Point>| point.map_or("none".to_string(), |(x, y)| format!("({}, {})", x, y));
+
+        let mut out = format!("Map {}x{}\n", self.width, self.height);
+        out.push_str(&format!("  floor          {} tiles ({:.0}%)\n", self.floor, percent(self.floor, tiles)));
+        let largest = self.region_sizes.first().copied().unwrap_or(0);
+        out.push_str(&format!(
+            "  regions        {} (largest {} tiles, {:.0}% of the floor)\n",
+            self.region_sizes.len(),
+            largest,
+            percent(largest, self.floor)
+        ));
+        out.push_str(&format!(
+            "  corridors      {} ({} tiles)\n",
+            self.corridor_lengths.len(),
+            self.corridor_lengths.iter().sum::<usize>()
+        ));
+        out.push_str(&format!("  dead ends      {}\n", self.dead_ends));
+        out.push_str(&format!("  entrance       {}\n", point(self.entrance)));
+        out.push_str(&format!("  exit           {}\n", point(self.exit)));
+        out.push_str(&format!(
+            "  route          {}\n",
+            self.route_length.map_or("none".to_string(), |steps| format!("{} steps", steps))
+        ));
+        out.push_str(&format!("  backtracking   {} steps\n", self.backtracking));
+        out.push_str(&format!(
+            "  contents       {} doors, {} decorations, {} monsters, {} items\n",
+            self.doors, self.decorations, self.monsters, self.items
+        ));
+        out.push_str(&format!(
+            "  hash           {:016x} (canonical {:016x})\n",
+            self.content_hash, self.canonical_hash
+        ));
+
+        out.push('\n');
+        out.push_str(&bar_chart("Region sizes (tiles)", &log_buckets(&self.region_sizes)));
+        out.push('\n');
+        out.push_str(&bar_chart("Corridor lengths (tiles)", &log_buckets(&self.corridor_lengths)));
+        out.push('\n');
+        out.push_str(&bar_chart(
+            "Distance from entrance (steps)",
+            &linear_buckets(&self.entrance_distances, 10),
+        ));
+        out
+    }
+}
@@ -0,0 +1,58 @@
+// Files the explorer writes, shown as OSC 8 hyperlinks where the terminal
+// makes those clickable and handed to the desktop's opener on request, the
+// same way the clipboard goes through whatever copy tool is installed.
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+const OPENERS: [(&str, &[&str]); 4] = [
+    ("open", &[]),
+    ("xdg-open", &[]),
+    ("wslview", &[]),
+    // The empty argument is the window title, so a path with spaces isn't taken for one
+    ("cmd.exe", &["/c", "start", ""]),
+];
+
+fn absolute(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+// A file:// URL, with every byte outside the unreserved set and the path
+// separators percent-encoded
+pub fn file_url(path: &Path) -> String {
+    let mut url = String::from("file://");
+    let path = absolute(path).to_string_lossy().replace('\\', "/");
+    if !path.starts_with('/') {
+        url.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => url.push(byte as char),
+            _ => url.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    url
+}
+
+// The path as its own text, linked to the file
+pub fn link(path: &Path) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", file_url(path), path.display())
+}
+
+// Returns which opener took the file, for the status line
+pub fn open(path: &Path) -> io::Result<&'static str> {
+    let path = absolute(path);
+    for (program, args) in OPENERS {
+        let status = Command::new(program)
+            .args(args)
+            .arg(&path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+        if status.is_ok_and(|status| status.success()) {
+            return Ok(program);
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::NotFound, "no opener installed"))
+}
@@ -0,0 +1,75 @@
+// A place as it was built and as it stands now, for games that show both:
+// a ruin that flickers back to its old self, a ghost's memory of a hall.
+// `original` is the map as generated with the history pass off, `current`
+// the same map aged through the config's [history] eras afterwards, so
+// both hold the same spawns and differ only where the years have left a
+// mark. The diff layer holds the current glyph of every changed tile.
+use crate::config::Config;
+use crate::dungeon::{Dungeon, Point, Tile};
+use crate::generator::{self, SeededRng};
+use crate::history::HistoryOptions;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Clone)]
+pub struct Overlay {
+    pub original: Dungeon,
+    pub current: Dungeon,
+}
+
+// One tile the years changed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TileChange {
+    pub position: Point,
+    pub was: Tile,
+    pub now: Tile,
+}
+
+impl Overlay {
+    pub fn generate(config: &Config, seed: u64) -> Self {
+        let mut built = config.clone();
+        built.history = HistoryOptions::default();
+        let (original, _) = generator::generate_reported(&built, seed);
+        let mut current = original.clone();
+        // On a stream of its own, so the as-built map is the same whatever
+        // the history
+        current.age(&config.history, &mut SeededRng::new(seed).for_topic("history"));
+        Self { original, current }
+    }
+
+    // Every changed tile in reading order, doors bricked over among them
+    pub fn changes(&self) -> Vec<TileChange> {
+        let mut changes = Vec::new();
+        for y in 0..self.original.height {
+            for x in 0..self.original.width {
+                let (was, now) = (self.original.tiles[y][x], self.current.tiles[y][x]);
+                let door_gone = self.original.door(x, y).is_some() && self.current.door(x, y).is_none();
+                if was != now || door_gone {
+                    changes.push(TileChange { position: (x, y), was, now });
+                }
+            }
+        }
+        changes
+    }
+
+    // Current glyphs where the map changed and spaces elsewhere, as the
+    // feature and entity layers are drawn
+    pub fn diff_to_ascii(&self) -> String {
+        let mut changed = vec![vec![false; self.current.width]; self.current.height];
+        for change in self.changes() {
+            changed[change.position.1][change.position.0] = true;
+        }
+        self.current
+            .rows_to_ascii(|x, y| if changed[y][x] { self.current.tiles[y][x].glyph() } else { ' ' })
+    }
+
+    // Writes original.txt, current.txt, diff.txt and history.txt into `dir`
+    pub fn write(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join("original.txt"), self.original.to_ascii())?;
+        fs::write(dir.join("current.txt"), self.current.to_ascii())?;
+        fs::write(dir.join("diff.txt"), self.diff_to_ascii())?;
+        fs::write(dir.join("history.txt"), self.current.history_log())
+    }
+}
@@ -0,0 +1,100 @@
+// Tile kinds past the built-in five, registered by the program before it
+// builds or loads any map that uses them:
+//
+//     let lava = palette::register(TileKind {
+//         name: "lava",
+//         walkable: true,
+//         opaque: false,
+//         glyph: '≈',
+//         color: Color::Red,
+//         rgb: [200, 60, 20],
+//         cost: 8,
+//     })?;
+//     dungeon.set_tile(x, y, lava);
+//
+// Each comes back as a `Tile::Custom`, which everything from the automaton to
+// the pathfinder and the exporters handles by its properties: a solid one
+// counts as wall to the automaton and is dug through like rock, a walkable
+// one costs what it says to cross, and it draws and saves as its glyph. The
+// registry is shared by the whole program and kinds stay registered for
+// good, so a map's tiles always mean the same thing.
+use crate::dungeon::Tile;
+use crossterm::style::Color;
+use std::io;
+use std::sync::RwLock;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TileKind {
+    pub name: &'static str,
+    // The two are apart: a chasm stops walkers but not sight, a curtain
+    // stops sight but not walkers
+    pub walkable: bool,
+    // Blocks line of sight, as wall does
+    pub opaque: bool,
+    // For the terminal and for ASCII maps, which read it back
+    pub glyph: char,
+    pub color: Color,
+    // What image exporters paint it with
+    pub rgb: [u8; 3],
+    // Path cost of stepping onto it, when walkable
+    pub cost: usize,
+}
+
+// A registered kind's place in the palette; only `register` hands them out
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CustomTile(u8);
+
+// Tile codes are a byte in content hashes, after the built-in kinds
+const MAX_KINDS: usize = u8::MAX as usize + 1 - Tile::ALL.len();
+
+// Glyphs ASCII maps already give a meaning to
+const RESERVED_GLYPHS: &str = "#.~%:'+=S^&<>M$ ";
+
+static KINDS: RwLock<Vec<TileKind>> = RwLock::new(Vec::new());
+
+pub fn register(kind: TileKind) -> io::Result<Tile> {
+    let refuse = |message: String| Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+    if kind.walkable && kind.cost == 0 {
+        return refuse(format!("tile '{}' is walkable, so its cost must be at least 1", kind.name));
+    }
+    if RESERVED_GLYPHS.contains(kind.glyph) || kind.glyph.is_control() {
+        return refuse(format!("glyph '{}' already means something in ASCII maps", kind.glyph));
+    }
+    let mut kinds = KINDS.write().unwrap();
+    if Tile::ALL.iter().any(|tile| tile.name() == kind.name) || kinds.iter().any(|other| other.name == kind.name) {
+        return refuse(format!("a tile named '{}' already exists", kind.name));
+    }
+    if let Some(other) = kinds.iter().find(|other| other.glyph == kind.glyph) {
+        return refuse(format!("glyph '{}' is already taken by '{}'", kind.glyph, other.name));
+    }
+    if kinds.len() == MAX_KINDS {
+        return refuse(format!("no room for more than {} custom tiles", MAX_KINDS));
+    }
+    kinds.push(kind);
+    Ok(Tile::Custom(CustomTile((kinds.len() - 1) as u8)))
+}
+
+// Every registered kind, in the order they were registered
+pub fn kinds() -> Vec<(Tile, TileKind)> {
+    let kinds = KINDS.read().unwrap();
+    kinds.iter().enumerate().map(|(index, &kind)| (Tile::Custom(CustomTile(index as u8)), kind)).collect()
+}
+
+pub fn find(name: &str) -> Option<Tile> {
+    kinds().into_iter().find(|(_, kind)| kind.name == name).map(|(tile, _)| tile)
+}
+
+pub fn find_glyph(glyph: char) -> Option<Tile> {
+    kinds().into_iter().find(|(_, kind)| kind.glyph == glyph).map(|(tile, _)| tile)
+}
+
+impl CustomTile {
+    pub fn kind(self) -> TileKind {
+        KINDS.read().unwrap()[self.0 as usize]
+    }
+
+    // Its place after the built-in kinds
+    pub(crate) fn code(self) -> u8 {
+        Tile::ALL.len() as u8 + self.0
+    }
+}
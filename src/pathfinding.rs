@@ -0,0 +1,575 @@
+use crate::door::DoorState;
+use crate::dungeon::{Dungeon, Point, Tile};
+use crate::grid::GridMap;
+use crate::jps;
+use crate::layers::Entity;
+use crate::palette;
+use crate::rows::render_rows;
+use crate::scratch::{cleared, Scratch};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+// Cost of entering each walkable terrain type; walls are never entered
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MovementCosts {
+    pub floor: usize,
+    pub water: usize,
+    pub rubble: usize,
+}
+
+impl Default for MovementCosts {
+    fn default() -> Self {
+        Self {
+            floor: 1,
+            water: 3,
+            rubble: 5,
+        }
+    }
+}
+
+impl MovementCosts {
+    pub fn cost(&self, tile: Tile) -> Option<usize> {
+        match tile {
+            Tile::Wall => None,
+            Tile::Floor | Tile::Bridge => Some(self.floor),
+            Tile::Water => Some(self.water),
+            Tile::Rubble => Some(self.rubble),
+            Tile::Custom(custom) => {
+                let kind = custom.kind();
+                kind.walkable.then_some(kind.cost)
+            }
+        }
+    }
+
+    // The least a step can cost, registered walkable tiles included
+    fn cheapest(&self) -> usize {
+        palette::kinds()
+            .iter()
+            .filter(|(_, kind)| kind.walkable)
+            .map(|(_, kind)| kind.cost)
+            .fold(self.floor.min(self.water).min(self.rubble), usize::min)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum PathAlgorithm {
+    #[default]
+    AStar,
+    // Jump point search: much faster on big open maps, but only valid when
+    // every walkable tile costs the same. Falls back to A* otherwise.
+    JumpPoint,
+}
+
+// Terrain costs plus how doors affect routing. A door cost of None makes that
+// kind of door impassable; otherwise it's added on top of the terrain cost.
+#[derive(Clone, Copy, Debug)]
+pub struct PathOptions {
+    pub algorithm: PathAlgorithm,
+    pub costs: MovementCosts,
+    pub closed_door_cost: Option<usize>,
+    pub locked_door_cost: Option<usize>,
+    pub secret_door_cost: Option<usize>,
+}
+
+impl Default for PathOptions {
+    fn default() -> Self {
+        Self {
+            algorithm: PathAlgorithm::AStar,
+            costs: MovementCosts::default(),
+            closed_door_cost: Some(1),
+            locked_door_cost: None,
+            secret_door_cost: None,
+        }
+    }
+}
+
+impl PathOptions {
+    pub fn with_costs(costs: MovementCosts) -> Self {
+        Self {
+            costs,
+            ..Self::default()
+        }
+    }
+
+    // Cost of stepping onto a tile, None where it can't be entered
+    pub fn step_cost<G: GridMap + ?Sized>(&self, dungeon: &G, (x, y): Point) -> Option<usize> {
+        let terrain = self.costs.cost(dungeon.tile(x, y))?;
+        let extra = match dungeon.door_state(x, y) {
+            Some(DoorState::Open) | None => Some(0),
+            Some(DoorState::Closed) => self.closed_door_cost,
+            Some(DoorState::Locked) => self.locked_door_cost,
+            Some(DoorState::Secret) => self.secret_door_cost,
+        };
+        extra.map(|extra| terrain + extra)
+    }
+
+    // The single step cost shared by every walkable tile, if there is one
+    pub fn uniform_cost<G: GridMap + ?Sized>(&self, dungeon: &G) -> Option<usize> {
+        let costs = &self.costs;
+        let same = costs.floor == costs.water && costs.floor == costs.rubble;
+        for y in 0..dungeon.height() {
+            for x in 0..dungeon.width() {
+                let door = dungeon.door_state(x, y).is_some();
+                if door && self.step_cost(dungeon, (x, y)).is_some_and(|cost| cost != costs.floor) {
+                    return None;
+                }
+                let tile = dungeon.tile(x, y);
+                let custom = matches!(tile, Tile::Custom(_));
+                if (custom || !same) && tile.is_walkable() && costs.cost(tile) != Some(costs.floor) {
+                    return None;
+                }
+            }
+        }
+        Some(costs.floor)
+    }
+}
+
+fn neighbors<G: GridMap + ?Sized>(dungeon: &G, (x, y): Point) -> impl Iterator<Item = Point> + '_ {
+    const STEPS: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+    STEPS.iter().filter_map(move |&(dx, dy)| {
+        let nx = x as i32 + dx;
+        let ny = y as i32 + dy;
+        if nx < 0 || ny < 0 || nx >= dungeon.width() as i32 || ny >= dungeon.height() as i32 {
+            return None;
+        }
+        Some((nx as usize, ny as usize))
+    })
+}
+
+// Breadth-first step counts from `start` over walkable tiles; None where unreachable
+pub fn bfs_distances<G: GridMap + ?Sized>(dungeon: &G, start: Point) -> Vec<Vec<Option<usize>>> {
+    let options = PathOptions::default();
+    let mut distances = vec![vec![None; dungeon.width()]; dungeon.height()];
+    let mut queue = VecDeque::new();
+
+    distances[start.1][start.0] = Some(0);
+    queue.push_back(start);
+
+    while let Some(point) = queue.pop_front() {
+        let distance = distances[point.1][point.0].unwrap();
+        for (nx, ny) in neighbors(dungeon, point) {
+            if distances[ny][nx].is_none() && options.step_cost(dungeon, (nx, ny)).is_some() {
+                distances[ny][nx] = Some(distance + 1);
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+
+    distances
+}
+
+// Shortest 4-connected path from start to goal, both ends included
+pub fn find_path<G: GridMap + ?Sized>(dungeon: &G, start: Point, goal: Point) -> Option<Vec<Point>> {
+    find_path_with(dungeon, start, goal, &PathOptions::default())
+}
+
+pub fn find_path_with<G: GridMap + ?Sized>(
+    dungeon: &G,
+    start: Point,
+    goal: Point,
+    options: &PathOptions,
+) -> Option<Vec<Point>> {
+    options.step_cost(dungeon, goal)?;
+    if options.algorithm == PathAlgorithm::JumpPoint {
+        if let Some(step) = options.uniform_cost(dungeon) {
+            return jps::find_path(dungeon, start, goal, options, step);
+        }
+    }
+    a_star(dungeon, start, goal, options)
+}
+
+// A* over step costs, so slow terrain and closed doors are avoided whenever a
+// detour is cheaper
+fn a_star<G: GridMap + ?Sized>(dungeon: &G, start: Point, goal: Point, options: &PathOptions) -> Option<Vec<Point>> {
+    // Manhattan distance at the cheapest terrain cost never overestimates
+    let cheapest = options.costs.cheapest();
+    let heuristic = |(x, y): Point| (x.abs_diff(goal.0) + y.abs_diff(goal.1)) * cheapest;
+
+    let mut cost = vec![vec![usize::MAX; dungeon.width()]; dungeon.height()];
+    let mut came_from = vec![vec![None; dungeon.width()]; dungeon.height()];
+    let mut heap = BinaryHeap::new();
+
+    cost[start.1][start.0] = 0;
+    heap.push(Reverse((heuristic(start), 0, start)));
+
+    while let Some(Reverse((_, current_cost, point))) = heap.pop() {
+        if point == goal {
+            let mut path = vec![goal];
+            let mut current = goal;
+            while current != start {
+                current = came_from[current.1][current.0].unwrap();
+                path.push(current);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        if current_cost > cost[point.1][point.0] {
+            continue;
+        }
+        for next in neighbors(dungeon, point) {
+            let Some(step) = options.step_cost(dungeon, next) else {
+                continue;
+            };
+            let next_cost = current_cost + step;
+            if next_cost < cost[next.1][next.0] {
+                cost[next.1][next.0] = next_cost;
+                came_from[next.1][next.0] = Some(point);
+                heap.push(Reverse((next_cost + heuristic(next), next_cost, next)));
+            }
+        }
+    }
+
+    None
+}
+
+// Dijkstra: cheapest total cost from `start` to every tile; None where unreachable
+pub fn distance_map<G: GridMap + ?Sized>(dungeon: &G, start: Point, options: &PathOptions) -> Vec<Vec<Option<usize>>> {
+    let mut cost = vec![vec![None; dungeon.width()]; dungeon.height()];
+    let mut heap = BinaryHeap::new();
+
+    cost[start.1][start.0] = Some(0);
+    heap.push(Reverse((0, start)));
+
+    while let Some(Reverse((current_cost, point))) = heap.pop() {
+        if cost[point.1][point.0].is_some_and(|best| current_cost > best) {
+            continue;
+        }
+        for next in neighbors(dungeon, point) {
+            let Some(step) = options.step_cost(dungeon, next) else {
+                continue;
+            };
+            let next_cost = current_cost + step;
+            if cost[next.1][next.0].is_none_or(|best| next_cost < best) {
+                cost[next.1][next.0] = Some(next_cost);
+                heap.push(Reverse((next_cost, next)));
+            }
+        }
+    }
+
+    cost
+}
+
+// Per-tile cost of entering each tile, as engines want it for their own routing
+pub fn cost_map<G: GridMap + ?Sized>(dungeon: &G, options: &PathOptions) -> Vec<Vec<Option<usize>>> {
+    (0..dungeon.height())
+        .map(|y| (0..dungeon.width()).map(|x| options.step_cost(dungeon, (x, y))).collect())
+        .collect()
+}
+
+// CSV with one row per map row; impassable tiles are written as -1
+pub fn write_cost_map<G: GridMap + Sync + ?Sized>(dungeon: &G, options: &PathOptions, path: &Path) -> io::Result<()> {
+    let rows = render_rows(dungeon.height(), dungeon.width(), |y| {
+        let cells: Vec<String> = (0..dungeon.width())
+            .map(|x| options.step_cost(dungeon, (x, y)).map_or("-1".to_string(), |cost| cost.to_string()))
+            .collect();
+        cells.join(",")
+    });
+    let mut out = BufWriter::new(File::create(path)?);
+    for row in rows {
+        writeln!(out, "{}", row)?;
+    }
+    out.flush()
+}
+
+fn farthest(distances: &[Vec<Option<usize>>]) -> Option<(Point, usize)> {
+    let mut best = None;
+    for (y, row) in distances.iter().enumerate() {
+        for (x, distance) in row.iter().enumerate() {
+            if let Some(distance) = *distance {
+                if best.is_none_or(|(_, d)| distance > d) {
+                    best = Some(((x, y), distance));
+                }
+            }
+        }
+    }
+    best
+}
+
+// Connected walkable areas, listed in scan order with each region's tiles
+pub fn regions<G: GridMap + ?Sized>(dungeon: &G) -> Vec<Vec<Point>> {
+    regions_in(dungeon, &mut Scratch::new())
+}
+
+// The same, with the search grids and queue borrowed from `scratch`
+pub fn regions_in<G: GridMap + ?Sized>(dungeon: &G, scratch: &mut Scratch) -> Vec<Vec<Point>> {
+    let options = PathOptions::default();
+    let (width, height) = (dungeon.width(), dungeon.height());
+    // Worked out once, row by row, rather than at every step of every search
+    let passable = cleared(&mut scratch.passable, width * height, false);
+    for (y, row) in passable.chunks_mut(width.max(1)).enumerate().take(height) {
+        for (x, cell) in row.iter_mut().enumerate() {
+            *cell = options.step_cost(dungeon, (x, y)).is_some();
+        }
+    }
+    // Which search reached each tile, numbered from 1. Tiles that can't be
+    // entered, like locked doors, start no search and belong to no region,
+    // or the region would spill through them into the rooms either side.
+    let marks = cleared(&mut scratch.marks, width * height, 0);
+    let queue = &mut scratch.queue;
+    let mut regions = Vec::new();
+
+    for y in 0..height {
+        for x in 0..width {
+            if marks[y * width + x] != 0 || !passable[y * width + x] {
+                continue;
+            }
+            let mark = regions.len() + 1;
+            let mut region = vec![(x, y)];
+            marks[y * width + x] = mark;
+            queue.clear();
+            queue.push_back((x, y));
+            while let Some((x, y)) = queue.pop_front() {
+                let steps = [
+                    (y > 0).then(|| (x, y - 1)),
+                    (x + 1 < width).then_some((x + 1, y)),
+                    (y + 1 < height).then_some((x, y + 1)),
+                    (x > 0).then(|| (x - 1, y)),
+                ];
+                for (nx, ny) in steps.into_iter().flatten() {
+                    let index = ny * width + nx;
+                    if marks[index] == 0 && passable[index] {
+                        marks[index] = mark;
+                        region.push((nx, ny));
+                        queue.push_back((nx, ny));
+                    }
+                }
+            }
+            region.sort_unstable_by_key(|&(x, y)| (y, x));
+            regions.push(region);
+        }
+    }
+
+    regions
+}
+
+// Region index of every walkable tile, matching the order `regions` lists them in
+pub fn region_labels<G: GridMap + ?Sized>(dungeon: &G) -> Vec<Vec<Option<usize>>> {
+    let mut labels = vec![vec![None; dungeon.width()]; dungeon.height()];
+    for (index, region) in regions(dungeon).iter().enumerate() {
+        for &(x, y) in region {
+            labels[y][x] = Some(index);
+        }
+    }
+    labels
+}
+
+// `matrix[i][j]` says whether `to[j]` can be walked to from `from[i]`
+pub fn reachability<G: GridMap + ?Sized>(dungeon: &G, from: &[Point], to: &[Point]) -> Vec<Vec<bool>> {
+    let labels = region_labels(dungeon);
+    let label = |&(x, y): &Point| labels[y][x];
+    from.iter()
+        .map(|a| {
+            to.iter()
+                .map(|b| label(a).is_some() && label(a) == label(b))
+                .collect()
+        })
+        .collect()
+}
+
+// Two tiles of a connected region (as listed by `regions`) that are as many
+// steps apart as any pair in it, plus that distance. Exact, but bounds on
+// each tile's eccentricity let it get away with a handful of BFS passes
+// instead of one per tile. Tiles a pass can't reach keep the bounds they
+// had, so a region that isn't connected after all still gets an answer.
+pub fn farthest_pair_in<G: GridMap + ?Sized>(dungeon: &G, region: &[Point]) -> Option<(Point, Point, usize)> {
+    let &first = region.first()?;
+    let mut lower = vec![0; region.len()];
+    let mut upper = vec![usize::MAX; region.len()];
+    let mut candidates: Vec<usize> = (0..region.len()).collect();
+    let mut best = (first, first, 0);
+    let mut pick_upper = true;
+
+    while !candidates.is_empty() {
+        // Alternate between the tile that could still be the farthest out
+        // and the most central one, which tightens everyone's bounds quickest
+        let index = if pick_upper {
+            candidates.iter().copied().max_by_key(|&i| upper[i])
+        } else {
+            candidates.iter().copied().min_by_key(|&i| lower[i])
+        }?;
+        pick_upper = !pick_upper;
+
+        let distances = bfs_distances(dungeon, region[index]);
+        let (far, eccentricity) = farthest(&distances)?;
+        if eccentricity > best.2 {
+            best = (region[index], far, eccentricity);
+        }
+        for (i, &(x, y)) in region.iter().enumerate() {
+            let Some(distance) = distances[y][x] else {
+                continue;
+            };
+            lower[i] = lower[i].max(distance).max(eccentricity - distance);
+            upper[i] = upper[i].min(eccentricity + distance);
+        }
+        candidates.retain(|&i| upper[i] > best.2);
+    }
+
+    Some(best)
+}
+
+// Farthest pair over every region of the map
+pub fn farthest_pair<G: GridMap + ?Sized>(dungeon: &G) -> Option<(Point, Point, usize)> {
+    regions(dungeon)
+        .iter()
+        .filter_map(|region| farthest_pair_in(dungeon, region))
+        .max_by_key(|&(_, _, distance)| distance)
+}
+
+// Where an Entrance or Exit entity is marked on the map, that's its spot
+fn marked(dungeon: &Dungeon, kind: Entity) -> Option<Point> {
+    dungeon
+        .entities()
+        .iter()
+        .filter(|(_, entity)| **entity == kind)
+        .map(|(point, _)| point)
+        .min_by_key(|&(x, y)| (y, x))
+}
+
+// Marked entrance and exit entities if the map has both; otherwise they sit
+// at opposite ends of the largest cave's longest walk, with the entrance at
+// whichever end comes first in reading order.
+pub fn entrance_and_exit(dungeon: &Dungeon) -> Option<(Point, Point)> {
+    let entrance = marked(dungeon, Entity::Entrance);
+    let exit = marked(dungeon, Entity::Exit);
+    if let (Some(entrance), Some(exit)) = (entrance, exit) {
+        return Some((entrance, exit));
+    }
+
+    let mut largest: Option<Vec<Point>> = None;
+    for region in regions(dungeon) {
+        if largest.as_ref().is_none_or(|best| region.len() > best.len()) {
+            largest = Some(region);
+        }
+    }
+
+    let (a, b, _) = farthest_pair_in(dungeon, &largest?)?;
+    if (a.1, a.0) <= (b.1, b.0) {
+        Some((a, b))
+    } else {
+        Some((b, a))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::door::{Door, DoorMaterial, Facing};
+    use crate::fixtures;
+    use crate::palette::TileKind;
+    use crossterm::style::Color;
+
+    fn kind(name: &'static str, glyph: char, cost: usize) -> TileKind {
+        TileKind {
+            name,
+            walkable: true,
+            opaque: false,
+            glyph,
+            color: Color::Green,
+            rgb: [60, 140, 60],
+            cost,
+        }
+    }
+
+    fn path_cost(dungeon: &Dungeon, path: &[Point], options: &PathOptions) -> usize {
+        path[1..].iter().map(|&point| options.step_cost(dungeon, point).unwrap()).sum()
+    }
+
+    // Three rows of open floor, 9 wide, routed along the middle one
+    fn open_rows() -> Dungeon {
+        let mut dungeon = Dungeon::new(9, 3);
+        for y in 0..3 {
+            for x in 0..9 {
+                dungeon.set_tile(x, y, Tile::Floor);
+            }
+        }
+        dungeon
+    }
+
+    #[test]
+    fn custom_tile_costs_count_for_jump_points_and_the_heuristic() {
+        let costs = MovementCosts {
+            floor: 3,
+            water: 3,
+            rubble: 3,
+        };
+        let (start, goal) = ((0, 1), (8, 1));
+
+        // A dear tile in the way: the detour round it is cheaper, so jump
+        // points mustn't take the map for uniform
+        let mud = palette::register(kind("test mud", 'ɱ', 20)).unwrap();
+        let mut dungeon = open_rows();
+        dungeon.set_tile(4, 1, mud);
+        let options = PathOptions {
+            algorithm: PathAlgorithm::JumpPoint,
+            ..PathOptions::with_costs(costs)
+        };
+        assert_eq!(options.uniform_cost(&dungeon), None);
+        let path = find_path_with(&dungeon, start, goal, &options).unwrap();
+        assert_eq!(path_cost(&dungeon, &path, &options), 30);
+
+        // A cheap lane along the top: the heuristic has to allow for it
+        let moss = palette::register(kind("test moss", 'µ', 1)).unwrap();
+        let mut dungeon = open_rows();
+        for x in 0..9 {
+            dungeon.set_tile(x, 0, moss);
+        }
+        let options = PathOptions::with_costs(costs);
+        let path = find_path_with(&dungeon, start, goal, &options).unwrap();
+        assert_eq!(path_cost(&dungeon, &path, &options), 12);
+        assert_eq!(distance_map(&dungeon, start, &options)[goal.1][goal.0], Some(12));
+    }
+
+    #[test]
+    fn the_farthest_pair_is_the_longest_walk_in_the_region() {
+        // An L of floor: its ends are 5 + 4 steps apart going round the corner
+        let dungeon = Dungeon::from_ascii("########\n#......#\n#.######\n#.######\n#.######\n#.######\n########\n").unwrap();
+        let region = &regions(&dungeon)[0];
+        let (a, b, distance) = farthest_pair_in(&dungeon, region).unwrap();
+        assert_eq!(distance, 9);
+        assert_eq!([a.min(b), a.max(b)], [(1, 5), (6, 1)]);
+        assert_eq!(farthest_pair(&dungeon), Some((a, b, distance)));
+
+        // Over the whole map, the larger of two rooms wins; each room's
+        // corners are 3 + 4 steps apart
+        let pair = fixtures::disconnected_pair();
+        let (a, b, distance) = farthest_pair(&pair).unwrap();
+        assert_eq!(distance, 7);
+        assert!(a.0 < 5 && b.0 < 5 || a.0 > 9 && b.0 > 9, "{:?} and {:?} are in different rooms", a, b);
+
+        // Tiles out of reach in the list don't sink the query
+        let mut both = regions(&pair).concat();
+        both.sort_unstable_by_key(|&(x, y)| (y, x));
+        assert_eq!(farthest_pair_in(&pair, &both).map(|(_, _, distance)| distance), Some(7));
+        assert_eq!(farthest_pair_in(&pair, &[]), None);
+    }
+
+    #[test]
+    fn reachability_says_which_tiles_share_a_region() {
+        let dungeon = fixtures::disconnected_pair();
+        let from = [(1, 1), (13, 5)];
+        let to = [(4, 5), (10, 1), (7, 3)];
+        assert_eq!(reachability(&dungeon, &from, &to), [[true, false, false], [false, true, false]]);
+        assert_eq!(reachability(&dungeon, &[(7, 3)], &[(7, 3)]), [[false]]);
+    }
+
+    #[test]
+    fn a_locked_door_parts_two_rooms_into_two_regions() {
+        let mut dungeon = fixtures::two_rooms();
+        dungeon.add_door(7, 3, Door::new(DoorState::Locked, DoorMaterial::Iron, Facing::East));
+        let parts = regions(&dungeon);
+        assert_eq!(parts.len(), 2);
+        let (left, right) = (&parts[0], &parts[1]);
+        assert!(left.contains(&(2, 2)) && right.contains(&(12, 4)));
+        assert!(left.iter().all(|point| !right.contains(point)));
+        assert!(parts.iter().all(|region| !region.contains(&(7, 3))));
+        assert_eq!(region_labels(&dungeon)[3][7], None);
+        assert_eq!(reachability(&dungeon, &[(2, 2)], &[(3, 3), (12, 4)]), [[true, false]]);
+
+        // Closed, the door joins them again
+        dungeon.add_door(7, 3, Door::new(DoorState::Closed, DoorMaterial::Iron, Facing::East));
+        assert_eq!(regions(&dungeon).len(), 1);
+    }
+}
@@ -0,0 +1,107 @@
+// How much floor a cave settles to, and whether it holds together, depends
+// on the starting wall share, the rules and the number of steps together.
+// Caves join up into one once a little over half the settled map is floor
+// (the percolation threshold for the blobby shapes the automaton leaves), so
+// that is the density worth aiming at. Only the automaton's first step has a
+// closed form; later ones keep shifting the density by an amount that
+// depends on the rules, so the mapping is measured on a few small probe
+// grids instead. They share their random draws across probabilities, which
+// keeps the measured floor share monotone in the wall probability.
+use crate::config::Config;
+use crate::dungeon::{Dungeon, Rules};
+use crate::generator;
+use crate::pathfinding::regions_in;
+use crate::scratch::Scratch;
+
+// Settled floor share the percolation fill aims for by default: just past
+// the threshold, where the main cave reliably spans the map
+pub const DEFAULT_TARGET_FLOOR: f64 = 0.62;
+
+const PROBE_SEEDS: [u64; 4] = [0x5eed_0001, 0x5eed_0002, 0x5eed_0003, 0x5eed_0004];
+const PROBE_SIZE: usize = 96;
+
+// What a configuration's cave looks like once the automaton is done
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Settled {
+    pub floor: f64,
+    // Share of the floor belonging to the largest connected region
+    pub largest_region: f64,
+}
+
+fn probe(width: usize, height: usize, wall_probability: f64, rules: &Rules, iterations: usize) -> Settled {
+    let (width, height) = (width.clamp(1, PROBE_SIZE), height.clamp(1, PROBE_SIZE));
+    let mut floor = 0.0;
+    let mut largest_region = 0.0;
+    let mut scratch = Scratch::new();
+    for seed in PROBE_SEEDS {
+        let mut dungeon = Dungeon::new(width, height);
+        dungeon.initialize_random(wall_probability, &mut generator::rng_for(seed));
+        for _ in 0..iterations {
+            if !dungeon.simulate_step_in(rules, &mut scratch) {
+                break;
+            }
+        }
+        let open = dungeon.tiles.iter().flatten().filter(|tile| tile.is_walkable()).count();
+        let largest = regions_in(&dungeon, &mut scratch).iter().map(Vec::len).max().unwrap_or(0);
+        floor += open as f64 / (width * height) as f64;
+        largest_region += if open == 0 { 0.0 } else { largest as f64 / open as f64 };
+    }
+    Settled {
+        floor: floor / PROBE_SEEDS.len() as f64,
+        largest_region: largest_region / PROBE_SEEDS.len() as f64,
+    }
+}
+
+// The cave `config` settles into, ignoring pins and post-passes
+pub fn settled(config: &Config) -> Settled {
+    probe(config.width, config.height, config.wall_probability, &config.rules, config.iterations)
+}
+
+// The wall probability whose cave settles closest to `target_floor` floor
+pub fn wall_probability_for(config: &Config, target_floor: f64) -> f64 {
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..16 {
+        let middle = (low + high) / 2.0;
+        let floor = probe(config.width, config.height, middle, &config.rules, config.iterations).floor;
+        if floor > target_floor {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    (low + high) / 2.0
+}
+
+// Plain-language warnings for settings that will almost certainly give
+// a map split into pockets or one featureless hall
+pub fn warnings(config: &Config) -> Vec<String> {
+    let settled = settled(config);
+    let mut warnings = Vec::new();
+    if settled.floor < 0.05 {
+        warnings.push(format!(
+            "wall_probability {:.2} will settle into almost solid rock ({:.0}% floor)",
+            config.wall_probability,
+            settled.floor * 100.0
+        ));
+    } else if settled.largest_region < 0.4 {
+        warnings.push(format!(
+            "wall_probability {:.2} will almost certainly split the map into disconnected pockets (largest cave {:.0}% of the floor)",
+            config.wall_probability,
+            settled.largest_region * 100.0
+        ));
+    } else if settled.floor > 0.9 {
+        warnings.push(format!(
+            "wall_probability {:.2} will leave the map almost entirely open ({:.0}% floor)",
+            config.wall_probability,
+            settled.floor * 100.0
+        ));
+    } else {
+        return warnings;
+    }
+    warnings.push(format!(
+        "wall_probability {:.2} would settle near {:.0}% floor, just past the point where caves join up; or set fill = \"percolation\"",
+        wall_probability_for(config, DEFAULT_TARGET_FLOOR),
+        DEFAULT_TARGET_FLOOR * 100.0
+    ));
+    warnings
+}
@@ -0,0 +1,94 @@
+use crate::dungeon::{Dungeon, Tile};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+const AROUND: [(i32, i32); 8] = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
+
+impl Dungeon {
+    // Chebyshev distance from each tile to the nearest wall, with everything
+    // beyond the map edge counting as wall
+    fn wall_clearance(&self) -> Vec<Vec<usize>> {
+        let mut queue = VecDeque::new();
+        let mut clearance: Vec<Vec<usize>> = (0..self.height)
+            .map(|y| {
+                (0..self.width)
+                    .map(|x| {
+                        let edge = x == 0 || y == 0 || x + 1 == self.width || y + 1 == self.height;
+                        if self.tiles[y][x] == Tile::Wall {
+                            queue.push_back((x, y));
+                            0
+                        } else if edge {
+                            queue.push_back((x, y));
+                            1
+                        } else {
+                            usize::MAX
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        self.spread_clearance(&mut clearance, queue);
+        clearance
+    }
+
+    fn spread_clearance(&self, clearance: &mut [Vec<usize>], mut queue: VecDeque<(usize, usize)>) {
+        while let Some((x, y)) = queue.pop_front() {
+            let next = clearance[y][x] + 1;
+            for (dx, dy) in AROUND {
+                let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                if nx < 0 || ny < 0 || nx >= self.width as i32 || ny >= self.height as i32 {
+                    continue;
+                }
+                let (nx, ny) = (nx as usize, ny as usize);
+                if next < clearance[ny][nx] {
+                    clearance[ny][nx] = next;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+    }
+
+    // Raises single-tile wall pillars until no open square wider than
+    // `max_span` tiles is left without support. Each pillar goes at the point
+    // farthest from any wall, which spreads them out evenly across a room.
+    // Pillars never touch another wall, so they can't cut a cave in two.
+    // Returns how many were placed.
+    pub fn place_pillars(&mut self, max_span: usize) -> usize {
+        if max_span == 0 {
+            return 0;
+        }
+        // A tile `d` steps from the nearest wall sits in an open square
+        // 2d - 1 tiles across
+        let unsupported = |distance: usize| 2 * distance > max_span + 1;
+        let mut clearance = self.wall_clearance();
+        let mut heap = BinaryHeap::new();
+        for (y, row) in clearance.iter().enumerate() {
+            for (x, &distance) in row.iter().enumerate() {
+                if unsupported(distance) {
+                    heap.push((distance, Reverse((y, x))));
+                }
+            }
+        }
+
+        // Clearance only ever shrinks, so stale heap entries are requeued at
+        // their current value rather than tracked down
+        let mut placed = 0;
+        while let Some((distance, Reverse((y, x)))) = heap.pop() {
+            let current = clearance[y][x];
+            if current != distance {
+                if unsupported(current) {
+                    heap.push((current, Reverse((y, x))));
+                }
+                continue;
+            }
+            if self.entities.contains(x, y) || self.frozen[y][x] {
+                continue;
+            }
+            self.set_tile(x, y, Tile::Wall);
+            clearance[y][x] = 0;
+            self.spread_clearance(&mut clearance, VecDeque::from([(x, y)]));
+            placed += 1;
+        }
+        placed
+    }
+}
@@ -0,0 +1,276 @@
+// Hand-placed constraints for scripted setpieces. The generator stamps them
+// over the noise before the automaton runs, holds them through every step
+// and stamps them again once the post-passes are done, so they always hold
+// in the final map. Open pins are then tunnelled into the main cave.
+use crate::config::{invalid, read_usize, unknown_key};
+use crate::dungeon::{Dungeon, Point, Tile};
+use crate::pathfinding::regions_in;
+use crate::scratch::{cleared, Scratch};
+use crate::toml::{Table, Value};
+use std::io;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pin {
+    // A rectangle of tiles kept open
+    Floor { x: usize, y: usize, width: usize, height: usize },
+    // A rectangle of tiles kept solid
+    Wall { x: usize, y: usize, width: usize, height: usize },
+    // A round chamber centred on a tile
+    Room { x: usize, y: usize, radius: usize },
+}
+
+impl Pin {
+    // What the pin forces on the tile, if anything
+    pub fn tile_at(&self, tx: usize, ty: usize) -> Option<Tile> {
+        match *self {
+            Pin::Floor { x, y, width, height } => {
+                ((x..x + width).contains(&tx) && (y..y + height).contains(&ty)).then_some(Tile::Floor)
+            }
+            Pin::Wall { x, y, width, height } => {
+                ((x..x + width).contains(&tx) && (y..y + height).contains(&ty)).then_some(Tile::Wall)
+            }
+            Pin::Room { x, y, radius } => {
+                let (dx, dy) = (tx.abs_diff(x), ty.abs_diff(y));
+                (dx * dx + dy * dy <= radius * radius + radius).then_some(Tile::Floor)
+            }
+        }
+    }
+
+    // Top-left and bottom-right corners of the tiles the pin can touch
+    fn bounds(&self) -> (Point, Point) {
+        match *self {
+            Pin::Floor { x, y, width, height } | Pin::Wall { x, y, width, height } => {
+                ((x, y), ((x + width).saturating_sub(1), (y + height).saturating_sub(1)))
+            }
+            Pin::Room { x, y, radius } => ((x.saturating_sub(radius), y.saturating_sub(radius)), (x + radius, y + radius)),
+        }
+    }
+
+    // One [[pin]] table
+    pub fn from_table(table: &Table) -> io::Result<Self> {
+        let kind = table
+            .get("kind")
+            .and_then(Value::as_str)
+            .ok_or_else(|| invalid("pin.kind", "must be \"floor\", \"wall\" or \"room\""))?;
+        let allowed: &[&str] = match kind {
+            "floor" | "wall" => &["kind", "x", "y", "width", "height"],
+            "room" => &["kind", "x", "y", "radius"],
+            _ => return Err(invalid("pin.kind", "must be \"floor\", \"wall\" or \"room\"")),
+        };
+        if let Some(key) = table.keys().find(|key| !allowed.contains(&key.as_str())) {
+            return Err(unknown_key("pin.", key));
+        }
+        let number = |key: &str| {
+            table
+                .get(key)
+                .ok_or_else(|| invalid(&format!("pin.{}", key), "is missing"))
+                .and_then(|value| read_usize(key, value))
+        };
+        Ok(match kind {
+            "room" => Pin::Room {
+                x: number("x")?,
+                y: number("y")?,
+                radius: number("radius")?,
+            },
+            _ => {
+                let (x, y, width, height) = (number("x")?, number("y")?, number("width")?, number("height")?);
+                if kind == "floor" {
+                    Pin::Floor { x, y, width, height }
+                } else {
+                    Pin::Wall { x, y, width, height }
+                }
+            }
+        })
+    }
+
+    pub fn to_toml(&self) -> String {
+        match *self {
+            Pin::Floor { x, y, width, height } => {
+                format!("kind = \"floor\"\nx = {}\ny = {}\nwidth = {}\nheight = {}\n", x, y, width, height)
+            }
+            Pin::Wall { x, y, width, height } => {
+                format!("kind = \"wall\"\nx = {}\ny = {}\nwidth = {}\nheight = {}\n", x, y, width, height)
+            }
+            Pin::Room { x, y, radius } => format!("kind = \"room\"\nx = {}\ny = {}\nradius = {}\n", x, y, radius),
+        }
+    }
+}
+
+// Later pins win where pins overlap
+pub(crate) fn pinned(pins: &[Pin], x: usize, y: usize) -> Option<Tile> {
+    pins.iter().rev().find_map(|pin| pin.tile_at(x, y))
+}
+
+impl Dungeon {
+    // Forces every pinned tile, clipped to the map
+    pub fn stamp_pins(&mut self, pins: &[Pin]) {
+        for pin in pins {
+            let ((left, top), (right, bottom)) = pin.bounds();
+            for y in top..=bottom.min(self.height.saturating_sub(1)) {
+                for x in left..=right.min(self.width.saturating_sub(1)) {
+                    if self.frozen[y][x] {
+                        continue;
+                    }
+                    if let Some(tile) = pinned(pins, x, y) {
+                        // Water and rubble already count as open floor
+                        if tile == Tile::Wall || self.tiles[y][x] == Tile::Wall {
+                            self.set_tile(x, y, tile);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Digs the cheapest tunnel from each open pin that ended up cut off to
+    // the largest cave, never through a wall pin. Returns tiles carved.
+    pub fn connect_pins(&mut self, pins: &[Pin]) -> usize {
+        self.connect_pins_in(pins, &mut Scratch::new())
+    }
+
+    pub fn connect_pins_in(&mut self, pins: &[Pin], scratch: &mut Scratch) -> usize {
+        let mut carved = 0;
+        for pin in pins {
+            let ((left, top), (right, bottom)) = pin.bounds();
+            let mut sources = Vec::new();
+            for y in top..=bottom.min(self.height.saturating_sub(1)) {
+                for x in left..=right.min(self.width.saturating_sub(1)) {
+                    if pin.tile_at(x, y) == Some(Tile::Floor) && self.tiles[y][x].is_walkable() {
+                        sources.push((x, y));
+                    }
+                }
+            }
+            if let Some(tunnel) = self.tunnel_to_main(&sources, pins, scratch) {
+                for (x, y) in tunnel {
+                    if !self.tiles[y][x].is_walkable() {
+                        self.tiles[y][x] = Tile::Floor;
+                        carved += 1;
+                    }
+                }
+            }
+        }
+        carved
+    }
+
+    // 0-1 BFS where open tiles are free and walls cost one, from `sources`
+    // to the nearest tile of the largest region. None when the sources are
+    // already part of it or it can't be reached.
+    pub(crate) fn tunnel_to_main(&self, sources: &[Point], pins: &[Pin], scratch: &mut Scratch) -> Option<Vec<Point>> {
+        let width = self.width;
+        let regions = regions_in(self, scratch);
+        // As `region_labels` has them, later regions winning shared tiles
+        let labels = cleared(&mut scratch.labels, width * self.height, None);
+        for (index, region) in regions.iter().enumerate() {
+            for &(x, y) in region {
+                labels[y * width + x] = Some(index);
+            }
+        }
+        let mut sizes = vec![0; regions.len()];
+        for &label in labels.iter().flatten() {
+            sizes[label] += 1;
+        }
+        let (main, _) = sizes.iter().enumerate().max_by_key(|&(index, size)| (size, std::cmp::Reverse(index)))?;
+        if sources.is_empty() || sources.iter().any(|&(x, y)| labels[y * width + x] == Some(main)) {
+            return None;
+        }
+
+        let cost = cleared(&mut scratch.costs, width * self.height, usize::MAX);
+        let came_from = cleared(&mut scratch.came_from, width * self.height, None);
+        let queue = &mut scratch.queue;
+        queue.clear();
+        for &(x, y) in sources {
+            cost[y * width + x] = 0;
+            queue.push_back((x, y));
+        }
+        while let Some((x, y)) = queue.pop_front() {
+            if labels[y * width + x] == Some(main) {
+                let mut tunnel = vec![(x, y)];
+                let mut current = (x, y);
+                while let Some(previous) = came_from[current.1 * width + current.0] {
+                    tunnel.push(previous);
+                    current = previous;
+                }
+                return Some(tunnel);
+            }
+            let steps = [
+                (x > 0).then(|| (x - 1, y)),
+                (y > 0).then(|| (x, y - 1)),
+                (x + 1 < width).then_some((x + 1, y)),
+                (y + 1 < self.height).then_some((x, y + 1)),
+            ];
+            for (nx, ny) in steps.into_iter().flatten() {
+                let locked = self.frozen[ny][nx] && !self.tiles[ny][nx].is_walkable();
+                if locked || pinned(pins, nx, ny) == Some(Tile::Wall) {
+                    continue;
+                }
+                let step = usize::from(!self.tiles[ny][nx].is_walkable());
+                let next_cost = cost[y * width + x] + step;
+                if next_cost < cost[ny * width + nx] {
+                    cost[ny * width + nx] = next_cost;
+                    came_from[ny * width + nx] = Some((x, y));
+                    if step == 0 {
+                        queue.push_front((nx, ny));
+                    } else {
+                        queue.push_back((nx, ny));
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::connectivity::Connectivity;
+    use crate::erosion::ErosionOptions;
+    use crate::generator::generate_seeded;
+    use crate::roughness::RoughnessOptions;
+    use crate::terrain::TerrainOptions;
+
+    #[test]
+    fn pinned_tiles_hold_through_every_pass() {
+        let pins = vec![
+            Pin::Floor { x: 4, y: 4, width: 10, height: 3 },
+            Pin::Wall { x: 30, y: 8, width: 6, height: 6 },
+            Pin::Room { x: 60, y: 20, radius: 4 },
+            // Over the floor pin's corner, which it wins
+            Pin::Wall { x: 12, y: 5, width: 3, height: 3 },
+        ];
+        // Every pass that reshapes the map turned up
+        let config = Config {
+            pins: pins.clone(),
+            roughness: RoughnessOptions {
+                amount: 0.5,
+                amplitude: 2,
+            },
+            erosion: ErosionOptions {
+                iterations: 3,
+                strength: 0.5,
+            },
+            terrain: TerrainOptions {
+                water: 0.2,
+                rubble: 0.2,
+            },
+            doors: 10,
+            connectivity: Connectivity::Prune,
+            clearance: (2, 2),
+            ..Config::default()
+        };
+        for seed in 0..6 {
+            let dungeon = generate_seeded(&config, seed);
+            for y in 0..dungeon.height {
+                for x in 0..dungeon.width {
+                    let tile = dungeon.tiles[y][x];
+                    match pinned(&pins, x, y) {
+                        Some(Tile::Wall) => assert_eq!(tile, Tile::Wall, "({}, {}) with seed {}", x, y, seed),
+                        Some(_) => assert!(tile.is_walkable(), "({}, {}) is {:?} with seed {}", x, y, tile, seed),
+                        None => {}
+                    }
+                }
+            }
+        }
+    }
+}
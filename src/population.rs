@@ -0,0 +1,196 @@
+use crate::autoexplore::auto_explore;
+use crate::config::{invalid, read_fraction, read_table, read_usize, unknown_key};
+use crate::dungeon::{Dungeon, Point, Tile};
+use crate::generator::SeededRng;
+use crate::layers::{Entity, Feature};
+use crate::pathfinding::{entrance_and_exit, PathOptions};
+use crate::toml::{self, Table, Value};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::io;
+
+// Built-in tables for every preset, overridable from the config's [spawns]
+const BUILTIN: &str = include_str!("data/spawns.toml");
+
+// What gets scattered over the floor and how thickly. Each list pairs a
+// name with its relative weight.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SpawnTable {
+    pub decoration_density: f64,
+    pub monster_density: f64,
+    pub item_density: f64,
+    // Steps an explorer walks between fights on average, counted along an
+    // auto-explore from the entrance; 0 scatters monsters by density instead
+    pub steps_between_fights: usize,
+    // Traps go where the walks between rooms are busiest, a tile's chance
+    // growing with its traffic
+    pub trap_density: f64,
+    pub decorations: Vec<(String, usize)>,
+    pub monsters: Vec<(String, usize)>,
+    pub items: Vec<(String, usize)>,
+    pub traps: Vec<(String, usize)>,
+}
+
+impl SpawnTable {
+    pub fn builtin(name: &str) -> Option<Self> {
+        let tables = toml::parse(BUILTIN).expect("built-in spawn tables parse");
+        let mut table = Self::default();
+        let builtin = read_table(name, tables.get(name)?).expect("built-in spawn tables are tables");
+        table.apply(builtin).expect("built-in spawn tables are valid");
+        Some(table)
+    }
+
+    // Overrides the settings present in `table`; a list given there replaces
+    // the built-in one rather than merging with it
+    pub fn apply(&mut self, table: &Table) -> io::Result<()> {
+        for (key, value) in table {
+            match key.as_str() {
+                "decoration_density" => self.decoration_density = read_fraction(key, value)?,
+                "monster_density" => self.monster_density = read_fraction(key, value)?,
+                "item_density" => self.item_density = read_fraction(key, value)?,
+                "steps_between_fights" => self.steps_between_fights = read_usize(key, value)?,
+                "trap_density" => self.trap_density = read_fraction(key, value)?,
+                "decorations" => self.decorations = read_weights(key, value)?,
+                "monsters" => self.monsters = read_weights(key, value)?,
+                "items" => self.items = read_weights(key, value)?,
+                "traps" => self.traps = read_weights(key, value)?,
+                _ => return Err(unknown_key("spawns.", key)),
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn read_weights(key: &str, value: &Value) -> io::Result<Vec<(String, usize)>> {
+    let mut weights = Vec::new();
+    for (name, weight) in read_table(key, value)? {
+        let weight = read_usize(name, weight)?;
+        if weight == 0 {
+            return Err(invalid(name, "must have a weight of at least 1"));
+        }
+        weights.push((name.clone(), weight));
+    }
+    Ok(weights)
+}
+
+impl Dungeon {
+    // Scatters decorations, monsters, items and traps over open floor, each
+    // tile getting at most one thing. Entrance and exit tiles are left alone.
+    // Each room of `label_regions` draws from its own stream, seeded once
+    // from `rng`, and the tiles between rooms share one more, so what a room
+    // holds doesn't shift when another room changes.
+    pub fn populate<R: Rng + ?Sized>(&mut self, table: &SpawnTable, rng: &mut R) {
+        let (labels, rooms) = self.label_regions();
+        let seeds = SeededRng::new(rng.gen());
+        let mut streams: Vec<StdRng> = (0..rooms).map(|room| seeds.for_room(room)).collect();
+        streams.push(seeds.for_topic("between rooms"));
+        let mut open: Vec<Vec<Point>> = vec![Vec::new(); rooms + 1];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.tiles[y][x] == Tile::Floor
+                    && !self.features.contains(x, y)
+                    && !self.entities.contains(x, y)
+                    && !self.frozen[y][x]
+                {
+                    open[labels[y][x].unwrap_or(rooms)].push((x, y));
+                }
+            }
+        }
+        for (tiles, stream) in open.iter_mut().zip(&mut streams) {
+            tiles.shuffle(stream);
+        }
+        let floor = open.iter().map(Vec::len).sum::<usize>() as f64;
+
+        // A room's share, rounded up or down at random so that small rooms
+        // still get theirs on average
+        let mut take = |density: f64, weights: &[(String, usize)]| {
+            let mut taken = Vec::new();
+            if weights.is_empty() {
+                return taken;
+            }
+            for (tiles, stream) in open.iter_mut().zip(&mut streams) {
+                let count = (tiles.len() as f64 * density + stream.gen::<f64>()) as usize;
+                for point in tiles.drain(..count.min(tiles.len())) {
+                    let (name, _) = weights.choose_weighted(&mut *stream, |(_, weight)| *weight).unwrap();
+                    taken.push((point, name.clone()));
+                }
+            }
+            taken
+        };
+
+        for ((x, y), name) in take(table.decoration_density, &table.decorations) {
+            self.features.set(x, y, Feature::Decoration(name));
+        }
+        let paced = table.steps_between_fights > 0;
+        for ((x, y), name) in take(if paced { 0.0 } else { table.monster_density }, &table.monsters) {
+            self.entities.set(x, y, Entity::Monster(name));
+        }
+        for ((x, y), name) in take(table.item_density, &table.items) {
+            self.entities.set(x, y, Entity::Item(name));
+        }
+        let traps = if table.traps.is_empty() { 0 } else { (floor * table.trap_density) as usize };
+        if traps > 0 {
+            self.lay_traps(open, &mut streams, traps, &table.traps);
+        }
+        if paced {
+            self.pace_encounters(table, rng);
+        }
+    }
+
+    // Weighted draws without replacement among the open tiles, grouped as
+    // `populate` groups them, each keyed by a uniform draw from its group's
+    // stream raised to one over its traffic; tiles no walk crosses are never
+    // picked
+    fn lay_traps(&mut self, open: Vec<Vec<Point>>, streams: &mut [StdRng], count: usize, traps: &[(String, usize)]) {
+        let traffic = self.traffic();
+        let mut keyed: Vec<(f64, usize, Point)> = Vec::new();
+        for (group, (tiles, stream)) in open.into_iter().zip(streams.iter_mut()).enumerate() {
+            for (x, y) in tiles.into_iter().filter(|&(x, y)| traffic[y][x] > 0.0) {
+                keyed.push((stream.gen::<f64>().powf(1.0 / traffic[y][x]), group, (x, y)));
+            }
+        }
+        keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+        for (_, group, (x, y)) in keyed.into_iter().take(count) {
+            let (name, _) = traps.choose_weighted(&mut streams[group], |(_, weight)| *weight).unwrap();
+            self.features.set(x, y, Feature::Trap(name.clone()));
+        }
+    }
+
+    // Walks the map the way a player likely would, auto-exploring from the
+    // entrance, and puts a monster in the way each time a fight is due. The
+    // gaps vary by up to half the configured spacing either side, and only
+    // tiles the walk reaches for the first time get one, so every monster
+    // is met where the pacing expects.
+    fn pace_encounters<R: Rng + ?Sized>(&mut self, table: &SpawnTable, rng: &mut R) {
+        let Some((entrance, exit)) = entrance_and_exit(self) else {
+            return;
+        };
+        if table.monsters.is_empty() {
+            return;
+        }
+        let spacing = table.steps_between_fights;
+        let gap = |rng: &mut R| rng.gen_range(spacing - spacing / 2..=spacing + spacing / 2);
+        let mut due = gap(rng);
+        let walk = auto_explore(self, entrance, PACING_SIGHT, &PathOptions::default());
+        let mut visited = vec![false; self.width * self.height];
+        for (step, &(x, y)) in walk.route.iter().enumerate() {
+            let first_visit = !std::mem::replace(&mut visited[y * self.width + x], true);
+            let free = self.tiles[y][x] == Tile::Floor
+                && !self.features.contains(x, y)
+                && !self.entities.contains(x, y)
+                && !self.frozen[y][x]
+                && (x, y) != entrance
+                && (x, y) != exit;
+            if step < due || !first_visit || !free {
+                continue;
+            }
+            let (name, _) = table.monsters.choose_weighted(rng, |(_, weight)| *weight).unwrap();
+            self.entities.set(x, y, Entity::Monster(name.clone()));
+            due = step + gap(rng);
+        }
+    }
+}
+
+// How far the simulated explorer sees, as in the walkthrough
+const PACING_SIGHT: usize = 8;
@@ -0,0 +1,22 @@
+// What most programs need, for a single glob import:
+//
+//     use dungeon_generator::prelude::*;
+//
+//     let config = Preset::Crypt.config();
+//     let dungeon = generate_seeded(&config, 7);
+//     let route = find_path(&dungeon, (1, 1), (10, 4));
+//
+// Only stable items go in, and new ones only in a minor release, since a
+// name added here can clash with one a glob import already brings in.
+pub use crate::bundle::Bundle;
+pub use crate::config::Config;
+pub use crate::connectivity::Connectivity;
+pub use crate::dungeon::{Dungeon, Point, Tile};
+pub use crate::events::{Event, Observer};
+pub use crate::generator::{
+    generate, generate_reported, generate_seeded, generate_with, GenerationAlgorithm, Report, SeededRng,
+};
+pub use crate::layers::{Entity, Feature};
+pub use crate::pathfinding::{find_path, PathOptions};
+pub use crate::preset::Preset;
+pub use crate::theme::Theme;
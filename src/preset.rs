@@ -0,0 +1,168 @@
+use crate::config::Config;
+use crate::connectivity::Connectivity;
+use crate::dungeon::Rules;
+use crate::erosion::ErosionOptions;
+use crate::generator::GenerationAlgorithm;
+use crate::population::SpawnTable;
+use crate::rooms::RoomOptions;
+use crate::roughness::RoughnessOptions;
+use crate::terrain::TerrainOptions;
+use crate::theme::Theme;
+
+// Named starting points that tune the generator, its post-passes and the
+// theme together. Any setting can still be overridden afterwards.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Preset {
+    Cavern,
+    Crypt,
+    Mine,
+    Sewer,
+    Fortress,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 5] = [Preset::Cavern, Preset::Crypt, Preset::Mine, Preset::Sewer, Preset::Fortress];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::Cavern => "cavern",
+            Preset::Crypt => "crypt",
+            Preset::Mine => "mine",
+            Preset::Sewer => "sewer",
+            Preset::Fortress => "fortress",
+        }
+    }
+
+    // One line on what the preset is for, as shown by the demo
+    pub fn description(self) -> &'static str {
+        match self {
+            Preset::Cavern => "sprawling natural caves with craggy walls and the odd pool",
+            Preset::Crypt => "open halls held up by rows of pillars and sealed off by doors",
+            Preset::Mine => "cramped tunnels choked with rubble",
+            Preset::Sewer => "flooded, worn channels behind a few doors",
+            Preset::Fortress => "broad, well-kept halls wide enough for a column to march through",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|preset| preset.name() == name.to_ascii_lowercase())
+    }
+
+    pub fn config(self) -> Config {
+        let base = Config {
+            spawns: SpawnTable::builtin(self.name()).unwrap(),
+            theme: Theme::builtin(self.name()).unwrap(),
+            ..Config::default()
+        };
+        match self {
+            // Pockets off the main cave are filled in rather than dug to
+            Preset::Cavern => Config {
+                algorithm: GenerationAlgorithm::CellularAutomaton,
+                connectivity: Connectivity::Prune,
+                roughness: RoughnessOptions {
+                    amount: 0.3,
+                    amplitude: 2,
+                },
+                erosion: ErosionOptions {
+                    iterations: 1,
+                    ..ErosionOptions::default()
+                },
+                terrain: TerrainOptions {
+                    water: 0.04,
+                    rubble: 0.05,
+                },
+                ..base
+            },
+            Preset::Crypt => Config {
+                algorithm: GenerationAlgorithm::CellularAutomaton,
+                connectivity: Connectivity::Connect,
+                wall_probability: 0.42,
+                iterations: 12,
+                rules: Rules {
+                    birth_limit: 5,
+                    death_limit: 4,
+                },
+                pillar_span: 5,
+                doors: 6,
+                ..base
+            },
+            Preset::Mine => Config {
+                algorithm: GenerationAlgorithm::CellularAutomaton,
+                connectivity: Connectivity::Connect,
+                wall_probability: 0.42,
+                iterations: 4,
+                rules: Rules {
+                    birth_limit: 4,
+                    death_limit: 3,
+                },
+                roughness: RoughnessOptions {
+                    amount: 0.4,
+                    amplitude: 1,
+                },
+                terrain: TerrainOptions {
+                    water: 0.0,
+                    rubble: 0.35,
+                },
+                ..base
+            },
+            // Channels joined the shortest way round, with a few loops
+            Preset::Sewer => Config {
+                algorithm: GenerationAlgorithm::CellularAutomaton,
+                connectivity: Connectivity::Spanning,
+                loop_tunnels: 3,
+                wall_probability: 0.48,
+                erosion: ErosionOptions {
+                    iterations: 2,
+                    strength: 0.4,
+                },
+                terrain: TerrainOptions {
+                    water: 0.25,
+                    rubble: 0.02,
+                },
+                doors: 3,
+                ..base
+            },
+            // Built rather than grown: big rooms and corridors
+            Preset::Fortress => Config {
+                algorithm: GenerationAlgorithm::Rooms,
+                connectivity: Connectivity::Connect,
+                rooms: RoomOptions {
+                    count: 10,
+                    min_size: 6,
+                    max_size: 12,
+                    ..RoomOptions::default()
+                },
+                pillar_span: 7,
+                doors: 10,
+                clearance: (2, 2),
+                ..base
+            },
+        }
+    }
+
+    pub fn names() -> String {
+        let names: Vec<&str> = Self::ALL.iter().map(|preset| preset.name()).collect();
+        names.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate_seeded;
+    use crate::pathfinding::PathOptions;
+    use crate::solver;
+
+    #[test]
+    fn every_preset_makes_maps_that_can_be_walked_through() {
+        for preset in Preset::ALL {
+            let config = preset.config();
+            for seed in 1..6 {
+                let dungeon = generate_seeded(&config, seed);
+                let solution = solver::solve(&dungeon, &PathOptions::with_costs(config.costs));
+                assert!(solution.is_some(), "no way through the {} map with seed {}", preset.name(), seed);
+            }
+        }
+    }
+}
+
@@ -0,0 +1,369 @@
+use crate::door::{DoorState, Facing};
+use crate::{Dungeon, Tile};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+// PDF units are points (1/72 inch)
+const MARGIN: f64 = 36.0;
+const LABEL_BAND: f64 = 16.0;
+const LEGEND_BAND: f64 = 44.0;
+
+// PDF fill operators per terrain type; plain floor stays paper white
+const TERRAIN_FILLS: [(Tile, &str, &str); 4] = [
+    (Tile::Wall, "0.55 g", "Wall"),
+    (Tile::Water, "0.78 0.87 1 rg", "Water"),
+    (Tile::Rubble, "0.82 g", "Rubble"),
+    (Tile::Bridge, "0.85 0.74 0.6 rg", "Bridge"),
+];
+
+// Custom tiles print as the wall or floor they stand for, keeping the page
+// to the fills the legend explains
+fn printed(tile: Tile) -> Tile {
+    match tile {
+        Tile::Custom(_) if tile.is_walkable() => Tile::Floor,
+        Tile::Custom(_) => Tile::Wall,
+        tile => tile,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PaperSize {
+    Letter,
+    A4,
+}
+
+impl PaperSize {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "letter" => Some(PaperSize::Letter),
+            "a4" => Some(PaperSize::A4),
+            _ => None,
+        }
+    }
+
+    fn dimensions(self) -> (f64, f64) {
+        match self {
+            PaperSize::Letter => (612.0, 792.0),
+            PaperSize::A4 => (595.28, 841.89),
+        }
+    }
+}
+
+pub struct PrintOptions {
+    pub paper: PaperSize,
+    // Side length of one tile on paper, in points. 18pt is a quarter inch.
+    pub cell_size: f64,
+}
+
+impl Default for PrintOptions {
+    fn default() -> Self {
+        Self {
+            paper: PaperSize::Letter,
+            cell_size: 18.0,
+        }
+    }
+}
+
+// Spreadsheet-style column names: A..Z, AA..AZ, BA..
+pub fn column_label(mut index: usize) -> String {
+    let mut label = Vec::new();
+    loop {
+        label.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    label.reverse();
+    String::from_utf8(label).unwrap()
+}
+
+struct PageLayout {
+    page_width: f64,
+    page_height: f64,
+    cols_per_page: usize,
+    rows_per_page: usize,
+    pages_across: usize,
+    pages_down: usize,
+}
+
+impl PageLayout {
+    fn new(dungeon: &Dungeon, options: &PrintOptions) -> Self {
+        let (page_width, page_height) = options.paper.dimensions();
+        let usable_width = page_width - 2.0 * MARGIN - LABEL_BAND;
+        let usable_height = page_height - 2.0 * MARGIN - LABEL_BAND - LEGEND_BAND;
+
+        let cols_per_page = ((usable_width / options.cell_size) as usize).max(1);
+        let rows_per_page = ((usable_height / options.cell_size) as usize).max(1);
+
+        Self {
+            page_width,
+            page_height,
+            cols_per_page,
+            rows_per_page,
+            pages_across: dungeon.width.div_ceil(cols_per_page),
+            pages_down: dungeon.height.div_ceil(rows_per_page),
+        }
+    }
+
+    fn page_count(&self) -> usize {
+        self.pages_across * self.pages_down
+    }
+}
+
+pub fn write_pdf(dungeon: &Dungeon, options: &PrintOptions, path: &Path) -> io::Result<usize> {
+    let layout = PageLayout::new(dungeon, options);
+    let mut pages = Vec::new();
+
+    for page_y in 0..layout.pages_down {
+        for page_x in 0..layout.pages_across {
+            let number = pages.len() + 1;
+            pages.push(page_content(dungeon, options, &layout, page_x, page_y, number));
+        }
+    }
+
+    let mut out = BufWriter::new(File::create(path)?);
+    write_document(&mut out, &layout, &pages)?;
+    out.flush()?;
+    Ok(pages.len())
+}
+
+fn page_content(
+    dungeon: &Dungeon,
+    options: &PrintOptions,
+    layout: &PageLayout,
+    page_x: usize,
+    page_y: usize,
+    number: usize,
+) -> String {
+    let cell = options.cell_size;
+    let x0 = page_x * layout.cols_per_page;
+    let y0 = page_y * layout.rows_per_page;
+    let x1 = (x0 + layout.cols_per_page).min(dungeon.width);
+    let y1 = (y0 + layout.rows_per_page).min(dungeon.height);
+
+    // Top-left corner of the grid, converted to PDF's bottom-left origin
+    let left = MARGIN + LABEL_BAND;
+    let top = layout.page_height - MARGIN - LABEL_BAND;
+    let cell_x = |x: usize| left + (x - x0) as f64 * cell;
+    let cell_y = |y: usize| top - (y - y0 + 1) as f64 * cell;
+
+    let mut ops = String::new();
+
+    // Terrain fills, ink-light so pencil notes stay readable
+    for (tile, fill, _) in TERRAIN_FILLS {
+        ops.push_str(fill);
+        ops.push('\n');
+        for y in y0..y1 {
+            for x in x0..x1 {
+                if printed(dungeon.tiles[y][x]) == tile {
+                    ops.push_str(&format!("{:.2} {:.2} {:.2} {:.2} re f\n", cell_x(x), cell_y(y), cell, cell));
+                }
+            }
+        }
+    }
+
+    // Doors are drawn as a bar across the passage; secret doors get an S
+    ops.push_str("0.15 g\n");
+    for ((x, y), door) in dungeon.doors() {
+        if x < x0 || x >= x1 || y < y0 || y >= y1 {
+            continue;
+        }
+        if door.state == DoorState::Secret {
+            push_text(&mut ops, cell_x(x) + cell * 0.3, cell_y(y) + cell * 0.25, cell * 0.6, "S");
+            continue;
+        }
+        let (w, h) = match door.facing {
+            Facing::East | Facing::West => (cell * 0.25, cell * 0.8),
+            Facing::North | Facing::South => (cell * 0.8, cell * 0.25),
+        };
+        ops.push_str(&format!(
+            "{:.2} {:.2} {:.2} {:.2} re f\n",
+            cell_x(x) + (cell - w) / 2.0,
+            cell_y(y) + (cell - h) / 2.0,
+            w,
+            h
+        ));
+    }
+
+    // Grid lines
+    let grid_right = cell_x(x1 - 1) + cell;
+    let grid_bottom = cell_y(y1 - 1);
+    ops.push_str("0.3 G 0.4 w\n");
+    for x in x0..=x1 {
+        let px = left + (x - x0) as f64 * cell;
+        ops.push_str(&format!("{:.2} {:.2} m {:.2} {:.2} l S\n", px, top, px, grid_bottom));
+    }
+    for y in y0..=y1 {
+        let py = top - (y - y0) as f64 * cell;
+        ops.push_str(&format!("{:.2} {:.2} m {:.2} {:.2} l S\n", left, py, grid_right, py));
+    }
+
+    // Coordinate labels use global positions so neighbouring pages line up
+    let font_size = (cell * 0.5).clamp(5.0, 9.0);
+    ops.push_str("0 g\n");
+    for x in x0..x1 {
+        let label = column_label(x);
+        let width = text_width(&label, font_size);
+        push_text(&mut ops, cell_x(x) + (cell - width) / 2.0, top + 4.0, font_size, &label);
+    }
+    for y in y0..y1 {
+        let label = (y + 1).to_string();
+        let width = text_width(&label, font_size);
+        push_text(
+            &mut ops,
+            left - width - 3.0,
+            cell_y(y) + (cell - font_size) / 2.0 + 1.0,
+            font_size,
+            &label,
+        );
+    }
+
+    // Legend
+    let legend_top = MARGIN + LEGEND_BAND - 8.0;
+    let swatch = 10.0;
+    let mut legend_x = MARGIN;
+    let mut entries = vec![(Some("0.55 g"), "Wall"), (None, "Floor")];
+    for (tile, fill, label) in &TERRAIN_FILLS[1..] {
+        if dungeon.tiles.iter().flatten().any(|&other| printed(other) == *tile) {
+            entries.push((Some(*fill), *label));
+        }
+    }
+    for (fill, label) in entries {
+        if let Some(fill) = fill {
+            ops.push_str(fill);
+            ops.push('\n');
+            ops.push_str(&format!("{:.2} {:.2} {:.2} {:.2} re f\n", legend_x, legend_top - swatch, swatch, swatch));
+        }
+        ops.push_str("0.3 G 0.4 w\n");
+        ops.push_str(&format!("{:.2} {:.2} {:.2} {:.2} re S\n", legend_x, legend_top - swatch, swatch, swatch));
+        ops.push_str("0 g\n");
+        push_text(&mut ops, legend_x + swatch + 5.0, legend_top - swatch + 2.0, 9.0, label);
+        legend_x += 70.0;
+    }
+    if dungeon.doors().next().is_some() {
+        ops.push_str("0.3 G 0.4 w\n");
+        ops.push_str(&format!("{:.2} {:.2} {:.2} {:.2} re S\n", legend_x, legend_top - swatch, swatch, swatch));
+        ops.push_str("0.15 g\n");
+        ops.push_str(&format!(
+            "{:.2} {:.2} {:.2} {:.2} re f\n",
+            legend_x + swatch * 0.375,
+            legend_top - swatch * 0.9,
+            swatch * 0.25,
+            swatch * 0.8
+        ));
+        ops.push_str("0 g\n");
+        push_text(&mut ops, legend_x + swatch + 5.0, legend_top - swatch + 2.0, 9.0, "Door (S = secret)");
+        legend_x += 110.0;
+    }
+    push_text(
+        &mut ops,
+        legend_x,
+        legend_top - swatch + 2.0,
+        9.0,
+        &format!("Grid {}1 - {}{}", column_label(0), column_label(dungeon.width - 1), dungeon.height),
+    );
+    push_text(
+        &mut ops,
+        MARGIN,
+        MARGIN,
+        8.0,
+        &format!(
+            "Page {} of {}    Columns {}-{}, rows {}-{}",
+            number,
+            layout.page_count(),
+            column_label(x0),
+            column_label(x1 - 1),
+            y0 + 1,
+            y1
+        ),
+    );
+
+    ops
+}
+
+fn push_text(ops: &mut String, x: f64, y: f64, size: f64, text: &str) {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('(', "\\(")
+        .replace(')', "\\)");
+    ops.push_str(&format!("BT /F1 {:.1} Tf {:.2} {:.2} Td ({}) Tj ET\n", size, x, y, escaped));
+}
+
+// Rough Helvetica advance width; good enough for centering short labels
+fn text_width(text: &str, size: f64) -> f64 {
+    text.len() as f64 * size * 0.56
+}
+
+fn write_document<W: Write>(out: &mut W, layout: &PageLayout, pages: &[String]) -> io::Result<()> {
+    // Object numbering: 1 catalog, 2 page tree, 3 font, then (page, contents) pairs
+    let page_id = |index: usize| 4 + index * 2;
+    let mut offsets = Vec::new();
+
+    let header = "%PDF-1.4\n";
+    out.write_all(header.as_bytes())?;
+    let mut written = header.len();
+
+    let mut emit = |out: &mut W, body: String, offsets: &mut Vec<usize>| -> io::Result<()> {
+        offsets.push(written);
+        written += body.len();
+        out.write_all(body.as_bytes())
+    };
+
+    emit(out, "1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n".to_string(), &mut offsets)?;
+
+    let kids: Vec<String> = (0..pages.len()).map(|i| format!("{} 0 R", page_id(i))).collect();
+    emit(
+        out,
+        format!(
+            "2 0 obj\n<< /Type /Pages /Kids [{}] /Count {} >>\nendobj\n",
+            kids.join(" "),
+            pages.len()
+        ),
+        &mut offsets,
+    )?;
+    emit(
+        out,
+        "3 0 obj\n<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>\nendobj\n".to_string(),
+        &mut offsets,
+    )?;
+
+    for (index, content) in pages.iter().enumerate() {
+        let id = page_id(index);
+        emit(
+            out,
+            format!(
+                "{} 0 obj\n<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] \
+                 /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>\nendobj\n",
+                id,
+                layout.page_width,
+                layout.page_height,
+                id + 1
+            ),
+            &mut offsets,
+        )?;
+        emit(
+            out,
+            format!(
+                "{} 0 obj\n<< /Length {} >>\nstream\n{}endstream\nendobj\n",
+                id + 1,
+                content.len(),
+                content
+            ),
+            &mut offsets,
+        )?;
+    }
+
+    let xref_offset = written;
+    let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", offsets.len() + 1);
+    for offset in &offsets {
+        xref.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    xref.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        offsets.len() + 1,
+        xref_offset
+    ));
+    out.write_all(xref.as_bytes())
+}
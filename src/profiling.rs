@@ -0,0 +1,121 @@
+// Timing spans around each generation step and pass, for seeing where a
+// pipeline spends its time. They are only recorded with the `profiling`
+// feature; without it `span` compiles to nothing. Recorded spans can be
+// written out as a Chrome trace, which chrome://tracing, Perfetto and
+// speedscope all draw as a flame chart, or as the folded stacks that
+// flamegraph.pl and inferno read.
+#[cfg(feature = "profiling")]
+use std::cell::{Cell, RefCell};
+use std::time::Duration;
+#[cfg(feature = "profiling")]
+use std::time::Instant;
+
+// Closes its span when dropped
+#[must_use]
+pub struct Span {
+    #[cfg(feature = "profiling")]
+    started: Instant,
+}
+
+pub fn span(name: &'static str) -> Span {
+    #[cfg(not(feature = "profiling"))]
+    let _ = name;
+    #[cfg(feature = "profiling")]
+    {
+        EPOCH.with(|epoch| {
+            if epoch.get().is_none() {
+                epoch.set(Some(Instant::now()));
+            }
+        });
+        OPEN.with(|open| open.borrow_mut().push((name, Duration::ZERO)));
+    }
+    Span {
+        #[cfg(feature = "profiling")]
+        started: Instant::now(),
+    }
+}
+
+#[cfg(feature = "profiling")]
+impl Drop for Span {
+    fn drop(&mut self) {
+        let duration = self.started.elapsed();
+        let epoch = EPOCH.with(|epoch| epoch.get()).unwrap_or(self.started);
+        let record = OPEN.with(|open| {
+            let mut open = open.borrow_mut();
+            let (name, inner) = open.pop().expect("span closed twice");
+            let stack = open.iter().map(|&(name, _)| name).chain([name]).collect();
+            if let Some((_, parent_inner)) = open.last_mut() {
+                *parent_inner += duration;
+            }
+            Record {
+                stack,
+                start: self.started - epoch,
+                duration,
+                own: duration.saturating_sub(inner),
+            }
+        });
+        RECORDS.with(|records| records.borrow_mut().push(record));
+    }
+}
+
+#[cfg(feature = "profiling")]
+thread_local! {
+    // When this thread opened its first span, which traces count from
+    static EPOCH: Cell<Option<Instant>> = const { Cell::new(None) };
+    // Spans open right now, outermost first, with the time spent so far in
+    // spans inside each
+    static OPEN: RefCell<Vec<(&'static str, Duration)>> = const { RefCell::new(Vec::new()) };
+    static RECORDS: RefCell<Vec<Record>> = const { RefCell::new(Vec::new()) };
+}
+
+// One closed span. Spans finish innermost first, so a pass comes after the
+// steps inside it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+    // Its own name last, after those of the spans it was opened inside
+    pub stack: Vec<&'static str>,
+    // Since the thread's first span
+    pub start: Duration,
+    pub duration: Duration,
+    // Leaving out the spans inside it
+    pub own: Duration,
+}
+
+impl Record {
+    pub fn name(&self) -> &'static str {
+        self.stack[self.stack.len() - 1]
+    }
+}
+
+// Every span this thread has closed since the last call; always none
+// without the feature
+pub fn take() -> Vec<Record> {
+    #[cfg(feature = "profiling")]
+    return RECORDS.with(|records| std::mem::take(&mut *records.borrow_mut()));
+    #[cfg(not(feature = "profiling"))]
+    Vec::new()
+}
+
+// Chrome's trace event format, times in microseconds
+pub fn to_trace(records: &[Record]) -> String {
+    let events: Vec<String> = records
+        .iter()
+        .map(|record| {
+            format!(
+                "{{\"name\":{},\"ph\":\"X\",\"pid\":1,\"tid\":1,\"ts\":{:.3},\"dur\":{:.3}}}",
+                crate::json::quote(record.name()),
+                record.start.as_secs_f64() * 1e6,
+                record.duration.as_secs_f64() * 1e6
+            )
+        })
+        .collect();
+    format!("{{\"traceEvents\":[\n{}\n]}}\n", events.join(",\n"))
+}
+
+// One `outer;inner microseconds` line per span, of its own time
+pub fn to_folded(records: &[Record]) -> String {
+    records
+        .iter()
+        .map(|record| format!("{} {}\n", record.stack.join(";"), record.own.as_micros()))
+        .collect()
+}
@@ -0,0 +1,188 @@
+// A whole game's level set written down in one YAML file and built in one
+// go, a bundle per level:
+//
+//     seed: 2024
+//     levels:
+//       - preset: cavern
+//         width: 60
+//         height: 40
+//       - preset: crypt
+//         count: 3
+//         ramp:
+//           spawns: { monster_density: [0.008, 0.02] }
+//       - preset: fortress
+//         prefab: arena.txt
+//
+// Each entry takes the same settings as a config file, plus `count` for a
+// run of levels alike but for their seeds, `ramp` for settings that climb
+// from their first value to their second over the run, and `prefab` for a
+// saved map stamped into the middle of the level and frozen, so the level
+// grows around it and is tunnelled through to it if need be. Levels are numbered from 1 across the whole file, and
+// each draws its seed from the recipe's unless it sets its own.
+use crate::bundle::Bundle;
+use crate::config::{invalid, read_table, read_usize, unknown_key, Config};
+use crate::connectivity::Connectivity;
+use crate::contracts::{self, ContractError};
+use crate::dungeon::Dungeon;
+use crate::generator::{self, SeededRng};
+use crate::toml::{Table, Value};
+use crate::yaml;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug)]
+pub struct Recipe {
+    pub seed: Option<u64>,
+    pub levels: Vec<Level>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Level {
+    // From 1, in the order the recipe lists them
+    pub number: usize,
+    pub config: Config,
+    pub prefab: Option<PathBuf>,
+}
+
+impl Recipe {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let base = path.parent().unwrap_or(Path::new(""));
+        Self::parse(&text, base).map_err(|error| io::Error::new(error.kind(), format!("{}: {}", path.display(), error)))
+    }
+
+    // Prefab paths are taken from `base`, the recipe's own directory
+    pub fn parse(text: &str, base: &Path) -> io::Result<Self> {
+        let table = yaml::parse(text)?;
+        let mut recipe = Self {
+            seed: None,
+            levels: Vec::new(),
+        };
+        for (key, value) in &table {
+            match key.as_str() {
+                "seed" => {
+                    let seed = value
+                        .as_integer()
+                        .and_then(|seed| u64::try_from(seed).ok())
+                        .ok_or_else(|| invalid(key, "must be a non-negative integer"))?;
+                    recipe.seed = Some(seed);
+                }
+                "levels" => {
+                    let entries = value.as_array().ok_or_else(|| invalid(key, "must be a list"))?;
+                    for (index, entry) in entries.iter().enumerate() {
+                        let entry = read_table(&format!("levels[{}]", index), entry)?;
+                        recipe
+                            .add_levels(entry, base)
+                            .map_err(|error| io::Error::new(error.kind(), format!("levels[{}]: {}", index, error)))?;
+                    }
+                }
+                _ => return Err(unknown_key("", key)),
+            }
+        }
+        if recipe.levels.is_empty() {
+            return Err(invalid("levels", "must list at least one level"));
+        }
+        Ok(recipe)
+    }
+
+    fn add_levels(&mut self, entry: &Table, base: &Path) -> io::Result<()> {
+        let mut settings = entry.clone();
+        let count = settings.remove("count").map(|value| read_usize("count", &value)).transpose()?.unwrap_or(1);
+        if count == 0 {
+            return Err(invalid("count", "must be at least 1"));
+        }
+        let ramp = settings.remove("ramp");
+        let ramp = ramp.as_ref().map(|value| read_table("ramp", value)).transpose()?;
+        let prefab = match settings.remove("prefab") {
+            Some(value) => Some(base.join(value.as_str().ok_or_else(|| invalid("prefab", "must be a path"))?)),
+            None => None,
+        };
+        for step in 0..count {
+            let share = if count > 1 { step as f64 / (count - 1) as f64 } else { 0.0 };
+            let mut table = settings.clone();
+            if let Some(ramp) = ramp {
+                merge_ramp(&mut table, ramp, share, "ramp.")?;
+            }
+            let mut config = Config::default();
+            config.apply(&table)?;
+            self.levels.push(Level {
+                number: self.levels.len() + 1,
+                config,
+                prefab: prefab.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    // Builds every level into `dir` as level-1.dungeon, level-2.dungeon and
+    // so on; returns the files written
+    pub fn build(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::create_dir_all(dir)?;
+        let seed = self.seed.unwrap_or_else(generator::random_seed);
+        let mut written = Vec::new();
+        for level in &self.levels {
+            let seed = level
+                .config
+                .seed
+                .unwrap_or_else(|| SeededRng::new(seed).child(&format!("level {}", level.number)).seed());
+            let (dungeon, report) = match &level.prefab {
+                Some(path) => {
+                    let mut dungeon = Dungeon::new(level.config.width, level.config.height);
+                    dungeon.stamp_prefab(&Dungeon::load(path)?).map_err(|error| {
+                        io::Error::new(error.kind(), format!("level {}: {}", level.number, error))
+                    })?;
+                    // A prefab nothing leads to is no use, so it gets tunnelled to
+                    let mut config = level.config.clone();
+                    if config.connectivity == Connectivity::Off {
+                        config.connectivity = Connectivity::Connect;
+                    }
+                    let report = generator::regenerate(&mut dungeon, &config, seed);
+                    (dungeon, report)
+                }
+                None => generator::generate_reported(&level.config, seed),
+            };
+            let violations = contracts::violations(&level.config.contracts, &dungeon);
+            if !violations.is_empty() {
+                let error = io::Error::from(ContractError { seed, violations });
+                return Err(io::Error::new(error.kind(), format!("level {}: {}", level.number, error)));
+            }
+            let path = dir.join(format!("level-{}.dungeon", level.number));
+            Bundle::new(dungeon, &report, seed, &level.config).save(&path)?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+}
+
+// Puts each [from, to] pair of `ramp` into `table` as the value `share` of
+// the way between them, keeping integers whole; nested tables merge into
+// the settings' own
+fn merge_ramp(table: &mut Table, ramp: &Table, share: f64, prefix: &str) -> io::Result<()> {
+    for (key, value) in ramp {
+        let value = match value {
+            Value::Table(inner) => {
+                let mut nested = match table.remove(key) {
+                    Some(Value::Table(nested)) => nested,
+                    Some(_) => return Err(invalid(key, "is ramped as a table but set as a value")),
+                    None => Table::new(),
+                };
+                merge_ramp(&mut nested, inner, share, &format!("{}{}.", prefix, key))?;
+                Value::Table(nested)
+            }
+            Value::Array(pair) => match pair[..] {
+                [Value::Integer(from), Value::Integer(to)] => {
+                    Value::Integer(from + ((to - from) as f64 * share).round() as i64)
+                }
+                [ref from, ref to] => match (from.as_float(), to.as_float()) {
+                    (Some(from), Some(to)) => Value::Float(from + (to - from) * share),
+                    _ => return Err(invalid(&format!("{}{}", prefix, key), "must ramp between two numbers")),
+                },
+                _ => return Err(invalid(&format!("{}{}", prefix, key), "must be a [from, to] pair")),
+            },
+            _ => return Err(invalid(&format!("{}{}", prefix, key), "must be a [from, to] pair or a table of them")),
+        };
+        table.insert(key.clone(), value);
+    }
+    Ok(())
+}
@@ -0,0 +1,469 @@
+// Rooms and corridors: rooms dropped at random where they fit, each joined
+// to the one placed before it by an L-shaped corridor, so every room can be
+// walked to from every other. Each room's shape is drawn by weight, and
+// every shape is one piece that covers the middle of its box, which is where
+// corridors meet it. Frozen tiles are left as they are
+// and pins are stamped afterwards, either of which can still cut a room off;
+// the connectivity pass is what mends that.
+//
+// Joining rooms in the order they were placed sends corridors criss-crossing
+// the map, often side by side. With `merge` up, a corridor between two rooms
+// the ones before it already join is left out, and a straight run that would
+// pass alongside one already dug, a few tiles off, jogs over and follows it
+// instead, so shared stretches are dug once.
+use crate::dungeon::{Dungeon, Point, Tile};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::VecDeque;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoomShape {
+    Rectangle,
+    // The ellipse filling the room's box
+    Circle,
+    // Two bars a third as thick as the box, crossing in the middle
+    Cross,
+    // The box with one corner cut away
+    LShape,
+    // A ragged blob dug by a random walk from the middle
+    Cavern,
+}
+
+impl RoomShape {
+    pub const ALL: [RoomShape; 5] = [
+        RoomShape::Rectangle,
+        RoomShape::Circle,
+        RoomShape::Cross,
+        RoomShape::LShape,
+        RoomShape::Cavern,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RoomShape::Rectangle => "rectangle",
+            RoomShape::Circle => "circle",
+            RoomShape::Cross => "cross",
+            RoomShape::LShape => "l_shape",
+            RoomShape::Cavern => "cavern",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|shape| shape.name() == name)
+    }
+}
+
+// Relative weight of each shape; 0 leaves it out
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RoomShapes {
+    pub rectangle: usize,
+    pub circle: usize,
+    pub cross: usize,
+    pub l_shape: usize,
+    pub cavern: usize,
+}
+
+impl RoomShapes {
+    pub fn weight(&self, shape: RoomShape) -> usize {
+        match shape {
+            RoomShape::Rectangle => self.rectangle,
+            RoomShape::Circle => self.circle,
+            RoomShape::Cross => self.cross,
+            RoomShape::LShape => self.l_shape,
+            RoomShape::Cavern => self.cavern,
+        }
+    }
+
+    pub fn weight_mut(&mut self, shape: RoomShape) -> &mut usize {
+        match shape {
+            RoomShape::Rectangle => &mut self.rectangle,
+            RoomShape::Circle => &mut self.circle,
+            RoomShape::Cross => &mut self.cross,
+            RoomShape::LShape => &mut self.l_shape,
+            RoomShape::Cavern => &mut self.cavern,
+        }
+    }
+
+    // Rectangles when every weight is 0
+    fn pick<R: Rng + ?Sized>(&self, rng: &mut R) -> RoomShape {
+        RoomShape::ALL
+            .choose_weighted(rng, |&shape| self.weight(shape))
+            .copied()
+            .unwrap_or(RoomShape::Rectangle)
+    }
+}
+
+impl Default for RoomShapes {
+    fn default() -> Self {
+        Self {
+            rectangle: 4,
+            circle: 1,
+            cross: 1,
+            l_shape: 1,
+            cavern: 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RoomOptions {
+    // Rooms attempted; fewer fit on a crowded map
+    pub count: usize,
+    // Side lengths of a room's box, walls not included
+    pub min_size: usize,
+    pub max_size: usize,
+    pub shapes: RoomShapes,
+    // Side of the small room dug out where three or more corridor arms
+    // meet; 0 leaves junctions as bare crossings
+    pub junction_size: usize,
+    // How readily corridors run together, from 0 for every corridor dug as
+    // drawn to 1 for runs up to `MERGE_REACH` tiles apart pulled together
+    pub merge: f64,
+}
+
+impl Default for RoomOptions {
+    fn default() -> Self {
+        Self {
+            count: 12,
+            min_size: 4,
+            max_size: 10,
+            shapes: RoomShapes::default(),
+            junction_size: 0,
+            merge: 0.0,
+        }
+    }
+}
+
+// Furthest apart two side-by-side runs are pulled together, at full merge
+pub const MERGE_REACH: usize = 4;
+
+// Tries at a spot for each room before giving up on it
+const PLACEMENT_TRIES: usize = 30;
+
+// Metadata key on the middle tile of every corridor junction, holding how
+// many corridor arms meet there
+pub const JUNCTION_KEY: &str = "junction";
+
+// Corridor arms leaving a tile, one bit per direction
+const LEFT: u8 = 1;
+const RIGHT: u8 = 2;
+const UP: u8 = 4;
+const DOWN: u8 = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Room {
+    left: usize,
+    top: usize,
+    width: usize,
+    height: usize,
+    shape: RoomShape,
+}
+
+impl Room {
+    fn center(&self) -> Point {
+        (self.left + self.width / 2, self.top + self.height / 2)
+    }
+
+    // With a wall's width of space all round, so rooms never merge
+    fn overlaps(&self, other: &Room) -> bool {
+        self.left <= other.left + other.width
+            && other.left <= self.left + self.width
+            && self.top <= other.top + other.height
+            && other.top <= self.top + self.height
+    }
+
+    // The tiles of its shape, in map coordinates
+    fn tiles<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<Point> {
+        let (width, height) = (self.width, self.height);
+        let (cx, cy) = (width / 2, height / 2);
+        let mut inside = vec![vec![false; width]; height];
+        match self.shape {
+            RoomShape::Rectangle => inside.iter_mut().for_each(|row| row.fill(true)),
+            RoomShape::Circle => {
+                let (rx, ry) = (width as f64 / 2.0, height as f64 / 2.0);
+                for (y, row) in inside.iter_mut().enumerate() {
+                    for (x, cell) in row.iter_mut().enumerate() {
+                        let (dx, dy) = ((x as f64 + 0.5 - rx) / rx, (y as f64 + 0.5 - ry) / ry);
+                        *cell = dx * dx + dy * dy <= 1.0;
+                    }
+                }
+            }
+            RoomShape::Cross => {
+                let (bar_width, bar_height) = ((width / 3).max(1), (height / 3).max(1));
+                let columns = cx.saturating_sub(bar_width / 2)..cx.saturating_sub(bar_width / 2) + bar_width;
+                let rows = cy.saturating_sub(bar_height / 2)..cy.saturating_sub(bar_height / 2) + bar_height;
+                for (y, row) in inside.iter_mut().enumerate() {
+                    for (x, cell) in row.iter_mut().enumerate() {
+                        *cell = columns.contains(&x) || rows.contains(&y);
+                    }
+                }
+            }
+            RoomShape::LShape => {
+                // Only the corner quarter past the middle on both axes goes,
+                // so the middle stays
+                let (right, bottom) = (rng.gen_bool(0.5), rng.gen_bool(0.5));
+                for (y, row) in inside.iter_mut().enumerate() {
+                    for (x, cell) in row.iter_mut().enumerate() {
+                        let beyond_x = if right { x > cx } else { x < cx };
+                        let beyond_y = if bottom { y > cy } else { y < cy };
+                        *cell = !(beyond_x && beyond_y);
+                    }
+                }
+            }
+            RoomShape::Cavern => {
+                // About half the box, or as much as the walk gets to before
+                // its step limit
+                let target = (width * height).div_ceil(2);
+                let (mut x, mut y) = (cx, cy);
+                inside[y][x] = true;
+                let mut dug = 1;
+                for _ in 0..width * height * 8 {
+                    if dug >= target {
+                        break;
+                    }
+                    match rng.gen_range(0..4) {
+                        0 if x > 0 => x -= 1,
+                        1 if x + 1 < width => x += 1,
+                        2 if y > 0 => y -= 1,
+                        3 if y + 1 < height => y += 1,
+                        _ => {}
+                    }
+                    if !inside[y][x] {
+                        inside[y][x] = true;
+                        dug += 1;
+                    }
+                }
+            }
+        }
+        let mut tiles = Vec::new();
+        for (y, row) in inside.iter().enumerate() {
+            for (x, &cell) in row.iter().enumerate() {
+                if cell {
+                    tiles.push((self.left + x, self.top + y));
+                }
+            }
+        }
+        tiles
+    }
+}
+
+impl Dungeon {
+    // Solid rock but for the rooms and their corridors. Frozen tiles keep
+    // everything on them.
+    pub fn carve_rooms<R: Rng + ?Sized>(&mut self, options: &RoomOptions, rng: &mut R) {
+        let frozen = &self.frozen;
+        self.features.retain(|(x, y), _| frozen[y][x]);
+        self.entities.retain(|(x, y), _| frozen[y][x]);
+        self.meta.retain(|(x, y), _| frozen[y][x]);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if !self.frozen[y][x] {
+                    self.tiles[y][x] = Tile::Wall;
+                }
+            }
+        }
+
+        // Rooms keep a wall between themselves and the map edge
+        let (room_width, room_height) = (self.width.saturating_sub(2), self.height.saturating_sub(2));
+        let largest = options.max_size.max(options.min_size).max(1);
+        let smallest = options.min_size.clamp(1, largest);
+        let mut rooms: Vec<Room> = Vec::new();
+        for _ in 0..options.count {
+            for _ in 0..PLACEMENT_TRIES {
+                let width = rng.gen_range(smallest..=largest).min(room_width);
+                let height = rng.gen_range(smallest..=largest).min(room_height);
+                if width == 0 || height == 0 {
+                    break;
+                }
+                let room = Room {
+                    left: 1 + rng.gen_range(0..=room_width - width),
+                    top: 1 + rng.gen_range(0..=room_height - height),
+                    width,
+                    height,
+                    shape: options.shapes.pick(rng),
+                };
+                if rooms.iter().all(|placed| !placed.overlaps(&room)) {
+                    rooms.push(room);
+                    break;
+                }
+            }
+        }
+
+        for room in &rooms {
+            for (x, y) in room.tiles(rng) {
+                self.carve_floor(x, y);
+            }
+        }
+        let mut arms = vec![vec![0u8; self.width]; self.height];
+        let reach = (options.merge * MERGE_REACH as f64).round() as usize;
+        // Straight runs dug so far
+        let mut runs: Vec<(Point, Point)> = Vec::new();
+        for pair in rooms.windows(2) {
+            let (from, to) = (pair[0].center(), pair[1].center());
+            // Which leg comes first is a coin toss, so corridors don't all
+            // bend the same way
+            let elbow = if rng.gen_bool(0.5) { (to.0, from.1) } else { (from.0, to.1) };
+            if reach > 0 && self.floor_joins(from, to) {
+                continue;
+            }
+            for (start, end) in [(from, elbow), (elbow, to)] {
+                for (start, end) in alongside(&runs, (start, end), reach) {
+                    self.dig_straight(start, end);
+                    mark_arms(&mut arms, start, end);
+                    runs.push((start, end));
+                }
+            }
+        }
+
+        // Inside a room's box corridors just run to its middle
+        let in_room = |x: usize, y: usize| {
+            rooms.iter().any(|room| {
+                (room.left..room.left + room.width).contains(&x) && (room.top..room.top + room.height).contains(&y)
+            })
+        };
+        for (y, row) in arms.iter().enumerate() {
+            for (x, &bits) in row.iter().enumerate() {
+                if bits.count_ones() < 3 || in_room(x, y) {
+                    continue;
+                }
+                if options.junction_size > 0 {
+                    let reach = options.junction_size / 2;
+                    let (left, top) = (x.saturating_sub(reach).max(1), y.saturating_sub(reach).max(1));
+                    let right = (left + options.junction_size - 1).min(self.width.saturating_sub(2));
+                    let bottom = (top + options.junction_size - 1).min(self.height.saturating_sub(2));
+                    self.dig_straight((left, top), (right, bottom));
+                }
+                self.set_meta(x, y, JUNCTION_KEY, bits.count_ones() as i64);
+            }
+        }
+    }
+
+    // Middle tiles of the corridor junctions, in reading order
+    pub fn junctions(&self) -> Vec<Point> {
+        let mut junctions: Vec<Point> =
+            self.meta.iter().filter(|(_, meta)| meta.contains(JUNCTION_KEY)).map(|(point, _)| point).collect();
+        junctions.sort_by_key(|&(x, y)| (y, x));
+        junctions
+    }
+
+    // Whether floor dug so far already walks from one to the other
+    fn floor_joins(&self, from: Point, to: Point) -> bool {
+        let mut seen = vec![vec![false; self.width]; self.height];
+        seen[from.1][from.0] = true;
+        let mut queue = VecDeque::from([from]);
+        while let Some((x, y)) = queue.pop_front() {
+            if (x, y) == to {
+                return true;
+            }
+            let steps = [
+                (x > 0).then(|| (x - 1, y)),
+                (y > 0).then(|| (x, y - 1)),
+                (x + 1 < self.width).then_some((x + 1, y)),
+                (y + 1 < self.height).then_some((x, y + 1)),
+            ];
+            for (nx, ny) in steps.into_iter().flatten() {
+                if !seen[ny][nx] && self.tiles[ny][nx].is_walkable() {
+                    seen[ny][nx] = true;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+        false
+    }
+
+    fn carve_floor(&mut self, x: usize, y: usize) {
+        if !self.frozen[y][x] {
+            self.tiles[y][x] = Tile::Floor;
+        }
+    }
+
+    // Along a row or a column, or over the block between two corners, both
+    // ends included
+    fn dig_straight(&mut self, (x0, y0): Point, (x1, y1): Point) {
+        for y in y0.min(y1)..=y0.max(y1) {
+            for x in x0.min(x1)..=x0.max(x1) {
+                self.carve_floor(x, y);
+            }
+        }
+    }
+}
+
+// The straight run from `start` to `end` as the runs to dig for it: itself,
+// or where it would pass alongside one of `runs` no more than `reach` tiles
+// off for half its length or more, a jog over onto the nearest such, along
+// it and a jog back
+fn alongside(runs: &[(Point, Point)], (start, end): (Point, Point), reach: usize) -> Vec<(Point, Point)> {
+    let across = start.1 == end.1;
+    // The run as (the line it lies on, its first and last place along it)
+    let line = |(a, b): (Point, Point)| {
+        if a.1 == b.1 {
+            (a.1, a.0.min(b.0), a.0.max(b.0))
+        } else {
+            (a.0, a.1.min(b.1), a.1.max(b.1))
+        }
+    };
+    let (on, low, high) = line((start, end));
+    let length = high - low + 1;
+    if length < 3 || start == end {
+        return vec![(start, end)];
+    }
+    let nearest = runs
+        .iter()
+        .filter(|&&(a, b)| a != b && (a.1 == b.1) == across)
+        .map(|&run| line(run))
+        .filter(|&(other, other_low, other_high)| {
+            let apart = on.abs_diff(other);
+            let shared = (high.min(other_high) + 1).saturating_sub(low.max(other_low));
+            (1..=reach).contains(&apart) && shared * 2 >= length
+        })
+        .min_by_key(|&(other, _, _)| on.abs_diff(other));
+    let Some((other, _, _)) = nearest else {
+        return vec![(start, end)];
+    };
+    let moved = |(x, y): Point| if across { (x, other) } else { (other, y) };
+    vec![(start, moved(start)), (moved(start), moved(end)), (moved(end), end)]
+}
+
+// Records the arms along a straight run: every tile but the far ends gets
+// one each way along it
+fn mark_arms(arms: &mut [Vec<u8>], (x0, y0): Point, (x1, y1): Point) {
+    let (low, high) = if y0 == y1 { (LEFT, RIGHT) } else { (UP, DOWN) };
+    let tiles: Vec<Point> = if y0 == y1 {
+        (x0.min(x1)..=x0.max(x1)).map(|x| (x, y0)).collect()
+    } else {
+        (y0.min(y1)..=y0.max(y1)).map(|y| (x0, y)).collect()
+    };
+    for (index, &(x, y)) in tiles.iter().enumerate() {
+        if index > 0 {
+            arms[y][x] |= low;
+        }
+        if index + 1 < tiles.len() {
+            arms[y][x] |= high;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::rng_for;
+    use crate::pathfinding::regions;
+
+    #[test]
+    fn merged_corridors_dig_less_and_still_join_every_room() {
+        for seed in [4, 5, 6] {
+            let dig = |merge: f64| {
+                let options = RoomOptions {
+                    count: 20,
+                    merge,
+                    ..RoomOptions::default()
+                };
+                let mut dungeon = Dungeon::new(80, 40);
+                dungeon.carve_rooms(&options, &mut rng_for(seed));
+                assert_eq!(regions(&dungeon).len(), 1, "merge {} left rooms apart with seed {}", merge, seed);
+                dungeon.tiles.iter().flatten().filter(|tile| tile.is_walkable()).count()
+            };
+            assert!(dig(1.0) < dig(0.0), "seed {}", seed);
+        }
+    }
+}
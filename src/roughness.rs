@@ -0,0 +1,120 @@
+use crate::dungeon::{Dungeon, Point, Tile};
+use rand::Rng;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RoughnessOptions {
+    // Chance that any stretch of cave wall gets an alcove or a jut
+    pub amount: f64,
+    // How many tiles deep alcoves and juts reach at most
+    pub amplitude: usize,
+}
+
+impl Default for RoughnessOptions {
+    fn default() -> Self {
+        Self {
+            amount: 0.0,
+            amplitude: 2,
+        }
+    }
+}
+
+const SIDES: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+// The eight neighbours in order around the tile, so consecutive entries
+// always touch edge to edge
+const RING: [(i32, i32); 8] = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+
+impl Dungeon {
+    fn walkable_at(&self, x: i32, y: i32) -> bool {
+        x >= 0
+            && y >= 0
+            && x < self.width as i32
+            && y < self.height as i32
+            && self.tiles[y as usize][x as usize].is_walkable()
+    }
+
+    // Whether walling this tile keeps its walkable neighbours connected to
+    // each other, judged from the surrounding ring alone
+    fn can_fill(&self, (x, y): Point) -> bool {
+        let (x, y) = (x as i32, y as i32);
+        let open = RING.map(|(dx, dy)| self.walkable_at(x + dx, y + dy));
+
+        // Split the ring into runs of open tiles and check every open side
+        // falls in the same run
+        let Some(gap) = open.iter().position(|&open| !open) else {
+            return true;
+        };
+        let mut run = 0;
+        let mut side_run = None;
+        for step in 1..=8 {
+            let index = (gap + step) % 8;
+            if !open[index] {
+                run += 1;
+                continue;
+            }
+            if index % 2 == 0 {
+                match side_run {
+                    None => side_run = Some(run),
+                    Some(seen) if seen != run => return false,
+                    Some(_) => {}
+                }
+            }
+        }
+        true
+    }
+
+    // Pushes alcoves into cave walls and juts of rock out from them, turning
+    // smooth tunnels craggy. Juts stop short of ever cutting a passage.
+    pub fn roughen<R: Rng + ?Sized>(&mut self, options: &RoughnessOptions, rng: &mut R) {
+        if options.amount <= 0.0 || options.amplitude == 0 {
+            return;
+        }
+
+        // Wall/floor boundaries as (tile, direction pointing away from the
+        // other side), gathered up front so new edges aren't roughened again
+        let mut edges = Vec::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                for (dx, dy) in SIDES {
+                    let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                    if !self.walkable_at(nx, ny) || self.tiles[y][x] != Tile::Wall {
+                        continue;
+                    }
+                    edges.push(((x, y), (-dx, -dy), true));
+                    edges.push(((nx as usize, ny as usize), (dx, dy), false));
+                }
+            }
+        }
+
+        for ((x, y), (dx, dy), alcove) in edges {
+            if rng.gen::<f64>() >= options.amount / 2.0 {
+                continue;
+            }
+            // An earlier jut may have filled the floor this alcove opens from
+            if alcove && !self.walkable_at(x as i32 - dx, y as i32 - dy) {
+                continue;
+            }
+            let depth = rng.gen_range(1..=options.amplitude) as i32;
+            for step in 0..depth {
+                let (tx, ty) = (x as i32 + dx * step, y as i32 + dy * step);
+                if tx < 0 || ty < 0 || tx >= self.width as i32 || ty >= self.height as i32 {
+                    break;
+                }
+                let point = (tx as usize, ty as usize);
+                if self.frozen[point.1][point.0] {
+                    break;
+                }
+                if alcove {
+                    self.tiles[point.1][point.0] = Tile::Floor;
+                } else {
+                    let occupied = self.features.contains(point.0, point.1)
+                        || self.entities.contains(point.0, point.1);
+                    if !self.tiles[point.1][point.0].is_walkable() || occupied || !self.can_fill(point) {
+                        break;
+                    }
+                    self.tiles[point.1][point.0] = Tile::Wall;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,28 @@
+// Export rows rendered on every core for big maps. Each row depends on the
+// map alone and rows come back in order, so the output is the same however
+// the work is split; small maps skip the threads altogether.
+use std::thread;
+
+// Output cells below which starting threads costs more than it saves
+const PARALLEL_CELLS: usize = 1 << 16;
+
+// `render(y)` for every row from 0 to `rows`, with `cells` the size of one
+// rendered row in pixels or characters
+pub(crate) fn render_rows<T: Send>(rows: usize, cells: usize, render: impl Fn(usize) -> T + Sync) -> Vec<T> {
+    let threads = thread::available_parallelism().map_or(1, |threads| threads.get()).min(rows);
+    if threads <= 1 || rows * cells < PARALLEL_CELLS {
+        return (0..rows).map(render).collect();
+    }
+    let chunk = rows.div_ceil(threads);
+    let render = &render;
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..rows)
+            .step_by(chunk)
+            .map(|start| scope.spawn(move || (start..rows.min(start + chunk)).map(render).collect::<Vec<T>>()))
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("export worker panicked"))
+            .collect()
+    })
+}
@@ -0,0 +1,38 @@
+// Working memory a generation run hands from one automaton step and pass to
+// the next, so big maps don't allocate the same grids and queues over and
+// over. Nothing in it outlives the call that uses it; only the allocations
+// are kept. One is made per run, and the functions taking one have plain
+// versions that make their own.
+use crate::dungeon::{Point, Tile};
+use std::collections::VecDeque;
+
+#[derive(Clone, Debug, Default)]
+pub struct Scratch {
+    // The automaton's back buffer, swapped with the map every step
+    pub(crate) tiles: Vec<Vec<Tile>>,
+    // A row of wall for the automaton to read past the map's top and bottom
+    pub(crate) solid: Vec<Tile>,
+    // Walls in each column of the three rows the automaton is looking at
+    pub(crate) column_walls: Vec<usize>,
+    // Which tiles a region search may step onto
+    pub(crate) passable: Vec<bool>,
+    pub(crate) queue: VecDeque<Point>,
+    // Flat grids, indexed `y * width + x`
+    pub(crate) marks: Vec<usize>,
+    pub(crate) labels: Vec<Option<usize>>,
+    pub(crate) costs: Vec<usize>,
+    pub(crate) came_from: Vec<Option<Point>>,
+}
+
+impl Scratch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+// `buffer` as `len` copies of `value`, in the memory it already has
+pub(crate) fn cleared<T: Clone>(buffer: &mut Vec<T>, len: usize, value: T) -> &mut [T] {
+    buffer.clear();
+    buffer.resize(len, value);
+    buffer
+}
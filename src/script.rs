@@ -0,0 +1,169 @@
+// Touch-ups written down as commands, so hand edits to a generated map can be
+// replayed on it exactly. Commands are separated by `;` or new lines, and `#`
+// starts a comment:
+//
+//     dig 3,4 10,4           floor along 3,4 to 10,4, and on to any further points
+//     flood region 2 water   every tile of region 2 becomes water
+//     collapse 20,5 6x3      the 6x3 block from 20,5 caves in to wall
+//
+// Regions are numbered as `pathfinding::regions` lists them on the map as it
+// stands when the command runs, so earlier commands can renumber them.
+use crate::dungeon::{Dungeon, Point, Tile};
+use crate::pathfinding::regions;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    // Straight trenches through consecutive points, wide enough to walk:
+    // diagonal ones step sideways rather than cutting corners
+    Dig(Vec<Point>),
+    Flood { region: usize, tile: Tile },
+    Collapse { corner: Point, width: usize, height: usize },
+}
+
+impl Command {
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let bad = |why: String| io::Error::new(io::ErrorKind::InvalidData, format!("'{}': {}", text, why));
+        match words[..] {
+            ["dig", ref points @ ..] if !points.is_empty() => {
+                points.iter().map(|point| parse_point(point).map_err(&bad)).collect::<io::Result<_>>().map(Command::Dig)
+            }
+            ["dig"] => Err(bad("dig needs at least one x,y point".to_string())),
+            ["flood", "region", region, tile] => Ok(Command::Flood {
+                region: region.parse().map_err(|_| bad(format!("'{}' is not a region number", region)))?,
+                tile: Tile::parse(tile).ok_or_else(|| bad(format!("'{}' is not wall, floor, water, rubble or bridge", tile)))?,
+            }),
+            ["flood", ..] => Err(bad("expected flood region <number> <tile>".to_string())),
+            ["collapse", corner, size] => {
+                let (width, height) = size
+                    .split_once('x')
+                    .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)))
+                    .ok_or_else(|| bad(format!("'{}' is not a WIDTHxHEIGHT size", size)))?;
+                Ok(Command::Collapse {
+                    corner: parse_point(corner).map_err(&bad)?,
+                    width,
+                    height,
+                })
+            }
+            ["collapse", ..] => Err(bad("expected collapse <x,y> <width>x<height>".to_string())),
+            [name, ..] => Err(bad(format!("unknown command '{}' (expected dig, flood or collapse)", name))),
+            [] => Err(bad("empty command".to_string())),
+        }
+    }
+}
+
+fn parse_point(text: &str) -> Result<Point, String> {
+    text.split_once(',')
+        .and_then(|(x, y)| Some((x.trim().parse().ok()?, y.trim().parse().ok()?)))
+        .ok_or_else(|| format!("'{}' is not an x,y point", text))
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Script {
+    pub commands: Vec<Command>,
+}
+
+impl Script {
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut commands = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("");
+            for command in line.split(';').filter(|command| !command.trim().is_empty()) {
+                let command = Command::parse(command.trim())
+                    .map_err(|error| io::Error::new(error.kind(), format!("line {}: {}", index + 1, error)))?;
+                commands.push(command);
+            }
+        }
+        Ok(Self { commands })
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|error| io::Error::new(error.kind(), format!("{}: {}", path.display(), error)))
+    }
+}
+
+// Each step along the line moves one tile across or one tile down, never both
+fn trench((x0, y0): Point, (x1, y1): Point) -> Vec<Point> {
+    let (x1, y1) = (x1 as i64, y1 as i64);
+    let (mut x, mut y) = (x0 as i64, y0 as i64);
+    let (dx, dy) = ((x1 - x).abs(), (y1 - y).abs());
+    let (sx, sy) = ((x1 - x).signum(), (y1 - y).signum());
+    let mut error = dx - dy;
+    let mut tiles = vec![(x as usize, y as usize)];
+    while (x, y) != (x1, y1) {
+        if 2 * error > -dy {
+            error -= dy;
+            x += sx;
+        } else {
+            error += dx;
+            y += sy;
+        }
+        tiles.push((x as usize, y as usize));
+    }
+    tiles
+}
+
+impl Dungeon {
+    // Runs the commands in order. A command that doesn't fit the map stops
+    // the script there, with the commands before it already applied.
+    pub fn apply_script(&mut self, script: &Script) -> io::Result<()> {
+        for (index, command) in script.commands.iter().enumerate() {
+            self.apply_command(command)
+                .map_err(|error| io::Error::new(error.kind(), format!("command {}: {}", index + 1, error)))?;
+        }
+        Ok(())
+    }
+
+    pub fn apply_command(&mut self, command: &Command) -> io::Result<()> {
+        let off_map = |(x, y): Point| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("({}, {}) is off the {}x{} map", x, y, self.width, self.height),
+            )
+        };
+        match command {
+            Command::Dig(points) => {
+                if let Some(&point) = points.iter().find(|&&(x, y)| x >= self.width || y >= self.height) {
+                    return Err(off_map(point));
+                }
+                let mut tiles = vec![points[0]];
+                for pair in points.windows(2) {
+                    tiles.extend(trench(pair[0], pair[1]));
+                }
+                // Water and rubble are already open ground and stay as they are
+                for (x, y) in tiles {
+                    if !self.tiles[y][x].is_walkable() {
+                        self.tiles[y][x] = Tile::Floor;
+                    }
+                }
+            }
+            Command::Flood { region, tile } => {
+                let regions = regions(self);
+                let cells = regions.get(*region).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("there is no region {} (the map has {})", region, regions.len()),
+                    )
+                })?;
+                for &(x, y) in cells {
+                    self.tiles[y][x] = *tile;
+                }
+            }
+            Command::Collapse { corner, width, height } => {
+                let (right, bottom) = (corner.0 + width.saturating_sub(1), corner.1 + height.saturating_sub(1));
+                if right >= self.width || bottom >= self.height {
+                    return Err(off_map((right, bottom)));
+                }
+                for row in &mut self.tiles[corner.1..corner.1 + height] {
+                    row[corner.0..corner.0 + width].fill(Tile::Wall);
+                }
+            }
+        }
+        self.prune_layers();
+        Ok(())
+    }
+}
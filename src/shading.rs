@@ -0,0 +1,31 @@
+// Ambient occlusion, roughly: floor hemmed in by rock catches less light, so
+// corridors and corners sit darker than open halls. It is only ever a darker
+// shade of the theme's color and leaves the map itself alone.
+use crate::dungeon::Dungeon;
+
+impl Dungeon {
+    // Share of the eight surrounding tiles that block light, with the map edge
+    // counting as rock
+    pub fn occlusion(&self, x: usize, y: usize) -> f64 {
+        let mut solid = 0;
+        for dy in -1i32..=1 {
+            for dx in -1i32..=1 {
+                if (dx, dy) == (0, 0) {
+                    continue;
+                }
+                let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                let inside = nx >= 0 && ny >= 0 && (nx as usize) < self.width && (ny as usize) < self.height;
+                if !inside || self.tiles[ny as usize][nx as usize].is_opaque() {
+                    solid += 1;
+                }
+            }
+        }
+        solid as f64 / 8.0
+    }
+}
+
+// `rgb` dimmed by `strength` at full occlusion and proportionally less below
+pub fn shade(rgb: [u8; 3], occlusion: f64, strength: f64) -> [u8; 3] {
+    let light = 1.0 - strength * occlusion;
+    rgb.map(|channel| (channel as f64 * light).round() as u8)
+}
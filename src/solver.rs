@@ -0,0 +1,214 @@
+// The cheapest way from the entrance to the exit for someone who knows the
+// whole map and plays by its rules: doors cost what the path options say,
+// and a `key` picked up on the way lets them through the locked doors it
+// fits. A key fits the lock carrying the same `lock` number in its tile
+// metadata, as `Dungeon::place_locks` numbers them; a key without a number
+// fits every lock, and a lock without one takes any key. A map the solver
+// can't finish can't be finished.
+use crate::door::DoorState;
+use crate::dungeon::{Dungeon, Point};
+use crate::layers::Entity;
+use crate::locks::{LOCK_KEY, MAX_KEYS};
+use crate::pathfinding::{entrance_and_exit, PathOptions};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+// The item that unlocks locked doors. Keys aren't used up, so one opens
+// its lock however often the route comes back through.
+pub const KEY: &str = "key";
+
+// The keys in hand, a bit for each: bit n is the key to lock n and bit 0 a
+// key without a number
+type Keys = u64;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Solution {
+    // Entrance to exit, both included
+    pub route: Vec<Point>,
+    // Total step cost under the path options
+    pub cost: usize,
+    // Items the route can't do without, where it picks them up
+    pub required: Vec<(Point, String)>,
+}
+
+impl Solution {
+    pub fn steps(&self) -> usize {
+        self.route.len() - 1
+    }
+
+    pub fn report(&self) -> String {
+        let mut out = format!("Solved in {} steps (cost {})\n", self.steps(), self.cost);
+        for ((x, y), name) in &self.required {
+            out += &format!("Needs the {} at ({}, {})\n", name, x, y);
+        }
+        out
+    }
+}
+
+// A tile's lock number, if it has one the solver can tell apart
+fn lock_number(dungeon: &Dungeon, (x, y): Point) -> Option<u32> {
+    let number = dungeon.meta(x, y)?.get(LOCK_KEY)?.as_int()?;
+    (1..=MAX_KEYS as i64).contains(&number).then_some(number as u32)
+}
+
+// The key lying on a tile, as its bit, or none
+fn key_at(dungeon: &Dungeon, (x, y): Point) -> Keys {
+    match dungeon.entities.get(x, y) {
+        Some(Entity::Item(name)) if name == KEY => match dungeon.meta(x, y).and_then(|meta| meta.get(LOCK_KEY)) {
+            None => 1,
+            Some(_) => lock_number(dungeon, (x, y)).map_or(0, |number| 1 << number),
+        },
+        _ => 0,
+    }
+}
+
+// The bit of a key in hand that opens the lock on `point`, if any does
+fn fitting_key(dungeon: &Dungeon, point: Point, keys: Keys) -> Option<Keys> {
+    let fits = match lock_number(dungeon, point) {
+        Some(number) if keys & 1 << number != 0 => 1 << number,
+        Some(_) => keys & 1,
+        // Any key in hand will do
+        None => keys & keys.wrapping_neg(),
+    };
+    (fits != 0).then_some(fits)
+}
+
+// Cost of stepping onto a tile with the given keys in hand
+fn step_cost(dungeon: &Dungeon, options: &PathOptions, point: Point, keys: Keys) -> Option<usize> {
+    match dungeon.door(point.0, point.1).map(|door| door.state) {
+        Some(DoorState::Locked) if fitting_key(dungeon, point, keys).is_some() => {
+            let terrain = options.costs.cost(dungeon.tiles[point.1][point.0])?;
+            Some(terrain + options.closed_door_cost?)
+        }
+        _ => options.step_cost(dungeon, point),
+    }
+}
+
+pub fn solve(dungeon: &Dungeon, options: &PathOptions) -> Option<Solution> {
+    let (entrance, exit) = entrance_and_exit(dungeon)?;
+    solve_between(dungeon, entrance, exit, options)
+}
+
+// Dijkstra over (tile, keys in hand) states. Only the key sets the walk
+// can actually gather get states, so locks in a row cost little more than
+// one.
+pub fn solve_between(dungeon: &Dungeon, start: Point, goal: Point, options: &PathOptions) -> Option<Solution> {
+    type State = (Point, Keys);
+    let mut cost: HashMap<State, usize> = HashMap::new();
+    let mut came_from: HashMap<State, State> = HashMap::new();
+    let mut heap = BinaryHeap::new();
+
+    let first = (start, key_at(dungeon, start));
+    cost.insert(first, 0);
+    heap.push(Reverse((0, first)));
+
+    while let Some(Reverse((current_cost, state))) = heap.pop() {
+        if cost.get(&state).is_some_and(|&best| current_cost > best) {
+            continue;
+        }
+        let ((x, y), keys) = state;
+        if (x, y) == goal {
+            let mut trail = vec![state];
+            while let Some(&previous) = came_from.get(trail.last().unwrap()) {
+                trail.push(previous);
+            }
+            trail.reverse();
+            return Some(Solution {
+                route: trail.iter().map(|&(point, _)| point).collect(),
+                cost: current_cost,
+                required: required_items(dungeon, options, &trail),
+            });
+        }
+        for (dx, dy) in [(0, -1), (1, 0), (0, 1), (-1, 0)] {
+            let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+            if nx < 0 || ny < 0 || nx as usize >= dungeon.width || ny as usize >= dungeon.height {
+                continue;
+            }
+            let next = (nx as usize, ny as usize);
+            let Some(step) = step_cost(dungeon, options, next, keys) else {
+                continue;
+            };
+            let next_state = (next, keys | key_at(dungeon, next));
+            let next_cost = current_cost + step;
+            if cost.get(&next_state).is_none_or(|&best| next_cost < best) {
+                cost.insert(next_state, next_cost);
+                came_from.insert(next_state, state);
+                heap.push(Reverse((next_cost, next_state)));
+            }
+        }
+    }
+    None
+}
+
+// Every key the route used on a locked door that would have cost more, or
+// been shut, without it, where the route picked it up, in the order it did
+fn required_items(dungeon: &Dungeon, options: &PathOptions, trail: &[(Point, Keys)]) -> Vec<(Point, String)> {
+    let mut used: Keys = 0;
+    for &(point, keys) in trail {
+        if dungeon.door(point.0, point.1).is_none_or(|door| door.state != DoorState::Locked) {
+            continue;
+        }
+        let Some(key) = fitting_key(dungeon, point, keys) else {
+            continue;
+        };
+        if options.step_cost(dungeon, point).is_none_or(|bare| Some(bare) > step_cost(dungeon, options, point, keys)) {
+            used |= key;
+        }
+    }
+    let mut required = Vec::new();
+    let mut held: Keys = 0;
+    for &(point, keys) in trail {
+        if keys & used & !held != 0 {
+            required.push((point, KEY.to_string()));
+        }
+        held = keys;
+    }
+    required
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::door::{Door, DoorMaterial, Facing};
+    use crate::dungeon::Tile;
+
+    // One row of floor from the entrance at 0 to the exit at 10, locks 1
+    // and 2 across it at 3 and 7, and a key at each of `keys` with the lock
+    // number it carries, if any
+    fn corridor(keys: &[(usize, Option<i64>)]) -> Dungeon {
+        let mut dungeon = Dungeon::new(11, 1);
+        for x in 0..11 {
+            dungeon.set_tile(x, 0, Tile::Floor);
+        }
+        dungeon.entities.set(0, 0, Entity::Entrance);
+        dungeon.entities.set(10, 0, Entity::Exit);
+        for (x, number) in [(3, 1), (7, 2)] {
+            dungeon.add_door(x, 0, Door::new(DoorState::Locked, DoorMaterial::Iron, Facing::East));
+            dungeon.set_meta(x, 0, LOCK_KEY, number);
+        }
+        for &(x, number) in keys {
+            dungeon.entities.set(x, 0, Entity::Item(KEY.to_string()));
+            if let Some(number) = number {
+                dungeon.set_meta(x, 0, LOCK_KEY, number);
+            }
+        }
+        dungeon
+    }
+
+    #[test]
+    fn keys_open_only_the_locks_with_their_number() {
+        let options = PathOptions::default();
+        let solution = solve(&corridor(&[(1, Some(1)), (5, Some(2))]), &options).unwrap();
+        assert_eq!(solution.steps(), 10);
+        assert_eq!(solution.required, vec![((1, 0), KEY.to_string()), ((5, 0), KEY.to_string())]);
+
+        // The key to the second lock lies before the first, and the key to
+        // the first behind it
+        assert_eq!(solve(&corridor(&[(1, Some(2)), (5, Some(1))]), &options), None);
+        assert_eq!(solve(&corridor(&[(1, Some(1))]), &options), None);
+
+        // A key without a number opens both
+        let solution = solve(&corridor(&[(1, None)]), &options).unwrap();
+        assert_eq!(solution.required, vec![((1, 0), KEY.to_string())]);
+    }
+}
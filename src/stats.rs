@@ -0,0 +1,254 @@
+// Opt-in local usage statistics. Nothing is recorded unless a stats file is
+// given, and nothing ever leaves it: each run appends one JSON object per
+// line so the log can be fed to jq or a spreadsheet as well as summarised
+// here.
+use crate::config::invalid;
+use crate::generator::Report;
+use crate::json;
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunStats {
+    // Seconds since the Unix epoch
+    pub time: u64,
+    pub algorithm: String,
+    pub width: usize,
+    pub height: usize,
+    pub seed: u64,
+    pub duration_ms: f64,
+    // Automaton steps actually run
+    pub steps: usize,
+    // Whether the map had settled by then. Records from before this was
+    // logged count as settled.
+    pub converged: bool,
+    // Tiles dug out afterwards to restore the configured clearance
+    pub carved: usize,
+    // Layout attempts generated in search of the best map. Records from
+    // before this was logged count as one.
+    pub attempts: usize,
+}
+
+impl RunStats {
+    pub fn new(width: usize, height: usize, seed: u64, report: &Report, duration: Duration) -> Self {
+        Self {
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs()),
+            algorithm: report.algorithm.to_string(),
+            width,
+            height,
+            seed,
+            duration_ms: duration.as_secs_f64() * 1000.0,
+            steps: report.steps,
+            converged: report.converged,
+            carved: report.carved,
+            attempts: report.attempts,
+        }
+    }
+
+    fn to_json(&self) -> String {
+        format!(
+            "{{\"time\":{},\"algorithm\":{},\"width\":{},\"height\":{},\"seed\":{},\"duration_ms\":{:.3},\"steps\":{},\"converged\":{},\"carved\":{},\"attempts\":{}}}",
+            self.time,
+            json::quote(&self.algorithm),
+            self.width,
+            self.height,
+            self.seed,
+            self.duration_ms,
+            self.steps,
+            self.converged,
+            self.carved,
+            self.attempts
+        )
+    }
+
+    fn from_json(line: &str) -> io::Result<Self> {
+        let fields = parse_object(line)?;
+        let text = |key: &str| {
+            fields
+                .get(key)
+                .ok_or_else(|| invalid(key, "is missing"))
+                .map(String::as_str)
+        };
+        let number = |key: &str| {
+            text(key)?
+                .parse::<f64>()
+                .map_err(|_| invalid(key, "must be a number"))
+        };
+        let count = |key: &str| {
+            text(key)?
+                .parse::<u64>()
+                .map_err(|_| invalid(key, "must be a whole number"))
+        };
+        Ok(Self {
+            time: count("time")?,
+            algorithm: text("algorithm")?.to_string(),
+            width: count("width")? as usize,
+            height: count("height")? as usize,
+            seed: count("seed")?,
+            duration_ms: number("duration_ms")?,
+            steps: count("steps")? as usize,
+            converged: match fields.get("converged").map(String::as_str) {
+                None | Some("true") => true,
+                Some("false") => false,
+                Some(_) => return Err(invalid("converged", "must be true or false")),
+            },
+            carved: count("carved")? as usize,
+            attempts: if fields.contains_key("attempts") { count("attempts")? as usize } else { 1 },
+        })
+    }
+}
+
+// Just enough JSON for the flat objects this module writes: string and
+// number values, returned as their text
+fn parse_object(line: &str) -> io::Result<BTreeMap<String, String>> {
+    let malformed = || io::Error::new(io::ErrorKind::InvalidData, "malformed stats record");
+    let mut chars = line.trim().chars().peekable();
+    let skip_space = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+    };
+    let string = |chars: &mut std::iter::Peekable<std::str::Chars>| -> io::Result<String> {
+        let mut out = String::new();
+        loop {
+            match chars.next().ok_or_else(malformed)? {
+                '"' => return Ok(out),
+                '\\' => match chars.next().ok_or_else(malformed)? {
+                    'u' => {
+                        let code: String = chars.by_ref().take(4).collect();
+                        let code = u32::from_str_radix(&code, 16).map_err(|_| malformed())?;
+                        out.push(char::from_u32(code).ok_or_else(malformed)?);
+                    }
+                    'n' => out.push('\n'),
+                    't' => out.push('\t'),
+                    c => out.push(c),
+                },
+                c => out.push(c),
+            }
+        }
+    };
+
+    let mut fields = BTreeMap::new();
+    if chars.next() != Some('{') {
+        return Err(malformed());
+    }
+    loop {
+        skip_space(&mut chars);
+        match chars.next() {
+            Some('}') if fields.is_empty() => break,
+            Some('"') => {}
+            _ => return Err(malformed()),
+        }
+        let key = string(&mut chars)?;
+        skip_space(&mut chars);
+        if chars.next() != Some(':') {
+            return Err(malformed());
+        }
+        skip_space(&mut chars);
+        let value = if chars.next_if_eq(&'"').is_some() {
+            string(&mut chars)?
+        } else {
+            let mut number = String::new();
+            while let Some(c) = chars.next_if(|c| !matches!(c, ',' | '}') && !c.is_whitespace()) {
+                number.push(c);
+            }
+            number
+        };
+        fields.insert(key, value);
+        skip_space(&mut chars);
+        match chars.next() {
+            Some(',') => {}
+            Some('}') => break,
+            _ => return Err(malformed()),
+        }
+    }
+    Ok(fields)
+}
+
+pub fn append_run(path: &Path, run: &RunStats) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", run.to_json())
+}
+
+// A missing file just means nothing has been recorded yet
+pub fn load_runs(path: &Path) -> io::Result<Vec<RunStats>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error),
+    };
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            RunStats::from_json(line).map_err(|error| {
+                io::Error::new(error.kind(), format!("{}:{}: {}", path.display(), index + 1, error))
+            })
+        })
+        .collect()
+}
+
+// Totals for one algorithm across the log
+#[derive(Clone, Debug, PartialEq)]
+pub struct Summary {
+    pub algorithm: String,
+    pub runs: usize,
+    pub tiles: usize,
+    pub total_ms: f64,
+    pub mean_ms: f64,
+    pub median_ms: f64,
+    pub max_ms: f64,
+    pub mean_steps: f64,
+    pub mean_attempts: f64,
+    // Runs the step limit cut off before the map settled
+    pub unconverged_runs: usize,
+    // Runs the clearance repair had to touch, and the tiles it dug in total
+    pub repaired_runs: usize,
+    pub carved: usize,
+}
+
+impl Summary {
+    // Time per thousand tiles, so runs of different sizes compare fairly
+    pub fn ms_per_kilotile(&self) -> f64 {
+        self.total_ms * 1000.0 / self.tiles.max(1) as f64
+    }
+}
+
+// One summary per algorithm, in name order
+pub fn summarize(runs: &[RunStats]) -> Vec<Summary> {
+    let mut by_algorithm: BTreeMap<&str, Vec<&RunStats>> = BTreeMap::new();
+    for run in runs {
+        by_algorithm.entry(&run.algorithm).or_default().push(run);
+    }
+    by_algorithm
+        .into_iter()
+        .map(|(algorithm, runs)| {
+            let mut durations: Vec<f64> = runs.iter().map(|run| run.duration_ms).collect();
+            durations.sort_by(f64::total_cmp);
+            let middle = durations.len() / 2;
+            let median_ms = if durations.len().is_multiple_of(2) {
+                (durations[middle - 1] + durations[middle]) / 2.0
+            } else {
+                durations[middle]
+            };
+            let total_ms: f64 = durations.iter().sum();
+            Summary {
+                algorithm: algorithm.to_string(),
+                runs: runs.len(),
+                tiles: runs.iter().map(|run| run.width * run.height).sum(),
+                total_ms,
+                mean_ms: total_ms / runs.len() as f64,
+                median_ms,
+                max_ms: durations[durations.len() - 1],
+                mean_steps: runs.iter().map(|run| run.steps).sum::<usize>() as f64 / runs.len() as f64,
+                mean_attempts: runs.iter().map(|run| run.attempts).sum::<usize>() as f64 / runs.len() as f64,
+                unconverged_runs: runs.iter().filter(|run| !run.converged).count(),
+                repaired_runs: runs.iter().filter(|run| run.carved > 0).count(),
+                carved: runs.iter().map(|run| run.carved).sum(),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,148 @@
+// What a config tends to make, rather than what one seed happened to: `count`
+// maps generated from consecutive seeds and measured, each measurement
+// summed up by its mean, spread and range. The maps are generated across
+// every core where there are enough of them; each depends on its seed alone,
+// so the survey is the same however the work is split.
+use crate::config::Config;
+use crate::generator;
+use crate::rows::render_rows;
+
+// The mean, sample standard deviation and range of a set of values
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Spread {
+    pub mean: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Spread {
+    // All zero for no values, and no deviation for one
+    pub fn of(values: &[f64]) -> Self {
+        if values.is_empty() {
+            return Self::default();
+        }
+        let count = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / count;
+        let squares: f64 = values.iter().map(|value| (value - mean).powi(2)).sum();
+        Self {
+            mean,
+            std_dev: if values.len() > 1 { (squares / (count - 1.0)).sqrt() } else { 0.0 },
+            min: values.iter().copied().fold(f64::INFINITY, f64::min),
+            max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Survey {
+    pub maps: usize,
+    pub first_seed: u64,
+    // Share of each map that is walkable, as a percentage
+    pub floor_percent: Spread,
+    pub regions: Spread,
+    // Share of the floor in the largest region, as a percentage
+    pub largest_region_percent: Spread,
+    // Steps from entrance to exit, over the maps that have a way through
+    pub route_length: Spread,
+    // Maps with no walk from the entrance to the exit
+    pub unroutable: usize,
+    pub dead_ends: Spread,
+}
+
+// One map's measurements
+struct Sample {
+    floor_percent: f64,
+    regions: f64,
+    largest_region_percent: f64,
+    route_length: Option<f64>,
+    dead_ends: f64,
+}
+
+// Maps from `first_seed` onwards, one seed each
+pub fn survey(config: &Config, first_seed: u64, count: usize) -> Survey {
+    let samples = render_rows(count, config.width * config.height, |index| {
+        let metrics = generator::generate_seeded(config, first_seed.wrapping_add(index as u64)).metrics();
+        let percent = |part: usize, whole: usize| part as f64 * 100.0 / whole.max(1) as f64;
+        Sample {
+            floor_percent: percent(metrics.floor, metrics.width * metrics.height),
+            regions: metrics.region_sizes.len() as f64,
+            largest_region_percent: percent(metrics.region_sizes.first().copied().unwrap_or(0), metrics.floor),
+            route_length: metrics.route_length.map(|steps| steps as f64),
+            dead_ends: metrics.dead_ends as f64,
+        }
+    });
+    let spread = |measure: fn(&Sample) -> f64| Spread::of(&samples.iter().map(measure).collect::<Vec<f64>>());
+    let routes: Vec<f64> = samples.iter().filter_map(|sample| sample.route_length).collect();
+    Survey {
+        maps: count,
+        first_seed,
+        floor_percent: spread(|sample| sample.floor_percent),
+        regions: spread(|sample| sample.regions),
+        largest_region_percent: spread(|sample| sample.largest_region_percent),
+        unroutable: count - routes.len(),
+        route_length: Spread::of(&routes),
+        dead_ends: spread(|sample| sample.dead_ends),
+    }
+}
+
+impl Survey {
+    // A row for each measurement, for a terminal
+    pub fn report(&self) -> String {
+        let last_seed = self.first_seed.wrapping_add(self.maps.saturating_sub(1) as u64);
+        let mut out = format!("{} map(s), seeds {} to {}\n", self.maps, self.first_seed, last_seed);
+        out.push_str(&format!("  {:<16} {:>8} {:>8} {:>8} {:>8}\n", "", "mean", "sd", "min", "max"));
+        let rows = [
+            ("floor %", self.floor_percent),
+            ("regions", self.regions),
+            ("largest region %", self.largest_region_percent),
+            ("route steps", self.route_length),
+            ("dead ends", self.dead_ends),
+        ];
+        for (name, spread) in rows {
+            out.push_str(&format!(
+                "  {:<16} {:>8.1} {:>8.1} {:>8.1} {:>8.1}\n",
+                name, spread.mean, spread.std_dev, spread.min, spread.max
+            ));
+        }
+        if self.unroutable > 0 {
+            out.push_str(&format!("  {} map(s) with no route from entrance to exit\n", self.unroutable));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spread_takes_the_sample_deviation() {
+        let spread = Spread::of(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]);
+        assert_eq!((spread.mean, spread.min, spread.max), (5.0, 2.0, 9.0));
+        assert!((spread.std_dev - (32.0f64 / 7.0).sqrt()).abs() < 1e-12);
+        assert_eq!(Spread::of(&[3.5]), Spread { mean: 3.5, std_dev: 0.0, min: 3.5, max: 3.5 });
+        assert_eq!(Spread::of(&[]), Spread::default());
+    }
+
+    #[test]
+    fn survey_sums_up_each_seed_measured_alone() {
+        let config = Config {
+            width: 40,
+            height: 24,
+            ..Config::default()
+        };
+        let survey = survey(&config, 3, 6);
+        assert_eq!((survey.maps, survey.first_seed), (6, 3));
+
+        let maps: Vec<_> = (3..9).map(|seed| generator::generate_seeded(&config, seed).metrics()).collect();
+        let floor: Vec<f64> = maps.iter().map(|map| map.floor as f64 * 100.0 / (40.0 * 24.0)).collect();
+        let regions: Vec<f64> = maps.iter().map(|map| map.region_sizes.len() as f64).collect();
+        let routes: Vec<f64> = maps.iter().filter_map(|map| map.route_length).map(|steps| steps as f64).collect();
+        assert_eq!(survey.floor_percent, Spread::of(&floor));
+        assert_eq!(survey.regions, Spread::of(&regions));
+        assert_eq!(survey.route_length, Spread::of(&routes));
+        assert_eq!(survey.unroutable, 6 - routes.len());
+        assert!(survey.floor_percent.std_dev > 0.0, "six seeds all made the same floor");
+    }
+}
@@ -0,0 +1,86 @@
+use crate::dungeon::{Dungeon, Point, Tile};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TerrainOptions {
+    // Share of floor tiles flooded into pools
+    pub water: f64,
+    // Chance that floor hugging a wall is strewn with rubble
+    pub rubble: f64,
+}
+
+impl Default for TerrainOptions {
+    fn default() -> Self {
+        Self { water: 0.0, rubble: 0.0 }
+    }
+}
+
+const POOL_SIZE: std::ops::RangeInclusive<usize> = 4..=24;
+
+impl Dungeon {
+    fn open_floor(&self) -> Vec<Point> {
+        let mut floor = Vec::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.tiles[y][x] == Tile::Floor && !self.features.contains(x, y) && !self.frozen[y][x] {
+                    floor.push((x, y));
+                }
+            }
+        }
+        floor
+    }
+
+    // Floods pools of water and drops rubble along cave walls
+    pub fn scatter_terrain<R: Rng + ?Sized>(&mut self, options: &TerrainOptions, rng: &mut R) {
+        let floor = self.open_floor();
+
+        let target = (floor.len() as f64 * options.water.clamp(0.0, 1.0)) as usize;
+        let mut flooded = 0;
+        let mut attempts = 0;
+        while flooded < target && attempts < target * 4 + 8 {
+            attempts += 1;
+            let &seed = floor.choose(rng).unwrap();
+            let size = rng.gen_range(POOL_SIZE).min(target - flooded);
+            flooded += self.grow_pool(seed, size, rng);
+        }
+
+        if options.rubble > 0.0 {
+            for (x, y) in self.open_floor() {
+                if self.count_wall_neighbors(x, y) >= 3 && rng.gen::<f64>() < options.rubble {
+                    self.tiles[y][x] = Tile::Rubble;
+                }
+            }
+        }
+    }
+
+    // Spreads water outward from `seed` through plain floor; returns tiles flooded
+    fn grow_pool<R: Rng + ?Sized>(&mut self, seed: Point, size: usize, rng: &mut R) -> usize {
+        let mut frontier = vec![seed];
+        let mut flooded = 0;
+
+        while flooded < size && !frontier.is_empty() {
+            let (x, y) = frontier.swap_remove(rng.gen_range(0..frontier.len()));
+            if self.tiles[y][x] != Tile::Floor || self.features.contains(x, y) || self.frozen[y][x] {
+                continue;
+            }
+            self.tiles[y][x] = Tile::Water;
+            flooded += 1;
+
+            if x > 0 {
+                frontier.push((x - 1, y));
+            }
+            if y > 0 {
+                frontier.push((x, y - 1));
+            }
+            if x + 1 < self.width {
+                frontier.push((x + 1, y));
+            }
+            if y + 1 < self.height {
+                frontier.push((x, y + 1));
+            }
+        }
+
+        flooded
+    }
+}
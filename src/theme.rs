@@ -0,0 +1,278 @@
+use crate::config::{invalid, read_fraction, read_table, unknown_key};
+use crate::dungeon::Tile;
+use crate::toml::{self, Table};
+use crossterm::style::Color;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// How one kind of tile is drawn: a glyph and color for the terminal, and the
+// exact color image exporters paint it with
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TileStyle {
+    pub glyph: char,
+    pub color: Color,
+    pub rgb: [u8; 3],
+}
+
+impl TileStyle {
+    pub const fn new(glyph: char, color: Color, rgb: [u8; 3]) -> Self {
+        Self { glyph, color, rgb }
+    }
+}
+
+// Terrain glyphs and colors; doors and entities keep their own fixed look so
+// they read the same under every theme. Theme files are TOML, e.g.
+//
+//     name = "lava"
+//
+//     [wall]
+//     glyph = "▓"
+//     color = "dark_red"
+//     rgb = "#5a1a10"
+//
+// where `color` is a terminal color name or "#rrggbb", and `rgb` defaults
+// to whatever `color` looks like. Tiles left out keep the classic look. A
+// top-level `shading = 0.5` sets how far true-color terminals darken floor
+// boxed in by walls; 0 turns it off.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    pub wall: TileStyle,
+    pub floor: TileStyle,
+    pub water: TileStyle,
+    pub rubble: TileStyle,
+    pub bridge: TileStyle,
+    // How much light a tile enclosed on every side loses
+    pub shading: f64,
+}
+
+const DEFAULT_SHADING: f64 = 0.5;
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::builtin("classic").unwrap()
+    }
+}
+
+impl Theme {
+    pub const BUILTIN: [&'static str; 8] =
+        ["classic", "cavern", "crypt", "mine", "sewer", "fortress", "colorblind", "high-contrast"];
+
+    pub fn builtin(name: &str) -> Option<Self> {
+        let (wall, floor, water, rubble, bridge) = match name {
+            "classic" => (
+                TileStyle::new('█', Color::DarkGrey, [72, 72, 80]),
+                TileStyle::new('·', Color::Yellow, [200, 180, 110]),
+                TileStyle::new('~', Color::Blue, [60, 100, 200]),
+                TileStyle::new('%', Color::Grey, [140, 130, 115]),
+                TileStyle::new('≡', Color::DarkYellow, [150, 104, 56]),
+            ),
+            "cavern" => (
+                TileStyle::new('█', Color::DarkYellow, [92, 70, 44]),
+                TileStyle::new('·', Color::Grey, [170, 160, 140]),
+                TileStyle::new('~', Color::DarkBlue, [40, 70, 140]),
+                TileStyle::new('%', Color::DarkYellow, [120, 96, 60]),
+                TileStyle::new('≡', Color::Yellow, [140, 110, 70]),
+            ),
+            "crypt" => (
+                TileStyle::new('█', Color::DarkMagenta, [70, 50, 80]),
+                TileStyle::new('·', Color::DarkGrey, [120, 116, 124]),
+                TileStyle::new('~', Color::DarkCyan, [40, 110, 120]),
+                TileStyle::new('%', Color::Grey, [150, 146, 140]),
+                TileStyle::new('≡', Color::Grey, [110, 96, 80]),
+            ),
+            "mine" => (
+                TileStyle::new('▓', Color::DarkRed, [100, 50, 36]),
+                TileStyle::new('·', Color::DarkYellow, [160, 120, 70]),
+                TileStyle::new('~', Color::Blue, [50, 90, 180]),
+                TileStyle::new('∴', Color::Yellow, [190, 160, 90]),
+                TileStyle::new('═', Color::DarkYellow, [130, 90, 50]),
+            ),
+            "sewer" => (
+                TileStyle::new('█', Color::DarkGreen, [44, 70, 48]),
+                TileStyle::new('·', Color::Grey, [140, 146, 130]),
+                TileStyle::new('≈', Color::Green, [80, 140, 60]),
+                TileStyle::new('%', Color::DarkYellow, [110, 96, 56]),
+                TileStyle::new('≡', Color::DarkGrey, [90, 90, 84]),
+            ),
+            "fortress" => (
+                TileStyle::new('█', Color::Grey, [110, 110, 116]),
+                TileStyle::new('.', Color::White, [220, 216, 206]),
+                TileStyle::new('~', Color::Cyan, [70, 150, 190]),
+                TileStyle::new('%', Color::DarkGrey, [90, 88, 84]),
+                TileStyle::new('═', Color::DarkYellow, [150, 110, 70]),
+            ),
+            // The Okabe-Ito palette, told apart with every kind of color
+            // blindness
+            "colorblind" => (
+                TileStyle::new('█', Color::DarkBlue, [0, 114, 178]),
+                TileStyle::new('·', Color::Yellow, [240, 228, 66]),
+                TileStyle::new('~', Color::Blue, [86, 180, 233]),
+                TileStyle::new('%', Color::DarkYellow, [230, 159, 0]),
+                TileStyle::new('≡', Color::Red, [213, 94, 0]),
+            ),
+            // Apart in lightness alone, so it reads in any vision and on
+            // a monochrome screen
+            "high-contrast" => (
+                TileStyle::new('█', Color::White, [235, 235, 235]),
+                TileStyle::new('·', Color::DarkGrey, [40, 40, 40]),
+                TileStyle::new('~', Color::Blue, [30, 110, 200]),
+                TileStyle::new('%', Color::Grey, [150, 150, 150]),
+                TileStyle::new('≡', Color::Yellow, [230, 200, 60]),
+            ),
+            _ => return None,
+        };
+        Some(Self {
+            name: name.to_string(),
+            wall,
+            floor,
+            water,
+            rubble,
+            bridge,
+            shading: DEFAULT_SHADING,
+        })
+    }
+
+    // A built-in theme by name, or else a theme file at that path
+    pub fn resolve(name_or_path: &str) -> io::Result<Self> {
+        match Self::builtin(name_or_path) {
+            Some(theme) => Ok(theme),
+            None if name_or_path.ends_with(".toml") => Self::load(Path::new(name_or_path)),
+            None => Err(invalid(
+                "theme",
+                &format!("must be a .toml file or one of {}", Self::BUILTIN.join(", ")),
+            )),
+        }
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        toml::parse(&text)
+            .and_then(|table| Self::from_table(&table))
+            .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", path.display(), error)))
+    }
+
+    pub fn from_table(table: &Table) -> io::Result<Self> {
+        let mut theme = Self {
+            name: "custom".to_string(),
+            ..Self::default()
+        };
+        for (key, value) in table {
+            let style = match key.as_str() {
+                "name" => {
+                    theme.name = value.as_str().ok_or_else(|| invalid(key, "must be a string"))?.to_string();
+                    continue;
+                }
+                "shading" => {
+                    theme.shading = read_fraction(key, value)?;
+                    continue;
+                }
+                "wall" => &mut theme.wall,
+                "floor" => &mut theme.floor,
+                "water" => &mut theme.water,
+                "rubble" => &mut theme.rubble,
+                "bridge" => &mut theme.bridge,
+                _ => return Err(unknown_key("", key)),
+            };
+            read_style(style, key, read_table(key, value)?)?;
+        }
+        Ok(theme)
+    }
+
+    pub fn style(&self, tile: Tile) -> TileStyle {
+        match tile {
+            Tile::Wall => self.wall,
+            Tile::Floor => self.floor,
+            Tile::Water => self.water,
+            Tile::Rubble => self.rubble,
+            Tile::Bridge => self.bridge,
+            Tile::Custom(custom) => {
+                let kind = custom.kind();
+                TileStyle::new(kind.glyph, kind.color, kind.rgb)
+            }
+        }
+    }
+}
+
+fn read_style(style: &mut TileStyle, tile: &str, table: &Table) -> io::Result<()> {
+    let mut rgb = None;
+    for (key, value) in table {
+        let text = value.as_str().ok_or_else(|| invalid(key, "must be a string"))?;
+        match key.as_str() {
+            "glyph" => {
+                let mut chars = text.chars();
+                style.glyph = match (chars.next(), chars.next()) {
+                    (Some(glyph), None) => glyph,
+                    _ => return Err(invalid(key, "must be a single character")),
+                };
+            }
+            "color" => {
+                style.color = parse_color(text).ok_or_else(|| invalid(key, "is not a color name or #rrggbb"))?;
+            }
+            "rgb" => rgb = Some(parse_hex(text).ok_or_else(|| invalid(key, "must be #rrggbb"))?),
+            _ => return Err(unknown_key(&format!("{}.", tile), key)),
+        }
+    }
+    style.rgb = rgb.unwrap_or_else(|| color_rgb(style.color));
+    Ok(())
+}
+
+fn parse_hex(text: &str) -> Option<[u8; 3]> {
+    let hex = text.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+// Terminal color names as snake_case, or "#rrggbb" for true color
+pub fn parse_color(text: &str) -> Option<Color> {
+    if let Some([r, g, b]) = parse_hex(text) {
+        return Some(Color::Rgb { r, g, b });
+    }
+    Some(match text {
+        "black" => Color::Black,
+        "dark_grey" => Color::DarkGrey,
+        "red" => Color::Red,
+        "dark_red" => Color::DarkRed,
+        "green" => Color::Green,
+        "dark_green" => Color::DarkGreen,
+        "yellow" => Color::Yellow,
+        "dark_yellow" => Color::DarkYellow,
+        "blue" => Color::Blue,
+        "dark_blue" => Color::DarkBlue,
+        "magenta" => Color::Magenta,
+        "dark_magenta" => Color::DarkMagenta,
+        "cyan" => Color::Cyan,
+        "dark_cyan" => Color::DarkCyan,
+        "white" => Color::White,
+        "grey" => Color::Grey,
+        _ => return None,
+    })
+}
+
+// What a terminal color typically looks like, for when a theme gives no rgb
+pub fn color_rgb(color: Color) -> [u8; 3] {
+    match color {
+        Color::Rgb { r, g, b } => [r, g, b],
+        Color::Black => [0, 0, 0],
+        Color::DarkGrey => [128, 128, 128],
+        Color::Red => [255, 0, 0],
+        Color::DarkRed => [128, 0, 0],
+        Color::Green => [0, 255, 0],
+        Color::DarkGreen => [0, 128, 0],
+        Color::Yellow => [255, 255, 0],
+        Color::DarkYellow => [128, 128, 0],
+        Color::Blue => [0, 0, 255],
+        Color::DarkBlue => [0, 0, 128],
+        Color::Magenta => [255, 0, 255],
+        Color::DarkMagenta => [128, 0, 128],
+        Color::Cyan => [0, 255, 255],
+        Color::DarkCyan => [0, 128, 128],
+        Color::White => [255, 255, 255],
+        Color::Grey => [192, 192, 192],
+        _ => [192, 192, 192],
+    }
+}
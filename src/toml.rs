@@ -0,0 +1,460 @@
+// Just enough TOML for config files: tables, arrays of tables, strings,
+// numbers, booleans, arrays and inline tables. No dates or dotted keys.
+use std::collections::BTreeMap;
+use std::io;
+
+pub type Table = BTreeMap<String, Value>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Array(Vec<Value>),
+    Table(Table),
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Value::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    // Integers are accepted wherever a float is expected
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Float(value) => Some(*value),
+            Value::Integer(value) => Some(*value as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Boolean(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_table(&self) -> Option<&Table> {
+        match self {
+            Value::Table(table) => Some(table),
+            _ => None,
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::Integer(_) => "integer",
+            Value::Float(_) => "float",
+            Value::Boolean(_) => "boolean",
+            Value::Array(_) => "array",
+            Value::Table(_) => "table",
+        }
+    }
+}
+
+// `text` as a basic string literal, escaped so `parse` reads it back exactly
+pub fn quote(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// `key` as written in a document: bare where it can be, quoted otherwise
+pub fn key(key: &str) -> String {
+    let bare = !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare {
+        key.to_string()
+    } else {
+        quote(key)
+    }
+}
+
+pub fn parse(text: &str) -> io::Result<Table> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        pos: 0,
+        line: 1,
+    };
+    parser.document()
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn error(&self, message: impl std::fmt::Display) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", self.line, message))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> io::Result<()> {
+        match self.bump() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(self.error(format!("expected '{}', found '{}'", expected, c))),
+            None => Err(self.error(format!("expected '{}', found end of file", expected))),
+        }
+    }
+
+    // Spaces and tabs only
+    fn skip_blank(&mut self) {
+        while matches!(self.peek(), Some(' ') | Some('\t')) {
+            self.bump();
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), Some('\n') | None) {
+                self.bump();
+            }
+        }
+    }
+
+    // Whitespace, newlines and comments, as allowed inside arrays
+    fn skip_trivia(&mut self) {
+        loop {
+            self.skip_blank();
+            self.skip_comment();
+            match self.peek() {
+                Some('\n') | Some('\r') => {
+                    self.bump();
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> io::Result<()> {
+        self.skip_blank();
+        self.skip_comment();
+        match self.peek() {
+            None | Some('\n') => Ok(()),
+            Some('\r') => {
+                self.bump();
+                Ok(())
+            }
+            Some(c) => Err(self.error(format!("unexpected '{}' after value", c))),
+        }
+    }
+
+    fn document(&mut self) -> io::Result<Table> {
+        let mut root = Table::new();
+        let mut path: Vec<String> = Vec::new();
+        let mut array_table = false;
+
+        loop {
+            self.skip_trivia();
+            match self.peek() {
+                None => return Ok(root),
+                Some('[') => {
+                    self.bump();
+                    array_table = self.peek() == Some('[');
+                    if array_table {
+                        self.bump();
+                    }
+                    path = self.header_path()?;
+                    self.expect(']')?;
+                    if array_table {
+                        self.expect(']')?;
+                    }
+                    self.end_of_line()?;
+                    self.open_table(&mut root, &path, array_table)?;
+                }
+                Some(_) => {
+                    let key = self.key()?;
+                    self.skip_blank();
+                    self.expect('=')?;
+                    self.skip_blank();
+                    let value = self.value()?;
+                    self.end_of_line()?;
+
+                    let table = self.current_table(&mut root, &path, array_table)?;
+                    if table.contains_key(&key) {
+                        return Err(self.error(format!("duplicate key '{}'", key)));
+                    }
+                    table.insert(key, value);
+                }
+            }
+        }
+    }
+
+    fn header_path(&mut self) -> io::Result<Vec<String>> {
+        let mut path = Vec::new();
+        loop {
+            self.skip_blank();
+            path.push(self.key()?);
+            self.skip_blank();
+            if self.peek() == Some('.') {
+                self.bump();
+            } else {
+                return Ok(path);
+            }
+        }
+    }
+
+    fn open_table(&self, root: &mut Table, path: &[String], array_table: bool) -> io::Result<()> {
+        let (last, parents) = path.split_last().unwrap();
+        let parent = self.walk(root, parents)?;
+        if array_table {
+            let entry = parent
+                .entry(last.clone())
+                .or_insert_with(|| Value::Array(Vec::new()));
+            match entry {
+                Value::Array(items) => items.push(Value::Table(Table::new())),
+                _ => return Err(self.error(format!("'{}' is not an array of tables", last))),
+            }
+        } else {
+            match parent.entry(last.clone()).or_insert_with(|| Value::Table(Table::new())) {
+                Value::Table(_) => {}
+                _ => return Err(self.error(format!("'{}' is not a table", last))),
+            }
+        }
+        Ok(())
+    }
+
+    fn current_table<'a>(
+        &self,
+        root: &'a mut Table,
+        path: &[String],
+        array_table: bool,
+    ) -> io::Result<&'a mut Table> {
+        if path.is_empty() {
+            return Ok(root);
+        }
+        let (last, parents) = path.split_last().unwrap();
+        let parent = self.walk(root, parents)?;
+        match parent.get_mut(last) {
+            Some(Value::Table(table)) if !array_table => Ok(table),
+            Some(Value::Array(items)) if array_table => match items.last_mut() {
+                Some(Value::Table(table)) => Ok(table),
+                _ => Err(self.error(format!("'{}' is not an array of tables", last))),
+            },
+            _ => Err(self.error(format!("'{}' is not a table", last))),
+        }
+    }
+
+    // Follows (creating as needed) nested tables; arrays of tables resolve to their last entry
+    fn walk<'a>(&self, mut table: &'a mut Table, path: &[String]) -> io::Result<&'a mut Table> {
+        for key in path {
+            let entry = table
+                .entry(key.clone())
+                .or_insert_with(|| Value::Table(Table::new()));
+            table = match entry {
+                Value::Table(inner) => inner,
+                Value::Array(items) => match items.last_mut() {
+                    Some(Value::Table(inner)) => inner,
+                    _ => return Err(self.error(format!("'{}' is not a table", key))),
+                },
+                _ => return Err(self.error(format!("'{}' is not a table", key))),
+            };
+        }
+        Ok(table)
+    }
+
+    fn key(&mut self) -> io::Result<String> {
+        match self.peek() {
+            Some('"') => self.basic_string(),
+            Some('\'') => self.literal_string(),
+            _ => {
+                let mut key = String::new();
+                while let Some(c) = self.peek() {
+                    if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                        key.push(c);
+                        self.bump();
+                    } else {
+                        break;
+                    }
+                }
+                if key.is_empty() {
+                    Err(self.error("expected a key"))
+                } else {
+                    Ok(key)
+                }
+            }
+        }
+    }
+
+    fn value(&mut self) -> io::Result<Value> {
+        match self.peek() {
+            Some('"') => self.basic_string().map(Value::String),
+            Some('\'') => self.literal_string().map(Value::String),
+            Some('[') => self.array(),
+            Some('{') => self.inline_table(),
+            Some('t') | Some('f') => {
+                let word = self.bare_word();
+                match word.as_str() {
+                    "true" => Ok(Value::Boolean(true)),
+                    "false" => Ok(Value::Boolean(false)),
+                    _ => Err(self.error(format!("invalid value '{}'", word))),
+                }
+            }
+            Some(_) => self.number(),
+            None => Err(self.error("expected a value")),
+        }
+    }
+
+    fn bare_word(&mut self) -> String {
+        let mut word = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '+' | '.') {
+                word.push(c);
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        word
+    }
+
+    fn number(&mut self) -> io::Result<Value> {
+        let word = self.bare_word();
+        let digits = word.replace('_', "");
+        if let Ok(value) = digits.parse::<i64>() {
+            return Ok(Value::Integer(value));
+        }
+        match digits.as_str() {
+            "inf" | "+inf" => return Ok(Value::Float(f64::INFINITY)),
+            "-inf" => return Ok(Value::Float(f64::NEG_INFINITY)),
+            _ => {}
+        }
+        digits
+            .parse::<f64>()
+            .map(Value::Float)
+            .map_err(|_| self.error(format!("invalid value '{}'", word)))
+    }
+
+    fn basic_string(&mut self) -> io::Result<String> {
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(value),
+                Some('\\') => match self.bump() {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some('r') => value.push('\r'),
+                    Some('"') => value.push('"'),
+                    Some('\\') => value.push('\\'),
+                    Some('u') => {
+                        let hex: String = (0..4).filter_map(|_| self.bump()).collect();
+                        let c = u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| self.error(format!("invalid escape \\u{}", hex)))?;
+                        value.push(c);
+                    }
+                    Some(c) => return Err(self.error(format!("invalid escape \\{}", c))),
+                    None => return Err(self.error("unterminated string")),
+                },
+                Some('\n') | None => return Err(self.error("unterminated string")),
+                Some(c) => value.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> io::Result<String> {
+        self.expect('\'')?;
+        let mut value = String::new();
+        loop {
+            match self.bump() {
+                Some('\'') => return Ok(value),
+                Some('\n') | None => return Err(self.error("unterminated string")),
+                Some(c) => value.push(c),
+            }
+        }
+    }
+
+    fn array(&mut self) -> io::Result<Value> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        loop {
+            self.skip_trivia();
+            if self.peek() == Some(']') {
+                self.bump();
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_trivia();
+            match self.peek() {
+                Some(',') => {
+                    self.bump();
+                }
+                Some(']') => {}
+                _ => return Err(self.error("expected ',' or ']' in array")),
+            }
+        }
+    }
+
+    fn inline_table(&mut self) -> io::Result<Value> {
+        self.expect('{')?;
+        let mut table = Table::new();
+        loop {
+            self.skip_blank();
+            if self.peek() == Some('}') {
+                self.bump();
+                return Ok(Value::Table(table));
+            }
+            let key = self.key()?;
+            self.skip_blank();
+            self.expect('=')?;
+            self.skip_blank();
+            let value = self.value()?;
+            table.insert(key, value);
+            self.skip_blank();
+            match self.peek() {
+                Some(',') => {
+                    self.bump();
+                }
+                Some('}') => {}
+                _ => return Err(self.error("expected ',' or '}' in inline table")),
+            }
+        }
+    }
+}
@@ -0,0 +1,155 @@
+// Where players are likely to walk. Every pair of rooms is joined by its
+// shortest walks, and each tile is scored by how many of them pass over it,
+// a walk shared between several equally short ones counting for its share.
+// Junctions and corridors between busy rooms come out hot, dead ends cold,
+// which makes the map a fair guide for where traps and ambushes will be
+// met. Rooms are those the explorer sees, bounded by walls and doors; a cave
+// with too few of them is sampled on a grid instead.
+use crate::dungeon::{Dungeon, Point};
+use crate::events::room_labels;
+use crate::rows::render_rows;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+// Tiles between sample points on a map without rooms to speak of
+const SAMPLE_SPACING: usize = 8;
+
+impl Dungeon {
+    // Shortest walks between rooms passing over each tile, by row; the rooms'
+    // own middle tiles count only the walks going through them
+    pub fn traffic(&self) -> Vec<Vec<f64>> {
+        let mut traffic = vec![vec![0.0; self.width]; self.height];
+        let anchors = self.traffic_anchors();
+        if anchors.len() < 2 {
+            return traffic;
+        }
+        let mut anchor = vec![false; self.width * self.height];
+        for &(x, y) in &anchors {
+            anchor[y * self.width + x] = true;
+        }
+
+        // Brandes' accumulation, counting only the walks that end at anchors
+        let mut distance = vec![usize::MAX; self.width * self.height];
+        let mut paths = vec![0.0f64; self.width * self.height];
+        let mut share = vec![0.0f64; self.width * self.height];
+        let mut order = Vec::new();
+        for &(sx, sy) in &anchors {
+            distance.fill(usize::MAX);
+            paths.fill(0.0);
+            share.fill(0.0);
+            order.clear();
+            let source = sy * self.width + sx;
+            distance[source] = 0;
+            paths[source] = 1.0;
+            let mut queue = VecDeque::from([(sx, sy)]);
+            while let Some((x, y)) = queue.pop_front() {
+                let index = y * self.width + x;
+                order.push((x, y));
+                for (nx, ny) in self.traffic_steps((x, y)) {
+                    let next = ny * self.width + nx;
+                    if distance[next] == usize::MAX {
+                        distance[next] = distance[index] + 1;
+                        queue.push_back((nx, ny));
+                    }
+                    if distance[next] == distance[index] + 1 {
+                        paths[next] += paths[index];
+                    }
+                }
+            }
+            for &(x, y) in order.iter().rev() {
+                let index = y * self.width + x;
+                let ending = if anchor[index] && index != source { 1.0 } else { 0.0 };
+                let onward = (ending + share[index]) / paths[index];
+                for (px, py) in self.traffic_steps((x, y)) {
+                    let previous = py * self.width + px;
+                    if distance[previous] != usize::MAX && distance[previous] + 1 == distance[index] {
+                        share[previous] += paths[previous] * onward;
+                    }
+                }
+                if index != source {
+                    traffic[y][x] += share[index];
+                }
+            }
+        }
+        // Each pair was walked once from either end
+        for value in traffic.iter_mut().flatten() {
+            *value /= 2.0;
+        }
+        traffic
+    }
+
+    // The heatmap as CSV, one map row per line, with -1 for rock
+    pub fn write_traffic_map(&self, path: &Path) -> io::Result<()> {
+        let traffic = self.traffic();
+        let rows = render_rows(self.height, self.width, |y| {
+            let cells: Vec<String> = (0..self.width)
+                .map(|x| {
+                    if self.tiles[y][x].is_walkable() {
+                        format!("{:.2}", traffic[y][x])
+                    } else {
+                        "-1".to_string()
+                    }
+                })
+                .collect();
+            cells.join(",")
+        });
+        let mut out = BufWriter::new(File::create(path)?);
+        for row in rows {
+            writeln!(out, "{}", row)?;
+        }
+        out.flush()
+    }
+
+    // The tile of each room nearest its middle, or walkable tiles spread on
+    // a grid when the map is one open space
+    fn traffic_anchors(&self) -> Vec<Point> {
+        let (labels, rooms) = room_labels(self);
+        if rooms >= 2 {
+            let mut sums = vec![(0, 0, 0); rooms];
+            for (y, row) in labels.iter().enumerate() {
+                for (x, label) in row.iter().enumerate() {
+                    if let Some(room) = *label {
+                        let (sx, sy, count) = &mut sums[room];
+                        (*sx, *sy, *count) = (*sx + x, *sy + y, *count + 1);
+                    }
+                }
+            }
+            let mut anchors: Vec<Option<(Point, usize)>> = vec![None; rooms];
+            for (y, row) in labels.iter().enumerate() {
+                for (x, label) in row.iter().enumerate() {
+                    if let Some(room) = *label {
+                        let (sx, sy, count) = sums[room];
+                        let off = x.abs_diff(sx / count) + y.abs_diff(sy / count);
+                        if anchors[room].is_none_or(|(_, best)| off < best) {
+                            anchors[room] = Some(((x, y), off));
+                        }
+                    }
+                }
+            }
+            return anchors.into_iter().flatten().map(|(point, _)| point).collect();
+        }
+        let mut anchors = Vec::new();
+        for y in (SAMPLE_SPACING / 2..self.height).step_by(SAMPLE_SPACING) {
+            for x in (SAMPLE_SPACING / 2..self.width).step_by(SAMPLE_SPACING) {
+                if self.tiles[y][x].is_walkable() {
+                    anchors.push((x, y));
+                }
+            }
+        }
+        anchors
+    }
+
+    fn traffic_steps(&self, (x, y): Point) -> impl Iterator<Item = Point> + '_ {
+        [
+            (x > 0).then(|| (x - 1, y)),
+            (y > 0).then(|| (x, y - 1)),
+            (x + 1 < self.width).then_some((x + 1, y)),
+            (y + 1 < self.height).then_some((x, y + 1)),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|&(nx, ny)| self.tiles[ny][nx].is_walkable())
+    }
+}
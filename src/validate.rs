@@ -0,0 +1,293 @@
+// Structural checks of a saved map, for maps that come out of a hand editor
+// or another tool rather than the generator. `Dungeon::load` pads short rows
+// and gives up at the first glyph it doesn't know; `validate` reads the file
+// as it stands and lists everything wrong with it, so one run shows all there
+// is to fix. A bundle goes through its own reader, which already turns away
+// ragged rows, unknown terrain and positions off the map. Ground cut off
+// from the rest is allowed, as the generator leaves it with connectivity
+// off, so long as nothing placed on the map is stranded there.
+use crate::bundle::{self, Bundle};
+use crate::door::DoorState;
+use crate::dungeon::{Dungeon, Point};
+use crate::layers::{Entity, Feature, LayerKind};
+use crate::locks::LOCK_KEY;
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Problems a report lists under one check before summing up the rest
+const SHOWN: usize = 10;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    // With a word on what was found
+    Passed(String),
+    Failed(Vec<String>),
+    // An earlier check left no map to run it on
+    Skipped,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub outcome: Outcome,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Validation {
+    pub checks: Vec<Check>,
+}
+
+impl Validation {
+    pub fn passed(&self) -> bool {
+        !self.checks.iter().any(|check| matches!(check.outcome, Outcome::Failed(_)))
+    }
+
+    // A line a check, with the problems of any that failed beneath it
+    pub fn report(&self) -> String {
+        let mut out = String::new();
+        for check in &self.checks {
+            let status = match &check.outcome {
+                Outcome::Passed(found) => format!("ok, {}", found),
+                Outcome::Failed(problems) if problems.len() == 1 => "1 problem".to_string(),
+                Outcome::Failed(problems) => format!("{} problems", problems.len()),
+                Outcome::Skipped => "skipped".to_string(),
+            };
+            out.push_str(&format!("  {:<14} {}\n", check.name, status));
+            if let Outcome::Failed(problems) = &check.outcome {
+                for problem in problems.iter().take(SHOWN) {
+                    out.push_str(&format!("    {}\n", problem));
+                }
+                if problems.len() > SHOWN {
+                    out.push_str(&format!("    and {} more\n", problems.len() - SHOWN));
+                }
+            }
+        }
+        let failed = self.checks.iter().filter(|check| matches!(check.outcome, Outcome::Failed(_))).count();
+        if failed == 0 {
+            out.push_str("Valid\n");
+        } else {
+            out.push_str(&format!("Invalid: {} of {} checks failed\n", failed, self.checks.len()));
+        }
+        out
+    }
+}
+
+fn check(name: &'static str, problems: Vec<String>, found: String) -> Check {
+    let outcome = if problems.is_empty() { Outcome::Passed(found) } else { Outcome::Failed(problems) };
+    Check { name, outcome }
+}
+
+// A map file as written by `to_ascii`, a `write_layers` directory or a
+// `.dungeon` bundle, as `Dungeon::load` takes. Only a file that can't be
+// read at all is an error; anything wrong inside it is in the report.
+pub fn validate(path: &Path) -> io::Result<Validation> {
+    let mut checks = Vec::new();
+    let dungeon = if bundle::is_bundle(path) {
+        match Bundle::load(path) {
+            Ok(bundle) => {
+                checks.push(check("format", Vec::new(), "a readable bundle".to_string()));
+                Some(bundle.dungeon)
+            }
+            Err(error) if error.kind() == io::ErrorKind::InvalidData => {
+                checks.push(check("format", vec![error.to_string()], String::new()));
+                None
+            }
+            Err(error) => return Err(error),
+        }
+    } else {
+        let drawings = drawings(path)?;
+        let (dimensions, glyphs) = (dimension_problems(&drawings), glyph_problems(&drawings));
+        let (width, height) = size(&drawings[0].1);
+        checks.push(check("dimensions", dimensions, format!("{}x{}", width, height)));
+        let loads = glyphs.is_empty() && width > 0;
+        checks.push(check("glyphs", glyphs, "every glyph known".to_string()));
+        if loads {
+            Some(Dungeon::load(path)?)
+        } else {
+            None
+        }
+    };
+
+    match dungeon {
+        Some(dungeon) => {
+            let (stranded, pieces) = connectivity_problems(&dungeon);
+            let found = match pieces {
+                1 => "one region".to_string(),
+                pieces => format!("{} regions, nothing placed off the main one", pieces),
+            };
+            checks.push(check("connectivity", stranded, found));
+            let placed = dungeon.features.len() + dungeon.entities.len() + dungeon.meta.len();
+            checks.push(check("positions", position_problems(&dungeon), format!("{} placed", placed)));
+        }
+        None => {
+            for name in ["connectivity", "positions"] {
+                checks.push(Check { name, outcome: Outcome::Skipped });
+            }
+        }
+    }
+    Ok(Validation { checks })
+}
+
+// Each file of the map with its text, the terrain first; a layers directory
+// may leave out the feature and entity files
+fn drawings(path: &Path) -> io::Result<Vec<(String, String, LayerKind)>> {
+    if !path.is_dir() {
+        return Ok(vec![(String::new(), fs::read_to_string(path)?, LayerKind::Terrain)]);
+    }
+    let mut drawings = Vec::new();
+    for kind in LayerKind::ALL {
+        let file = format!("{}.txt", kind.name());
+        match fs::read_to_string(path.join(&file)) {
+            Ok(text) => drawings.push((format!("{}: ", file), text, kind)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound && kind != LayerKind::Terrain => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(drawings)
+}
+
+// Widest line by lines
+fn size(text: &str) -> (usize, usize) {
+    (text.lines().map(|line| line.chars().count()).max().unwrap_or(0), text.lines().count())
+}
+
+// Every line as wide as the widest terrain line, and every layer as tall
+// as the terrain
+fn dimension_problems(drawings: &[(String, String, LayerKind)]) -> Vec<String> {
+    let (width, height) = size(&drawings[0].1);
+    if width == 0 {
+        return vec![format!("{}the map is empty", drawings[0].0)];
+    }
+    let mut problems = Vec::new();
+    for (file, text, _) in drawings {
+        let lines = text.lines().count();
+        if lines != height {
+            problems.push(format!("{}{} lines, not {}", file, lines, height));
+        }
+        for (y, line) in text.lines().enumerate() {
+            let length = line.chars().count();
+            if length != width {
+                problems.push(format!("{}line {} is {} wide, not {}", file, y + 1, length, width));
+            }
+        }
+    }
+    problems
+}
+
+fn glyph_problems(drawings: &[(String, String, LayerKind)]) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (file, text, kind) in drawings {
+        for (y, line) in text.lines().enumerate() {
+            for (x, glyph) in line.chars().enumerate() {
+                // The sparse layers leave empty cells blank
+                let blank = glyph == ' ' && *kind != LayerKind::Terrain;
+                if !blank && !Dungeon::reads_glyph(glyph) {
+                    problems.push(format!("{}unknown glyph '{}' at line {}, column {}", file, glyph, y + 1, x + 1));
+                }
+            }
+        }
+    }
+    problems
+}
+
+// Every entity on the same stretch of ground as the entrance, or as the
+// largest stretch where none is marked, every door taken as open since
+// keys are checked for below. Also returns how many stretches there are.
+fn connectivity_problems(dungeon: &Dungeon) -> (Vec<String>, usize) {
+    let mut piece = vec![vec![None; dungeon.width]; dungeon.height];
+    let mut sizes: Vec<usize> = Vec::new();
+    for y in 0..dungeon.height {
+        for x in 0..dungeon.width {
+            if piece[y][x].is_some() || !dungeon.tiles[y][x].is_walkable() {
+                continue;
+            }
+            let label = sizes.len();
+            sizes.push(0);
+            piece[y][x] = Some(label);
+            let mut queue = VecDeque::from([(x, y)]);
+            while let Some((x, y)) = queue.pop_front() {
+                sizes[label] += 1;
+                for (dx, dy) in [(0, -1), (1, 0), (0, 1), (-1, 0)] {
+                    let (nx, ny) = (x as isize + dx, y as isize + dy);
+                    if nx < 0 || ny < 0 || nx as usize >= dungeon.width || ny as usize >= dungeon.height {
+                        continue;
+                    }
+                    let (nx, ny) = (nx as usize, ny as usize);
+                    if piece[ny][nx].is_none() && dungeon.tiles[ny][nx].is_walkable() {
+                        piece[ny][nx] = Some(label);
+                        queue.push_back((nx, ny));
+                    }
+                }
+            }
+        }
+    }
+
+    let entrance = dungeon.entities.iter().find(|(_, entity)| **entity == Entity::Entrance).map(|(point, _)| point);
+    let main = match entrance {
+        Some((x, y)) => piece.get(y).and_then(|row| row.get(x)).copied().flatten(),
+        None => (0..sizes.len()).max_by_key(|&label| (sizes[label], std::cmp::Reverse(label))),
+    };
+    let from = if entrance.is_some() { "the entrance" } else { "the main region" };
+    let mut entities: Vec<(Point, &Entity)> = dungeon.entities.iter().collect();
+    entities.sort_by_key(|&((x, y), _)| (y, x));
+    let mut problems = Vec::new();
+    for ((x, y), entity) in entities {
+        let here = piece.get(y).and_then(|row| row.get(x)).copied().flatten();
+        // Off the map or in wall, which the positions check reports
+        if here.is_some() && here != main {
+            problems.push(format!("entity '{}' at ({}, {}) can't be reached from {}", entity.glyph(), x, y, from));
+        }
+    }
+    (problems, sizes.len())
+}
+
+// Features and entities on ground that can hold them, meta on the map, and
+// every lock number on both a locked door and a key
+fn position_problems(dungeon: &Dungeon) -> Vec<String> {
+    let on_map = |x: usize, y: usize| x < dungeon.width && y < dungeon.height;
+    let walkable = |x: usize, y: usize| on_map(x, y) && dungeon.tiles[y][x].is_walkable();
+    let mut problems = Vec::new();
+    let mut features: Vec<_> = dungeon.features.iter().collect();
+    features.sort_by_key(|&((x, y), _)| (y, x));
+    for ((x, y), feature) in features {
+        if !walkable(x, y) {
+            problems.push(format!("feature '{}' at ({}, {}) is not on walkable ground", feature.glyph(), x, y));
+        }
+    }
+    let mut entities: Vec<_> = dungeon.entities.iter().collect();
+    entities.sort_by_key(|&((x, y), _)| (y, x));
+    for ((x, y), entity) in entities {
+        if !walkable(x, y) {
+            problems.push(format!("entity '{}' at ({}, {}) is not on walkable ground", entity.glyph(), x, y));
+        }
+    }
+
+    // Lock number to whether a locked door and a key carry it
+    let mut locks: BTreeMap<i64, (bool, bool)> = BTreeMap::new();
+    let mut meta: Vec<_> = dungeon.meta.iter().collect();
+    meta.sort_by_key(|&((x, y), _)| (y, x));
+    for ((x, y), values) in meta {
+        if !on_map(x, y) {
+            problems.push(format!("meta at ({}, {}) is off the map", x, y));
+            continue;
+        }
+        let Some(lock) = values.get(LOCK_KEY).and_then(|value| value.as_int()) else {
+            continue;
+        };
+        match (dungeon.features.get(x, y), dungeon.entities.get(x, y)) {
+            (Some(Feature::Door(door)), _) if door.state == DoorState::Locked => locks.entry(lock).or_default().0 = true,
+            (_, Some(Entity::Item(_))) => locks.entry(lock).or_default().1 = true,
+            _ => problems.push(format!("lock {} at ({}, {}) marks neither a locked door nor a key", lock, x, y)),
+        }
+    }
+    for (lock, (door, key)) in locks {
+        if !door {
+            problems.push(format!("lock {} has a key but no locked door", lock));
+        } else if !key {
+            problems.push(format!("lock {} has a locked door but no key", lock));
+        }
+    }
+    problems
+}
@@ -0,0 +1,152 @@
+// Resource veins running through the rock, for games about mining. Each vein
+// is a random walk through wall tiles from a wall picked at random, so some
+// show in cave walls and some lie buried until dug out. Every tile a vein
+// passes through is tagged in the meta layer with its resource under
+// `ORE_KEY` and the vein's richness under `RICHNESS_KEY`; where two veins
+// cross, the first one placed keeps the tile. Frozen walls carry no ore.
+use crate::config::{invalid, read_usize, unknown_key};
+use crate::dungeon::{Dungeon, Point, Tile};
+use crate::loot::read_rolls;
+use crate::population::read_weights;
+use crate::toml::{self, Table};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::io;
+
+pub const ORE_KEY: &str = "ore";
+pub const RICHNESS_KEY: &str = "richness";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct VeinOptions {
+    pub count: usize,
+    // Tiles each walk tags, between the two; a walk boxed in by open ground
+    // stops short
+    pub length: (usize, usize),
+    // Richness each vein is given, between the two
+    pub richness: (usize, usize),
+    // Resources paired with their relative weights
+    pub resources: Vec<(String, usize)>,
+}
+
+impl Default for VeinOptions {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            length: (6, 18),
+            richness: (1, 5),
+            resources: vec![("copper".to_string(), 2), ("gold".to_string(), 1), ("iron".to_string(), 3)],
+        }
+    }
+}
+
+impl VeinOptions {
+    pub(crate) fn apply(&mut self, table: &Table) -> io::Result<()> {
+        for (key, value) in table {
+            match key.as_str() {
+                "count" => self.count = read_usize(key, value)?,
+                "length" => self.length = read_rolls(key, value)?,
+                "richness" => self.richness = read_rolls(key, value)?,
+                "resources" => self.resources = read_weights(key, value)?,
+                _ => return Err(unknown_key("veins.", key)),
+            }
+        }
+        if self.length.0 == 0 {
+            return Err(invalid("veins.length", "must be at least 1"));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn to_toml(&self) -> String {
+        let resources: Vec<String> =
+            self.resources.iter().map(|(name, weight)| format!("{} = {}", toml::key(name), weight)).collect();
+        format!(
+            "\n[veins]\ncount = {}\nlength = [{}, {}]\nrichness = [{}, {}]\nresources = {{ {} }}\n",
+            self.count,
+            self.length.0,
+            self.length.1,
+            self.richness.0,
+            self.richness.1,
+            resources.join(", ")
+        )
+    }
+}
+
+// One tile of ore, as read back from the meta layer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ore<'a> {
+    pub resource: &'a str,
+    pub richness: usize,
+}
+
+const SIDES: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+impl Dungeon {
+    fn diggable_rock(&self, x: i32, y: i32) -> bool {
+        x >= 0
+            && y >= 0
+            && x < self.width as i32
+            && y < self.height as i32
+            && self.tiles[y as usize][x as usize] == Tile::Wall
+            && !self.frozen[y as usize][x as usize]
+    }
+
+    // Returns the veins placed, fewer than asked for only on a map with no
+    // rock to put them in
+    pub fn seed_veins<R: Rng + ?Sized>(&mut self, options: &VeinOptions, rng: &mut R) -> usize {
+        if options.count == 0 || options.resources.is_empty() {
+            return 0;
+        }
+        let mut rock: Vec<Point> = Vec::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.diggable_rock(x as i32, y as i32) {
+                    rock.push((x, y));
+                }
+            }
+        }
+        if rock.is_empty() {
+            return 0;
+        }
+
+        for _ in 0..options.count {
+            let (resource, _) = options.resources.choose_weighted(rng, |(_, weight)| *weight).unwrap();
+            let richness = rng.gen_range(options.richness.0..=options.richness.1.max(options.richness.0));
+            let length = rng.gen_range(options.length.0..=options.length.1.max(options.length.0));
+            let (mut x, mut y) = *rock.choose(rng).unwrap();
+            let mut tagged = 0;
+            // A walk may double back over itself, so it gets a few tries a tile
+            for _ in 0..length * 4 {
+                if !self.meta(x, y).is_some_and(|meta| meta.contains(ORE_KEY)) {
+                    self.set_meta(x, y, ORE_KEY, resource.as_str());
+                    self.set_meta(x, y, RICHNESS_KEY, richness as i64);
+                    tagged += 1;
+                }
+                if tagged == length {
+                    break;
+                }
+                let steps: Vec<Point> = SIDES
+                    .iter()
+                    .map(|&(dx, dy)| (x as i32 + dx, y as i32 + dy))
+                    .filter(|&(nx, ny)| self.diggable_rock(nx, ny))
+                    .map(|(nx, ny)| (nx as usize, ny as usize))
+                    .collect();
+                let Some(&next) = steps.choose(rng) else {
+                    break;
+                };
+                (x, y) = next;
+            }
+        }
+        options.count
+    }
+
+    // The ore here, wherever a vein left it; digging a wall out leaves its
+    // ore on the floor for the game to collect
+    pub fn ore(&self, x: usize, y: usize) -> Option<Ore<'_>> {
+        let meta = self.meta(x, y)?;
+        let richness = meta.get(RICHNESS_KEY).and_then(|value| value.as_int()).unwrap_or(0);
+        Some(Ore {
+            resource: meta.get(ORE_KEY)?.as_str()?,
+            richness: richness.max(0) as usize,
+        })
+    }
+}
@@ -0,0 +1,189 @@
+use crate::exploration::{Exploration, Visibility};
+use crate::gif::GifEncoder;
+use crate::door::DoorState;
+use crate::pathfinding::{entrance_and_exit, PathOptions};
+use crate::solver;
+use crate::theme::Theme;
+use crate::{Dungeon, Point, Tile};
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+const UNSEEN: u8 = 0;
+const WALL_REMEMBERED: u8 = 1;
+const FLOOR_REMEMBERED: u8 = 2;
+const WALL_VISIBLE: u8 = 3;
+const FLOOR_VISIBLE: u8 = 4;
+const TRAIL: u8 = 5;
+const PLAYER: u8 = 6;
+const ENTRANCE: u8 = 7;
+const EXIT: u8 = 8;
+const DOOR_REMEMBERED: u8 = 9;
+const DOOR_VISIBLE: u8 = 10;
+const WATER_REMEMBERED: u8 = 11;
+const WATER_VISIBLE: u8 = 12;
+const RUBBLE_REMEMBERED: u8 = 13;
+const RUBBLE_VISIBLE: u8 = 14;
+
+// Remembered tiles are drawn at this fraction of their theme color
+const REMEMBERED_BRIGHTNESS: f32 = 0.4;
+
+fn palette(theme: &Theme) -> [[u8; 3]; 15] {
+    let dim = |[r, g, b]: [u8; 3]| {
+        [r, g, b].map(|channel| (channel as f32 * REMEMBERED_BRIGHTNESS) as u8)
+    };
+    [
+        [0, 0, 0],
+        dim(theme.wall.rgb),
+        dim(theme.floor.rgb),
+        theme.wall.rgb,
+        theme.floor.rgb,
+        [170, 110, 60],
+        [220, 40, 40],
+        [60, 120, 220],
+        [60, 200, 90],
+        [70, 44, 24],
+        [150, 90, 40],
+        dim(theme.water.rgb),
+        theme.water.rgb,
+        dim(theme.rubble.rgb),
+        theme.rubble.rgb,
+    ]
+}
+
+pub struct WalkthroughOptions {
+    // Pixels per tile edge
+    pub scale: usize,
+    pub sight_radius: usize,
+    pub frame_delay_centis: u16,
+    pub path_options: PathOptions,
+    pub theme: Theme,
+}
+
+impl Default for WalkthroughOptions {
+    fn default() -> Self {
+        Self {
+            scale: 4,
+            sight_radius: 8,
+            frame_delay_centis: 6,
+            path_options: PathOptions::default(),
+            theme: Theme::default(),
+        }
+    }
+}
+
+// Walks the cheapest entrance->exit route, revealing the map as it goes and
+// fetching keys for locked doors as the solver does. Returns the number of
+// steps in the route.
+pub fn write_walkthrough(
+    dungeon: &Dungeon,
+    options: &WalkthroughOptions,
+    path: &Path,
+) -> io::Result<usize> {
+    let (entrance, exit) = entrance_and_exit(dungeon).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "dungeon has no floor to walk")
+    })?;
+    let route = solver::solve_between(dungeon, entrance, exit, &options.path_options)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no way from the entrance to the exit, even with the keys"))?
+        .route;
+
+    let width = dungeon.width * options.scale;
+    let height = dungeon.height * options.scale;
+    let out = BufWriter::new(File::create(path)?);
+    let mut encoder = GifEncoder::new(out, width as u16, height as u16, &palette(&options.theme))?;
+
+    let mut exploration = Exploration::for_dungeon(dungeon);
+    for (step, &position) in route.iter().enumerate() {
+        exploration.look(dungeon, position, options.sight_radius);
+
+        let frame = render_frame(dungeon, options.scale, &exploration, &route[..=step], (entrance, exit));
+        // Linger on the final frame so the loop reads as finished
+        let delay = if step + 1 == route.len() {
+            options.frame_delay_centis * 25
+        } else {
+            options.frame_delay_centis
+        };
+        encoder.add_frame(&frame, delay)?;
+    }
+
+    encoder.finish()?;
+    Ok(route.len() - 1)
+}
+
+fn render_frame(
+    dungeon: &Dungeon,
+    scale: usize,
+    exploration: &Exploration,
+    trail: &[Point],
+    (entrance, exit): (Point, Point),
+) -> Vec<u8> {
+    let mut tiles = vec![vec![UNSEEN; dungeon.width]; dungeon.height];
+
+    for (y, row) in tiles.iter_mut().enumerate() {
+        for (x, cell) in row.iter_mut().enumerate() {
+            // Secret doors pass for wall
+            let door = dungeon.door(x, y).map(|door| door.state == DoorState::Secret);
+            let look = match (dungeon.tiles[y][x], door) {
+                (Tile::Wall, _) | (_, Some(true)) => (WALL_VISIBLE, WALL_REMEMBERED),
+                (_, Some(false)) => (DOOR_VISIBLE, DOOR_REMEMBERED),
+                (Tile::Floor | Tile::Bridge, None) => (FLOOR_VISIBLE, FLOOR_REMEMBERED),
+                (Tile::Water, None) => (WATER_VISIBLE, WATER_REMEMBERED),
+                (Tile::Rubble, None) => (RUBBLE_VISIBLE, RUBBLE_REMEMBERED),
+                (tile @ Tile::Custom(_), None) if tile.is_walkable() => (FLOOR_VISIBLE, FLOOR_REMEMBERED),
+                (Tile::Custom(_), None) => (WALL_VISIBLE, WALL_REMEMBERED),
+            };
+            *cell = match exploration.visibility(x, y) {
+                Visibility::Visible => look.0,
+                Visibility::Remembered => look.1,
+                Visibility::Unseen => UNSEEN,
+            };
+        }
+    }
+    for &(x, y) in trail {
+        tiles[y][x] = TRAIL;
+    }
+    tiles[entrance.1][entrance.0] = ENTRANCE;
+    if exploration.is_seen(exit.0, exit.1) {
+        tiles[exit.1][exit.0] = EXIT;
+    }
+    let (px, py) = *trail.last().unwrap();
+    tiles[py][px] = PLAYER;
+
+    let mut pixels = Vec::with_capacity(dungeon.width * dungeon.height * scale * scale);
+    for row in &tiles {
+        for _ in 0..scale {
+            for &index in row {
+                pixels.extend(std::iter::repeat_n(index, scale));
+            }
+        }
+    }
+    pixels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate_reported;
+    use crate::layers::Entity;
+    use crate::Config;
+
+    #[test]
+    fn walks_through_locked_doors_with_their_keys() {
+        let mut config = Config::default();
+        config.locks.keys = 2;
+        let (dungeon, report) = generate_reported(&config, 5);
+        // The route has room for only one
+        assert_eq!(report.locks, 1);
+        let options = WalkthroughOptions::default();
+        let path = std::env::temp_dir().join(format!("walkthrough-{}.gif", std::process::id()));
+        let steps = write_walkthrough(&dungeon, &options, &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(steps, solver::solve(&dungeon, &options.path_options).unwrap().steps());
+
+        // Without its keys the exit can't be reached
+        let mut keyless = dungeon.clone();
+        keyless.entities.retain(|_, entity| !matches!(entity, Entity::Item(name) if name == solver::KEY));
+        let error = write_walkthrough(&keyless, &options, &path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,178 @@
+// Where a map sits in a larger world, for engines that stitch many maps
+// into one coordinate system: which edge of the map faces north, how long a
+// tile is in world units, and where the map's south-west corner lies. World
+// coordinates run east along x and north along y, so with the defaults, the
+// top edge north and one unit a tile from the origin, a map's rows are
+// simply flipped. The frame is set from the `[world]` section of a config,
+// travels with the map in bundles, turns the compass in `Dungeon::describe`
+// and `Dungeon::history`, and places the walls of an exported mesh.
+use crate::config::{invalid, unknown_key};
+use crate::describe::Compass;
+use crate::dungeon::{Dungeon, Point};
+use crate::json::Json;
+use crate::toml::{self, Table};
+use std::io;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NorthEdge {
+    #[default]
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+impl NorthEdge {
+    pub const ALL: [NorthEdge; 4] = [NorthEdge::Top, NorthEdge::Right, NorthEdge::Bottom, NorthEdge::Left];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            NorthEdge::Top => "top",
+            NorthEdge::Right => "right",
+            NorthEdge::Bottom => "bottom",
+            NorthEdge::Left => "left",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|edge| edge.name() == name)
+    }
+
+    // Quarter turns clockwise from the top edge
+    pub fn quarter_turns(self) -> usize {
+        self as usize
+    }
+
+    // A tile and the map's size as they read with north up: the tile's
+    // column counted from the west and row counted from the north, then the
+    // map's width and height that way up
+    pub fn orient(self, (x, y): Point, (width, height): (usize, usize)) -> (Point, (usize, usize)) {
+        match self {
+            NorthEdge::Top => ((x, y), (width, height)),
+            NorthEdge::Right => ((y, width - 1 - x), (height, width)),
+            NorthEdge::Bottom => ((width - 1 - x, height - 1 - y), (width, height)),
+            NorthEdge::Left => ((height - 1 - y, x), (height, width)),
+        }
+    }
+
+    // A direction read off the map as if its top were north, turned to the
+    // world's
+    pub fn compass(self, on_map: Compass) -> Compass {
+        let index = Compass::ALL.iter().position(|&compass| compass == on_map).unwrap_or(0);
+        Compass::ALL[(index + 8 - 2 * self.quarter_turns()) % 8]
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WorldFrame {
+    pub north: NorthEdge,
+    // World units along a tile's side
+    pub tile_size: f64,
+    // World position of the map's south-west corner
+    pub origin: (f64, f64),
+}
+
+impl Default for WorldFrame {
+    fn default() -> Self {
+        Self {
+            north: NorthEdge::Top,
+            tile_size: 1.0,
+            origin: (0.0, 0.0),
+        }
+    }
+}
+
+impl WorldFrame {
+    // A position on a map of the given size, in tiles from its top-left
+    // corner, as a world position
+    pub fn to_world(self, (x, y): (f64, f64), (width, height): (usize, usize)) -> (f64, f64) {
+        let (width, height) = (width as f64, height as f64);
+        let (east, north) = match self.north {
+            NorthEdge::Top => (x, height - y),
+            NorthEdge::Right => (y, x),
+            NorthEdge::Bottom => (width - x, y),
+            NorthEdge::Left => (height - y, width - x),
+        };
+        (self.origin.0 + east * self.tile_size, self.origin.1 + north * self.tile_size)
+    }
+
+    pub(crate) fn apply(&mut self, table: &Table) -> io::Result<()> {
+        for (key, value) in table {
+            match key.as_str() {
+                "north" => {
+                    let name = value.as_str().ok_or_else(|| invalid(key, "must be a string"))?;
+                    self.north = NorthEdge::parse(name)
+                        .ok_or_else(|| invalid(key, "must be \"top\", \"right\", \"bottom\" or \"left\""))?;
+                }
+                "tile_size" => {
+                    self.tile_size = value
+                        .as_float()
+                        .filter(|size| size.is_finite() && *size > 0.0)
+                        .ok_or_else(|| invalid(key, "must be a positive number"))?;
+                }
+                "origin" => {
+                    let pair = || invalid(key, "must be a pair of numbers, [x, y]");
+                    self.origin = match value.as_array() {
+                        Some([x, y]) => (x.as_float().ok_or_else(pair)?, y.as_float().ok_or_else(pair)?),
+                        _ => return Err(pair()),
+                    };
+                }
+                _ => return Err(unknown_key("world.", key)),
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn to_toml(self) -> String {
+        format!(
+            "\n[world]\nnorth = {}\ntile_size = {:?}\norigin = [{:?}, {:?}]\n",
+            toml::quote(self.north.name()),
+            self.tile_size,
+            self.origin.0,
+            self.origin.1
+        )
+    }
+
+    pub(crate) fn to_json(self) -> String {
+        format!(
+            "{{\"north\":\"{}\",\"tile_size\":{:?},\"origin\":[{:?},{:?}]}}",
+            self.north.name(),
+            self.tile_size,
+            self.origin.0,
+            self.origin.1
+        )
+    }
+
+    pub(crate) fn from_json(world: &Json) -> io::Result<Self> {
+        let north = world.get("north").and_then(Json::as_str).ok_or_else(|| invalid("north", "must be a string"))?;
+        let pair = || invalid("origin", "must be a pair of numbers");
+        let origin = match world.get("origin").and_then(Json::as_array) {
+            Some([x, y]) => (x.as_f64().ok_or_else(pair)?, y.as_f64().ok_or_else(pair)?),
+            _ => return Err(pair()),
+        };
+        Ok(Self {
+            north: NorthEdge::parse(north).ok_or_else(|| invalid("north", "must be top, right, bottom or left"))?,
+            tile_size: world
+                .get("tile_size")
+                .and_then(Json::as_f64)
+                .filter(|size| *size > 0.0)
+                .ok_or_else(|| invalid("tile_size", "must be a positive number"))?,
+            origin,
+        })
+    }
+}
+
+impl Dungeon {
+    pub fn world(&self) -> WorldFrame {
+        self.world
+    }
+
+    pub fn set_world(&mut self, world: WorldFrame) {
+        self.world = world;
+    }
+
+    // The world position of a tile's centre
+    pub fn world_position(&self, (x, y): Point) -> (f64, f64) {
+        self.world.to_world((x as f64 + 0.5, y as f64 + 0.5), (self.width, self.height))
+    }
+}
@@ -0,0 +1,434 @@
+// Just enough YAML for level recipes: block mappings and sequences nested by
+// indentation, `[a, b]` and `{ key: value }` on one line, plain and quoted
+// scalars, and comments. No anchors, tags, multi-line scalars or nulls.
+// Documents come out as TOML values, so whatever reads a config table can
+// read the same settings from YAML.
+use crate::toml::{Table, Value};
+use std::io;
+
+pub(crate) fn parse(text: &str) -> io::Result<Table> {
+    let mut lines = Vec::new();
+    for (index, raw) in text.lines().enumerate() {
+        let number = index + 1;
+        if raw[..raw.len() - raw.trim_start().len()].contains('\t') {
+            return Err(error(number, "tabs can't indent YAML"));
+        }
+        let content = strip_comment(raw).trim_end();
+        let trimmed = content.trim_start();
+        if trimmed.is_empty() || (index == 0 && trimmed == "---") {
+            continue;
+        }
+        lines.push(Line {
+            number,
+            indent: content.len() - trimmed.len(),
+            text: trimmed.to_string(),
+        });
+    }
+    let mut parser = Parser { lines, pos: 0 };
+    let Some(first) = parser.lines.first() else {
+        return Ok(Table::new());
+    };
+    if is_item(&first.text) {
+        return Err(error(first.number, "a recipe is a mapping, not a list"));
+    }
+    let root = parser.mapping(first.indent)?;
+    if let Some(line) = parser.lines.get(parser.pos) {
+        return Err(error(line.number, "unexpected indentation"));
+    }
+    Ok(root)
+}
+
+struct Line {
+    number: usize,
+    indent: usize,
+    text: String,
+}
+
+struct Parser {
+    lines: Vec<Line>,
+    pos: usize,
+}
+
+impl Parser {
+    // Whatever block starts on the current line, at its indentation
+    fn block(&mut self, indent: usize) -> io::Result<Value> {
+        if is_item(&self.lines[self.pos].text) {
+            self.sequence(indent).map(Value::Array)
+        } else {
+            self.mapping(indent).map(Value::Table)
+        }
+    }
+
+    fn mapping(&mut self, indent: usize) -> io::Result<Table> {
+        let mut table = Table::new();
+        while let Some(line) = self.lines.get(self.pos) {
+            if line.indent < indent || (line.indent == indent && is_item(&line.text)) {
+                break;
+            }
+            let number = line.number;
+            if line.indent > indent {
+                return Err(error(number, "unexpected indentation"));
+            }
+            let (key, rest) = split_key(&line.text).ok_or_else(|| error(number, "expected 'key: value'"))?;
+            let key = scalar_text(key, number)?;
+            self.pos += 1;
+            let value = if rest.is_empty() {
+                self.nested(indent, number)?
+            } else {
+                inline(rest, number)?
+            };
+            if table.insert(key.clone(), value).is_some() {
+                return Err(error(number, format!("duplicate key '{}'", key)));
+            }
+        }
+        Ok(table)
+    }
+
+    fn sequence(&mut self, indent: usize) -> io::Result<Vec<Value>> {
+        let mut items = Vec::new();
+        while let Some(line) = self.lines.get(self.pos) {
+            if line.indent != indent || !is_item(&line.text) {
+                break;
+            }
+            let number = line.number;
+            let rest = line.text[1..].trim_start().to_string();
+            if rest.is_empty() {
+                self.pos += 1;
+                items.push(self.nested(indent, number)?);
+            } else if split_key(&rest).is_some() {
+                // `- key: value` opens a mapping whose other keys line up
+                // under the first
+                let inner = indent + line.text.len() - rest.len();
+                self.lines[self.pos].indent = inner;
+                self.lines[self.pos].text = rest;
+                items.push(Value::Table(self.mapping(inner)?));
+            } else {
+                self.pos += 1;
+                items.push(inline(&rest, number)?);
+            }
+        }
+        if let Some(line) = self.lines.get(self.pos).filter(|line| line.indent > indent) {
+            return Err(error(line.number, "unexpected indentation"));
+        }
+        Ok(items)
+    }
+
+    // The block under a key or dash with nothing after it; a list may sit
+    // at the key's own indentation
+    fn nested(&mut self, indent: usize, number: usize) -> io::Result<Value> {
+        match self.lines.get(self.pos) {
+            Some(next) if next.indent > indent => {
+                let inner = next.indent;
+                self.block(inner)
+            }
+            Some(next) if next.indent == indent && is_item(&next.text) => self.sequence(indent).map(Value::Array),
+            _ => Err(error(number, "has no value")),
+        }
+    }
+}
+
+fn error(line: usize, message: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", line, message))
+}
+
+fn is_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+// Everything from a `#` that starts a word outside quotes
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (index, c) in line.char_indices() {
+        match quote {
+            Some(open) if c == open => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '#' && previous.is_whitespace() => return &line[..index],
+            None => {}
+        }
+        previous = c;
+    }
+    line
+}
+
+// Splits `key: value` at the first colon followed by a space or the end of
+// the line, outside quotes and brackets
+fn split_key(text: &str) -> Option<(&str, &str)> {
+    let mut quote = None;
+    let mut depth = 0;
+    for (index, c) in text.char_indices() {
+        match quote {
+            Some(open) if c == open => quote = None,
+            Some(_) => {}
+            None => match c {
+                '"' | '\'' => quote = Some(c),
+                '[' | '{' => depth += 1,
+                ']' | '}' => depth -= 1,
+                ':' if depth == 0 && text[index + 1..].chars().next().is_none_or(char::is_whitespace) => {
+                    return Some((text[..index].trim_end(), text[index + 1..].trim()));
+                }
+                _ => {}
+            },
+        }
+    }
+    None
+}
+
+// A value on one line: a flow list or mapping, or a scalar
+fn inline(text: &str, line: usize) -> io::Result<Value> {
+    let mut flow = Flow {
+        chars: text.chars().collect(),
+        pos: 0,
+        line,
+    };
+    let value = flow.value()?;
+    flow.skip_spaces();
+    if flow.pos < flow.chars.len() {
+        return Err(error(line, format!("unexpected '{}' after value", flow.chars[flow.pos])));
+    }
+    Ok(value)
+}
+
+fn scalar_text(text: &str, line: usize) -> io::Result<String> {
+    match inline(text, line)? {
+        Value::String(text) => Ok(text),
+        Value::Integer(value) => Ok(value.to_string()),
+        _ => Err(error(line, format!("'{}' can't be a key", text))),
+    }
+}
+
+struct Flow {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Flow {
+    fn skip_spaces(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn value(&mut self) -> io::Result<Value> {
+        self.skip_spaces();
+        match self.chars.get(self.pos) {
+            Some('[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                while !self.closes(']')? {
+                    items.push(self.value()?);
+                    self.separator(']')?;
+                }
+                Ok(Value::Array(items))
+            }
+            Some('{') => {
+                self.pos += 1;
+                let mut table = Table::new();
+                while !self.closes('}')? {
+                    let key = match self.value()? {
+                        Value::String(key) => key,
+                        Value::Integer(key) => key.to_string(),
+                        _ => return Err(error(self.line, "expected a key")),
+                    };
+                    self.skip_spaces();
+                    if self.chars.get(self.pos) != Some(&':') {
+                        return Err(error(self.line, format!("expected ':' after '{}'", key)));
+                    }
+                    self.pos += 1;
+                    let value = self.value()?;
+                    if table.insert(key.clone(), value).is_some() {
+                        return Err(error(self.line, format!("duplicate key '{}'", key)));
+                    }
+                    self.separator('}')?;
+                }
+                Ok(Value::Table(table))
+            }
+            Some(&quote) if quote == '"' || quote == '\'' => self.quoted(quote).map(Value::String),
+            Some(_) => {
+                // Plain scalars inside brackets end at the brackets' own
+                // punctuation
+                let start = self.pos;
+                while let Some(&c) = self.chars.get(self.pos) {
+                    let ends_key = c == ':' && self.chars.get(self.pos + 1).is_none_or(|c| c.is_whitespace());
+                    if matches!(c, ',' | ']' | '}') || ends_key {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+                let text: String = self.chars[start..self.pos].iter().collect();
+                Ok(plain(text.trim()))
+            }
+            None => Err(error(self.line, "expected a value")),
+        }
+    }
+
+    // Whether the list or mapping ends here, stepping past the bracket if so
+    fn closes(&mut self, close: char) -> io::Result<bool> {
+        self.skip_spaces();
+        match self.chars.get(self.pos) {
+            Some(&c) if c == close => {
+                self.pos += 1;
+                Ok(true)
+            }
+            Some(_) => Ok(false),
+            None => Err(error(self.line, format!("expected '{}'", close))),
+        }
+    }
+
+    fn separator(&mut self, close: char) -> io::Result<()> {
+        self.skip_spaces();
+        match self.chars.get(self.pos) {
+            Some(',') => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(&c) if c == close => Ok(()),
+            _ => Err(error(self.line, format!("expected ',' or '{}'", close))),
+        }
+    }
+
+    // Double quotes take backslash escapes; single quotes only '' for a quote
+    fn quoted(&mut self, quote: char) -> io::Result<String> {
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let Some(&c) = self.chars.get(self.pos) else {
+                return Err(error(self.line, "unterminated string"));
+            };
+            self.pos += 1;
+            match c {
+                '\'' if quote == '\'' && self.chars.get(self.pos) == Some(&'\'') => {
+                    self.pos += 1;
+                    out.push('\'');
+                }
+                c if c == quote => return Ok(out),
+                '\\' if quote == '"' => {
+                    let escaped = self.chars.get(self.pos).copied();
+                    self.pos += 1;
+                    out.push(match escaped {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some(c @ ('"' | '\\')) => c,
+                        Some(c) => return Err(error(self.line, format!("invalid escape \\{}", c))),
+                        None => return Err(error(self.line, "unterminated string")),
+                    });
+                }
+                c => out.push(c),
+            }
+        }
+    }
+}
+
+// Booleans and numbers as YAML reads them, anything else as text
+fn plain(text: &str) -> Value {
+    match text {
+        "true" | "True" | "TRUE" => return Value::Boolean(true),
+        "false" | "False" | "FALSE" => return Value::Boolean(false),
+        _ => {}
+    }
+    if let Ok(value) = text.replace('_', "").parse::<i64>() {
+        return Value::Integer(value);
+    }
+    let numeric = text.chars().next().is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.'));
+    match text.parse::<f64>() {
+        Ok(value) if numeric && value.is_finite() => Value::Float(value),
+        _ => Value::String(text.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(value: &str) -> Value {
+        Value::String(value.to_string())
+    }
+
+    fn table(entries: Vec<(&str, Value)>) -> Value {
+        Value::Table(entries.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+    }
+
+    fn failure(yaml: &str) -> String {
+        parse(yaml).expect_err("the YAML parsed").to_string()
+    }
+
+    #[test]
+    fn blocks_nest_by_indentation() {
+        let yaml = "\
+---
+layout:
+  width: 80
+  flags:
+  - rooms
+  - caves
+stages:
+  - name: carve
+    steps: 3
+  -
+    name: smooth
+  - [1, 2.5, true]
+inline: { size: [4, 4], name: hall }
+";
+        let expected = table(vec![
+            (
+                "layout",
+                table(vec![
+                    ("width", Value::Integer(80)),
+                    ("flags", Value::Array(vec![text("rooms"), text("caves")])),
+                ]),
+            ),
+            (
+                "stages",
+                Value::Array(vec![
+                    table(vec![("name", text("carve")), ("steps", Value::Integer(3))]),
+                    table(vec![("name", text("smooth"))]),
+                    Value::Array(vec![Value::Integer(1), Value::Float(2.5), Value::Boolean(true)]),
+                ]),
+            ),
+            (
+                "inline",
+                table(vec![
+                    ("size", Value::Array(vec![Value::Integer(4), Value::Integer(4)])),
+                    ("name", text("hall")),
+                ]),
+            ),
+        ]);
+        assert_eq!(Value::Table(parse(yaml).unwrap()), expected);
+    }
+
+    #[test]
+    fn quotes_keep_what_plain_scalars_would_not() {
+        let yaml = "double: \"a: b # c\\n\\\"d\\\"\"\nsingle: 'it''s'\nnumber: \"12\"\n\"quoted key\": [\"x, y\", 'z]']\n";
+        let parsed = parse(yaml).unwrap();
+        assert_eq!(parsed["double"], text("a: b # c\n\"d\""));
+        assert_eq!(parsed["single"], text("it's"));
+        assert_eq!(parsed["number"], text("12"));
+        assert_eq!(parsed["quoted key"], Value::Array(vec![text("x, y"), text("z]")]));
+    }
+
+    #[test]
+    fn comments_end_at_the_line_and_need_a_space_before_them() {
+        let yaml = "# a recipe\nname: cave # the kind\n  # indented comments don't count\ncolour: red#ish\nempty: {} #\n";
+        let parsed = parse(yaml).unwrap();
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed["name"], text("cave"));
+        assert_eq!(parsed["colour"], text("red#ish"));
+        assert_eq!(parsed["empty"], table(vec![]));
+        assert!(parse("# nothing but comments\n\n").unwrap().is_empty());
+    }
+
+    #[test]
+    fn errors_name_the_line_at_fault() {
+        assert_eq!(failure("a: 1\n\tb: 2\n"), "line 2: tabs can't indent YAML");
+        assert_eq!(failure("a: 1\n  b: 2\n"), "line 2: unexpected indentation");
+        assert_eq!(failure("a: 1\nb:\nc: 3\n"), "line 2: has no value");
+        assert_eq!(failure("a: 1\n# note\na: 2\n"), "line 3: duplicate key 'a'");
+        assert_eq!(failure("a:\n  - 1\n  - 'open\n"), "line 3: unterminated string");
+        assert_eq!(failure("a: [1, 2\n"), "line 1: expected ',' or ']'");
+        assert_eq!(failure("a: \"\\q\"\n"), "line 1: invalid escape \\q");
+        assert_eq!(failure("- a\n"), "line 1: a recipe is a mapping, not a list");
+        assert_eq!(failure("a: 1\njust words\n"), "line 2: expected 'key: value'");
+    }
+}
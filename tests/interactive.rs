@@ -0,0 +1,619 @@
+// End-to-end checks of the interactive modes, driven through a pty
+#![cfg(unix)]
+
+mod pty;
+
+use dungeon_generator::autoexplore;
+use dungeon_generator::bundle::Bundle;
+use dungeon_generator::describe::Compass;
+use dungeon_generator::events::{self, Event, RegionWatch};
+use dungeon_generator::exploration::Exploration;
+use dungeon_generator::fingerprint::Fingerprint;
+use dungeon_generator::fixtures;
+use dungeon_generator::pathfinding::{self, PathOptions};
+use dungeon_generator::solver;
+use dungeon_generator::survey;
+use dungeon_generator::world::{NorthEdge, WorldFrame};
+use dungeon_generator::{generator, Config, SeededRng, Tile};
+use pty::{scratch_dir, Pty};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+
+const DOWN: &str = "\x1b[B";
+const RIGHT: &str = "\x1b[C";
+
+fn seeded(seed: u64) -> Config {
+    Config {
+        seed: Some(seed),
+        ..Config::default()
+    }
+}
+
+// The map as the explorer draws it with the classic theme at full zoom
+fn drawn_map(config: &Config) -> Vec<String> {
+    let dungeon = generator::generate_seeded(config, config.seed.unwrap());
+    dungeon
+        .to_ascii()
+        .lines()
+        .map(|line| {
+            line.chars()
+                .map(|c| match c {
+                    '#' | 'S' => '█',
+                    '.' => '·',
+                    '=' => '+',
+                    c => c,
+                })
+                .collect::<String>()
+        })
+        .collect()
+}
+
+// Starts the explorer and waits for its first full frame
+fn explorer(name: &str, args: &[&str]) -> Pty {
+    let pty = Pty::spawn(&scratch_dir(name), args);
+    pty.wait_for("q quit");
+    pty
+}
+
+#[test]
+fn explorer_draws_the_seeded_map_and_panel() {
+    let mut pty = explorer("draw", &["--seed", "7"]);
+    let screen = pty.screen();
+    let expected = drawn_map(&seeded(7));
+    assert_eq!(screen.lines()[..expected.len()], expected[..], "screen was:\n{}", screen);
+    assert_eq!(screen.line(expected.len() + 1), "Seed 7");
+    assert!(screen.line(expected.len() + 2).starts_with("Wall probability"));
+
+    pty.send("q");
+    assert!(pty.wait_exit().success());
+}
+
+#[test]
+fn arrow_keys_pick_and_adjust_a_parameter() {
+    let mut pty = explorer("adjust", &["--seed", "7"]);
+    let iterations = seeded(7).iterations;
+    pty.send(DOWN);
+    pty.send(RIGHT);
+    let adjusted = format!("{:<18}{:>6}", "Iterations", iterations + 1);
+    let screen = pty.wait_until("the iteration count to go up", |screen| screen.contains(&adjusted));
+
+    // Same seed, one more automaton step
+    let config = Config {
+        iterations: iterations + 1,
+        ..seeded(7)
+    };
+    let expected = drawn_map(&config);
+    assert_eq!(screen.lines()[..expected.len()], expected[..], "screen was:\n{}", screen);
+
+    pty.send("q");
+    assert!(pty.wait_exit().success());
+}
+
+#[test]
+fn zooming_out_redraws_the_map_at_half_height() {
+    let mut pty = explorer("zoom", &["--seed", "7"]);
+    let height = seeded(7).height;
+    pty.send("-");
+    pty.wait_until("the panel to move up", |screen| screen.find("Seed 7") == Some(height / 2 + 1));
+    pty.send("+");
+    pty.wait_until("the panel to move back", |screen| screen.find("Seed 7") == Some(height + 1));
+
+    pty.send("q");
+    assert!(pty.wait_exit().success());
+}
+
+#[test]
+fn ctrl_c_quits_even_in_raw_mode() {
+    let mut pty = explorer("interrupt", &["--seed", "7"]);
+    pty.send("\x03");
+    assert!(pty.wait_exit().success());
+}
+
+#[test]
+fn resizing_clears_and_redraws_the_frame() {
+    let mut pty = explorer("resize", &["--seed", "7"]);
+    let clears = pty.clears();
+    pty.resize(pty::ROWS - 4, pty::COLUMNS - 10);
+    let screen = pty.wait_until("a fresh frame", |screen| pty.clears() > clears && screen.contains("q quit"));
+    let expected = drawn_map(&seeded(7));
+    assert_eq!(screen.lines()[..expected.len()], expected[..], "screen was:\n{}", screen);
+
+    pty.send("q");
+    assert!(pty.wait_exit().success());
+}
+
+#[test]
+fn f_saves_a_favorite() {
+    let dir = scratch_dir("favorite");
+    let mut pty = Pty::spawn(&dir, &["--seed", "7", "--favorites", "favorites.toml"]);
+    pty.wait_for("q quit");
+    pty.send("f");
+    pty.wait_for("Saved seed 7 to favorites.toml");
+    pty.send("q");
+    assert!(pty.wait_exit().success());
+
+    let saved = fs::read_to_string(dir.join("favorites.toml")).unwrap();
+    assert!(saved.contains("seed = 7"), "favorites file was:\n{}", saved);
+}
+
+#[test]
+fn c_copies_the_map_through_the_terminal() {
+    // No clipboard tools on the test's PATH, so it falls back to OSC 52
+    let mut pty = explorer("copy", &["--seed", "7"]);
+    pty.send("c");
+    let screen = pty.wait_for("Copied map via terminal (OSC 52)");
+    let dungeon = generator::generate_seeded(&seeded(7), 7);
+    assert_eq!(screen.clipboard, [dungeon.to_ascii()]);
+
+    pty.send("s");
+    let screen = pty.wait_for("Copied seed and config via terminal (OSC 52)");
+    assert_eq!(screen.clipboard.len(), 2);
+    assert_eq!(Config::from_toml(&screen.clipboard[1]).unwrap(), seeded(7));
+
+    pty.send("q");
+    assert!(pty.wait_exit().success());
+}
+
+#[test]
+fn question_mark_lists_the_keys_and_config_rebinds_them() {
+    let mut pty = explorer("help", &["--seed", "7"]);
+    pty.send("?");
+    let screen = pty.wait_for("any key closes this");
+    for listed in ["Shift+Tab  Pick the previous room", "+ =        Zoom in", "q Esc      Quit"] {
+        assert!(screen.contains(listed), "screen was:\n{}", screen);
+    }
+    pty.send(" ");
+    let expected = drawn_map(&seeded(7));
+    pty.wait_until("the map to come back", |screen| screen.lines()[..expected.len()] == expected[..]);
+    pty.send("q");
+    assert!(pty.wait_exit().success());
+
+    let dir = scratch_dir("keys");
+    fs::write(dir.join("keys.toml"), "seed = 7\n\n[keys]\nnew_seed = \"n\"\nhelp = \"h\"\nquit = \"Q\"\n").unwrap();
+    let mut pty = Pty::spawn(&dir, &["--config", "keys.toml"]);
+    let screen = pty.wait_for("Q quit");
+    assert!(screen.contains("n new seed  f favorite") && screen.contains("h keys"), "screen was:\n{}", screen);
+    pty.send("n");
+    pty.wait_until("a new seed", |screen| screen.find("Seed 7").is_none());
+    // q is no longer bound, so the explorer is still there to show its keys
+    pty.send("q");
+    pty.send("h");
+    let screen = pty.wait_for("any key closes this");
+    assert!(screen.contains("n          Generate from a new seed"), "screen was:\n{}", screen);
+    pty.send("x");
+    pty.send("Q");
+    assert!(pty.wait_exit().success());
+
+    let error = Config::from_toml("[keys]\nnew_seed = \"x\"\n").unwrap_err();
+    assert_eq!(error.to_string(), "'x' is bound to both new_seed and reroll_room");
+    let config = Config::from_toml("[keys]\nzoom_in = [\"+\", \"pageup\"]\nfavorite = []\n").unwrap();
+    assert_eq!(Config::from_toml(&config.to_toml()).unwrap(), config);
+    assert!(Config::from_toml("[keys]\nhelp = \"f13\"\n").is_err());
+}
+
+#[test]
+fn tab_picks_a_room_and_x_rerolls_only_it() {
+    let mut pty = explorer("reroll", &["--seed", "7"]);
+    let mut dungeon = generator::generate_seeded(&seeded(7), 7);
+    let label = dungeon.region_labels(7).remove(0);
+    pty.send("\t");
+    pty.wait_for(&format!("{}  x reroll  Tab next", label.name));
+    pty.send("x");
+    pty.wait_for(&format!("Rerolled {}", label.name));
+
+    let (regions, _) = dungeon.label_regions();
+    let tiles: Vec<_> = (0..dungeon.height())
+        .flat_map(|y| (0..dungeon.width()).map(move |x| (x, y)))
+        .filter(|&(x, y)| regions[y][x] == Some(label.region))
+        .collect();
+    let before = dungeon.clone();
+    let reroll_seed = SeededRng::new(7).child("room reroll 1").seed();
+    assert!(generator::reroll_region(&mut dungeon, &seeded(7), &tiles, reroll_seed));
+    for (y, row) in regions.iter().enumerate() {
+        for (x, &region) in row.iter().enumerate() {
+            if region != Some(label.region) {
+                assert_eq!(dungeon.tile(x, y), before.tile(x, y), "tile ({}, {}) outside the room changed", x, y);
+            }
+        }
+    }
+    pty.send("c");
+    let screen = pty.wait_for("Copied map via terminal (OSC 52)");
+    assert_eq!(screen.clipboard, [dungeon.to_ascii()]);
+
+    pty.send("q");
+    assert!(pty.wait_exit().success());
+}
+
+#[test]
+fn e_exports_a_linked_image_and_o_opens_it() {
+    let dir = scratch_dir("export");
+    let opener = dir.join("xdg-open");
+    fs::write(&opener, "#!/bin/sh\necho \"$1\" > opened.txt\n").unwrap();
+    fs::set_permissions(&opener, fs::Permissions::from_mode(0o755)).unwrap();
+    let mut pty = Pty::spawn(&dir, &["--seed", "7"]);
+    pty.wait_for("e image");
+
+    pty.send("e");
+    let screen = pty.wait_for("Exported image to dungeon-7.png  o open");
+    let image = dir.join("dungeon-7.png").canonicalize().unwrap();
+    assert!(fs::read(&image).unwrap().starts_with(b"\x89PNG"));
+    assert_eq!(screen.links, [format!("file://{}", image.display())]);
+
+    pty.send("o");
+    pty.wait_for("Opened dungeon-7.png with xdg-open");
+    assert_eq!(fs::read_to_string(dir.join("opened.txt")).unwrap().trim_end(), image.to_str().unwrap());
+    pty.send("q");
+    assert!(pty.wait_exit().success());
+}
+
+#[test]
+fn watch_regenerates_each_time_the_config_is_saved() {
+    let dir = scratch_dir("watch");
+    let path = dir.join("dungeon.toml");
+    fs::write(&path, "width = 30\nheight = 12\nseed = 5\n").unwrap();
+    let mut pty = Pty::spawn(&dir, &["--watch", "dungeon.toml"]);
+    let screen = pty.wait_for("Watching dungeon.toml  seed 5");
+    let small = Config::from_toml("width = 30\nheight = 12\nseed = 5\n").unwrap();
+    assert_eq!(screen.lines()[..12], drawn_map(&small)[..], "screen was:\n{}", screen);
+
+    fs::write(&path, "width = 40\nheight = 16\nseed = 5\n").unwrap();
+    let screen = pty.wait_for("Regenerated after save 1");
+    let wide = Config::from_toml("width = 40\nheight = 16\nseed = 5\n").unwrap();
+    assert_eq!(screen.lines()[..16], drawn_map(&wide)[..], "screen was:\n{}", screen);
+
+    // A broken save keeps the last good map
+    fs::write(&path, "width = \"wide\"\n").unwrap();
+    let screen = pty.wait_for("Couldn't reload after save 2");
+    assert_eq!(screen.lines()[..16], drawn_map(&wide)[..], "screen was:\n{}", screen);
+    pty.send("q");
+    assert!(pty.wait_exit().success());
+}
+
+#[test]
+fn status_lines_follow_the_environment_language() {
+    let mut pty = Pty::spawn_with(&scratch_dir("lang-env"), &["--seed", "7"], &[("LANG", "fr_FR.UTF-8")]);
+    let screen = pty.wait_for("q quitter");
+    assert!(screen.contains("Donjon terminé !"), "screen was:\n{}", screen);
+    assert!(screen.contains("Graine 7"));
+    pty.send("q");
+    assert!(pty.wait_exit().success());
+}
+
+#[test]
+fn lang_takes_a_catalog_file_over_a_base_locale() {
+    let dir = scratch_dir("lang-file");
+    fs::write(dir.join("pt.toml"), "locale = \"pt\"\nbase = \"es\"\ncomplete = \"Masmorra pronta!\"\n").unwrap();
+    let mut pty = Pty::spawn_with(&dir, &["--seed", "7", "--lang", "pt.toml"], &[("LANG", "de_DE.UTF-8")]);
+    let screen = pty.wait_for("q salir");
+    assert!(screen.contains("Masmorra pronta!"), "screen was:\n{}", screen);
+    assert!(screen.contains("Semilla 7"));
+    pty.send("q");
+    assert!(pty.wait_exit().success());
+}
+
+#[test]
+fn no_color_draws_the_map_in_monochrome() {
+    let mut pty = Pty::spawn_with(&scratch_dir("no-color"), &["--seed", "7"], &[("NO_COLOR", "1")]);
+    let screen = pty.wait_for("q quit");
+    let expected = drawn_map(&seeded(7));
+    assert_eq!(screen.lines()[..expected.len()], expected[..], "screen was:\n{}", screen);
+    // Reverse video for the selected parameter is all the styling left
+    let output = String::from_utf8_lossy(&pty.output()).into_owned();
+    for sequence in output.split("\x1b[").skip(1) {
+        let Some(end) = sequence.find(|c: char| c.is_ascii_alphabetic()) else {
+            continue;
+        };
+        if sequence[end..].starts_with('m') {
+            assert!(["0", "7"].contains(&&sequence[..end]), "output set a style: {:?}", &sequence[..end]);
+        }
+    }
+
+    pty.send("q");
+    assert!(pty.wait_exit().success());
+}
+
+#[test]
+fn dumb_terminal_gets_the_plain_map_printed() {
+    let pty = Pty::spawn_with(&scratch_dir("dumb"), &["--seed", "7"], &[("TERM", "dumb")]);
+    let screen = pty.wait_for("Seed 7");
+    let expected: Vec<String> = generator::generate_seeded(&seeded(7), 7)
+        .to_ascii()
+        .lines()
+        .map(String::from)
+        .collect();
+    assert_eq!(screen.lines()[..expected.len()], expected[..], "screen was:\n{}", screen);
+    assert!(!pty.output().contains(&0x1b), "output had escape sequences");
+    assert!(pty.wait_exit().success());
+}
+
+#[test]
+fn themes_with_floor_like_wall_get_a_warning() {
+    let dir = scratch_dir("contrast");
+    let theme = "name = \"murk\"\n[wall]\ncolor = \"#505050\"\n[floor]\ncolor = \"#555555\"\n";
+    fs::write(dir.join("murk.toml"), theme).unwrap();
+    let pty = Pty::spawn_with(&dir, &["--seed", "7", "--theme", "murk.toml"], &[("TERM", "dumb")]);
+    let screen = pty.wait_for("Seed 7");
+    assert!(
+        screen.contains("Warning: theme 'murk' draws floor and wall nearly alike in images for anyone"),
+        "screen was:\n{}",
+        screen
+    );
+    assert!(pty.wait_exit().success());
+
+    let pty = Pty::spawn_with(&dir, &["--seed", "7", "--theme", "colorblind"], &[("TERM", "dumb")]);
+    let screen = pty.wait_for("Seed 7");
+    assert!(!screen.contains("Warning"), "screen was:\n{}", screen);
+    assert!(pty.wait_exit().success());
+}
+
+#[test]
+fn survey_sums_up_consecutive_seeds() {
+    let pty = Pty::spawn_with(&scratch_dir("survey"), &["survey", "6", "--seed", "3"], &[("TERM", "dumb")]);
+    let screen = pty.wait_for("dead ends");
+    assert!(pty.wait_exit().success());
+    let expected = survey::survey(&seeded(3), 3, 6);
+    assert_eq!(expected.maps, 6);
+    assert!(expected.floor_percent.min <= expected.floor_percent.mean);
+    assert!(expected.floor_percent.mean <= expected.floor_percent.max);
+    for line in expected.report().lines() {
+        assert!(screen.contains(line.trim_end()), "no {:?} in:\n{}", line, screen);
+    }
+}
+
+#[test]
+fn dumb_terminal_refuses_the_demo() {
+    let pty = Pty::spawn_with(&scratch_dir("dumb-demo"), &["demo"], &[("TERM", "dumb")]);
+    pty.wait_for("needs a terminal with cursor control");
+    assert!(!pty.wait_exit().success());
+}
+
+#[test]
+fn demo_steps_through_slides_and_quits() {
+    let mut pty = Pty::spawn(&scratch_dir("demo"), &["demo", "--seed", "7"]);
+    pty.wait_for("Cellular automaton: random noise");
+    pty.send(" ");
+    pty.wait_for("Cellular automaton, step 1:");
+    pty.send(" ");
+    pty.wait_for("Cellular automaton, step 2:");
+    pty.send("q");
+    assert!(pty.wait_exit().success());
+}
+
+#[test]
+fn o_auto_explores_until_nothing_reachable_is_left() {
+    let dir = scratch_dir("explore");
+    fs::write(dir.join("small.toml"), "width = 40\nheight = 20\n").unwrap();
+    let mut pty = Pty::spawn(&dir, &["explore", "--seed", "7", "--config", "small.toml"]);
+    let screen = pty.wait_for("o auto-explore");
+    assert!(screen.contains("@"), "screen was:\n{}", screen);
+    pty.send("o");
+    let screen = pty.wait_for("Nothing left to explore");
+
+    // Everything the walk could reach ended up on screen
+    let config = Config {
+        width: 40,
+        height: 20,
+        ..seeded(7)
+    };
+    let dungeon = generator::generate_seeded(&config, 7);
+    let (entrance, _) = pathfinding::entrance_and_exit(&dungeon).unwrap();
+    let run = autoexplore::auto_explore(&dungeon, entrance, 8, &PathOptions::default());
+    assert!(run.missed(&dungeon, &PathOptions::default()).is_empty());
+    for (x, y) in run.route {
+        assert_ne!(screen.lines()[y].chars().nth(x), Some(' '), "({}, {}) left dark:\n{}", x, y, screen);
+    }
+
+    pty.send("q");
+    assert!(pty.wait_exit().success());
+}
+
+#[test]
+fn bell_rings_for_each_door_opened_and_room_found() {
+    let dir = scratch_dir("bell");
+    fs::write(dir.join("doors.toml"), "width = 40\nheight = 20\ndoors = 4\n").unwrap();
+    let mut pty = Pty::spawn(&dir, &["explore", "--bell", "--seed", "2", "--config", "doors.toml"]);
+    pty.wait_for("o auto-explore");
+    pty.send("o");
+    pty.wait_for("Nothing left to explore");
+
+    // The same walk through the library, counting what it heard
+    let config = Config {
+        width: 40,
+        height: 20,
+        doors: 4,
+        ..seeded(2)
+    };
+    let mut dungeon = generator::generate_seeded(&config, 2);
+    let options = PathOptions::default();
+    let (mut position, _) = pathfinding::entrance_and_exit(&dungeon).unwrap();
+    let mut exploration = Exploration::for_dungeon(&dungeon);
+    let mut rooms = RegionWatch::new(&dungeon);
+    let mut heard = Vec::new();
+    exploration.look(&dungeon, position, 8);
+    rooms.update(&exploration, &mut heard);
+    while let Some(next) = autoexplore::next_step(&dungeon, &exploration, position, &options) {
+        assert!(events::step_onto(&mut dungeon, next, &options, &mut heard));
+        position = next;
+        exploration.look(&dungeon, position, 8);
+        rooms.update(&exploration, &mut heard);
+    }
+    let doors = heard.iter().filter(|event| matches!(event, Event::DoorOpened(_))).count();
+    let found = heard.iter().filter(|event| matches!(event, Event::RegionRevealed { .. })).count();
+    assert!(doors > 0 && found > 0, "heard {:?}", heard);
+    let bells = pty.output().iter().filter(|&&byte| byte == 0x07).count();
+    assert_eq!(bells, doors + found);
+
+    pty.send("q");
+    assert!(pty.wait_exit().success());
+}
+
+#[test]
+fn solve_plays_the_route_back_and_reports_it() {
+    let mut pty = Pty::spawn(&scratch_dir("solve"), &["solve", "--seed", "7"]);
+    pty.wait_for("any key skips ahead");
+    pty.send(" ");
+    pty.wait_for("any key exits");
+    pty.send(" ");
+
+    let dungeon = generator::generate_seeded(&seeded(7), 7);
+    let solution = solver::solve(&dungeon, &PathOptions::default()).unwrap();
+    let screen = pty.wait_for("(cost");
+    assert!(screen.contains(solution.report().trim_end()), "screen was:\n{}", screen);
+    assert!(pty.wait_exit().success());
+}
+
+#[test]
+fn truecolor_terminals_get_occlusion_shading() {
+    let mut pty = Pty::spawn_with(&scratch_dir("truecolor"), &["--seed", "7"], &[("COLORTERM", "truecolor")]);
+    let screen = pty.wait_for("q quit");
+    let expected = drawn_map(&seeded(7));
+    assert_eq!(screen.lines()[..expected.len()], expected[..], "screen was:\n{}", screen);
+
+    // Open floor and floor in a tight spot come out in different shades
+    let output = String::from_utf8_lossy(&pty.output()).into_owned();
+    let mut shades: Vec<&str> = output
+        .split("\x1b[38;2;")
+        .skip(1)
+        .filter(|sequence| sequence.contains("m·"))
+        .map(|sequence| &sequence[..sequence.find('m').unwrap()])
+        .collect();
+    shades.sort();
+    shades.dedup();
+    assert!(shades.len() > 1, "floor shades: {:?}", shades);
+
+    pty.send("q");
+    assert!(pty.wait_exit().success());
+}
+
+#[test]
+fn ambient_mist_drifts_over_the_explorer() {
+    let mut pty = explorer("ambient", &["--seed", "7", "--ambient", "mist"]);
+    pty.wait_until("mist on the floor", |screen| screen.contains("░") || screen.contains("▒"));
+    // The panel keeps taking keys while the mist plays
+    pty.send("r");
+    pty.wait_until("a new seed", |screen| !screen.contains("Seed 7"));
+    pty.send("q");
+    assert!(pty.wait_exit().success());
+}
+
+#[test]
+fn describe_names_each_room_and_its_ways_out() {
+    let described = fixtures::two_rooms().describe(1);
+    assert_eq!(described.rooms.len(), 2);
+    let (left, right) = (&described.rooms[0], &described.rooms[1]);
+    assert_eq!(described.start, Some(left.region));
+    assert_eq!(left.contents, ["the entrance"]);
+    assert_eq!(right.contents, ["the exit"]);
+    assert_eq!((left.exits.len(), left.exits[0].direction, left.exits[0].to), (1, Compass::East, right.region));
+    assert_eq!((right.exits.len(), right.exits[0].direction, right.exits[0].to), (1, Compass::West, left.region));
+    let text = described.to_text();
+    assert!(text.contains(&format!("Exits: east to {}.", right.name)), "text was:\n{}", text);
+    assert!(fixtures::disconnected_pair().describe(1).to_text().contains("No ways out to another room."));
+
+    let dir = scratch_dir("describe");
+    let expected = generator::generate_seeded(&seeded(3), 3).describe(3);
+    for (file, text) in [("map.txt", expected.to_text()), ("map.json", expected.to_json())] {
+        let pty = Pty::spawn(&dir, &["--seed", "3", "--describe", file]);
+        pty.wait_for(&format!("Wrote a description of {} room(s) to {}", expected.rooms.len(), file));
+        assert!(pty.wait_exit().success());
+        assert_eq!(fs::read_to_string(dir.join(file)).unwrap(), text);
+    }
+}
+
+#[test]
+fn world_frame_turns_the_compass_and_travels_in_bundles() {
+    let mut two_rooms = fixtures::two_rooms();
+    let frame = WorldFrame { north: NorthEdge::Right, tile_size: 2.0, origin: (100.0, 50.0) };
+    two_rooms.set_world(frame);
+    // The right room lies north of the left once the right edge faces north
+    let described = two_rooms.describe(1);
+    let directions = (described.rooms[0].exits[0].direction, described.rooms[1].exits[0].direction);
+    assert_eq!(directions, (Compass::North, Compass::South));
+    assert_eq!(described.world, frame);
+    // The top-left tile is by the south-west corner, and a tile further right is further north
+    assert_eq!(two_rooms.world_position((0, 0)), (101.0, 51.0));
+    assert_eq!(two_rooms.world_position((3, 0)), (101.0, 57.0));
+    assert_eq!(WorldFrame::default().to_world((0.0, 0.0), (15, 7)), (0.0, 7.0));
+
+    let dir = scratch_dir("world");
+    let toml = "width = 40\nheight = 20\nseed = 4\n\
+                \n[world]\nnorth = \"right\"\ntile_size = 2.0\norigin = [100.0, 50]\n";
+    fs::write(dir.join("world.toml"), toml).unwrap();
+    let config = Config::from_toml(toml).unwrap();
+    assert_eq!(config.world, frame);
+    assert_eq!(Config::from_toml(&config.to_toml()).unwrap(), config);
+    assert!(Config::from_toml("[world]\norigin = [1.0]\n").is_err());
+    assert!(Config::from_toml("[world]\ntile_size = 0\n").is_err());
+
+    let pty = Pty::spawn(&dir, &["--config", "world.toml", "--bundle", "map.dungeon"]);
+    pty.wait_for("Wrote map, spawns, generation info and thumbnail to map.dungeon");
+    assert!(pty.wait_exit().success());
+    assert_eq!(Bundle::load(&dir.join("map.dungeon")).unwrap().dungeon.world(), frame);
+}
+
+#[test]
+fn fingerprints_stay_close_for_small_changes_and_go_in_bundles() {
+    // Seven rows leave the top row of cells empty; the corridor row joins the two rooms' bits
+    let two_rooms = fixtures::two_rooms().fingerprint();
+    assert_eq!(two_rooms, Fingerprint(0x0000_6767_7f67_6700));
+    assert_eq!(two_rooms.preview()[2], " ██▀▀███");
+    assert_eq!(two_rooms.preview().len(), 4);
+    assert!(two_rooms.preview().iter().all(|row| row.chars().count() == 8));
+    assert_eq!(Fingerprint::parse(&two_rooms.to_string()), Some(two_rooms));
+    assert_eq!(fixtures::all_open(3, 3).fingerprint(), Fingerprint(0));
+
+    let dungeon = generator::generate_seeded(&seeded(4), 4);
+    let mut touched = dungeon.clone();
+    for x in 10..14 {
+        touched.set_tile(x, 12, Tile::Rubble);
+    }
+    let fingerprint = dungeon.fingerprint();
+    assert!(fingerprint.is_near_duplicate(touched.fingerprint()));
+    let other = generator::generate_seeded(&seeded(5), 5).fingerprint();
+    let apart = fingerprint.distance(other);
+    assert!(!fingerprint.is_near_duplicate(other), "{} and {} are {} apart", fingerprint, other, apart);
+
+    let dir = scratch_dir("fingerprint");
+    let pty = Pty::spawn(&dir, &["--seed", "4", "--bundle", "map.dungeon"]);
+    pty.wait_for("Wrote map, spawns, generation info and thumbnail to map.dungeon");
+    assert!(pty.wait_exit().success());
+    let bundle = String::from_utf8_lossy(&fs::read(dir.join("map.dungeon")).unwrap()).into_owned();
+    assert!(bundle.contains(&format!("\"fingerprint\": \"{}\"", fingerprint)));
+}
+
+#[test]
+fn validate_lists_every_problem_and_fails() {
+    let dir = scratch_dir("validate");
+    fs::write(dir.join("two-rooms.txt"), fixtures::TWO_ROOMS).unwrap();
+    fs::write(dir.join("pair.txt"), fixtures::DISCONNECTED_PAIR).unwrap();
+    // The two rooms' entrance and exit with the corridor between them gone
+    let stranded = fixtures::TWO_ROOMS.replace("#.............#", "#....#####....#");
+    fs::write(dir.join("stranded.txt"), stranded).unwrap();
+    fs::write(dir.join("broken.txt"), "#####\n#.?.#\n#..\n####!\n").unwrap();
+
+    let pty = Pty::spawn(&dir, &["validate", "two-rooms.txt"]);
+    let screen = pty.wait_for("positions      ok");
+    assert!(screen.contains("connectivity   ok, one region"), "was:\n{}", screen);
+    assert!(pty.wait_exit().success());
+
+    // Ground cut off is allowed, so long as nothing is stranded on it
+    let pty = Pty::spawn(&dir, &["validate", "pair.txt"]);
+    pty.wait_for("2 regions, nothing placed off the main one");
+    assert!(pty.wait_exit().success());
+
+    let pty = Pty::spawn(&dir, &["validate", "stranded.txt"]);
+    pty.wait_for("entity '>' at (12, 4) can't be reached from the entrance");
+    assert!(!pty.wait_exit().success());
+
+    let pty = Pty::spawn(&dir, &["validate", "broken.txt"]);
+    let screen = pty.wait_for("failed validation");
+    for expected in [
+        "line 3 is 3 wide, not 5",
+        "unknown glyph '?' at line 2, column 3",
+        "unknown glyph '!' at line 4, column 5",
+        "connectivity   skipped",
+    ] {
+        assert!(screen.contains(expected), "no {:?} in:\n{}", expected, screen);
+    }
+    assert!(!pty.wait_exit().success());
+}
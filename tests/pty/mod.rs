@@ -0,0 +1,170 @@
+// Runs the binary on a pseudo-terminal so interactive modes can be driven
+// with keystrokes and checked against what they draw.
+mod screen;
+
+pub use screen::Screen;
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const ROWS: u16 = 48;
+pub const COLUMNS: u16 = 120;
+
+// Long enough for the explorer's generation animation on a slow machine
+pub const TIMEOUT: Duration = Duration::from_secs(20);
+
+pub struct Pty {
+    master: File,
+    child: Child,
+    output: Arc<Mutex<Vec<u8>>>,
+}
+
+impl Pty {
+    // Starts the binary in `dir` with a bare PATH, so nothing outside the
+    // test (a clipboard tool, say) changes what it does
+    pub fn spawn(dir: &Path, args: &[&str]) -> Self {
+        Self::spawn_with(dir, args, &[])
+    }
+
+    // Same, with extra environment variables; these win over the defaults
+    pub fn spawn_with(dir: &Path, args: &[&str], vars: &[(&str, &str)]) -> Self {
+        let (mut master, mut slave) = (0, 0);
+        let size = libc::winsize {
+            ws_row: ROWS,
+            ws_col: COLUMNS,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+        let opened = unsafe { libc::openpty(&mut master, &mut slave, std::ptr::null_mut(), std::ptr::null(), &size) };
+        assert_eq!(opened, 0, "openpty failed: {}", std::io::Error::last_os_error());
+        let (master, slave) = unsafe { (File::from_raw_fd(master), OwnedFd::from_raw_fd(slave)) };
+
+        let mut command = Command::new(env!("CARGO_BIN_EXE_dungeon_generator"));
+        command
+            .args(args)
+            .current_dir(dir)
+            .env_clear()
+            .env("TERM", "xterm-256color")
+            .env("PATH", dir)
+            .envs(vars.iter().copied())
+            .stdin(Stdio::from(slave.try_clone().unwrap()))
+            .stdout(Stdio::from(slave.try_clone().unwrap()))
+            .stderr(Stdio::from(slave));
+        // Its own session with the pty as controlling terminal, as a shell
+        // would set it up
+        unsafe {
+            command.pre_exec(|| {
+                if libc::setsid() < 0 || libc::ioctl(0, libc::TIOCSCTTY as _, 0) < 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        let child = command.spawn().expect("failed to start the binary");
+        // The parent's copies of the slave end were moved into the command
+        // and closed by now, so reads fail once the child is gone
+        drop(command);
+
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let mut reader = master.try_clone().unwrap();
+        let sink = Arc::clone(&output);
+        thread::spawn(move || {
+            let mut buffer = [0; 4096];
+            while let Ok(read) = reader.read(&mut buffer) {
+                if read == 0 {
+                    break;
+                }
+                sink.lock().unwrap().extend_from_slice(&buffer[..read]);
+            }
+        });
+        Self { master, child, output }
+    }
+
+    pub fn send(&mut self, keys: &str) {
+        self.master.write_all(keys.as_bytes()).unwrap();
+        self.master.flush().unwrap();
+    }
+
+    // Changes the window size, which signals the child like a real
+    // terminal would. The replayed screen keeps its original size.
+    pub fn resize(&self, rows: u16, columns: u16) {
+        let size = libc::winsize {
+            ws_row: rows,
+            ws_col: columns,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+        let resized = unsafe { libc::ioctl(self.master.as_raw_fd(), libc::TIOCSWINSZ, &size) };
+        assert_eq!(resized, 0, "resize failed: {}", std::io::Error::last_os_error());
+    }
+
+    // The raw bytes written so far
+    pub fn output(&self) -> Vec<u8> {
+        self.output.lock().unwrap().clone()
+    }
+
+    // How many times the whole screen has been cleared so far
+    pub fn clears(&self) -> usize {
+        self.output().windows(4).filter(|window| window == b"\x1b[2J").count()
+    }
+
+    // Everything drawn so far, replayed onto a blank terminal
+    pub fn screen(&self) -> Screen {
+        Screen::parse(ROWS as usize, COLUMNS as usize, &self.output())
+    }
+
+    // Polls the screen until `done` holds, failing the test with the last
+    // frame if it never does
+    pub fn wait_until(&self, what: &str, done: impl Fn(&Screen) -> bool) -> Screen {
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            let screen = self.screen();
+            if done(&screen) {
+                return screen;
+            }
+            if Instant::now() > deadline {
+                panic!("timed out waiting for {}; screen was:\n{}", what, screen);
+            }
+            thread::sleep(Duration::from_millis(25));
+        }
+    }
+
+    pub fn wait_for(&self, text: &str) -> Screen {
+        self.wait_until(&format!("{:?}", text), |screen| screen.contains(text))
+    }
+
+    pub fn wait_exit(mut self) -> ExitStatus {
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            if let Some(status) = self.child.try_wait().unwrap() {
+                return status;
+            }
+            if Instant::now() > deadline {
+                panic!("binary didn't exit; screen was:\n{}", self.screen());
+            }
+            thread::sleep(Duration::from_millis(25));
+        }
+    }
+}
+
+impl Drop for Pty {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// A fresh scratch directory per test
+pub fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("dungeon-pty-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -0,0 +1,198 @@
+// Just enough of a VT100 to replay what crossterm sends: cursor moves,
+// clears, text, OSC 52 clipboard writes and OSC 8 links. Colours and modes
+// are ignored.
+#[derive(Clone, Debug)]
+pub struct Screen {
+    cells: Vec<Vec<char>>,
+    cursor: (usize, usize),
+    // Whatever was copied through OSC 52, decoded, oldest first
+    pub clipboard: Vec<String>,
+    // Where each OSC 8 hyperlink pointed, oldest first
+    pub links: Vec<String>,
+}
+
+impl Screen {
+    pub fn new(rows: usize, columns: usize) -> Self {
+        Self {
+            cells: vec![vec![' '; columns]; rows],
+            cursor: (0, 0),
+            clipboard: Vec::new(),
+            links: Vec::new(),
+        }
+    }
+
+    pub fn parse(rows: usize, columns: usize, bytes: &[u8]) -> Self {
+        let mut screen = Self::new(rows, columns);
+        let text = String::from_utf8_lossy(bytes);
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\x1b' => match chars.next() {
+                    Some('[') => {
+                        let mut params = String::new();
+                        while let Some(&c) = chars.peek() {
+                            chars.next();
+                            if ('@'..='~').contains(&c) {
+                                screen.csi(&params, c);
+                                break;
+                            }
+                            params.push(c);
+                        }
+                    }
+                    Some(']') => {
+                        let mut payload = String::new();
+                        while let Some(c) = chars.next() {
+                            match c {
+                                '\x07' => break,
+                                '\x1b' => {
+                                    chars.next_if_eq(&'\\');
+                                    break;
+                                }
+                                c => payload.push(c),
+                            }
+                        }
+                        screen.osc(&payload);
+                    }
+                    Some('(' | ')') => {
+                        chars.next();
+                    }
+                    _ => {}
+                },
+                '\r' => screen.cursor.1 = 0,
+                '\n' => screen.line_feed(),
+                '\x08' => screen.cursor.1 = screen.cursor.1.saturating_sub(1),
+                c if c.is_control() => {}
+                c => screen.put(c),
+            }
+        }
+        screen
+    }
+
+    fn rows(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn columns(&self) -> usize {
+        self.cells.first().map_or(0, Vec::len)
+    }
+
+    fn put(&mut self, c: char) {
+        if self.cursor.1 >= self.columns() {
+            self.cursor.1 = 0;
+            self.line_feed();
+        }
+        let (row, column) = self.cursor;
+        self.cells[row][column] = c;
+        self.cursor.1 += 1;
+    }
+
+    fn line_feed(&mut self) {
+        if self.cursor.0 + 1 < self.rows() {
+            self.cursor.0 += 1;
+        } else {
+            self.cells.remove(0);
+            self.cells.push(vec![' '; self.columns()]);
+        }
+    }
+
+    fn csi(&mut self, params: &str, command: char) {
+        // Private modes such as ?25l and ?1049h change nothing we draw
+        if params.starts_with('?') {
+            return;
+        }
+        let numbers: Vec<usize> = params.split(';').map(|n| n.parse().unwrap_or(0)).collect();
+        let number = |index: usize, default: usize| numbers.get(index).copied().filter(|&n| n > 0).unwrap_or(default);
+        let (rows, columns) = (self.rows(), self.columns());
+        let (row, column) = self.cursor;
+        match command {
+            'H' | 'f' => self.cursor = ((number(0, 1) - 1).min(rows - 1), (number(1, 1) - 1).min(columns - 1)),
+            'A' => self.cursor.0 = row.saturating_sub(number(0, 1)),
+            'B' => self.cursor.0 = (row + number(0, 1)).min(rows - 1),
+            'C' => self.cursor.1 = (column + number(0, 1)).min(columns - 1),
+            'D' => self.cursor.1 = column.saturating_sub(number(0, 1)),
+            'G' => self.cursor.1 = (number(0, 1) - 1).min(columns - 1),
+            'J' => match numbers[0] {
+                0 => {
+                    self.clear_line(row, column..columns);
+                    (row + 1..rows).for_each(|row| self.clear_line(row, 0..columns));
+                }
+                1 => {
+                    (0..row).for_each(|row| self.clear_line(row, 0..columns));
+                    self.clear_line(row, 0..column + 1);
+                }
+                _ => (0..rows).for_each(|row| self.clear_line(row, 0..columns)),
+            },
+            'K' => match numbers[0] {
+                0 => self.clear_line(row, column..columns),
+                1 => self.clear_line(row, 0..column + 1),
+                _ => self.clear_line(row, 0..columns),
+            },
+            _ => {}
+        }
+    }
+
+    fn clear_line(&mut self, row: usize, columns: std::ops::Range<usize>) {
+        let width = self.columns();
+        for cell in &mut self.cells[row][columns.start.min(width)..columns.end.min(width)] {
+            *cell = ' ';
+        }
+    }
+
+    fn osc(&mut self, payload: &str) {
+        if let Some(data) = payload.strip_prefix("52;").and_then(|rest| rest.split_once(';')).map(|(_, data)| data) {
+            self.clipboard.push(String::from_utf8_lossy(&base64_decode(data)).into_owned());
+        }
+        // A link's text follows as usual; an empty target closes it
+        if let Some(target) = payload.strip_prefix("8;").and_then(|rest| rest.split_once(';')).map(|(_, uri)| uri) {
+            if !target.is_empty() {
+                self.links.push(target.to_string());
+            }
+        }
+    }
+
+    // One row with trailing blanks trimmed
+    pub fn line(&self, row: usize) -> String {
+        self.cells[row].iter().collect::<String>().trim_end().to_string()
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        (0..self.rows()).map(|row| self.line(row)).collect()
+    }
+
+    pub fn contains(&self, text: &str) -> bool {
+        self.lines().iter().any(|line| line.contains(text))
+    }
+
+    // The first row containing `text`
+    pub fn find(&self, text: &str) -> Option<usize> {
+        self.lines().iter().position(|line| line.contains(text))
+    }
+}
+
+impl std::fmt::Display for Screen {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for line in self.lines() {
+            writeln!(f, "|{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+fn base64_decode(text: &str) -> Vec<u8> {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = Vec::new();
+    let (mut bits, mut count) = (0u32, 0);
+    for byte in text.bytes() {
+        let Some(value) = ALPHABET.iter().position(|&c| c == byte) else {
+            continue;
+        };
+        bits = bits << 6 | value as u32;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            out.push((bits >> count) as u8);
+            bits &= (1 << count) - 1;
+        }
+    }
+    out
+}